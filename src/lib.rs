@@ -0,0 +1,185 @@
+// Copyright (c) 2016 srither developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Slither Link solver.
+//!
+//! The functions of this crate take and return puzzles in the text format,
+//! which is enough for most uses. The underlying crates are re-exported for
+//! the finer control over puzzles and solving.
+//!
+//! ```
+//! let solution = srither::solve_str("3_\n__\n").unwrap();
+//! assert_eq!(srither::solve_str(&solution).unwrap(), solution);
+//! ```
+
+#![warn(bad_style)]
+#![warn(missing_copy_implementations)]
+#![warn(missing_debug_implementations)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(trivial_numeric_casts)]
+#![warn(unused)]
+#![warn(unused_extern_crates)]
+#![warn(unused_import_braces)]
+#![warn(unused_qualifications)]
+#![warn(unused_results)]
+
+#![cfg_attr(feature="dev", feature(plugin))]
+#![cfg_attr(feature="dev", plugin(clippy))]
+#![cfg_attr(feature="dev", warn(mut_mut))]
+#![cfg_attr(feature="dev", warn(string_add))]
+#![cfg_attr(feature="dev", warn(string_add_assign))]
+
+pub extern crate srither_core;
+pub extern crate srither_solver;
+pub extern crate srither_gen;
+pub extern crate srither_testdata;
+
+use std::fmt;
+use std::error::Error as ErrorTrait;
+
+use srither_core::geom::Size;
+use srither_core::puzzle::{ParsePuzzleError, Puzzle};
+use srither_gen::{Difficulty, Generator};
+use srither_solver as solver;
+
+/// An error type which is returned from the functions of this crate.
+#[derive(Copy, Clone, Debug)]
+pub enum Error {
+    /// The input is not a puzzle in the text format.
+    Parse(ParsePuzzleError),
+    /// The puzzle has no solution, or cannot be solved.
+    Solver(solver::Error),
+    /// No puzzle of the options is generated.
+    Gen(srither_gen::Error),
+}
+
+impl From<ParsePuzzleError> for Error {
+    fn from(err: ParsePuzzleError) -> Error {
+        Error::Parse(err)
+    }
+}
+
+impl From<solver::Error> for Error {
+    fn from(err: solver::Error) -> Error {
+        Error::Solver(err)
+    }
+}
+
+impl From<srither_gen::Error> for Error {
+    fn from(err: srither_gen::Error) -> Error {
+        Error::Gen(err)
+    }
+}
+
+impl ErrorTrait for Error {
+    fn description(&self) -> &str {
+        match *self {
+            Error::Parse(ref e) => e.description(),
+            Error::Solver(ref e) => e.description(),
+            Error::Gen(ref e) => e.description(),
+        }
+    }
+    fn cause(&self) -> Option<&ErrorTrait> {
+        match *self {
+            Error::Parse(ref e) => Some(e),
+            Error::Solver(ref e) => Some(e),
+            Error::Gen(ref e) => Some(e),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.description().fmt(f)
+    }
+}
+
+/// Solves the puzzle in the text format, and returns the first solution in
+/// the text format.
+pub fn solve_str(input: &str) -> Result<String, Error> {
+    let puzzle = try!(input.parse::<Puzzle>());
+    let solution = try!(solver::solve(&puzzle));
+    Ok(solution.to_string())
+}
+
+/// Returns the number of the solutions of the puzzle in the text format,
+/// counting up to `max`.
+pub fn count_solutions_str(input: &str, max: u64) -> Result<u64, Error> {
+    let puzzle = try!(input.parse::<Puzzle>());
+    let mut num = 0;
+    let count = try!(solver::for_each_solution(&puzzle, &Default::default(), |_| {
+        num += 1;
+        num < max
+    }));
+    Ok(count)
+}
+
+/// Generates a puzzle of the rows and the columns with a unique solution,
+/// and returns it in the text format. The same seed always gives the same
+/// puzzle. With a difficulty, only the puzzles of it are generated, which
+/// may fail for a small size.
+///
+/// # Panics
+///
+/// Panics if the rows or the columns are zero.
+pub fn generate_str(rows: i32,
+                    cols: i32,
+                    difficulty: Option<Difficulty>,
+                    seed: u64)
+                    -> Result<String, Error> {
+    let mut gen = Generator::new(Size(rows, cols));
+    gen.set_difficulty(difficulty);
+    let puzzle = try!(gen.generate(&mut srither_gen::seeded_rng(seed)));
+    Ok(puzzle.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use srither_core::puzzle::Puzzle;
+    use srither_gen::Difficulty;
+    use srither_testdata as testdata;
+    use super::{Error, count_solutions_str, generate_str, solve_str};
+
+    #[test]
+    fn solve() {
+        let input = "22\n22\n";
+        let output = solve_str(input).unwrap();
+        assert_eq!(1, count_solutions_str(input, 10).unwrap());
+        assert_eq!("+-+-+\n|2x2|\n+x+x+\n|2x2|\n+-+-+\n", output);
+
+        match solve_str("5_\n__\n") {
+            Err(Error::Parse(_)) => {}
+            r => panic!("{:?}", r),
+        }
+        match solve_str("00\n00\n") {
+            Err(Error::Solver(_)) => {}
+            r => panic!("{:?}", r),
+        }
+        assert_eq!(0, count_solutions_str("00\n00\n", 10).unwrap());
+    }
+    #[test]
+    fn generate() {
+        let puzzle = generate_str(4, 5, None, 1).unwrap();
+        let expected = "___22\n__2__\n__1_1\n323__\n".parse::<Puzzle>().unwrap();
+        assert_eq!(expected.to_string(), puzzle);
+        assert_eq!(1, count_solutions_str(&puzzle, 2).unwrap());
+
+        let easy = generate_str(4, 5, Some(Difficulty::Easy), 1).unwrap();
+        assert_eq!(easy, generate_str(4, 5, Some(Difficulty::Easy), 1).unwrap());
+        assert_eq!(1, count_solutions_str(&easy, 2).unwrap());
+    }
+
+    #[test]
+    fn corpus() {
+        for entry in testdata::entries() {
+            let count = count_solutions_str(entry.source(), entry.num_solutions() + 1).unwrap();
+            assert_eq!((entry.name(), entry.num_solutions()), (entry.name(), count));
+        }
+    }
+}
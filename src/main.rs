@@ -20,47 +20,43 @@
 #![warn(unused_qualifications)]
 #![warn(unused_results)]
 
-#![feature(test)]
-
 #![cfg_attr(feature="dev", feature(plugin))]
 #![cfg_attr(feature="dev", plugin(clippy))]
 #![cfg_attr(feature="dev", warn(mut_mut))]
 #![cfg_attr(feature="dev", warn(string_add))]
 #![cfg_attr(feature="dev", warn(string_add_assign))]
 
-extern crate term;
-extern crate argparse;
-extern crate test as rustc_test;
 extern crate time;
 
-extern crate srither_core;
-extern crate srither_solver;
+extern crate srither_cli_lib;
 
 use std::{io, process};
 use std::io::prelude::*;
 
-use error::AppResult;
-use parse_arg::Config;
-
-mod error;
-mod parse_arg;
-mod pprint;
-
-mod solve;
-mod test;
-mod bench;
-
-fn run() -> AppResult<()> {
-    match Config::parse() {
-        Config::Solve(config) => solve::run(config),
-        Config::Test(config) => test::run(config),
-        Config::Bench(config) => bench::run(config),
-    }
-}
+use srither_cli_lib::{Config, Invocation, history};
 
 fn main() {
-    if let Err(e) = run() {
-        let _ = writeln!(&mut io::stderr(), "{}", e);
-        process::exit(255);
+    let Invocation { command, config, history_file } = Config::parse();
+    let start = time::get_time();
+    let stdin = io::stdin();
+    let result = srither_cli_lib::run(config,
+                                      &mut stdin.lock(),
+                                      &mut io::stdout(),
+                                      &mut io::stderr(),
+                                      history_file.as_ref());
+    if let Some(ref path) = history_file {
+        if command != "history" {
+            if let Err(e) = history::record(path, &command, start, &result) {
+                let _ = writeln!(&mut io::stderr(), "cannot record the run to the history: {}", e);
+            }
+        }
+    }
+    match result {
+        Ok(true) => {}
+        Ok(false) => process::exit(1),
+        Err(e) => {
+            let _ = writeln!(&mut io::stderr(), "{}", e);
+            process::exit(255);
+        }
     }
 }
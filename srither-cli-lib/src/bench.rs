@@ -0,0 +1,134 @@
+// Copyright (c) 2016 srither developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Benchmarks the solver on the puzzles.
+
+use std::u64;
+use time;
+use rustc_test::{Bencher, DynBenchFn, DynTestName, ShouldPanic, TDynBenchFn, TestDesc,
+                 TestDescAndFn, self as test};
+
+use srither_core::puzzle::Puzzle;
+use srither_solver::{Backtrack, SolveOptions, Solutions, self as solver};
+use srither_testdata as testdata;
+
+use error::AppResult;
+use io_util;
+use parse_arg::BenchConfig;
+
+struct BenchFn {
+    puzzle: Puzzle,
+    derive_all: bool,
+    options: SolveOptions,
+}
+
+impl TDynBenchFn for BenchFn {
+    fn run(&self, harness: &mut Bencher) {
+        harness.iter(|| solve(&self.puzzle, self.derive_all, &self.options))
+    }
+}
+
+impl BenchFn {
+    fn new(puzzle: Puzzle, derive_all: bool, options: SolveOptions) -> BenchFn {
+        BenchFn {
+            puzzle: puzzle,
+            derive_all: derive_all,
+            options: options,
+        }
+    }
+}
+
+/// Runs the benchmarks by the test harness, which reports to the standard
+/// output.
+pub fn run(config: BenchConfig) -> AppResult<()> {
+    let derive_all = config.derive_all;
+    let mut inputs = vec![];
+    for file in config.input_files {
+        let buf = try!(io_util::read_file(&file));
+        inputs.push((file, try!(buf.parse::<Puzzle>())));
+    }
+    for &category in &config.corpus {
+        for entry in testdata::entries_of(category) {
+            let name = format!("corpus/{}/{}", category.name(), entry.name());
+            inputs.push((name, entry.puzzle()));
+        }
+    }
+    let inputs = if let Some(n) = config.only_hardest {
+        take_hardest(inputs, n, derive_all)
+    } else {
+        inputs
+    };
+    // Each puzzle is measured with each strategy, named after it if more
+    // than one are given.
+    let mut tests = vec![];
+    for (name, puzzle) in inputs {
+        for &backtrack in &config.backtrack {
+            let name = if config.backtrack.len() > 1 {
+                format!("{} [{}]", name, backtrack_name(backtrack))
+            } else {
+                name.clone()
+            };
+            let options = SolveOptions { backtrack: backtrack, ..SolveOptions::default() };
+            tests.push(TestDescAndFn {
+                desc: TestDesc {
+                    name: DynTestName(name),
+                    ignore: false,
+                    should_panic: ShouldPanic::No,
+                },
+                testfn: DynBenchFn(Box::new(BenchFn::new(puzzle.clone(), derive_all, options))),
+            });
+        }
+    }
+
+    test::test_main(&["".to_owned(), "--bench".to_owned()], tests);
+
+    Ok(())
+}
+
+fn backtrack_name(backtrack: Backtrack) -> &'static str {
+    match backtrack {
+        Backtrack::Auto => "auto",
+        Backtrack::Clone => "clone",
+        Backtrack::Snapshot => "snapshot",
+    }
+}
+
+fn get_elapse(puzzle: &Puzzle, derive_all: bool) -> u64 {
+    let start = time::precise_time_ns();
+    let _ = test::black_box(solve(puzzle, derive_all, &SolveOptions::default()));
+    time::precise_time_ns() - start
+}
+
+fn take_hardest(inputs: Vec<(String, Puzzle)>,
+                n: usize,
+                derive_all: bool)
+                -> Vec<(String, Puzzle)> {
+    let mut inputs = inputs.into_iter()
+                           .map(|input| (get_elapse(&input.1, derive_all), input))
+                           .collect::<Vec<_>>();
+    inputs.sort_by(|a, b| (a.0, &(a.1).0).cmp(&(b.0, &(b.1).0)).reverse());
+    inputs.into_iter()
+          .map(|pair| pair.1)
+          .take(n)
+          .collect()
+}
+
+fn solve(puzzle: &Puzzle, derive_all: bool, options: &SolveOptions) -> AppResult<()> {
+    if derive_all {
+        // The puzzles are given explicitly, so all solutions are derived even
+        // if they have too few hints.
+        let options = SolveOptions { max_solutions: Some(u64::MAX), ..options.clone() };
+        for solution in try!(Solutions::with_options(puzzle, &options)) {
+            let _ = test::black_box(solution);
+        }
+    } else {
+        let _ = test::black_box(try!(solver::solve_with_options(puzzle, options)));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,210 @@
+// Copyright (c) 2016 srither developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! On-disk cache of the solutions, the ratings and the statistics.
+//!
+//! Each puzzle is stored in a pack file named after the fingerprint of the
+//! puzzle. The first entry is the puzzle itself and the rest are its
+//! solutions. The metadata of the first entry tell whether the solutions are
+//! all of them, and have the statistics of solving for the first solution
+//! with the theorem set of them, and the score rating the puzzle by them. As
+//! rotated puzzles have the same fingerprint, the stored puzzle is compared
+//! with the given one before its solutions are used. The files of the other
+//! formats of the cache, e.g. written before the envelope, are missed.
+
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use srither_core::geom::{Geom, Point};
+use srither_core::puzzle::{Edge, Puzzle, Side};
+use srither_core::puzzle_set::{Entry, Metadata, PuzzleSet};
+use srither_solver::SolverStats;
+
+use envelope;
+use error::AppResult;
+
+// The keys of the statistics in the metadata.
+const STATS_KEYS: &'static [&'static str] = &["theorem-passes",
+                                              "theorems-applied",
+                                              "connect-analyses",
+                                              "probes",
+                                              "branches",
+                                              "max-depth"];
+
+#[derive(Clone, Debug)]
+pub struct Cache {
+    dir: PathBuf,
+}
+
+impl Cache {
+    pub fn open<P: AsRef<Path>>(dir: P) -> AppResult<Cache> {
+        try!(fs::create_dir_all(dir.as_ref()));
+        Ok(Cache { dir: dir.as_ref().to_owned() })
+    }
+
+    /// Returns the cached solutions of the puzzle. Only the first solution is
+    /// returned unless `derive_all` is set.
+    pub fn get(&self, puzzle: &Puzzle, derive_all: bool) -> Option<Vec<Puzzle>> {
+        let (metadata, mut solutions) = match self.read(puzzle) {
+            Some(record) => record,
+            None => return None,
+        };
+        if derive_all && metadata.get("solutions") != Some("all") {
+            return None;
+        }
+        if !derive_all {
+            if solutions.is_empty() {
+                return None;
+            }
+            solutions.truncate(1);
+        }
+        Some(solutions)
+    }
+
+    /// Returns the first cached solution of the puzzle and the statistics of
+    /// solving for it with the theorem set.
+    pub fn get_stats(&self, puzzle: &Puzzle, theorem_set: u32) -> Option<(Puzzle, SolverStats)> {
+        let (metadata, solutions) = match self.read(puzzle) {
+            Some(record) => record,
+            None => return None,
+        };
+        if metadata.get("theorem-set") != Some(&theorem_set.to_string()[..]) {
+            return None;
+        }
+        let mut values = vec![];
+        for key in STATS_KEYS {
+            match metadata.get(key).and_then(|v| u32::from_str(v).ok()) {
+                Some(v) => values.push(v),
+                None => return None,
+            }
+        }
+        let stats = SolverStats {
+            theorem_passes: values[0],
+            theorems_applied: values[1],
+            connect_analyses: values[2],
+            probes: values[3],
+            branches: values[4],
+            max_depth: values[5],
+            ..SolverStats::default()
+        };
+        solutions.into_iter().next().map(|solution| (solution, stats))
+    }
+
+    /// Stores the solutions of the puzzle. `derive_all` tells whether the
+    /// solutions are all of the solutions or only the first one. The
+    /// statistics stored are kept.
+    pub fn put(&self, puzzle: &Puzzle, derive_all: bool, solutions: &[Puzzle]) -> AppResult<()> {
+        let mut metadata = self.read(puzzle).map(|r| r.0).unwrap_or_else(Metadata::new);
+        metadata.set("solutions",
+                     if derive_all {
+                         "all"
+                     } else {
+                         "first"
+                     });
+        self.write(puzzle, metadata, solutions)
+    }
+
+    /// Stores the statistics of solving the puzzle for the first solution
+    /// with the theorem set, and the score rating the puzzle by them. The
+    /// solutions stored are kept, or the first solution is stored if none.
+    pub fn put_stats(&self,
+                     puzzle: &Puzzle,
+                     theorem_set: u32,
+                     solution: &Puzzle,
+                     stats: &SolverStats)
+                     -> AppResult<()> {
+        let (mut metadata, mut solutions) = self.read(puzzle)
+                                                .unwrap_or_else(|| (Metadata::new(), vec![]));
+        if solutions.is_empty() {
+            metadata.set("solutions", "first");
+            solutions.push(solution.clone());
+        }
+        metadata.set("theorem-set", &theorem_set.to_string());
+        metadata.set("score", &format!("{:.1}", stats.score()));
+        let values = [stats.theorem_passes,
+                      stats.theorems_applied,
+                      stats.connect_analyses,
+                      stats.probes,
+                      stats.branches,
+                      stats.max_depth];
+        for (key, value) in STATS_KEYS.iter().zip(&values) {
+            metadata.set(key, &value.to_string());
+        }
+        self.write(puzzle, metadata, &solutions)
+    }
+
+    // Reads the metadata and the solutions of the puzzle, if stored in the
+    // format of the cache.
+    fn read(&self, puzzle: &Puzzle) -> Option<(Metadata, Vec<Puzzle>)> {
+        let mut buf = String::new();
+        let read = File::open(self.path(puzzle)).and_then(|mut f| f.read_to_string(&mut buf));
+        if read.is_err() {
+            return None;
+        }
+        // The files of the other formats are solved again, and replaced.
+        let first = buf.lines().next().and_then(|line| envelope::parse(&envelope::CACHE, line));
+        if first.map(|e| e.format) != Some(envelope::CACHE.format) {
+            return None;
+        }
+        let set = match buf.parse::<PuzzleSet>() {
+            Ok(set) => set,
+            Err(_) => return None,
+        };
+
+        let mut entries = set.into_entries().into_iter();
+        let metadata = match entries.next() {
+            Some(e) => {
+                if e.puzzle != *puzzle {
+                    return None;
+                }
+                e.metadata
+            }
+            None => return None,
+        };
+        Some((metadata, entries.map(|e| restore_sides(e.puzzle)).collect()))
+    }
+
+    fn write(&self, puzzle: &Puzzle, metadata: Metadata, solutions: &[Puzzle]) -> AppResult<()> {
+        let mut head = Entry::new(puzzle.clone());
+        head.metadata = metadata;
+
+        let mut set = PuzzleSet::new();
+        set.push(head);
+        set.extend(solutions.iter().map(|s| Entry::new(s.clone())));
+
+        let mut f = try!(File::create(self.path(puzzle)));
+        try!(writeln!(f, "{}", envelope::header(&envelope::CACHE)));
+        try!(write!(f, "{}", set));
+        Ok(())
+    }
+
+    fn path(&self, puzzle: &Puzzle) -> PathBuf {
+        self.dir.join(format!("{:016x}.txt", puzzle.fingerprint()))
+    }
+}
+
+// The text form has only edges, so restores the sides by crossing lines from
+// the outside.
+fn restore_sides(mut puzzle: Puzzle) -> Puzzle {
+    for r in 0..puzzle.row() {
+        let mut side = Side::Out;
+        for c in 0..puzzle.column() {
+            let p = Point(r, c);
+            if puzzle.edge_v(p) == Some(Edge::Line) {
+                side = match side {
+                    Side::In => Side::Out,
+                    Side::Out => Side::In,
+                };
+            }
+            puzzle.set_side(p, Some(side));
+        }
+    }
+    puzzle
+}
@@ -0,0 +1,206 @@
+// Copyright (c) 2016 srither developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Compares the passes of the deduction with the recorded checkpoints.
+
+use std::io;
+use std::io::prelude::*;
+
+use srither_core::geom::{Geom, Point};
+use srither_core::puzzle::{Edge, Puzzle, Side};
+use srither_solver as solver;
+
+use coord;
+use envelope;
+use error::{AppError, AppResult};
+use io_util;
+use parse_arg::CheckpointConfig;
+use play;
+use solve;
+
+// The board after a pass, written as the step and the states of the
+// horizontal edges, the vertical edges and the cells in row-major order.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Record {
+    step: String,
+    edges_h: String,
+    edges_v: String,
+    sides: String,
+}
+
+/// Writes the checkpoints of the passes of the deduction to `out`, or
+/// compares them with the recorded ones and reports the first divergence.
+/// Returns false if the passes diverge from the recorded ones.
+pub fn run<O: Write>(config: CheckpointConfig, out: &mut O) -> AppResult<bool> {
+    let mut options = try!(solve::solve_options(config.strategy_file.as_ref()));
+    options.theorem_set = config.theorem_set;
+    options.disabled_theorems = config.disabled_theorems.clone();
+
+    let buf = try!(io_util::read_file(&config.input_file));
+    let puzzle = try!(Puzzle::parse_any(&buf)).0.hints_only();
+    let records = try!(solver::trace_passes(&puzzle, &options))
+                      .into_iter()
+                      .map(|c| record(solve::step_name(c.step), &c.board))
+                      .collect::<Vec<_>>();
+
+    match config.compare_file {
+        Some(ref path) => {
+            let (hints, recorded) = try!(read_checkpoints(path));
+            if hints != puzzle {
+                return Err(invalid_checkpoints("the checkpoints are of another puzzle"));
+            }
+            compare(out, &puzzle, &recorded, &records)
+        }
+        None => {
+            try!(writeln!(out, "{}", envelope::header(&envelope::CHECKPOINTS)));
+            try!(writeln!(out, "# hints: {}", play::hint_rows(&puzzle)));
+            for (i, r) in records.iter().enumerate() {
+                try!(writeln!(out, "{} {} {} {} {}", i, r.step, r.edges_h, r.edges_v, r.sides));
+            }
+            Ok(true)
+        }
+    }
+}
+
+fn record(step: String, board: &Puzzle) -> Record {
+    let edge = |e: Option<Edge>| {
+        match e {
+            Some(Edge::Line) => '-',
+            Some(Edge::Cross) => 'x',
+            None => '.',
+        }
+    };
+    let (row, column) = (board.row(), board.column());
+    Record {
+        step: step,
+        edges_h: (0..((row + 1) * column))
+                     .map(|i| edge(board.edge_h(Point(i / column, i % column))))
+                     .collect(),
+        edges_v: (0..(row * (column + 1)))
+                     .map(|i| edge(board.edge_v(Point(i / (column + 1), i % (column + 1)))))
+                     .collect(),
+        sides: board.points()
+                    .map(|p| {
+                        match board.side(p) {
+                            Some(Side::In) => 'i',
+                            Some(Side::Out) => 'o',
+                            None => '.',
+                        }
+                    })
+                    .collect(),
+    }
+}
+
+fn read_checkpoints(path: &str) -> AppResult<(Puzzle, Vec<Record>)> {
+    let buf = try!(io_util::read_file(path));
+    let mut lines = buf.lines().map(|l| l.trim()).filter(|l| !l.is_empty());
+    match lines.next().and_then(|line| envelope::parse(&envelope::CHECKPOINTS, line)) {
+        Some(envelope) => try!(envelope::check(&envelope::CHECKPOINTS, &envelope)),
+        None => return Err(invalid_checkpoints("not a checkpoint file")),
+    }
+    let puzzle = match lines.next() {
+        Some(line) if line.starts_with("# hints:") => {
+            let rows = line["# hints:".len()..].trim().replace('/', "\n");
+            try!(rows.parse::<Puzzle>())
+        }
+        _ => return Err(invalid_checkpoints("the checkpoints have no hints")),
+    };
+
+    let (row, column) = (puzzle.row() as usize, puzzle.column() as usize);
+    let mut records = vec![];
+    for line in lines {
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        if words.len() != 5 || words[0] != records.len().to_string() ||
+           words[2].len() != (row + 1) * column || words[3].len() != row * (column + 1) ||
+           words[4].len() != row * column {
+            return Err(invalid_checkpoints("invalid checkpoint"));
+        }
+        records.push(Record {
+            step: words[1].to_owned(),
+            edges_h: words[2].to_owned(),
+            edges_v: words[3].to_owned(),
+            sides: words[4].to_owned(),
+        });
+    }
+    Ok((puzzle, records))
+}
+
+// Reports the first pass where the runs diverge, and returns true if they do
+// not.
+fn compare<O: Write>(out: &mut O,
+                     puzzle: &Puzzle,
+                     recorded: &[Record],
+                     records: &[Record])
+                     -> AppResult<bool> {
+    for (i, (old, new)) in recorded.iter().zip(records).enumerate() {
+        if old == new {
+            continue;
+        }
+        try!(writeln!(out, "pass {} diverges:", i));
+        if old.step != new.step {
+            try!(writeln!(out, "  step: {} (recorded), {} (this run)", old.step, new.step));
+        }
+
+        let column = puzzle.column();
+        let diffs = [(&old.edges_h, &new.edges_h, "horizontal edge", column),
+                     (&old.edges_v, &new.edges_v, "vertical edge", column + 1),
+                     (&old.sides, &new.sides, "cell", column)];
+        let mut first = true;
+        let mut num_diffs = 0;
+        for &(old, new, what, width) in &diffs {
+            for (k, (a, b)) in old.chars().zip(new.chars()).enumerate() {
+                if a == b {
+                    continue;
+                }
+                num_diffs += 1;
+                if first {
+                    let k = k as i32;
+                    try!(writeln!(out,
+                                  "  {} at {}: {} (recorded), {} (this run)",
+                                  what,
+                                  coord::format(Point(k / width, k % width)),
+                                  state_name(a),
+                                  state_name(b)));
+                    first = false;
+                }
+            }
+        }
+        try!(writeln!(out, "  {} states differ", num_diffs));
+        return Ok(false);
+    }
+
+    if recorded.len() != records.len() {
+        let (n, which) = if recorded.len() < records.len() {
+            (recorded.len(), "this run")
+        } else {
+            (records.len(), "the recorded run")
+        };
+        try!(writeln!(out, "pass {} exists only in {}", n, which));
+        return Ok(false);
+    }
+
+    match records.len() {
+        1 => try!(writeln!(out, "no divergence in 1 pass")),
+        n => try!(writeln!(out, "no divergence in {} passes", n)),
+    }
+    Ok(true)
+}
+
+fn state_name(c: char) -> &'static str {
+    match c {
+        '-' => "line",
+        'x' => "cross",
+        'i' => "inside",
+        'o' => "outside",
+        _ => "unknown",
+    }
+}
+
+fn invalid_checkpoints(msg: &str) -> AppError {
+    io::Error::new(io::ErrorKind::InvalidData, msg).into()
+}
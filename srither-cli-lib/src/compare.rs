@@ -0,0 +1,169 @@
+// Copyright (c) 2016 srither developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Compares the solving time of the puzzles between two runs.
+
+use std::fs;
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+use time;
+
+use srither_core::puzzle::Puzzle;
+use srither_solver::{Backend, SolveOptions, Solutions};
+
+use error::AppResult;
+use io_util;
+use json::Json;
+use parse_arg::{CompareConfig, Format};
+
+/// The results of `compare`.
+#[derive(Clone, Debug)]
+pub struct CompareReport {
+    /// The puzzle files compared, in order.
+    pub files: Vec<PathBuf>,
+    /// The number of the puzzles whose solutions differ among the backends.
+    pub mismatches: usize,
+    /// The total time of each backend in nanoseconds, in the order given.
+    pub total_ns: Vec<u64>,
+}
+
+/// Solves the puzzles of the inputs by each backend, and writes the
+/// mismatches of the solutions and the times to `out`.
+pub fn run<O: Write>(config: CompareConfig, out: &mut O) -> AppResult<CompareReport> {
+    let mut files = vec![];
+    for input in &config.inputs {
+        try!(collect_files(Path::new(input), &mut files));
+    }
+
+    let mut total_ns = vec![0; config.backends.len()];
+    let mut num_mismatch = 0;
+    let mut reports = vec![];
+
+    for file in &files {
+        let buf = try!(io_util::read_file(file));
+        let puzzle = try!(buf.parse::<Puzzle>());
+
+        let results = config.backends
+                            .iter()
+                            .map(|&backend| solve_all(&puzzle, backend))
+                            .collect::<Vec<_>>();
+        for (total, &(_, ns)) in total_ns.iter_mut().zip(&results) {
+            *total += ns;
+        }
+
+        let ok = results.iter().all(|r| same_solutions(&r.0, &results[0].0));
+        if !ok {
+            num_mismatch += 1;
+        }
+
+        if config.format == Format::Json {
+            let times = config.backends
+                              .iter()
+                              .zip(&results)
+                              .map(|(&backend, r)| (backend_name(backend), r.1.into()))
+                              .collect();
+            reports.push(Json::object(vec![("file", file.display().to_string().into()),
+                                           ("ok", ok.into()),
+                                           ("times_ns", Json::object(times))]));
+            continue;
+        }
+
+        let timings = config.backends
+                            .iter()
+                            .zip(&results)
+                            .map(|(&backend, r)| {
+                                format!("{} {}", backend_name(backend), format_ns(r.1))
+                            })
+                            .collect::<Vec<_>>();
+        try!(writeln!(out,
+                      "{}: {} ({})",
+                      file.display(),
+                      if ok {
+                          "ok"
+                      } else {
+                          "MISMATCH"
+                      },
+                      timings.join(", ")));
+    }
+
+    match config.format {
+        Format::Text => {
+            try!(writeln!(out, ""));
+            try!(writeln!(out, "{} puzzles, {} mismatches", files.len(), num_mismatch));
+            for (&backend, &ns) in config.backends.iter().zip(&total_ns) {
+                try!(writeln!(out, "{:>8}: {}", backend_name(backend), format_ns(ns)));
+            }
+        }
+        Format::Json => {
+            let totals = config.backends
+                               .iter()
+                               .zip(&total_ns)
+                               .map(|(&backend, &ns)| (backend_name(backend), ns.into()))
+                               .collect();
+            try!(writeln!(out,
+                          "{}",
+                          Json::object(vec![("files", Json::Array(reports)),
+                                            ("mismatches", (num_mismatch as u64).into()),
+                                            ("total_ns", Json::object(totals))])));
+        }
+    }
+
+    Ok(CompareReport {
+        files: files,
+        mismatches: num_mismatch,
+        total_ns: total_ns,
+    })
+}
+
+fn collect_files(path: &Path, files: &mut Vec<PathBuf>) -> AppResult<()> {
+    if !try!(fs::metadata(path)).is_dir() {
+        files.push(path.to_owned());
+        return Ok(());
+    }
+
+    let mut entries = vec![];
+    for entry in try!(fs::read_dir(path)) {
+        entries.push(try!(entry).path());
+    }
+    entries.sort();
+    for entry in entries {
+        try!(collect_files(&entry, files));
+    }
+    Ok(())
+}
+
+/// Derives all solutions of the puzzle with the backend, returning `None` if
+/// the solver fails.
+fn solve_all(puzzle: &Puzzle, backend: Backend) -> (Option<Vec<Puzzle>>, u64) {
+    let options = SolveOptions { backend: backend, ..SolveOptions::default() };
+    let start = time::precise_time_ns();
+    let solutions = Solutions::with_options(puzzle, &options)
+                        .ok()
+                        .map(|it| it.collect::<Vec<_>>());
+    (solutions, time::precise_time_ns() - start)
+}
+
+fn same_solutions(a: &Option<Vec<Puzzle>>, b: &Option<Vec<Puzzle>>) -> bool {
+    match (a.as_ref(), b.as_ref()) {
+        (Some(a), Some(b)) => a.len() == b.len() && a.iter().all(|s| b.contains(s)),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+fn backend_name(backend: Backend) -> &'static str {
+    match backend {
+        Backend::Native => "native",
+        Backend::Simple => "simple",
+        Backend::Adaptive => "adaptive",
+    }
+}
+
+fn format_ns(ns: u64) -> String {
+    format!("{}.{:03} ms", ns / 1000000, ns / 1000 % 1000)
+}
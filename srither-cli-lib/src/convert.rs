@@ -0,0 +1,107 @@
+// Copyright (c) 2016 srither developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Converts the puzzles between the formats.
+
+use std::io::prelude::*;
+
+use srither_core::puzzle::Puzzle;
+use srither_core::puzzle_set::PuzzleSet;
+
+use envelope;
+use error::AppResult;
+use io_util;
+use parse_arg::ConvertConfig;
+use pipeline;
+
+/// Converts the puzzles of the input files, or of `input` if none, and
+/// writes them to `out`. Returns the converted puzzles in order.
+pub fn run<I, O, E>(config: ConvertConfig,
+                    input: &mut I,
+                    out: &mut O,
+                    err: &mut E)
+                    -> AppResult<Vec<String>>
+    where I: Read,
+          O: Write,
+          E: Write
+{
+    let mut outputs = vec![];
+    if config.input_files.is_empty() {
+        outputs.extend(try!(convert(&config, input, out, err)));
+    } else {
+        for file in &config.input_files {
+            let mut f = try!(io_util::open(file));
+            outputs.extend(try!(convert(&config, &mut f, out, err)));
+        }
+    }
+    Ok(outputs)
+}
+
+fn convert<T, O, E>(config: &ConvertConfig,
+                    input: &mut T,
+                    out: &mut O,
+                    err: &mut E)
+                    -> AppResult<Vec<String>>
+    where T: Read,
+          O: Write,
+          E: Write
+{
+    let buf = try!(io_util::read_to_string(input));
+    let puzzle = match config.from {
+        Some(format) => try!(format.parse(&buf)),
+        None => {
+            match Puzzle::parse_any(&buf) {
+                Ok((puzzle, _)) => puzzle,
+                Err(e) => {
+                    // A file of several puzzles with the headers of metadata.
+                    try!(envelope::check_text(&envelope::PACK, &buf));
+                    match buf.parse::<PuzzleSet>() {
+                        Ok(set) => return convert_set(config, set, out, err),
+                        Err(_) => return Err(e.into()),
+                    }
+                }
+            }
+        }
+    };
+    let output = config.to.emit(&puzzle);
+    try!(write!(out, "{}", output));
+    Ok(vec![output])
+}
+
+/// Converts the puzzles of the set, each written after its headers and
+/// separated by an empty line as in the set.
+fn convert_set<O, E>(config: &ConvertConfig,
+                     set: PuzzleSet,
+                     out: &mut O,
+                     err: &mut E)
+                     -> AppResult<Vec<String>>
+    where O: Write,
+          E: Write
+{
+    let to = config.to;
+    let metadata = set.entries().iter().map(|e| e.metadata.clone()).collect::<Vec<_>>();
+    let puzzles = set.into_entries().into_iter().map(|e| e.puzzle).collect();
+    let mut outputs = vec![];
+    try!(pipeline::run(&config.pipeline,
+                       puzzles,
+                       move |puzzle, _| to.emit(&puzzle),
+                       |i, outcome| {
+                           let output = try!(outcome.done(i));
+                           if i > 0 {
+                               try!(writeln!(out, ""));
+                           }
+                           for &(ref key, ref value) in metadata[i].iter() {
+                               try!(writeln!(out, "# {}: {}", key, value));
+                           }
+                           try!(write!(out, "{}", output));
+                           outputs.push(output);
+                           Ok(())
+                       },
+                       err));
+    Ok(outputs)
+}
@@ -0,0 +1,177 @@
+// Copyright (c) 2016 srither developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! The convention of the coordinates written and read by the commands.
+
+use std::str::FromStr;
+use std::sync::atomic::{ATOMIC_USIZE_INIT, AtomicUsize, Ordering};
+
+use srither_core::geom::Point;
+
+/// How a cell is written as a pair of numbers.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CoordStyle {
+    /// The row and then the column, counted from 0 (`rc0`, the default).
+    RowColumn0,
+    /// The row and then the column, counted from 1 (`rc1`).
+    RowColumn1,
+    /// The column (x) and then the row (y), counted from 0 (`xy0`).
+    Xy0,
+    /// The column (x) and then the row (y), counted from 1 (`xy1`).
+    Xy1,
+}
+
+impl FromStr for CoordStyle {
+    type Err = ();
+
+    fn from_str(src: &str) -> Result<CoordStyle, ()> {
+        match src {
+            "rc0" => Ok(CoordStyle::RowColumn0),
+            "rc1" => Ok(CoordStyle::RowColumn1),
+            "xy0" => Ok(CoordStyle::Xy0),
+            "xy1" => Ok(CoordStyle::Xy1),
+            _ => Err(()),
+        }
+    }
+}
+
+impl CoordStyle {
+    /// Returns the number of the first row and column.
+    pub fn origin(self) -> i32 {
+        match self {
+            CoordStyle::RowColumn0 | CoordStyle::Xy0 => 0,
+            CoordStyle::RowColumn1 | CoordStyle::Xy1 => 1,
+        }
+    }
+
+    fn is_xy(self) -> bool {
+        self == CoordStyle::Xy0 || self == CoordStyle::Xy1
+    }
+
+    /// Returns the pair of numbers of the point, in the written order.
+    pub fn to_pair(self, p: Point) -> (i32, i32) {
+        let (a, b) = (p.0 + self.origin(), p.1 + self.origin());
+        if self.is_xy() {
+            (b, a)
+        } else {
+            (a, b)
+        }
+    }
+
+    /// Returns the point of the pair of numbers in the written order.
+    pub fn from_pair(self, a: i32, b: i32) -> Point {
+        let (r, c) = if self.is_xy() {
+            (b, a)
+        } else {
+            (a, b)
+        };
+        Point(r - self.origin(), c - self.origin())
+    }
+
+    /// Returns the point written as `(a, b)`.
+    pub fn format(self, p: Point) -> String {
+        let (a, b) = self.to_pair(p);
+        format!("({}, {})", a, b)
+    }
+
+    /// Returns the names of the two numbers, e.g. `"R C"`, for usages.
+    pub fn names(self) -> &'static str {
+        if self.is_xy() {
+            "X Y"
+        } else {
+            "R C"
+        }
+    }
+}
+
+static COORD_STYLE: AtomicUsize = ATOMIC_USIZE_INIT;
+
+/// Sets the style of the coordinates of the commands, given by `--coords`.
+pub fn set_style(style: CoordStyle) {
+    let n = match style {
+        CoordStyle::RowColumn0 => 0,
+        CoordStyle::RowColumn1 => 1,
+        CoordStyle::Xy0 => 2,
+        CoordStyle::Xy1 => 3,
+    };
+    COORD_STYLE.store(n, Ordering::Relaxed);
+}
+
+/// Returns the style of the coordinates of the commands.
+pub fn style() -> CoordStyle {
+    match COORD_STYLE.load(Ordering::Relaxed) {
+        1 => CoordStyle::RowColumn1,
+        2 => CoordStyle::Xy0,
+        3 => CoordStyle::Xy1,
+        _ => CoordStyle::RowColumn0,
+    }
+}
+
+/// Returns the point written in the style of the commands.
+pub fn format(p: Point) -> String {
+    style().format(p)
+}
+
+/// Parses the point written as two numbers in the style of the commands.
+pub fn parse(args: &[&str]) -> Option<Point> {
+    if args.len() != 2 {
+        return None;
+    }
+    match (args[0].parse(), args[1].parse()) {
+        (Ok(a), Ok(b)) => Some(style().from_pair(a, b)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use srither_core::geom::Point;
+    use super::{CoordStyle, parse};
+
+    const STYLES: &'static [CoordStyle] = &[CoordStyle::RowColumn0,
+                                            CoordStyle::RowColumn1,
+                                            CoordStyle::Xy0,
+                                            CoordStyle::Xy1];
+
+    #[test]
+    fn styles() {
+        for (&style, name) in STYLES.iter().zip(&["rc0", "rc1", "xy0", "xy1"]) {
+            assert_eq!(Ok(style), name.parse());
+        }
+        assert_eq!(Err(()), "rc".parse::<CoordStyle>());
+        assert_eq!(Err(()), "RC0".parse::<CoordStyle>());
+
+        // The first and the last cells of a board with 18 rows and 10 columns.
+        let pairs = [[(0, 0), (17, 9)], [(1, 1), (18, 10)], [(0, 0), (9, 17)], [(1, 1), (10, 18)]];
+        for (&style, pairs) in STYLES.iter().zip(&pairs) {
+            for (&p, &(a, b)) in [Point(0, 0), Point(17, 9)].iter().zip(pairs) {
+                assert_eq!((a, b), style.to_pair(p));
+                assert_eq!(p, style.from_pair(a, b));
+                assert_eq!(format!("({}, {})", a, b), style.format(p));
+            }
+        }
+
+        // The numbers before the first cell are out of the board, which is
+        // checked by the callers.
+        assert_eq!(Point(-1, -1), CoordStyle::RowColumn1.from_pair(0, 0));
+        assert_eq!(Point(-1, 0), CoordStyle::Xy1.from_pair(1, 0));
+    }
+
+    #[test]
+    fn parse_args() {
+        // The style of the commands is left to the default.
+        assert_eq!(Some(Point(0, 0)), parse(&["0", "0"]));
+        assert_eq!(Some(Point(17, 9)), parse(&["17", "9"]));
+        assert_eq!(Some(Point(-1, 2147483647)), parse(&["-1", "2147483647"]));
+        assert_eq!(None, parse(&["2147483648", "0"]));
+        assert_eq!(None, parse(&["0"]));
+        assert_eq!(None, parse(&["0", "0", "0"]));
+        assert_eq!(None, parse(&["0", "a"]));
+        assert_eq!(None, parse(&["", "0"]));
+    }
+}
@@ -0,0 +1,56 @@
+// Copyright (c) 2016 srither developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Finds the smallest puzzles on which the solver fails.
+
+use std::io::prelude::*;
+
+use srither_core::puzzle::Puzzle;
+use srither_solver as solver;
+
+use error::AppResult;
+use io_util;
+use parse_arg::CounterexampleConfig;
+
+/// Checks the theorems of the input files, or of `input` if none, and
+/// writes the results to `out`. Returns the counterexamples found, if any,
+/// in order.
+pub fn run<I, O>(config: CounterexampleConfig,
+                 input: &mut I,
+                 out: &mut O)
+                 -> AppResult<Vec<Option<Puzzle>>>
+    where I: Read,
+          O: Write
+{
+    let mut results = vec![];
+    if config.input_files.is_empty() {
+        results.push(try!(check("<stdin>", input, out)));
+    } else {
+        for file in &config.input_files {
+            let mut f = try!(io_util::open(file));
+            results.push(try!(check(file, &mut f, out)));
+        }
+    }
+
+    Ok(results)
+}
+
+fn check<T: Read, O: Write>(name: &str, input: &mut T, out: &mut O) -> AppResult<Option<Puzzle>> {
+    let buf = try!(io_util::read_to_string(input));
+
+    let board = try!(solver::find_counterexample(&buf));
+    match board {
+        Some(ref board) => {
+            try!(writeln!(out, "{}: counterexample found", name));
+            try!(write!(out, "{}", board));
+        }
+        None => try!(writeln!(out, "{}: no counterexample found", name)),
+    }
+
+    Ok(board)
+}
@@ -0,0 +1,100 @@
+// Copyright (c) 2016 srither developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! The puzzles written in words, one statement per line, for screen readers.
+
+use std::io::prelude::*;
+
+use srither_core::geom::{Geom, Point};
+use srither_core::loop_bits::LoopBits;
+use srither_core::loop_path::{self, Loop};
+use srither_core::puzzle::{Puzzle, Side};
+
+use coord;
+use error::AppResult;
+
+/// Prints the size and the hints of the puzzle, and the loops of its lines
+/// as the runs of the edges in the same direction.
+pub fn print<W: Write>(out: &mut W, puzzle: &Puzzle) -> AppResult<()> {
+    let hints = puzzle.points()
+                      .filter_map(|p| puzzle.hint(p).map(|n| (p, n)))
+                      .collect::<Vec<_>>();
+    try!(writeln!(out,
+                  "{}, {}, {}.",
+                  plural(puzzle.row() as usize, "row"),
+                  plural(puzzle.column() as usize, "column"),
+                  plural(hints.len(), "hint")));
+    // A line per row, so that the rows can be skipped by line.
+    for r in 0..puzzle.row() {
+        let row = hints.iter()
+                       .filter(|&&(p, _)| p.0 == r)
+                       .map(|&(p, n)| format!("{} at {}", n, coord::format(p)))
+                       .collect::<Vec<_>>();
+        if !row.is_empty() {
+            try!(writeln!(out, "Hints: {}.", row.join(", ")));
+        }
+    }
+
+    let loops = match loop_path::extract(&LoopBits::from_puzzle(puzzle)) {
+        Some(loops) => loops,
+        None => {
+            try!(writeln!(out, "The lines do not form loops."));
+            return Ok(());
+        }
+    };
+    let inside = puzzle.points().filter(|&p| puzzle.side(p) == Some(Side::In)).count();
+    let edges = loops.iter().fold(0, |n, l| n + l.vertices().len());
+    try!(writeln!(out,
+                  "{}, {}, {} inside.",
+                  plural(loops.len(), "loop"),
+                  plural(edges, "edge"),
+                  plural(inside, "cell")));
+    for (i, lp) in loops.iter().enumerate() {
+        try!(writeln!(out,
+                      "Loop {} starts at the top-left corner of the cell {}, and goes {}.",
+                      i + 1,
+                      coord::format(lp.vertices()[0]),
+                      runs(lp).join(", ")));
+    }
+    Ok(())
+}
+
+// Returns the runs of the edges of the loop in the same direction, e.g.
+// `right 3`.
+fn runs(lp: &Loop) -> Vec<String> {
+    let vertices = lp.vertices();
+    let n = vertices.len();
+    let mut runs: Vec<(&'static str, u32)> = vec![];
+    for i in 0..n {
+        let dir = direction(vertices[i], vertices[(i + 1) % n]);
+        if let Some(last) = runs.last_mut() {
+            if last.0 == dir {
+                last.1 += 1;
+                continue;
+            }
+        }
+        runs.push((dir, 1));
+    }
+    runs.into_iter().map(|(dir, len)| format!("{} {}", dir, len)).collect()
+}
+
+fn direction(p: Point, q: Point) -> &'static str {
+    match (q.0 - p.0, q.1 - p.1) {
+        (-1, 0) => "up",
+        (1, 0) => "down",
+        (0, -1) => "left",
+        _ => "right",
+    }
+}
+
+fn plural(n: usize, noun: &str) -> String {
+    match n {
+        1 => format!("1 {}", noun),
+        _ => format!("{} {}s", n, noun),
+    }
+}
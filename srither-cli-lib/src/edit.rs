@@ -0,0 +1,303 @@
+// Copyright (c) 2016 srither developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Edits the hints of a puzzle interactively, reporting whether its solution
+//! is unique as the hints change.
+//!
+//! The solutions are counted by a worker thread, so the commands are answered
+//! while it counts, and `status` waits for the count of the board. The worker
+//! waits for the edits to pause before counting, and gives up a count as soon
+//! as the board changes under it.
+
+use std::fmt;
+use std::fs::File;
+use std::io::prelude::*;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+use srither_core::geom::{Geom, Point};
+use srither_core::puzzle::Puzzle;
+use srither_solver::{self as solver, ErrorKind, SolveOptions};
+
+use coord;
+use error::AppResult;
+use io_util;
+use parse_arg::EditConfig;
+use pprint::{self, Config as PpConfig, Mode as PpMode};
+use theme::Theme;
+
+// `R C` is replaced by the names of the coordinates in the style given by
+// `--coords`.
+const HELP: &'static str = "\
+commands:
+  hint R C N        put the hint N (0-4) in the cell
+  clear R C         remove the hint of the cell
+  undo              undo the last change
+  show              print the board
+  status            print whether the solution is unique, waiting for the count
+  save FILE         write the puzzle to FILE
+  help              print this message
+  quit              exit";
+
+// Whether the solution of the board is unique.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Status {
+    Checking,
+    NoSolution,
+    Unique,
+    Multiple,
+    Failed(String),
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Status::Checking => write!(f, "checking..."),
+            Status::NoSolution => write!(f, "no solution"),
+            Status::Unique => write!(f, "unique solution"),
+            Status::Multiple => write!(f, "multiple solutions"),
+            Status::Failed(ref e) => write!(f, "cannot be checked: {}", e),
+        }
+    }
+}
+
+// The boards sent to the worker and the statuses sent back are numbered by
+// the generation, which is the number of the boards sent. A count is given
+// up when a newer board is sent.
+struct Checker {
+    boards: Sender<(usize, Puzzle)>,
+    statuses: Receiver<(usize, Status)>,
+    latest: Arc<AtomicUsize>,
+    generation: usize,
+    status: Status,
+}
+
+impl Checker {
+    fn new(debounce: Duration) -> Checker {
+        let (boards, board_rx) = mpsc::channel();
+        let (status_tx, statuses) = mpsc::channel();
+        let latest = Arc::new(AtomicUsize::new(0));
+        let worker_latest = latest.clone();
+        let _ = thread::spawn(move || {
+            count_solutions(board_rx, status_tx, worker_latest, debounce)
+        });
+        Checker {
+            boards: boards,
+            statuses: statuses,
+            latest: latest,
+            generation: 0,
+            status: Status::Checking,
+        }
+    }
+
+    // Starts counting the solutions of the board, giving up the count of the
+    // previous one.
+    fn check(&mut self, puzzle: &Puzzle) {
+        self.generation += 1;
+        self.latest.store(self.generation, Ordering::SeqCst);
+        self.status = Status::Checking;
+        if self.boards.send((self.generation, puzzle.clone())).is_err() {
+            self.status = Status::Failed("the worker has stopped".to_owned());
+        }
+    }
+
+    // Returns the status of the board last sent, waiting for its count.
+    fn wait(&mut self) -> &Status {
+        while self.status == Status::Checking {
+            match self.statuses.recv() {
+                Ok((generation, status)) => {
+                    if generation == self.generation {
+                        self.status = status;
+                    }
+                }
+                Err(_) => self.status = Status::Failed("the worker has stopped".to_owned()),
+            }
+        }
+        &self.status
+    }
+}
+
+impl Drop for Checker {
+    fn drop(&mut self) {
+        // No board has the generation 0, so the count going on is given up.
+        self.latest.store(0, Ordering::SeqCst);
+    }
+}
+
+// Counts the solutions of the boards received up to two, until the channel
+// is closed.
+fn count_solutions(boards: Receiver<(usize, Puzzle)>,
+                   statuses: Sender<(usize, Status)>,
+                   latest: Arc<AtomicUsize>,
+                   debounce: Duration) {
+    while let Ok(mut board) = boards.recv() {
+        // Only the last board is counted after the edits pause.
+        loop {
+            thread::sleep(debounce);
+            let mut newer = false;
+            while let Ok(b) = boards.try_recv() {
+                board = b;
+                newer = true;
+            }
+            if !newer {
+                break;
+            }
+        }
+
+        let (generation, puzzle) = board;
+        let stop = || latest.load(Ordering::SeqCst) != generation;
+        // Two solutions are enough to tell that the solution is not unique.
+        let mut count = 0;
+        let result = solver::for_each_solution_until(&puzzle, &SolveOptions::default(), stop, |_| {
+            count += 1;
+            count < 2
+        });
+        let status = match result {
+            Ok(None) => continue,
+            Ok(Some(0)) => Status::NoSolution,
+            Ok(Some(1)) => Status::Unique,
+            Ok(Some(_)) => Status::Multiple,
+            Err(ref e) if e.kind() == ErrorKind::InvalidBoard => Status::NoSolution,
+            Err(e) => Status::Failed(e.to_string()),
+        };
+        if statuses.send((generation, status)).is_err() {
+            return;
+        }
+    }
+}
+
+/// Reads the commands from `input`, and writes the board and the statuses of
+/// its solution to `out`. Returns the puzzle at the end, which is written to
+/// the output file of the configuration if any.
+pub fn run<I, O>(config: EditConfig, input: &mut I, out: &mut O) -> AppResult<Puzzle>
+    where I: BufRead,
+          O: Write
+{
+    let mut puzzle = match config.input_file {
+        Some(ref file) => {
+            let buf = try!(io_util::read_file(file));
+            try!(Puzzle::parse_any(&buf)).0.hints_only()
+        }
+        None => Puzzle::new(config.size),
+    };
+    let mut history: Vec<Puzzle> = vec![];
+    let mut checker = Checker::new(Duration::from_millis(config.debounce_ms));
+    checker.check(&puzzle);
+
+    try!(show(out, &puzzle));
+    try!(prompt(out));
+    for line in input.lines() {
+        let line = try!(line);
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        let mut changed = None;
+        match words.get(0).cloned() {
+            Some("hint") => {
+                let hint = match words.get(3).cloned() {
+                    Some(n) if n.len() == 1 && n >= "0" && n <= "4" => n.parse::<u8>().ok(),
+                    _ => None,
+                };
+                let p = if words.len() == 4 {
+                    parse_point(&puzzle, &words[1..3])
+                } else {
+                    None
+                };
+                match (p, hint) {
+                    (Some(p), Some(hint)) => changed = Some((p, Some(hint))),
+                    _ => try!(writeln!(out, "usage: hint {} 0-4", coord::style().names())),
+                }
+            }
+            Some("clear") => {
+                match parse_point(&puzzle, &words[1..]) {
+                    Some(p) => changed = Some((p, None)),
+                    None => try!(writeln!(out, "usage: clear {}", coord::style().names())),
+                }
+            }
+            Some("undo") => {
+                match history.pop() {
+                    Some(prev) => {
+                        puzzle = prev;
+                        checker.check(&puzzle);
+                        try!(show(out, &puzzle));
+                    }
+                    None => try!(writeln!(out, "no change to undo")),
+                }
+            }
+            Some("show") => try!(show(out, &puzzle)),
+            Some("status") => try!(writeln!(out, "status: {}", checker.wait())),
+            Some("save") => {
+                match words.get(1) {
+                    Some(path) if words.len() == 2 => {
+                        try!(save(path, &puzzle));
+                        try!(writeln!(out, "saved to {}", path));
+                    }
+                    _ => try!(writeln!(out, "usage: save FILE")),
+                }
+            }
+            Some("help") => try!(writeln!(out, "{}", HELP.replace("R C", coord::style().names()))),
+            Some("quit") | Some("exit") => break,
+            Some(cmd) => {
+                try!(writeln!(out, "unknown command `{}` (type `help` for commands)", cmd))
+            }
+            None => {}
+        }
+        if let Some((p, hint)) = changed {
+            if puzzle.hint(p) == hint {
+                try!(writeln!(out, "no change"));
+            } else {
+                history.push(puzzle.clone());
+                puzzle.set_hint(p, hint);
+                checker.check(&puzzle);
+                try!(show(out, &puzzle));
+            }
+        }
+        try!(prompt(out));
+    }
+
+    if let Some(ref path) = config.output_file {
+        try!(save(path, &puzzle));
+    }
+    Ok(puzzle)
+}
+
+fn save(path: &str, puzzle: &Puzzle) -> AppResult<()> {
+    let mut f = try!(File::create(path));
+    try!(write!(f, "{}", puzzle));
+    Ok(())
+}
+
+fn show<O: Write>(out: &mut O, puzzle: &Puzzle) -> AppResult<()> {
+    let mode = if pprint::is_pprintable() {
+        PpMode::Color
+    } else {
+        PpMode::Ascii
+    };
+    let conf = PpConfig {
+        mode: mode,
+        cell_width: 2,
+        cell_height: 1,
+        theme: Theme::default(),
+    };
+    pprint::print(out, &conf, puzzle)
+}
+
+fn prompt<O: Write>(out: &mut O) -> AppResult<()> {
+    try!(write!(out, "> "));
+    try!(out.flush());
+    Ok(())
+}
+
+fn parse_point(puzzle: &Puzzle, args: &[&str]) -> Option<Point> {
+    coord::parse(args).and_then(|p| if puzzle.contains(p) {
+        Some(p)
+    } else {
+        None
+    })
+}
@@ -0,0 +1,255 @@
+// Copyright (c) 2016 srither developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! The versioned envelope of the files written by the commands.
+//!
+//! Each file starts with a line of its kind, the version of its format and
+//! the version of srither writing it, e.g. `# srither pack v1 (srither
+//! 0.1.0)`. The line is a comment without `:`, so the packs with it are still
+//! read as packs by any version. The files written before the envelope have
+//! only `# srither` and the kind on the line, if any, and are of the format
+//! `v1`.
+//!
+//! The loaders read the formats from the oldest of the kind to the current
+//! one, migrating the older ones, and fail on the others with the versions
+//! of srither to read them.
+
+use std::io;
+
+use error::AppResult;
+
+/// A kind of the files, with the versions of its format read.
+#[derive(Copy, Clone, Debug)]
+pub struct Kind {
+    /// The name of the kind, following `# srither` on the line.
+    pub name: &'static str,
+    /// The format written by this version.
+    pub format: u32,
+    /// The oldest format read by this version.
+    pub oldest: u32,
+}
+
+/// The packs of puzzles written by the commands.
+pub const PACK: Kind = Kind {
+    name: "pack",
+    format: 1,
+    oldest: 1,
+};
+/// The files of the solution cache.
+pub const CACHE: Kind = Kind {
+    name: "cache",
+    format: 1,
+    oldest: 1,
+};
+/// The boards after each pass written by `checkpoint`.
+pub const CHECKPOINTS: Kind = Kind {
+    name: "checkpoints",
+    format: 2,
+    oldest: 1,
+};
+/// The manifests of the packs written by `pack --manifest`.
+pub const MANIFEST: Kind = Kind {
+    name: "pack manifest",
+    format: 2,
+    oldest: 1,
+};
+/// The logs of the changes saved by `play`.
+pub const PLAY_LOG: Kind = Kind {
+    name: "play log",
+    format: 2,
+    oldest: 1,
+};
+/// The scores of the calibration of `rate`.
+pub const CALIBRATION: Kind = Kind {
+    name: "calibration",
+    format: 2,
+    oldest: 1,
+};
+/// The states of the search saved by `solve --resume-file`.
+pub const DEEPENING: Kind = Kind {
+    name: "deepening",
+    format: 1,
+    oldest: 1,
+};
+/// The history of the runs.
+pub const HISTORY: Kind = Kind {
+    name: "history",
+    format: 1,
+    oldest: 1,
+};
+
+/// The envelope read from a file.
+#[derive(Clone, Debug)]
+pub struct Envelope {
+    /// The format of the file.
+    pub format: u32,
+    /// The version of srither writing the file, unknown for the files written
+    /// before the envelope.
+    pub version: Option<String>,
+}
+
+/// The line of the envelope of the kind written by this version.
+pub fn header(kind: &Kind) -> String {
+    format!("# srither {} v{} (srither {})",
+            kind.name,
+            kind.format,
+            env!("CARGO_PKG_VERSION"))
+}
+
+/// Parses the line as the envelope of the kind, or returns `None` if it is
+/// not.
+pub fn parse(kind: &Kind, line: &str) -> Option<Envelope> {
+    let prefix = format!("# srither {}", kind.name);
+    if !line.starts_with(&prefix) {
+        return None;
+    }
+    let rest = line[prefix.len()..].trim();
+    if rest.is_empty() {
+        return Some(Envelope {
+            format: 1,
+            version: None,
+        });
+    }
+    if !rest.starts_with('v') {
+        return None;
+    }
+    let (format, version) = match rest.find(' ') {
+        Some(i) => (&rest[1..i], Some(rest[i..].trim())),
+        None => (&rest[1..], None),
+    };
+    let format = match format.parse() {
+        Ok(format) => format,
+        Err(_) => return None,
+    };
+    let version = match version {
+        Some(v) if v.starts_with("(srither ") && v.ends_with(')') => {
+            Some(v["(srither ".len()..v.len() - 1].trim().to_owned())
+        }
+        Some(_) => return None,
+        None => None,
+    };
+    Some(Envelope {
+        format: format,
+        version: version,
+    })
+}
+
+/// Checks that this version reads the format of the envelope.
+pub fn check(kind: &Kind, envelope: &Envelope) -> AppResult<()> {
+    let written = match envelope.version {
+        Some(ref version) => format!("srither {}", version),
+        None => "an unknown version of srither".to_owned(),
+    };
+    let msg = if envelope.format > kind.format {
+        format!("the {} file is written by a newer version ({}) in format v{}, and srither {} \
+                 reads up to v{}; upgrade srither to read it",
+                kind.name,
+                written,
+                envelope.format,
+                env!("CARGO_PKG_VERSION"),
+                kind.format)
+    } else if envelope.format < kind.oldest {
+        format!("the {} file is written by an older version ({}) in format v{}, and srither {} \
+                 reads v{} to v{}; write it again with this version",
+                kind.name,
+                written,
+                envelope.format,
+                env!("CARGO_PKG_VERSION"),
+                kind.oldest,
+                kind.format)
+    } else {
+        return Ok(());
+    };
+    Err(io::Error::new(io::ErrorKind::InvalidData, msg).into())
+}
+
+/// Reads and checks the envelope of the kind on the first line of the text,
+/// if any, for the kinds also written without the envelope.
+pub fn check_text(kind: &Kind, text: &str) -> AppResult<Option<Envelope>> {
+    let first = text.lines().map(|l| l.trim()).find(|l| !l.is_empty());
+    match first.and_then(|line| parse(kind, line)) {
+        Some(envelope) => {
+            try!(check(kind, &envelope));
+            Ok(Some(envelope))
+        }
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CHECKPOINTS, Envelope, Kind, MANIFEST, PACK, check, check_text, header, parse};
+
+    // A kind no more reading its first format.
+    const NEWER: Kind = Kind {
+        name: "pack",
+        format: 3,
+        oldest: 2,
+    };
+
+    fn check_parse(kind: &Kind, line: &str, format: u32, version: Option<&str>) {
+        let envelope = parse(kind, line).unwrap();
+        assert_eq!(format, envelope.format);
+        assert_eq!(version, envelope.version.as_ref().map(|v| &v[..]));
+    }
+
+    fn envelope(format: u32, version: Option<&str>) -> Envelope {
+        Envelope {
+            format: format,
+            version: version.map(|v| v.to_owned()),
+        }
+    }
+
+    #[test]
+    fn parse_line() {
+        let line = header(&CHECKPOINTS);
+        check_parse(&CHECKPOINTS, &line, 2, Some(env!("CARGO_PKG_VERSION")));
+        check_parse(&PACK, "# srither pack v1 (srither 0.1.0)", 1, Some("0.1.0"));
+        check_parse(&PACK, "# srither pack v12", 12, None);
+        // The files written before the envelope.
+        check_parse(&PACK, "# srither pack", 1, None);
+        check_parse(&MANIFEST, "# srither pack manifest ", 1, None);
+
+        // The other kinds and the broken lines.
+        assert!(parse(&PACK, "# srither pack manifest v2").is_none());
+        assert!(parse(&MANIFEST, "# srither pack v1").is_none());
+        assert!(parse(&PACK, "# puzzle").is_none());
+        assert!(parse(&PACK, "# srither pack 1").is_none());
+        assert!(parse(&PACK, "# srither pack vx").is_none());
+        assert!(parse(&PACK, "# srither pack v-1").is_none());
+        assert!(parse(&PACK, "# srither pack v1 srither 0.1.0").is_none());
+        assert!(parse(&PACK, "# srither pack v1 (srither 0.1.0").is_none());
+    }
+
+    #[test]
+    fn check_format() {
+        assert!(check(&CHECKPOINTS, &envelope(1, None)).is_ok());
+        assert!(check(&CHECKPOINTS, &envelope(2, Some("0.1.0"))).is_ok());
+        assert_eq!(format!("IO error: the checkpoints file is written by a newer version \
+                            (srither 9.0.0) in format v3, and srither {} reads up to v2; \
+                            upgrade srither to read it",
+                           env!("CARGO_PKG_VERSION")),
+                   check(&CHECKPOINTS, &envelope(3, Some("9.0.0"))).unwrap_err().to_string());
+        assert_eq!(format!("IO error: the pack file is written by an older version (an \
+                            unknown version of srither) in format v1, and srither {} reads \
+                            v2 to v3; write it again with this version",
+                           env!("CARGO_PKG_VERSION")),
+                   check(&NEWER, &envelope(1, None)).unwrap_err().to_string());
+    }
+
+    #[test]
+    fn text() {
+        assert!(check_text(&PACK, "").unwrap().is_none());
+        assert!(check_text(&PACK, "# puzzle\n# srither pack v2\n").unwrap().is_none());
+        let envelope = check_text(&PACK, "\n  # srither pack v1 (srither 0.1.0)\n").unwrap();
+        assert_eq!(Some(1), envelope.map(|e| e.format));
+        assert!(check_text(&PACK, "# srither pack v2\n").is_err());
+        assert!(check_text(&NEWER, "# srither pack\n").is_err());
+        assert!(check_text(&NEWER, &header(&NEWER)).is_ok());
+    }
+}
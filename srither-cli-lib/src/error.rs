@@ -10,15 +10,31 @@ use std::{fmt, io};
 use std::error::Error;
 use term;
 
+use srither_core::format::ParseFormatError;
 use srither_core::puzzle::ParsePuzzleError;
-use srither_solver as solver;
+use srither_core::puzzle_set::ParsePuzzleSetError;
+use srither_gen as gen;
+use srither_solver::{self as solver, ParseStrategyError};
 
+/// An error of a command.
 #[derive(Debug)]
 pub enum AppError {
+    /// An I/O error.
     Io(io::Error),
+    /// An error of the terminal.
     Term(term::Error),
+    /// An invalid puzzle.
     ParsePuzzle(ParsePuzzleError),
+    /// An invalid puzzle in a format.
+    ParseFormat(ParseFormatError),
+    /// An invalid pack.
+    ParsePuzzleSet(ParsePuzzleSetError),
+    /// An invalid strategy of the solver.
+    ParseStrategy(ParseStrategyError),
+    /// An error of the solver.
     Solver(solver::Error),
+    /// An error of the generator.
+    Gen(gen::Error),
 }
 
 impl From<io::Error> for AppError {
@@ -39,19 +55,47 @@ impl From<ParsePuzzleError> for AppError {
     }
 }
 
+impl From<ParseFormatError> for AppError {
+    fn from(err: ParseFormatError) -> AppError {
+        AppError::ParseFormat(err)
+    }
+}
+
+impl From<ParsePuzzleSetError> for AppError {
+    fn from(err: ParsePuzzleSetError) -> AppError {
+        AppError::ParsePuzzleSet(err)
+    }
+}
+
+impl From<ParseStrategyError> for AppError {
+    fn from(err: ParseStrategyError) -> AppError {
+        AppError::ParseStrategy(err)
+    }
+}
+
 impl From<solver::Error> for AppError {
     fn from(err: solver::Error) -> AppError {
         AppError::Solver(err)
     }
 }
 
+impl From<gen::Error> for AppError {
+    fn from(err: gen::Error) -> AppError {
+        AppError::Gen(err)
+    }
+}
+
 impl Error for AppError {
     fn description(&self) -> &str {
         match *self {
             AppError::Io(ref e) => e.description(),
             AppError::Term(ref e) => e.description(),
             AppError::ParsePuzzle(ref e) => e.description(),
+            AppError::ParseFormat(ref e) => e.description(),
+            AppError::ParsePuzzleSet(ref e) => e.description(),
+            AppError::ParseStrategy(ref e) => e.description(),
             AppError::Solver(ref e) => e.description(),
+            AppError::Gen(ref e) => e.description(),
         }
     }
     fn cause(&self) -> Option<&Error> {
@@ -59,7 +103,11 @@ impl Error for AppError {
             AppError::Io(ref e) => Some(e),
             AppError::Term(ref e) => Some(e),
             AppError::ParsePuzzle(ref e) => Some(e),
+            AppError::ParseFormat(ref e) => Some(e),
+            AppError::ParsePuzzleSet(ref e) => Some(e),
+            AppError::ParseStrategy(ref e) => Some(e),
             AppError::Solver(ref e) => Some(e),
+            AppError::Gen(ref e) => Some(e),
         }
     }
 }
@@ -70,9 +118,14 @@ impl fmt::Display for AppError {
             AppError::Io(ref e) => write!(f, "IO error: {}", e),
             AppError::Term(ref e) => write!(f, "terminal error: {}", e),
             AppError::ParsePuzzle(ref e) => write!(f, "parse puzzle error: {}", e),
+            AppError::ParseFormat(ref e) => write!(f, "parse puzzle error: {}", e),
+            AppError::ParsePuzzleSet(ref e) => write!(f, "parse puzzle set error: {}", e),
+            AppError::ParseStrategy(ref e) => write!(f, "parse strategy error: {}", e),
             AppError::Solver(ref e) => write!(f, "solver error: {}", e),
+            AppError::Gen(ref e) => write!(f, "generator error: {}", e),
         }
     }
 }
 
+/// The result of a command.
 pub type AppResult<T> = Result<T, AppError>;
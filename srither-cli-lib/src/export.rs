@@ -0,0 +1,107 @@
+// Copyright (c) 2016 srither developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Exports the packs as printable documents.
+
+use std::io;
+use std::fs::File;
+use std::io::prelude::*;
+
+use srither_core::geom::Geom;
+use srither_core::puzzle::Puzzle;
+use srither_core::puzzle_set::PuzzleSet;
+use srither_solver as solver;
+
+use error::AppResult;
+use history;
+use io_util;
+use layout::Layout;
+use pack;
+use parse_arg::{ExportConfig, ExportFormat};
+use pdf::{self, Page};
+
+// The margin of a page, and the height of its heading, in points.
+const MARGIN: f64 = 36.0;
+const HEADING: f64 = 28.0;
+// The height of the label above each board.
+const LABEL: f64 = 14.0;
+// The largest cell, about 1 cm, for a few small puzzles on a page.
+const MAX_CELL: f64 = 28.0;
+
+/// Exports the packs of the input files, or of `input` if none, and writes
+/// the document to the output file, or to `out` if none. Returns the
+/// document.
+pub fn run<I, O>(config: ExportConfig, input: &mut I, out: &mut O) -> AppResult<Vec<u8>>
+    where I: Read,
+          O: Write
+{
+    let mut set = PuzzleSet::new();
+    if config.input_files.is_empty() {
+        set.extend(try!(pack::read(input)).into_entries());
+    } else {
+        for file in &config.input_files {
+            let mut f = try!(io_util::open(file));
+            set.extend(try!(pack::read(&mut f)).into_entries());
+        }
+    }
+    if set.is_empty() {
+        let msg = "no puzzles found to export";
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, msg).into());
+    }
+
+    let puzzles = set.entries().iter().map(|e| e.puzzle.hints_only()).collect::<Vec<_>>();
+    let mut answers = vec![];
+    for puzzle in &puzzles {
+        history::note(puzzle);
+        answers.push(try!(solver::solve(puzzle)));
+    }
+
+    let output = match config.format {
+        ExportFormat::Pdf => {
+            let mut pages = sheets("Puzzles", &puzzles, config.per_page);
+            pages.extend(sheets("Answers", &answers, config.per_page));
+            pdf::encode(&pages)
+        }
+    };
+    match config.output_file {
+        Some(ref path) => try!(try!(File::create(path)).write_all(&output)),
+        None => try!(out.write_all(&output)),
+    }
+    Ok(output)
+}
+
+/// Lays out the puzzles on the pages of A4 in a grid of `per_page` slots,
+/// each board labeled by its number and scaled to fit its slot.
+fn sheets(heading: &str, puzzles: &[Puzzle], per_page: usize) -> Vec<Page> {
+    let columns = (per_page as f64).sqrt().ceil() as usize;
+    let rows = (per_page + columns - 1) / columns;
+    let slot_w = (pdf::A4.0 - 2.0 * MARGIN) / columns as f64;
+    let slot_h = (pdf::A4.1 - 2.0 * MARGIN - HEADING) / rows as f64;
+
+    let mut pages = vec![];
+    for (n, chunk) in puzzles.chunks(per_page).enumerate() {
+        let mut page = Page::new(pdf::A4);
+        page.text(heading, (MARGIN, MARGIN + HEADING / 2.0), 16.0);
+        for (i, puzzle) in chunk.iter().enumerate() {
+            let x = MARGIN + (i % columns) as f64 * slot_w;
+            let y = MARGIN + HEADING + (i / columns) as f64 * slot_h;
+            // A board of the cell size is one cell larger than the puzzle,
+            // for the margin of half a cell on each side.
+            let cell = (slot_w / (puzzle.column() + 1) as f64)
+                           .min((slot_h - LABEL) / (puzzle.row() + 1) as f64)
+                           .min(MAX_CELL);
+            let layout = Layout::new(puzzle, cell);
+            let left = x + (slot_w - layout.width) / 2.0;
+            let label = format!("{}", n * per_page + i + 1);
+            page.text(&label, (left + cell / 2.0, y + LABEL - 4.0), 10.0);
+            page.draw(&layout, (left, y + LABEL));
+        }
+        pages.push(page);
+    }
+    pages
+}
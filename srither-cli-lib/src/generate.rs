@@ -0,0 +1,308 @@
+// Copyright (c) 2016 srither developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Generates the puzzles.
+
+use std::io;
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::path::Path;
+use rand::{OsRng, Rng};
+use time;
+
+use srither_core::geom::{Geom, Size};
+use srither_core::puzzle::Puzzle;
+use srither_core::puzzle_set::{Entry, PuzzleSet};
+use srither_gen::{Difficulty, Generator, HintMask, Symmetry, random_loop, seeded_rng};
+use srither_solver::{self as solver, SolveOptions, Solutions};
+
+use error::AppResult;
+use history;
+use io_util;
+use pack;
+use parse_arg::GenerateConfig;
+use size_spec;
+
+/// Generates the puzzles, and writes the pack to `out`. Returns the pack, the
+/// answers with `--book`, or an empty one with `--dry-run`, which writes the
+/// plan instead.
+pub fn run<O: Write>(config: GenerateConfig, out: &mut O) -> AppResult<PuzzleSet> {
+    let mask = match config.mask_file {
+        Some(ref path) => {
+            let buf = try!(io_util::read_file(path));
+            Some(try!(buf.parse::<HintMask>()))
+        }
+        None => None,
+    };
+    let size = match (config.size, mask.as_ref().map(|m| m.size())) {
+        (Some(size), Some(mask_size)) if size != mask_size => {
+            let msg = "the size differs from that of the mask";
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, msg).into());
+        }
+        (Some(size), _) | (None, Some(size)) => size,
+        (None, None) => Size(10, 10),
+    };
+
+    let mut gen = Generator::new(size);
+    gen.set_hint_density(config.hint_density);
+    gen.set_loop_length(config.loop_length.0, config.loop_length.1);
+    gen.set_symmetry(config.symmetry);
+    gen.set_difficulty(config.difficulty);
+    gen.set_mask(mask);
+    gen.set_forbidden_layouts(config.forbidden_layouts.clone());
+
+    // Each puzzle is generated from its own seed, recorded in the metadata,
+    // so that any of them can be generated again alone.
+    let first = match config.seed {
+        Some(seed) => seed,
+        None => try!(OsRng::new()).next_u64(),
+    };
+
+    if config.dry_run {
+        try!(plan(out, &config, &mut gen, first));
+        return Ok(PuzzleSet::new());
+    }
+    if let Some(ref dir) = config.book_dir {
+        return book(out, &config, &mut gen, first, Path::new(dir));
+    }
+
+    let mut set = PuzzleSet::new();
+    for i in 0..config.count {
+        set.push(try!(generate(&config, &gen, first.wrapping_add(i))));
+    }
+    try!(pack::write(out, &set));
+    Ok(set)
+}
+
+fn generate(config: &GenerateConfig, gen: &Generator, seed: u64) -> AppResult<Entry> {
+    let mut entry = Entry::new(try!(gen.generate(&mut seeded_rng(seed))));
+    history::note(&entry.puzzle);
+    entry.metadata.set("seed", &seed.to_string());
+    entry.metadata.set("size", &size_spec::to_string(gen.size()));
+    if config.hint_density > 0.0 {
+        entry.metadata.set("hint-density", &config.hint_density.to_string());
+    }
+    if config.loop_length != (0.0, 1.0) {
+        let (min, max) = config.loop_length;
+        entry.metadata.set("loop-length", &format!("{} to {}", min, max));
+    }
+    if config.symmetry != Symmetry::None {
+        entry.metadata.set("symmetry", config.symmetry.name());
+    }
+    if let Some(difficulty) = gen.difficulty() {
+        entry.metadata.set("difficulty", difficulty.name());
+    }
+    if let Some(ref path) = config.mask_file {
+        entry.metadata.set("mask", path);
+    }
+    if let Some(ref layouts) = config.forbid {
+        entry.metadata.set("forbid", layouts);
+    }
+    Ok(entry)
+}
+
+// Generates the puzzles of the difficulties ramping from easy to expert in
+// equal parts, and writes them in the order of the difficulties and the
+// scores of `rate`, so that the puzzles also ramp in a difficulty.
+fn book<O: Write>(out: &mut O,
+                  config: &GenerateConfig,
+                  gen: &mut Generator,
+                  first: u64,
+                  dir: &Path)
+                  -> AppResult<PuzzleSet> {
+    let levels = Difficulty::all();
+    let mut puzzles = vec![];
+    for i in 0..config.count {
+        let level = levels[(i * levels.len() as u64 / config.count) as usize];
+        gen.set_difficulty(Some(level));
+        let mut entry = try!(generate(config, gen, first.wrapping_add(i)));
+        let (solution, stats) = try!(solver::solve_with_stats(&entry.puzzle,
+                                                              &SolveOptions::default()));
+        entry.metadata.set("score", &format!("{:.1}", stats.score()));
+        puzzles.push((level, stats.score(), entry, solution));
+    }
+    puzzles.sort_by(|a, b| (a.0, a.1).partial_cmp(&(b.0, b.1)).unwrap());
+
+    try!(fs::create_dir_all(dir));
+    let width = config.count.to_string().len();
+    let mut answers = PuzzleSet::new();
+    for (i, (_, _, entry, solution)) in puzzles.into_iter().enumerate() {
+        let name = format!("{:01$}", i + 1, width);
+        let mut f = try!(File::create(dir.join(format!("{}.txt", name))));
+        try!(write!(f, "{}", entry.puzzle));
+
+        let mut answer = Entry::new(solution);
+        answer.metadata.set("title", &name);
+        for &(ref key, ref value) in entry.metadata.iter() {
+            answer.metadata.set(key, value);
+        }
+        answers.push(answer);
+    }
+    let mut f = try!(File::create(dir.join("answers.txt")));
+    try!(pack::write(&mut f, &answers));
+
+    try!(writeln!(out,
+                  "{} puzzles and the answers written to {}",
+                  answers.len(),
+                  dir.display()));
+    Ok(answers)
+}
+
+// The loops timed to estimate the time of a solver call.
+const CALIBRATION_SAMPLES: u64 = 3;
+
+// Prints the plan of the generation with the estimated time, without
+// generating the puzzles. The numbers of the solver calls are given by the
+// generator, and their time is measured on the sample puzzles of the size.
+fn plan<O: Write>(out: &mut O,
+                  config: &GenerateConfig,
+                  gen: &mut Generator,
+                  first: u64)
+                  -> AppResult<()> {
+    // The puzzles of a book are of the difficulties, and rated once more.
+    let book = config.book_dir.is_some();
+    if book {
+        gen.set_difficulty(Some(Difficulty::Easy));
+    }
+    let workload = gen.workload();
+    let (unique_ns, rating_ns) = try!(calibrate(gen, first, workload.difficulty_checks > 0));
+
+    let per_loop = workload.unique_checks as f64 * unique_ns +
+                   workload.difficulty_checks as f64 * rating_ns;
+    let extra = if book {
+        rating_ns
+    } else {
+        0.0
+    };
+    let min = workload.min_attempts as f64 * per_loop + extra;
+    let max = workload.max_attempts as f64 * per_loop + extra;
+    let count = config.count as f64;
+
+    try!(writeln!(out,
+                  "generate {} puzzles of {}, seeds {} to {}",
+                  config.count,
+                  size_spec::to_string(gen.size()),
+                  first,
+                  first.wrapping_add(config.count.saturating_sub(1))));
+    let difficulty = if book {
+        "easy to expert"
+    } else {
+        gen.difficulty().map_or("any", |d| d.name())
+    };
+    try!(writeln!(out,
+                  "  symmetry: {}, hint density: {}, difficulty: {}",
+                  config.symmetry.name(),
+                  config.hint_density,
+                  difficulty));
+    if config.loop_length != (0.0, 1.0) {
+        // The loops out of the range are drawn again, which the solver calls
+        // do not show.
+        try!(writeln!(out,
+                      "  loop length: {} to {} of the longest",
+                      config.loop_length.0,
+                      config.loop_length.1));
+    }
+    if let Some(ref path) = config.mask_file {
+        try!(writeln!(out, "  mask: {}", path));
+    }
+    if let Some(ref layouts) = config.forbid {
+        try!(writeln!(out, "  forbidden layouts: {}", layouts));
+    }
+    if let Some(ref dir) = config.book_dir {
+        try!(writeln!(out, "  book: {}", dir));
+    }
+    try!(writeln!(out,
+                  "  solver calls per loop: {} uniqueness checks, {} difficulty ratings at most",
+                  workload.unique_checks,
+                  workload.difficulty_checks));
+    try!(writeln!(out,
+                  "  loops per puzzle: {} to {}",
+                  workload.min_attempts,
+                  workload.max_attempts));
+    if workload.difficulty_checks > 0 {
+        try!(writeln!(out,
+                      "  calibration: {} per uniqueness check, {} per rating, by {} sample loops",
+                      format_ns(unique_ns),
+                      format_ns(rating_ns),
+                      CALIBRATION_SAMPLES));
+    } else {
+        try!(writeln!(out,
+                      "  calibration: {} per uniqueness check, by {} sample loops",
+                      format_ns(unique_ns),
+                      CALIBRATION_SAMPLES));
+    }
+    try!(writeln!(out,
+                  "  estimated time: {} to {} per puzzle, {} to {} in total",
+                  format_time(min),
+                  format_time(max),
+                  format_time(min * count),
+                  format_time(max * count)));
+    Ok(())
+}
+
+// Returns the mean time in nanoseconds of a uniqueness check and a rating of
+// the difficulty, measured on the random loops with the half of the hints, as
+// the hints are removed from all to the fewest in the generation.
+fn calibrate(gen: &Generator, first: u64, rating: bool) -> AppResult<(f64, f64)> {
+    let options = SolveOptions { max_solutions: Some(2), ..SolveOptions::default() };
+    let mut unique_ns = 0;
+    let mut rating_ns = 0;
+    for i in 0..CALIBRATION_SAMPLES {
+        let mut rng = seeded_rng(first.wrapping_add(i));
+        let puzzle = sample(gen, &mut rng);
+
+        let start = time::precise_time_ns();
+        for solution in try!(Solutions::with_options(&puzzle, &options)) {
+            let _ = solution;
+        }
+        unique_ns += time::precise_time_ns() - start;
+
+        if rating {
+            let start = time::precise_time_ns();
+            let _ = try!(Difficulty::of(&puzzle));
+            rating_ns += time::precise_time_ns() - start;
+        }
+    }
+    let n = CALIBRATION_SAMPLES as f64;
+    Ok((unique_ns as f64 / n, rating_ns as f64 / n))
+}
+
+fn sample<R>(gen: &Generator, rng: &mut R) -> Puzzle
+    where R: Rng
+{
+    let mut puzzle = random_loop(rng, gen.size()).hints_only();
+    let mut points = vec![];
+    for p in puzzle.points() {
+        if gen.mask().map_or(true, |m| m.allows(p)) {
+            points.push(p);
+        } else {
+            puzzle.set_hint(p, None);
+        }
+    }
+    rng.shuffle(&mut points);
+    for &p in &points[..points.len() / 2] {
+        puzzle.set_hint(p, None);
+    }
+    puzzle
+}
+
+fn format_ns(ns: f64) -> String {
+    let ns = ns as u64;
+    format!("{}.{:03} ms", ns / 1000000, ns / 1000 % 1000)
+}
+
+fn format_time(ns: f64) -> String {
+    let secs = ns / 1e9;
+    if secs < 60.0 {
+        format!("{:.1} s", secs)
+    } else if secs < 3600.0 {
+        format!("{:.1} min", secs / 60.0)
+    } else {
+        format!("{:.1} h", secs / 3600.0)
+    }
+}
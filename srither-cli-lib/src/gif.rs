@@ -0,0 +1,254 @@
+// Copyright (c) 2016 srither developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! The GIF backend of the animations, encoding the grayscale images without
+//! any external library.
+//!
+//! The pixels are the indices of a palette of the 256 levels of gray, and
+//! each frame is a whole image compressed by LZW.
+
+use std::collections::HashMap;
+
+use png::Image;
+
+/// Encodes the frames of the same size with their delays in milliseconds in
+/// GIF, played forever.
+pub fn encode(frames: &[(Image, u32)]) -> Vec<u8> {
+    let (width, height) = (frames[0].0.width(), frames[0].0.height());
+    let mut gif = b"GIF89a".to_vec();
+    push_u16(&mut gif, width as u16);
+    push_u16(&mut gif, height as u16);
+    // The global palette of 256 colors of 8 bits, the background of white,
+    // and square pixels.
+    gif.extend_from_slice(&[0xf7, 0xff, 0]);
+    for i in 0..256 {
+        gif.extend_from_slice(&[i as u8, i as u8, i as u8]);
+    }
+    // The extension of Netscape to play the animation forever.
+    gif.extend_from_slice(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00");
+
+    for &(ref image, delay) in frames {
+        // The delay in hundredths of a second.
+        gif.extend_from_slice(&[0x21, 0xf9, 4, 0]);
+        push_u16(&mut gif, (delay / 10) as u16);
+        gif.extend_from_slice(&[0, 0]);
+
+        gif.push(0x2c);
+        push_u16(&mut gif, 0);
+        push_u16(&mut gif, 0);
+        push_u16(&mut gif, width as u16);
+        push_u16(&mut gif, height as u16);
+        gif.push(0);
+
+        gif.push(MIN_CODE_SIZE as u8);
+        for block in lzw(image.pixels()).chunks(255) {
+            gif.push(block.len() as u8);
+            gif.extend_from_slice(block);
+        }
+        gif.push(0);
+    }
+    gif.push(0x3b);
+    gif
+}
+
+fn push_u16(buf: &mut Vec<u8>, n: u16) {
+    buf.extend_from_slice(&[n as u8, (n >> 8) as u8]);
+}
+
+// The codes of the pixels, the clear code and the end code.
+const MIN_CODE_SIZE: u32 = 8;
+const CLEAR: u32 = 1 << MIN_CODE_SIZE;
+const END: u32 = CLEAR + 1;
+// The largest code, after which the table is cleared.
+const MAX_CODE: u32 = (1 << 12) - 1;
+
+// The bits of the codes, packed from the least significant bit of each byte.
+struct CodeWriter {
+    bytes: Vec<u8>,
+    bits: u32,
+    len: u32,
+}
+
+impl CodeWriter {
+    fn write(&mut self, code: u32, width: u32) {
+        self.bits |= code << self.len;
+        self.len += width;
+        while self.len >= 8 {
+            self.bytes.push(self.bits as u8);
+            self.bits >>= 8;
+            self.len -= 8;
+        }
+    }
+}
+
+// Compresses the pixels by the variable width codes of LZW. The width grows
+// when the next code does not fit in it, and the table is cleared when the
+// codes run out, in step with the decoders.
+fn lzw(pixels: &[u8]) -> Vec<u8> {
+    let mut w = CodeWriter {
+        bytes: vec![],
+        bits: 0,
+        len: 0,
+    };
+    let mut width = MIN_CODE_SIZE + 1;
+    let mut table = HashMap::new();
+    // The last code assigned, implied by each code written.
+    let mut hi = END;
+    w.write(CLEAR, width);
+
+    let mut code = pixels[0] as u32;
+    for &p in &pixels[1..] {
+        if let Some(&c) = table.get(&(code, p)) {
+            code = c;
+            continue;
+        }
+        w.write(code, width);
+        hi += 1;
+        if hi == 1 << width {
+            width += 1;
+        }
+        if hi == MAX_CODE {
+            w.write(CLEAR, width);
+            width = MIN_CODE_SIZE + 1;
+            hi = END;
+            table.clear();
+        } else {
+            let _ = table.insert((code, p), hi);
+        }
+        code = p as u32;
+    }
+    w.write(code, width);
+    hi += 1;
+    if hi == 1 << width {
+        width += 1;
+    }
+    if hi == MAX_CODE {
+        w.write(CLEAR, width);
+        width = MIN_CODE_SIZE + 1;
+    }
+    w.write(END, width);
+
+    let mut bytes = w.bytes;
+    if w.len > 0 {
+        bytes.push(w.bits as u8);
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use layout::{Layout, Shape};
+    use png::Image;
+    use super::{CLEAR, END, MIN_CODE_SIZE, encode, lzw};
+
+    // Decodes the codes of LZW as the readers of GIF do, and returns the
+    // pixels, the widest code and the number of the clear codes.
+    fn unlzw(bytes: &[u8]) -> (Vec<u8>, u32, usize) {
+        let (mut bits, mut len, mut pos) = (0u32, 0u32, 0);
+        let mut width = MIN_CODE_SIZE + 1;
+        let mut table: Vec<Vec<u8>> = vec![];
+        let mut prev: Option<Vec<u8>> = None;
+        let (mut pixels, mut widest, mut clears) = (vec![], width, 0);
+        loop {
+            while len < width {
+                bits |= (bytes[pos] as u32) << len;
+                len += 8;
+                pos += 1;
+            }
+            let code = bits & ((1 << width) - 1);
+            bits >>= width;
+            len -= width;
+
+            if code == CLEAR {
+                table = (0..256).map(|p| vec![p as u8]).collect();
+                // The clear and the end codes.
+                table.push(vec![]);
+                table.push(vec![]);
+                width = MIN_CODE_SIZE + 1;
+                prev = None;
+                clears += 1;
+                continue;
+            }
+            if code == END {
+                break;
+            }
+            let entry = match prev {
+                None => table[code as usize].clone(),
+                Some(ref prev) => {
+                    let mut entry = if (code as usize) < table.len() {
+                        table[code as usize].clone()
+                    } else {
+                        assert_eq!(table.len(), code as usize);
+                        prev.clone()
+                    };
+                    if (code as usize) == table.len() {
+                        entry.push(prev[0]);
+                    }
+                    let mut added = prev.clone();
+                    added.push(entry[0]);
+                    table.push(added);
+                    if table.len() == 1 << width && width < 12 {
+                        width += 1;
+                    }
+                    entry
+                }
+            };
+            pixels.extend_from_slice(&entry);
+            prev = Some(entry);
+            widest = if width > widest { width } else { widest };
+        }
+        assert_eq!(bytes.len(), pos);
+        (pixels, widest, clears)
+    }
+
+    #[test]
+    fn lzw_round_trip() {
+        let (pixels, widest, clears) = unlzw(&lzw(&[255]));
+        assert_eq!((vec![255], MIN_CODE_SIZE + 1, 1), (pixels, widest, clears));
+
+        // The runs make the codes of the repeated sequences.
+        let input = (0..3000).map(|i| if i % 7 < 3 { 0 } else { 255 }).collect::<Vec<u8>>();
+        let (pixels, _, clears) = unlzw(&lzw(&input));
+        assert_eq!(input, pixels);
+        assert_eq!(1, clears);
+
+        // The bytes of a random sequence make a code for each pair, so the
+        // codes grow to 12 bits and the table is cleared.
+        let mut x = 1u32;
+        let input = (0..20000)
+                        .map(|_| {
+                            x = x.wrapping_mul(1103515245).wrapping_add(12345);
+                            (x >> 16) as u8
+                        })
+                        .collect::<Vec<u8>>();
+        let (pixels, widest, clears) = unlzw(&lzw(&input));
+        assert_eq!(input, pixels);
+        assert_eq!(12, widest);
+        assert!(clears > 2);
+    }
+
+    #[test]
+    fn header() {
+        let layout = Layout {
+            width: 3.0,
+            height: 2.0,
+            shapes: vec![Shape::Dot((0.5, 0.5), 0.5)],
+        };
+        let image = Image::render(&layout);
+        let gif = encode(&[(image.clone(), 500), (image, 1000)]);
+        assert!(gif.starts_with(b"GIF89a\x03\x00\x02\x00\xf7\xff\x00\x00\x00\x00\x01\x01\x01"));
+        // The palette of 256 grays is followed by the loop of Netscape.
+        assert_eq!(b"\xff\xff\xff\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00",
+                   &gif[13 + 255 * 3..13 + 256 * 3 + 19]);
+        // The controls of the frames with the delays in hundredths of a second.
+        let control = b"\x21\xf9\x04\x00\x32\x00\x00\x00\x2c\x00\x00\x00\x00\x03\x00\x02\x00\x00";
+        assert_eq!(&control[..], &gif[13 + 256 * 3 + 19..13 + 256 * 3 + 19 + control.len()]);
+        assert!(gif.windows(6).any(|w| w == b"\x21\xf9\x04\x00\x64\x00"));
+        assert_eq!(Some(&0x3b), gif.last());
+    }
+}
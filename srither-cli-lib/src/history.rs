@@ -0,0 +1,345 @@
+// Copyright (c) 2016 srither developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! The local history of the runs of the commands.
+//!
+//! A run is recorded only if a history file is given, by `--history FILE` or
+//! `SRITHER_HISTORY`, and only to that file. The file starts with the
+//! envelope of its format, and each run is a line of the fields separated by
+//! tabs: the start time in seconds since the epoch, the command,
+//! the duration in seconds, the outcome (`ok` or `failed: ` and the error),
+//! the fingerprints of the puzzles separated by commas, and the arguments.
+//!
+//! The puzzles are noted by the commands on the main thread while they run,
+//! as they are read or generated.
+
+use std::{env, io};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::prelude::*;
+use std::path::Path;
+use time::{self, Timespec};
+
+use srither_core::puzzle::Puzzle;
+
+use envelope;
+use error::AppResult;
+use io_util;
+use parse_arg::HistoryConfig;
+
+thread_local!(static PUZZLES: RefCell<Vec<u64>> = RefCell::new(vec![]));
+
+/// Notes the puzzle processed by the current run.
+pub fn note(puzzle: &Puzzle) {
+    PUZZLES.with(|puzzles| puzzles.borrow_mut().push(puzzle.fingerprint()));
+}
+
+/// A run recorded in the history.
+#[derive(Clone, Debug)]
+pub struct Run {
+    /// The start time in seconds since the epoch.
+    pub start: i64,
+    /// The name of the command.
+    pub command: String,
+    /// The duration in seconds.
+    pub duration: f64,
+    /// The error of the run, if failed.
+    pub error: Option<String>,
+    /// The fingerprints of the puzzles processed by the run.
+    pub puzzles: Vec<u64>,
+    /// The arguments of the command line.
+    pub args: Vec<String>,
+}
+
+/// Appends the run of the command started at the time, with the puzzles
+/// noted, to the history file.
+pub fn record<T>(path: &str,
+                 command: &str,
+                 start: Timespec,
+                 result: &AppResult<T>)
+                 -> AppResult<()> {
+    let elapsed = time::get_time() - start;
+    let run = Run {
+        start: start.sec,
+        command: command.to_owned(),
+        duration: elapsed.num_milliseconds() as f64 / 1000.0,
+        error: result.as_ref().err().map(|e| e.to_string()),
+        puzzles: PUZZLES.with(|puzzles| puzzles.borrow().clone()),
+        args: env::args().skip(1).collect(),
+    };
+    let mut f = try!(OpenOptions::new().create(true).append(true).open(path));
+    if try!(f.metadata()).len() == 0 {
+        try!(writeln!(f, "{}", envelope::header(&envelope::HISTORY)));
+    }
+    try!(writeln!(f, "{}", format(&run)));
+    Ok(())
+}
+
+/// Writes the runs recorded in the history file to `out`, and returns them.
+pub fn run<O: Write>(config: HistoryConfig,
+                     path: Option<&String>,
+                     out: &mut O)
+                     -> AppResult<Vec<Run>> {
+    let path = match path {
+        Some(path) => path,
+        None => {
+            let msg = "no history file given by `--history FILE` or SRITHER_HISTORY";
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, msg).into());
+        }
+    };
+    let buf = if Path::new(path).exists() {
+        try!(io_util::read_file(path))
+    } else {
+        // Nothing recorded yet.
+        String::new()
+    };
+
+    let _ = try!(envelope::check_text(&envelope::HISTORY, &buf));
+
+    let mut runs = vec![];
+    let lines = buf.lines().enumerate().filter(|&(_, l)| !l.is_empty() && !l.starts_with('#'));
+    for (i, line) in lines {
+        match parse(line) {
+            Some(run) => runs.push(run),
+            None => {
+                let msg = format!("{}: invalid run at line {}", path, i + 1);
+                return Err(io::Error::new(io::ErrorKind::InvalidData, msg).into());
+            }
+        }
+    }
+    runs.retain(|run| {
+        config.command.as_ref().map_or(true, |c| *c == run.command) &&
+        config.puzzle.map_or(true, |fp| run.puzzles.contains(&fp))
+    });
+    if let Some(last) = config.last {
+        if runs.len() > last {
+            let first = runs.len() - last;
+            runs = runs.split_off(first);
+        }
+    }
+
+    if runs.is_empty() {
+        try!(writeln!(out, "no runs recorded"));
+    } else if config.summary {
+        try!(summarize(out, &runs));
+    } else {
+        for run in &runs {
+            try!(list(out, run));
+        }
+    }
+    Ok(runs)
+}
+
+fn list<O: Write>(out: &mut O, run: &Run) -> AppResult<()> {
+    let start = time::at(Timespec::new(run.start, 0));
+    let outcome = match run.error {
+        Some(ref e) => format!("failed: {}", e),
+        None => "ok".to_owned(),
+    };
+    try!(writeln!(out,
+                  "{}  {:.3} s  {}",
+                  start.strftime("%Y-%m-%d %H:%M:%S").unwrap(),
+                  run.duration,
+                  outcome));
+    try!(writeln!(out, "  srither {}", run.args.join(" ")));
+    if !run.puzzles.is_empty() {
+        let puzzles = run.puzzles.iter().map(|fp| format!("{:016x}", fp)).collect::<Vec<_>>();
+        try!(writeln!(out, "  puzzles: {}", puzzles.join(", ")));
+    }
+    Ok(())
+}
+
+// Writes the number of the runs, the failures and the durations of each
+// command.
+fn summarize<O: Write>(out: &mut O, runs: &[Run]) -> AppResult<()> {
+    let mut commands = BTreeMap::<&str, Vec<&Run>>::new();
+    for run in runs {
+        commands.entry(&run.command).or_insert_with(Vec::new).push(run);
+    }
+    try!(writeln!(out,
+                  "{:<16}{:>6}{:>8}{:>12}{:>12}{:>12}",
+                  "command",
+                  "runs",
+                  "failed",
+                  "min",
+                  "mean",
+                  "max"));
+    for (command, runs) in commands {
+        let failed = runs.iter().filter(|r| r.error.is_some()).count();
+        let durations = runs.iter().map(|r| r.duration).collect::<Vec<_>>();
+        let min = durations.iter().cloned().fold(durations[0], f64::min);
+        let max = durations.iter().cloned().fold(durations[0], f64::max);
+        let mean = durations.iter().fold(0.0, |sum, &d| sum + d) / durations.len() as f64;
+        try!(writeln!(out,
+                      "{:<16}{:>6}{:>8}{:>10.3} s{:>10.3} s{:>10.3} s",
+                      command,
+                      runs.len(),
+                      failed,
+                      min,
+                      mean,
+                      max));
+    }
+    Ok(())
+}
+
+// The tabs and the line breaks in the fields, e.g. of the errors, are written
+// as spaces.
+fn format(run: &Run) -> String {
+    let outcome = match run.error {
+        Some(ref e) => format!("failed: {}", e),
+        None => "ok".to_owned(),
+    };
+    let puzzles = run.puzzles.iter().map(|fp| format!("{:016x}", fp)).collect::<Vec<_>>();
+    let fields = vec![run.start.to_string(),
+                      run.command.clone(),
+                      format!("{:.3}", run.duration),
+                      outcome,
+                      puzzles.join(",")];
+    fields.iter()
+          .chain(run.args.iter())
+          .map(|f| f.replace("\t", " ").replace("\r", " ").replace("\n", " "))
+          .collect::<Vec<_>>()
+          .join("\t")
+}
+
+fn parse(line: &str) -> Option<Run> {
+    let fields = line.split('\t').collect::<Vec<_>>();
+    if fields.len() < 5 {
+        return None;
+    }
+    let error = match fields[3] {
+        "ok" => None,
+        outcome if outcome.starts_with("failed: ") => Some(outcome["failed: ".len()..].to_owned()),
+        _ => return None,
+    };
+    let mut puzzles = vec![];
+    for fp in fields[4].split(',').filter(|s| !s.is_empty()) {
+        puzzles.push(match u64::from_str_radix(fp, 16) {
+            Ok(fp) => fp,
+            Err(_) => return None,
+        });
+    }
+    Some(Run {
+        start: match fields[0].parse() {
+            Ok(start) => start,
+            Err(_) => return None,
+        },
+        command: fields[1].to_owned(),
+        duration: match fields[2].parse() {
+            Ok(duration) => duration,
+            Err(_) => return None,
+        },
+        error: error,
+        puzzles: puzzles,
+        args: fields[5..].iter().map(|&s| s.to_owned()).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+    use std::fs::File;
+    use std::io::prelude::*;
+
+    use envelope;
+    use parse_arg::HistoryConfig;
+    use super::{Run, format, parse, run};
+
+    fn config(last: Option<usize>) -> HistoryConfig {
+        HistoryConfig {
+            summary: false,
+            command: None,
+            puzzle: None,
+            last: last,
+        }
+    }
+
+    fn run_line(start: i64, command: &str, error: Option<&str>) -> String {
+        format(&Run {
+            start: start,
+            command: command.to_owned(),
+            duration: 0.25,
+            error: error.map(|e| e.to_owned()),
+            puzzles: vec![0xb10e60d71135df91, 1],
+            args: vec![command.to_owned(), "a\tb".to_owned()],
+        })
+    }
+
+    fn commands(file: &String, config: HistoryConfig) -> Vec<(i64, String)> {
+        run(config, Some(file), &mut vec![])
+            .unwrap()
+            .into_iter()
+            .map(|run| (run.start, run.command))
+            .collect()
+    }
+
+    #[test]
+    fn line() {
+        let line = run_line(1000, "solve", Some("IO error:\tbroken\nfile"));
+        assert_eq!("1000\tsolve\t0.250\tfailed: IO error: broken file\t\
+                    b10e60d71135df91,0000000000000001\tsolve\ta b",
+                   line);
+        let run = parse(&line).unwrap();
+        assert_eq!(Some("IO error: broken file".to_owned()), run.error);
+        assert_eq!(vec![0xb10e60d71135df91, 1], run.puzzles);
+        assert_eq!(vec!["solve".to_owned(), "a b".to_owned()], run.args);
+
+        let run = parse("0\tgen\t0\tok\t").unwrap();
+        assert_eq!((None, vec![], vec![]), (run.error, run.puzzles, run.args));
+        assert!(parse("0\tgen\t0\tok").is_none());
+        assert!(parse("0\tgen\t0\tfailed\t").is_none());
+        assert!(parse("0\tgen\tx\tok\t").is_none());
+        assert!(parse("0\tgen\t0\tok\tx").is_none());
+    }
+
+    #[test]
+    fn runs() {
+        let file = env::temp_dir().join("srither-history-test");
+        let file = file.to_str().unwrap().to_owned();
+        let _ = fs::remove_file(&file);
+
+        // Nothing recorded yet.
+        let mut out = vec![];
+        assert!(run(config(None), Some(&file), &mut out).unwrap().is_empty());
+        assert_eq!("no runs recorded\n", String::from_utf8(out).unwrap());
+        assert!(run(config(None), None, &mut vec![]).is_err());
+
+        {
+            let mut f = File::create(&file).unwrap();
+            writeln!(f, "{}", envelope::header(&envelope::HISTORY)).unwrap();
+            for &(start, command) in &[(1, "solve"), (2, "gen"), (3, "solve")] {
+                writeln!(f, "{}", run_line(start, command, None)).unwrap();
+            }
+        }
+        let all = vec![(1, "solve".to_owned()), (2, "gen".to_owned()), (3, "solve".to_owned())];
+        assert_eq!(all, commands(&file, config(None)));
+        // The last runs from none to more than recorded.
+        assert!(commands(&file, config(Some(0))).is_empty());
+        assert_eq!(all[2..].to_vec(), commands(&file, config(Some(1))));
+        assert_eq!(all, commands(&file, config(Some(3))));
+        assert_eq!(all, commands(&file, config(Some(10))));
+        // The last runs of the command.
+        let solve = HistoryConfig { command: Some("solve".to_owned()), ..config(Some(1)) };
+        assert_eq!(all[2..].to_vec(), commands(&file, solve));
+
+        {
+            let mut f = fs::OpenOptions::new().append(true).open(&file).unwrap();
+            writeln!(f, "4\tgen").unwrap();
+        }
+        assert_eq!(format!("IO error: {}: invalid run at line 5", file),
+                   run(config(None), Some(&file), &mut vec![]).unwrap_err().to_string());
+
+        {
+            let mut f = File::create(&file).unwrap();
+            writeln!(f, "# srither history v2").unwrap();
+        }
+        assert!(run(config(None), Some(&file), &mut vec![]).is_err());
+        fs::remove_file(&file).unwrap();
+    }
+}
@@ -0,0 +1,134 @@
+// Copyright (c) 2016 srither developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Reading the text inputs of the commands, the same on any platform.
+//!
+//! The inputs are UTF-8, with or without a byte order mark, or UTF-16 of
+//! either byte order with a byte order mark, as written by the editors on
+//! Windows. The line endings `\r\n` and `\r` are turned into `\n`, so the
+//! parsers only see `\n`. The errors of the files name their paths, written
+//! as given, which may be of any Unicode characters.
+
+use std::io;
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::Path;
+
+use error::AppResult;
+
+/// Opens the file, with the path in the error.
+pub fn open<P: AsRef<Path>>(path: P) -> AppResult<File> {
+    let path = path.as_ref();
+    File::open(path).map_err(|e| with_path(path, e).into())
+}
+
+/// Reads the whole text file.
+pub fn read_file<P: AsRef<Path>>(path: P) -> AppResult<String> {
+    let path = path.as_ref();
+    let mut f = try!(open(path));
+    read_to_string(&mut f).map_err(|e| with_path(path, e).into())
+}
+
+/// Reads the whole text input, e.g. the standard input.
+pub fn read_to_string<R: Read>(input: &mut R) -> io::Result<String> {
+    let mut bytes = vec![];
+    let _ = try!(input.read_to_end(&mut bytes));
+    decode(&bytes)
+}
+
+fn with_path(path: &Path, err: io::Error) -> io::Error {
+    io::Error::new(err.kind(), format!("{}: {}", path.display(), err))
+}
+
+// Decodes the bytes by the byte order mark, and normalizes the line endings.
+fn decode(bytes: &[u8]) -> io::Result<String> {
+    let text = if bytes.starts_with(b"\xef\xbb\xbf") {
+        utf8(&bytes[3..])
+    } else if bytes.starts_with(b"\xff\xfe") {
+        utf16(&bytes[2..], |b| (b[1] as u16) << 8 | b[0] as u16)
+    } else if bytes.starts_with(b"\xfe\xff") {
+        utf16(&bytes[2..], |b| (b[0] as u16) << 8 | b[1] as u16)
+    } else {
+        utf8(bytes)
+    };
+    match text {
+        Some(text) => Ok(normalize_newlines(&text)),
+        None => {
+            let msg = "the input is neither UTF-8 nor UTF-16 with a byte order mark";
+            Err(io::Error::new(io::ErrorKind::InvalidData, msg))
+        }
+    }
+}
+
+fn utf8(bytes: &[u8]) -> Option<String> {
+    String::from_utf8(bytes.to_vec()).ok()
+}
+
+fn utf16<F>(bytes: &[u8], unit: F) -> Option<String>
+    where F: Fn(&[u8]) -> u16
+{
+    if bytes.len() % 2 != 0 {
+        return None;
+    }
+    let units = bytes.chunks(2).map(unit).collect::<Vec<_>>();
+    String::from_utf16(&units).ok()
+}
+
+fn normalize_newlines(text: &str) -> String {
+    if !text.contains('\r') {
+        return text.to_owned();
+    }
+    text.replace("\r\n", "\n").replace("\r", "\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use super::{decode, read_to_string};
+
+    fn check_error(input: &[u8]) {
+        let err = decode(input).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, err.kind());
+        assert_eq!("the input is neither UTF-8 nor UTF-16 with a byte order mark",
+                   err.to_string());
+    }
+
+    #[test]
+    fn utf8() {
+        assert_eq!("3_\n_3\n", decode(b"3_\n_3\n").unwrap());
+        assert_eq!("3_\n_3\n", decode(b"\xef\xbb\xbf3_\n_3\n").unwrap());
+        assert_eq!("# \u{3042}\n", decode("# \u{3042}\n".as_bytes()).unwrap());
+        assert_eq!("", decode(b"").unwrap());
+        assert_eq!("", decode(b"\xef\xbb\xbf").unwrap());
+        check_error(b"3_\xff\n");
+        check_error(b"\xef\xbb\xbf\xc3");
+    }
+
+    #[test]
+    fn utf16() {
+        assert_eq!("3_\n\u{3042}", decode(b"\xff\xfe3\0_\0\n\0\x42\x30").unwrap());
+        assert_eq!("3_\n\u{3042}", decode(b"\xfe\xff\x003\0_\0\n\x30\x42").unwrap());
+        // A surrogate pair.
+        assert_eq!("\u{1f600}", decode(b"\xff\xfe\x3d\xd8\x00\xde").unwrap());
+        assert_eq!("", decode(b"\xff\xfe").unwrap());
+        // An odd number of bytes, and an unpaired surrogate.
+        check_error(b"\xff\xfe3\0_");
+        check_error(b"\xfe\xff\x003\0");
+        check_error(b"\xff\xfe\x3d\xd8");
+    }
+
+    #[test]
+    fn newlines() {
+        assert_eq!("3_\n_3\n", decode(b"3_\r\n_3\r\n").unwrap());
+        assert_eq!("3_\n_3\n", decode(b"3_\r_3\r").unwrap());
+        assert_eq!("3_\n\n_3\n", decode(b"3_\r\n\r_3\n").unwrap());
+        assert_eq!("3_\n_3\n", decode(b"\xff\xfe3\0_\0\r\0\n\0_\x003\0\r\0").unwrap());
+        assert_eq!("3_\n_3\n",
+                   read_to_string(&mut &b"\xef\xbb\xbf3_\r\n_3\r\n"[..]).unwrap());
+    }
+}
@@ -0,0 +1,180 @@
+// Copyright (c) 2016 srither developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! JSON values written by the commands with `--format json`.
+//!
+//! Each command writes one JSON value per line. The fields of the objects
+//! are only added, never renamed or removed, so that tools reading them keep
+//! working.
+
+use std::fmt;
+
+/// A JSON value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Creates an object with the fields in order.
+    pub fn object(fields: Vec<(&str, Json)>) -> Json {
+        Json::Object(fields.into_iter().map(|(k, v)| (k.to_owned(), v)).collect())
+    }
+}
+
+impl From<bool> for Json {
+    fn from(b: bool) -> Json {
+        Json::Bool(b)
+    }
+}
+
+impl From<f64> for Json {
+    fn from(n: f64) -> Json {
+        Json::Number(n)
+    }
+}
+
+impl From<u32> for Json {
+    fn from(n: u32) -> Json {
+        Json::Number(n as f64)
+    }
+}
+
+impl From<i32> for Json {
+    fn from(n: i32) -> Json {
+        Json::Number(n as f64)
+    }
+}
+
+impl From<u64> for Json {
+    fn from(n: u64) -> Json {
+        Json::Number(n as f64)
+    }
+}
+
+impl From<usize> for Json {
+    fn from(n: usize) -> Json {
+        Json::Number(n as f64)
+    }
+}
+
+impl<'a> From<&'a str> for Json {
+    fn from(s: &'a str) -> Json {
+        Json::String(s.to_owned())
+    }
+}
+
+impl From<String> for Json {
+    fn from(s: String) -> Json {
+        Json::String(s)
+    }
+}
+
+impl<T: Into<Json>> From<Option<T>> for Json {
+    fn from(x: Option<T>) -> Json {
+        x.map(Into::into).unwrap_or(Json::Null)
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            Json::Number(n) if n.is_finite() => write!(f, "{}", n),
+            Json::Number(_) => write!(f, "null"),
+            Json::String(ref s) => write_str(f, s),
+            Json::Array(ref values) => {
+                try!(write!(f, "["));
+                for (i, v) in values.iter().enumerate() {
+                    if i > 0 {
+                        try!(write!(f, ","));
+                    }
+                    try!(write!(f, "{}", v));
+                }
+                write!(f, "]")
+            }
+            Json::Object(ref fields) => {
+                try!(write!(f, "{{"));
+                for (i, &(ref k, ref v)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        try!(write!(f, ","));
+                    }
+                    try!(write_str(f, k));
+                    try!(write!(f, ":{}", v));
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+// Writes the string quoted, escaping the characters JSON does not allow.
+fn write_str(f: &mut fmt::Formatter, s: &str) -> fmt::Result {
+    try!(write!(f, "\""));
+    for c in s.chars() {
+        match c {
+            '"' => try!(write!(f, "\\\"")),
+            '\\' => try!(write!(f, "\\\\")),
+            '\n' => try!(write!(f, "\\n")),
+            '\r' => try!(write!(f, "\\r")),
+            '\t' => try!(write!(f, "\\t")),
+            c if (c as u32) < 0x20 => try!(write!(f, "\\u{:04x}", c as u32)),
+            c => try!(write!(f, "{}", c)),
+        }
+    }
+    write!(f, "\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64;
+    use super::Json;
+
+    fn check(expected: &str, value: Json) {
+        assert_eq!(expected, value.to_string());
+    }
+
+    #[test]
+    fn values() {
+        check("null", Json::Null);
+        check("true", Json::from(true));
+        check("3", Json::from(3u32));
+        check("-1", Json::from(-1));
+        check("0.5", Json::from(0.5));
+        check("null", Json::from(f64::NAN));
+        check("null", Json::from(f64::INFINITY));
+        check("null", Json::from(None::<u32>));
+        check("[]", Json::Array(vec![]));
+        check("{}", Json::object(vec![]));
+        check("[1,\"a\",[null]]",
+              Json::Array(vec![Json::from(1), Json::from("a"), Json::Array(vec![Json::Null])]));
+        check("{\"b\":2,\"a\":{\"c\":false}}",
+              Json::object(vec![("b", Json::from(2)),
+                                ("a", Json::object(vec![("c", Json::from(false))]))]));
+    }
+
+    #[test]
+    fn escape() {
+        check("\"\"", Json::from(""));
+        check("\"say \\\"hi\\\"\"", Json::from("say \"hi\""));
+        check("\"C:\\\\dir\\\\\"", Json::from("C:\\dir\\"));
+        check("\"a\\nb\\r\\n\\tc\"", Json::from("a\nb\r\n\tc"));
+        check("\"\\u0000\\u0008\\u000c\\u001f \"", Json::from("\u{0}\u{8}\u{c}\u{1f} "));
+        // The characters not needing the escape are written as they are.
+        check("\"/\u{7f}\u{e9}\u{2028}\u{1f600}\"",
+              Json::from("/\u{7f}\u{e9}\u{2028}\u{1f600}"));
+        check("{\"a\\\"b\":\"\\\\\"}",
+              Json::object(vec![("a\"b", Json::from("\\"))]));
+    }
+}
@@ -0,0 +1,91 @@
+// Copyright (c) 2016 srither developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! The layout of a board as shapes in pixels, shared by the image backends.
+//!
+//! The board is drawn by the dots at the corners of the cells, the lines of
+//! the loop and the hints, all in black on white. The hints are drawn by
+//! strokes in the style of seven-segment digits instead of a font, so that
+//! every backend draws them the same without a font file. The crosses are not
+//! drawn.
+
+use srither_core::geom::{Geom, Point};
+use srither_core::puzzle::{Edge, Puzzle};
+
+/// A point of the image, in pixels from the top-left corner.
+pub type Pos = (f64, f64);
+
+/// A shape to draw.
+#[derive(Clone, Debug)]
+pub enum Shape {
+    /// A filled circle of the center and the radius.
+    Dot(Pos, f64),
+    /// A polyline through the points, of the width with round ends.
+    Stroke(Vec<Pos>, f64),
+}
+
+/// The shapes of a board, and the size of the image.
+#[derive(Clone, Debug)]
+pub struct Layout {
+    pub width: f64,
+    pub height: f64,
+    pub shapes: Vec<Shape>,
+}
+
+// The strokes of the digits, in the ratios to the cell from its top-left
+// corner.
+static DIGITS: [&'static [&'static [Pos]]; 5] =
+    [&[&[(0.35, 0.25), (0.65, 0.25), (0.65, 0.75), (0.35, 0.75), (0.35, 0.25)]],
+     &[&[(0.42, 0.32), (0.52, 0.25), (0.52, 0.75)]],
+     &[&[(0.35, 0.25), (0.65, 0.25), (0.65, 0.5), (0.35, 0.5), (0.35, 0.75), (0.65, 0.75)]],
+     &[&[(0.35, 0.25), (0.65, 0.25), (0.65, 0.75), (0.35, 0.75)], &[(0.38, 0.5), (0.65, 0.5)]],
+     &[&[(0.35, 0.25), (0.35, 0.55), (0.68, 0.55)], &[(0.6, 0.25), (0.6, 0.75)]]];
+
+impl Layout {
+    /// Lays out the hints and the lines of the puzzle in the cells of the
+    /// pixels, with a margin of half a cell around the board.
+    pub fn new(puzzle: &Puzzle, cell: f64) -> Layout {
+        let margin = cell / 2.0;
+        let pos = |r: i32, c: i32| (margin + c as f64 * cell, margin + r as f64 * cell);
+        let mut shapes = vec![];
+
+        for r in 0..(puzzle.row() + 1) {
+            for c in 0..(puzzle.column() + 1) {
+                let p = Point(r, c);
+                if c < puzzle.column() && puzzle.edge_h(p) == Some(Edge::Line) {
+                    shapes.push(Shape::Stroke(vec![pos(r, c), pos(r, c + 1)], cell / 10.0));
+                }
+                if r < puzzle.row() && puzzle.edge_v(p) == Some(Edge::Line) {
+                    shapes.push(Shape::Stroke(vec![pos(r, c), pos(r + 1, c)], cell / 10.0));
+                }
+            }
+        }
+        for r in 0..(puzzle.row() + 1) {
+            for c in 0..(puzzle.column() + 1) {
+                shapes.push(Shape::Dot(pos(r, c), cell / 16.0));
+            }
+        }
+        for p in puzzle.points() {
+            if let Some(n) = puzzle.hint(p) {
+                let (x, y) = pos(p.0, p.1);
+                for stroke in DIGITS[n as usize] {
+                    let points = stroke.iter()
+                                       .map(|&(dx, dy)| (x + dx * cell, y + dy * cell))
+                                       .collect();
+                    shapes.push(Shape::Stroke(points, cell / 14.0));
+                }
+            }
+        }
+
+        Layout {
+            width: puzzle.column() as f64 * cell + 2.0 * margin,
+            height: puzzle.row() as f64 * cell + 2.0 * margin,
+            shapes: shapes,
+        }
+    }
+}
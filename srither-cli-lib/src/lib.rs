@@ -0,0 +1,453 @@
+// Copyright (c) 2016 srither developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! The commands of the srither command line tool, as a library.
+//!
+//! Each command is a module with a `run` function taking the configuration
+//! of the command and the sinks of its output, and returning the results of
+//! the command, so that the commands can be run in the process, e.g. by GUI
+//! wrappers and tests. The standard output of the command is written to
+//! `out`, and the progress to `err`. The commands reading the standard input
+//! without input files, and the interactive ones, read `input` instead.
+//!
+//! ```
+//! use srither_cli_lib::{TransformConfig, TransformOp, transform};
+//!
+//! let config = TransformConfig {
+//!     ops: vec![TransformOp::FlipH],
+//!     input_files: vec![],
+//! };
+//! let mut out = vec![];
+//! let puzzles = transform::run(config, &mut &b"3_\n__\n"[..], &mut out).unwrap();
+//! assert_eq!(1, puzzles.len());
+//! assert_eq!("+ + +\n   3 \n+ + +\n     \n+ + +\n",
+//!            String::from_utf8(out).unwrap());
+//! ```
+//!
+//! The output is colored as `--color` tells, `auto` by default, which colors
+//! it only if the standard output of the process is a terminal. The
+//! wrappers writing the output elsewhere may set it by `set_color_policy`.
+//!
+//! `test` and `bench` run the puzzles by the test harness of Rust, which
+//! reports to the standard output of the process.
+
+#![warn(bad_style)]
+#![warn(missing_copy_implementations)]
+#![warn(missing_debug_implementations)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(trivial_numeric_casts)]
+#![warn(unused)]
+#![warn(unused_extern_crates)]
+#![warn(unused_import_braces)]
+#![warn(unused_qualifications)]
+#![warn(unused_results)]
+
+#![feature(test)]
+
+#![cfg_attr(feature="dev", feature(plugin))]
+#![cfg_attr(feature="dev", plugin(clippy))]
+#![cfg_attr(feature="dev", warn(mut_mut))]
+#![cfg_attr(feature="dev", warn(string_add))]
+#![cfg_attr(feature="dev", warn(string_add_assign))]
+
+extern crate term;
+extern crate argparse;
+extern crate rand;
+extern crate test as rustc_test;
+extern crate time;
+
+extern crate srither_core;
+extern crate srither_solver;
+extern crate srither_gen;
+extern crate srither_testdata;
+
+use std::io::prelude::*;
+
+pub use error::{AppError, AppResult};
+pub use parse_arg::{BenchConfig, CheckpointConfig, CompareConfig, Config, ConvertConfig,
+                    CounterexampleConfig, EditConfig, ExportConfig, ExportFormat, Format,
+                    GenerateConfig, HintPolicyArg, HistoryConfig, ImageFormat, Invocation,
+                    OutputMode, PackAction, PackConfig, PipelineConfig, PlayConfig, RateConfig,
+                    RecheckConfig, RenderConfig, ReplConfig, SoakConfig, SolveConfig,
+                    TestConfig, TransformConfig, TransformOp};
+pub use pprint::{ColorPolicy, set_color_policy};
+
+mod cache;
+mod coord;
+mod describe;
+mod envelope;
+mod error;
+mod gif;
+mod io_util;
+mod json;
+mod layout;
+mod parse_arg;
+mod pdf;
+mod pipeline;
+mod png;
+mod pprint;
+mod size_spec;
+mod theme;
+
+pub mod solve;
+pub mod test;
+pub mod bench;
+pub mod pack;
+pub mod compare;
+pub mod counterexample;
+pub mod repl;
+pub mod rate;
+pub mod soak;
+pub mod recheck;
+pub mod transform;
+pub mod convert;
+pub mod render;
+pub mod export;
+pub mod play;
+pub mod checkpoint;
+pub mod generate;
+pub mod history;
+pub mod edit;
+
+/// Runs the command of the configuration as the command line tool does, and
+/// returns false if the command has found failures, e.g. the mismatches of
+/// `recheck`, for which the tool exits with the status 1. The results of the
+/// command are returned by the `run` function of its module.
+pub fn run<I, O, E>(config: Config,
+                    input: &mut I,
+                    out: &mut O,
+                    err: &mut E,
+                    history_file: Option<&String>)
+                    -> AppResult<bool>
+    where I: BufRead,
+          O: Write,
+          E: Write
+{
+    match config {
+        Config::Solve(config) => solve::run(config, input, out, err).map(|_| true),
+        Config::Test(config) => test::run(config).map(|_| true),
+        Config::Bench(config) => bench::run(config).map(|_| true),
+        Config::Pack(config) => pack::run(config, input, out).map(|r| r.is_ok()),
+        Config::Compare(config) => compare::run(config, out).map(|r| r.mismatches == 0),
+        Config::Counterexample(config) => {
+            counterexample::run(config, input, out).map(|_| true)
+        }
+        Config::Repl(config) => repl::run(config, input, out).map(|_| true),
+        Config::Rate(config) => rate::run(config, out, err).map(|_| true),
+        Config::Transform(config) => transform::run(config, input, out).map(|_| true),
+        Config::Soak(config) => soak::run(config, out).map(|r| r.failures == 0),
+        Config::Recheck(config) => {
+            recheck::run(config, input, out, err).map(|r| r.failures == 0)
+        }
+        Config::Convert(config) => convert::run(config, input, out, err).map(|_| true),
+        Config::Render(config) => render::run(config, input, out).map(|_| true),
+        Config::Export(config) => export::run(config, input, out).map(|_| true),
+        Config::Play(config) => play::run(config, input, out).map(|_| true),
+        Config::Checkpoint(config) => checkpoint::run(config, out),
+        Config::Generate(config) => generate::run(config, out).map(|_| true),
+        Config::History(config) => history::run(config, history_file, out).map(|_| true),
+        Config::Edit(config) => edit::run(config, input, out).map(|_| true),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, fs};
+    use std::fs::File;
+    use srither_core::format;
+    use srither_core::geom::Size;
+    use srither_core::puzzle::Puzzle;
+    use io_util;
+    use pprint::{Config as PpConfig, Mode as PpMode};
+    use theme::Theme;
+
+    #[test]
+    fn convert_to_sinks() {
+        let config = Config::Convert(ConvertConfig {
+            from: None,
+            to: format::find("compact").unwrap(),
+            pipeline: PipelineConfig {
+                jobs: 1,
+                timeout: None,
+                progress: true,
+            },
+            input_files: vec![],
+        });
+        let input = b"# srither pack v1\n3_\n__\n\n_3\n__\n";
+        let mut out = vec![];
+        let mut err = vec![];
+        assert!(run(config, &mut &input[..], &mut out, &mut err, None).unwrap());
+        assert_eq!("2x2-3\n\n2x2-a3\n", String::from_utf8(out).unwrap());
+        assert!(String::from_utf8(err).unwrap().ends_with("] 2/2\n"));
+    }
+
+    fn solve_config() -> SolveConfig {
+        SolveConfig {
+            derive_all: false,
+            output_mode: OutputMode::None,
+            cache_dir: None,
+            strategy_file: None,
+            profile_steps: false,
+            count: false,
+            max_guess_depth: None,
+            max_solutions: None,
+            solutions: None,
+            restart: None,
+            disabled_theorems: vec![],
+            effort: false,
+            explain: false,
+            normalize: None,
+            resume_file: None,
+            slice: None,
+            in_format: None,
+            format: Format::Text,
+            pipeline: PipelineConfig {
+                jobs: 1,
+                timeout: None,
+                progress: false,
+            },
+            input_files: vec![],
+        }
+    }
+
+    #[test]
+    fn solve_json() {
+        let config = SolveConfig { format: Format::Json, ..solve_config() };
+        let input = b"22\n22\n";
+        let mut out = vec![];
+        let _ = solve::run(config.clone(), &mut &input[..], &mut out, &mut vec![]).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains(concat!(r#""size":{"rows":2,"columns":2},"#,
+                                     r#""hints":[[2,2],[2,2]],"#,
+                                     r#""solution_edges":[{"edge_h":[[true,true],[false,false],"#,
+                                     r#"[true,true]],"edge_v":[[true,false,true],"#,
+                                     r#"[true,false,true]]}],"unique":true,"#)));
+        assert!(out.contains(r#""guesses":0,"theorems_applied":48"#));
+
+        let input = b"3_\n_3\n";
+        let mut out = vec![];
+        let _ = solve::run(config, &mut &input[..], &mut out, &mut vec![]).unwrap();
+        assert!(String::from_utf8(out).unwrap().contains(r#""unique":false,"#));
+    }
+
+    #[test]
+    fn solve_emoji() {
+        let output_mode = OutputMode::Pretty(PpConfig {
+            mode: PpMode::Emoji,
+            cell_width: 2,
+            cell_height: 1,
+            theme: Theme::default(),
+        });
+        let config = SolveConfig { output_mode: output_mode, ..solve_config() };
+        let input = b"___\n312\n";
+        let mut out = vec![];
+        let _ = solve::run(config, &mut &input[..], &mut out, &mut vec![]).unwrap();
+        assert_eq!(concat!("\u{2b1c}\u{2b1b}\u{2b1b}\n",
+                           "3\u{fe0f}\u{20e3}1\u{fe0f}\u{20e3}2\u{fe0f}\u{20e3}\n"),
+                   String::from_utf8(out).unwrap());
+    }
+
+    #[test]
+    fn resume_file() {
+        let file = env::temp_dir().join("srither-resume-file-test");
+        let file = file.to_str().unwrap().to_owned();
+        let config = SolveConfig {
+            derive_all: false,
+            output_mode: OutputMode::None,
+            cache_dir: None,
+            strategy_file: None,
+            profile_steps: false,
+            count: true,
+            max_guess_depth: None,
+            max_solutions: None,
+            solutions: None,
+            restart: None,
+            disabled_theorems: vec![],
+            effort: false,
+            explain: false,
+            normalize: None,
+            resume_file: Some(file.clone()),
+            slice: None,
+            in_format: None,
+            format: Format::Text,
+            pipeline: PipelineConfig {
+                jobs: 1,
+                timeout: None,
+                progress: false,
+            },
+            input_files: vec![],
+        };
+        let input = b"3__\n_1_\n";
+        let state = concat!("# srither deepening v1\n",
+                            "# puzzle: b10e60d71135df91\n",
+                            "# bound: 0\n",
+                            "# nodes: 0\n",
+                            "pending\n");
+        {
+            let mut f = File::create(&file).unwrap();
+            f.write_all(state.as_bytes()).unwrap();
+        }
+
+        // The finished state is resumed as it is.
+        for _ in 0..2 {
+            let mut out = vec![];
+            let solved = solve::run(config.clone(), &mut &input[..], &mut out, &mut vec![])
+                             .unwrap();
+            assert_eq!(Some(3), solved[0].count);
+            assert_eq!("3\n", String::from_utf8(out).unwrap());
+            let state = io_util::read_file(&file).unwrap();
+            assert_eq!(3, state.lines().filter(|l| l.starts_with("solution")).count());
+            assert!(!state.contains("pending"));
+        }
+
+        let input = b"3__\n_2_\n";
+        assert!(solve::run(config, &mut &input[..], &mut vec![], &mut vec![]).is_err());
+        fs::remove_file(&file).unwrap();
+    }
+
+    fn rate_config(pack_file: String) -> RateConfig {
+        RateConfig {
+            pack_file: pack_file,
+            calibration_file: None,
+            annotate: false,
+            theorem_set: srither_solver::THEOREM_SET_VERSION,
+            disabled_theorems: vec![],
+            cache_dir: None,
+            format: Format::Text,
+            pipeline: PipelineConfig {
+                jobs: 1,
+                timeout: None,
+                progress: false,
+            },
+        }
+    }
+
+    #[test]
+    fn rate_cache() {
+        let dir = env::temp_dir().join("srither-rate-cache-test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let pack = dir.join("pack.txt").to_str().unwrap().to_owned();
+        let cache_dir = dir.join("cache").to_str().unwrap().to_owned();
+        {
+            let mut f = File::create(&pack).unwrap();
+            f.write_all(b"# srither pack v1\n22\n22\n").unwrap();
+        }
+        let config = RateConfig { cache_dir: Some(cache_dir.clone()), ..rate_config(pack) };
+        let score = rate::run(config.clone(), &mut vec![], &mut vec![]).unwrap()[0].score;
+
+        // The statistics are read from the cache, so a branch written to it
+        // raises the score.
+        let file = fs::read_dir(&cache_dir).unwrap().next().unwrap().unwrap().path();
+        let buf = io_util::read_file(&file).unwrap();
+        assert!(buf.contains("# branches: 0\n"));
+        {
+            let mut f = File::create(&file).unwrap();
+            f.write_all(buf.replace("# branches: 0\n", "# branches: 1\n").as_bytes()).unwrap();
+        }
+        let ratings = rate::run(config, &mut vec![], &mut vec![]).unwrap();
+        assert_eq!(score + 50.0, ratings[0].score);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rate_calibration() {
+        let dir = env::temp_dir().join("srither-rate-calibration-test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let pack = dir.join("pack.txt").to_str().unwrap().to_owned();
+        let calibration = dir.join("calibration.txt").to_str().unwrap().to_owned();
+        {
+            let mut f = File::create(&pack).unwrap();
+            f.write_all(b"# srither pack v1\n22\n22\n").unwrap();
+        }
+        let write = |content: &str| {
+            let mut f = File::create(&calibration).unwrap();
+            f.write_all(content.as_bytes()).unwrap();
+        };
+        let config = RateConfig {
+            calibration_file: Some(calibration.clone()),
+            ..rate_config(pack)
+        };
+        let rate = || {
+            let mut err = vec![];
+            let result = rate::run(config.clone(), &mut vec![], &mut err);
+            (result, String::from_utf8(err).unwrap())
+        };
+
+        // The file created is of this version.
+        let (result, err) = rate();
+        assert_eq!(Some(50.0), result.unwrap()[0].calibrated_percentile);
+        assert_eq!("", err);
+        let (result, err) = rate();
+        assert!(result.is_ok());
+        assert_eq!("", err);
+
+        let theorem_set = srither_solver::THEOREM_SET_VERSION;
+        write(&format!("# srither calibration v2 (srither 0.0.1)\n# theorem-set: {}\n0\n",
+                       theorem_set));
+        let (result, err) = rate();
+        assert!(result.is_ok());
+        assert!(err.contains("written by srither 0.0.1"));
+
+        write("# srither calibration\n# version: 0.0.1\n0\n");
+        let (result, err) = rate();
+        assert!(result.is_ok());
+        assert!(err.contains("written by srither 0.0.1"));
+
+        write(&format!("# srither calibration v2 (srither {})\n# theorem-set: {}\n0\n",
+                       env!("CARGO_PKG_VERSION"),
+                       theorem_set + 1));
+        let msg = rate().0.unwrap_err().to_string();
+        let expected = format!("theorem set version {}, not {}", theorem_set + 1, theorem_set);
+        assert!(msg.contains(&expected));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn edit_status() {
+        // The boards are drawn in ASCII even on a terminal.
+        set_color_policy(ColorPolicy::Never);
+        let config = EditConfig {
+            input_file: None,
+            size: Size(2, 3),
+            output_file: None,
+            debounce_ms: 0,
+        };
+        let input = b"hint 1 0 3\nhint 1 1 1\nstatus\nhint 1 2 2\nstatus\nhint 0 0 0\nstatus\n\
+                      undo\nstatus\nclear 9 9\nquit\nhint 0 0 0\n";
+        let mut out = vec![];
+        let puzzle = edit::run(config, &mut &input[..], &mut out).unwrap();
+        assert_eq!("___\n312\n".parse::<Puzzle>().unwrap(), puzzle);
+        let board = |rows: [&str; 2]| {
+            let mut s = "   0  1  2 \n  +~~+~~+~~+\n".to_owned();
+            for (r, row) in rows.iter().enumerate() {
+                let cells = row.chars()
+                               .map(|c| format!("{} ?", if c == '_' { ' ' } else { c }))
+                               .collect::<String>();
+                s.push_str(&format!("{} ?{}{} \n", r, cells, r));
+                s.push_str("  +~~+~~+~~+\n");
+            }
+            s + "   0  1  2 \n> "
+        };
+        let expected = vec![board(["___", "___"]),
+                            board(["___", "3__"]),
+                            board(["___", "31_"]),
+                            "status: multiple solutions\n> ".to_owned(),
+                            board(["___", "312"]),
+                            "status: unique solution\n> ".to_owned(),
+                            board(["0__", "312"]),
+                            "status: no solution\n> ".to_owned(),
+                            board(["___", "312"]),
+                            "status: unique solution\n> ".to_owned(),
+                            "usage: clear R C\n> ".to_owned()];
+        assert_eq!(expected.concat(), String::from_utf8(out).unwrap());
+    }
+}
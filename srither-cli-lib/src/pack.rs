@@ -0,0 +1,502 @@
+// Copyright (c) 2016 srither developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Builds, verifies and inspects the packs of puzzles.
+
+use std::{io, u32};
+use std::collections::{BTreeMap, HashSet};
+use std::ascii::AsciiExt;
+use std::fs::File;
+use std::io::prelude::*;
+use rand::{Isaac64Rng, OsRng, Rng};
+
+use srither_core::format::{self, PuzzleFormat};
+use srither_core::geom::Geom;
+use srither_core::loop_bits::LoopBits;
+use srither_core::orientation::{self, Orientation};
+use srither_core::puzzle::Puzzle;
+use srither_core::puzzle_set::{Entry, PuzzleSet};
+use srither_gen::seeded_rng;
+use srither_solver::{self as solver, SolveOptions, SolverStats, Step};
+
+use coord;
+use envelope;
+use error::{AppError, AppResult};
+use io_util;
+use parse_arg::{PackAction, PackConfig};
+use solve;
+
+/// The results of `pack`.
+#[derive(Clone, Debug)]
+pub enum PackReport {
+    /// The pack written by `sample`, `shuffle` and `progression`.
+    Pack(PuzzleSet),
+    /// The number of the solutions of each puzzle found by `alternatives`, up
+    /// to `--max-solutions`.
+    Alternatives(Vec<usize>),
+    /// The number of the mismatches found by `verify`.
+    Verify(usize),
+}
+
+impl PackReport {
+    /// Returns false if `verify` has found mismatches.
+    pub fn is_ok(&self) -> bool {
+        match *self {
+            PackReport::Verify(num_failure) => num_failure == 0,
+            PackReport::Pack(_) | PackReport::Alternatives(_) => true,
+        }
+    }
+}
+
+/// Runs the action on the packs of the input files, or of `input` if none,
+/// and writes the pack or the report to `out`.
+pub fn run<I, O>(config: PackConfig, input: &mut I, out: &mut O) -> AppResult<PackReport>
+    where I: Read,
+          O: Write
+{
+    match config.action {
+        PackAction::Verify => return verify(&config, input, out).map(PackReport::Verify),
+        PackAction::Alternatives => {
+            let set = try!(select(&config, input));
+            let mut counts = vec![];
+            for (i, entry) in set.entries().iter().enumerate() {
+                let title = entry.metadata.get("title").unwrap_or("");
+                try!(writeln!(out, "#{} {}", i + 1, title));
+                counts.push(try!(alternatives(out, &entry.puzzle, config.max_solutions)));
+            }
+            return Ok(PackReport::Alternatives(counts));
+        }
+        PackAction::Progression => {
+            let set = try!(progression(try!(select(&config, input))));
+            try!(write(out, &set));
+            return Ok(PackReport::Pack(set));
+        }
+        PackAction::Sample | PackAction::Shuffle => {}
+    }
+
+    let mut config = config;
+    if config.manifest.is_some() {
+        if config.input_files.is_empty() {
+            return Err(invalid_manifest("cannot write manifest of the pack read from stdin"));
+        }
+        // The seed is recorded so that the pack can be built again.
+        if config.seed.is_none() {
+            config.seed = Some(try!(OsRng::new()).next_u64());
+        }
+    }
+
+    let set = try!(build(&config, input));
+    try!(write(out, &set));
+    if let Some(ref path) = config.manifest {
+        try!(write_manifest(path, &config, &set));
+    }
+    Ok(PackReport::Pack(set))
+}
+
+/// Reads the puzzles of the inputs, normalized and filtered by the
+/// difficulty.
+fn select<I: Read>(config: &PackConfig, input: &mut I) -> AppResult<PuzzleSet> {
+    let mut set = PuzzleSet::new();
+    if config.input_files.is_empty() {
+        set.extend(try!(read_as(config.in_format, input)).into_entries());
+    } else {
+        for file in &config.input_files {
+            let mut f = try!(io_util::open(file));
+            set.extend(try!(read_as(config.in_format, &mut f)).into_entries());
+        }
+    }
+
+    if let Some(normalization) = config.normalize {
+        for entry in set.entries_mut() {
+            let (puzzle, o) = orientation::normalize(&entry.puzzle, normalization);
+            // The puzzles normalized before keep the orientation of the original.
+            let prev = entry.metadata
+                            .get("orientation")
+                            .and_then(|o| o.parse::<Orientation>().ok())
+                            .unwrap_or(Orientation::identity());
+            entry.puzzle = puzzle;
+            entry.metadata.set("orientation", &prev.then(o).to_string());
+        }
+    }
+
+    if let Some(ref difficulty) = config.difficulty {
+        set.entries_mut().retain(|e| {
+            e.metadata
+             .get("difficulty")
+             .map(|d| d.eq_ignore_ascii_case(difficulty))
+             .unwrap_or(false)
+        });
+    }
+    Ok(set)
+}
+
+/// Builds the pack shuffled or sampled from the inputs.
+fn build<I: Read>(config: &PackConfig, input: &mut I) -> AppResult<PuzzleSet> {
+    let mut set = try!(select(config, input));
+    let mut rng = try!(new_rng(config.seed));
+    rng.shuffle(set.entries_mut());
+
+    if let (PackAction::Sample, Some(n)) = (config.action, config.count) {
+        set.entries_mut().truncate(n);
+    }
+    Ok(set)
+}
+
+/// Writes the manifest of the pack: the versions, the options to build the
+/// pack again and the fingerprints of the puzzles in order.
+fn write_manifest(path: &str, config: &PackConfig, set: &PuzzleSet) -> AppResult<()> {
+    let mut f = try!(File::create(path));
+    try!(writeln!(f, "{}", envelope::header(&envelope::MANIFEST)));
+    try!(writeln!(f, "# theorem-set: {}", solver::THEOREM_SET_VERSION));
+    try!(writeln!(f, "# action: {}", action_name(config.action)));
+    if let Some(seed) = config.seed {
+        try!(writeln!(f, "# seed: {}", seed));
+    }
+    if let (PackAction::Sample, Some(count)) = (config.action, config.count) {
+        try!(writeln!(f, "# count: {}", count));
+    }
+    if let Some(ref difficulty) = config.difficulty {
+        try!(writeln!(f, "# difficulty: {}", difficulty));
+    }
+    if let Some(normalization) = config.normalize {
+        try!(writeln!(f, "# normalize: {}", normalization));
+    }
+    if let Some(format) = config.in_format {
+        try!(writeln!(f, "# in-format: {}", format.name()));
+    }
+    for file in &config.input_files {
+        try!(writeln!(f, "# input: {}", file));
+    }
+    for entry in set.entries() {
+        try!(writeln!(f, "{:016x}", entry.puzzle.fingerprint()));
+    }
+    Ok(())
+}
+
+fn action_name(action: PackAction) -> &'static str {
+    match action {
+        PackAction::Sample => "sample",
+        PackAction::Shuffle => "shuffle",
+        PackAction::Alternatives => "alternatives",
+        PackAction::Verify => "verify",
+        PackAction::Progression => "progression",
+    }
+}
+
+// A manifest read from a file.
+struct Manifest {
+    version: String,
+    theorem_set: u32,
+    config: PackConfig,
+    fingerprints: Vec<u64>,
+}
+
+fn read_manifest(path: &str) -> AppResult<Manifest> {
+    let buf = try!(io_util::read_file(path));
+    let mut lines = buf.lines().map(|l| l.trim()).filter(|l| !l.is_empty());
+    let header = lines.next().and_then(|line| envelope::parse(&envelope::MANIFEST, line));
+    let envelope = match header {
+        Some(envelope) => envelope,
+        None => return Err(invalid_manifest("not a pack manifest")),
+    };
+    try!(envelope::check(&envelope::MANIFEST, &envelope));
+
+    // The manifests of v1 have the version of srither on a line of its own.
+    let mut version = envelope.version.unwrap_or_default();
+    let mut theorem_set = 1;
+    let mut config = PackConfig {
+        action: PackAction::Shuffle,
+        count: None,
+        difficulty: None,
+        seed: None,
+        max_solutions: 0,
+        normalize: None,
+        in_format: None,
+        manifest: None,
+        input_files: vec![],
+    };
+    let mut fingerprints = vec![];
+    for line in lines {
+        if !line.starts_with('#') {
+            match u64::from_str_radix(line, 16) {
+                Ok(fp) => fingerprints.push(fp),
+                Err(_) => return Err(invalid_manifest("invalid fingerprint")),
+            }
+            continue;
+        }
+        let (key, value) = match line[1..].find(':') {
+            Some(i) => (line[1..i + 1].trim(), line[i + 2..].trim()),
+            None => return Err(invalid_manifest("invalid manifest line")),
+        };
+        let ok = match key {
+            "version" => {
+                version = value.to_owned();
+                true
+            }
+            "theorem-set" => value.parse().map(|v| theorem_set = v).is_ok(),
+            "action" => value.parse().map(|a| config.action = a).is_ok(),
+            "seed" => value.parse().map(|s| config.seed = Some(s)).is_ok(),
+            "count" => value.parse().map(|n| config.count = Some(n)).is_ok(),
+            "difficulty" => {
+                config.difficulty = Some(value.to_owned());
+                true
+            }
+            "normalize" => value.parse().map(|n| config.normalize = Some(n)).is_ok(),
+            "in-format" => format::find(value).map(|f| config.in_format = Some(f)).is_some(),
+            "input" => {
+                config.input_files.push(value.to_owned());
+                true
+            }
+            _ => false,
+        };
+        if !ok {
+            return Err(invalid_manifest("invalid manifest entry"));
+        }
+    }
+    match config.action {
+        PackAction::Sample | PackAction::Shuffle => {}
+        PackAction::Alternatives | PackAction::Verify | PackAction::Progression => {
+            return Err(invalid_manifest("invalid action in manifest"));
+        }
+    }
+    if config.input_files.is_empty() {
+        return Err(invalid_manifest("manifest has no input"));
+    }
+    Ok(Manifest {
+        version: version,
+        theorem_set: theorem_set,
+        config: config,
+        fingerprints: fingerprints,
+    })
+}
+
+/// Builds the pack in the manifest again, and checks the fingerprints of the
+/// puzzles against the manifest, and those of the given packs if any.
+/// Returns the number of the mismatches.
+fn verify<I, O>(config: &PackConfig, input: &mut I, out: &mut O) -> AppResult<usize>
+    where I: Read,
+          O: Write
+{
+    let path = match config.manifest {
+        Some(ref path) => path,
+        None => return Err(invalid_manifest("verify needs --manifest")),
+    };
+    let manifest = try!(read_manifest(path));
+    if manifest.version != env!("CARGO_PKG_VERSION") {
+        try!(writeln!(out,
+                      "note: the manifest is written by version {}, verifying with {}",
+                      manifest.version,
+                      env!("CARGO_PKG_VERSION")));
+    }
+    if manifest.theorem_set != solver::THEOREM_SET_VERSION {
+        try!(writeln!(out,
+                      "note: the manifest uses theorem set version {}, verifying with {}",
+                      manifest.theorem_set,
+                      solver::THEOREM_SET_VERSION));
+    }
+
+    let rebuilt = try!(build(&manifest.config, input));
+    let mut num_failure = try!(check_fingerprints(out,
+                                                  "rebuilt pack",
+                                                  &manifest.fingerprints,
+                                                  &rebuilt));
+    for file in &config.input_files {
+        let mut f = try!(io_util::open(file));
+        let set = try!(read(&mut f));
+        num_failure += try!(check_fingerprints(out, file, &manifest.fingerprints, &set));
+    }
+    Ok(num_failure)
+}
+
+// Compares the fingerprints of the puzzles in the set with the expected ones,
+// and returns the number of the mismatches.
+fn check_fingerprints<O: Write>(out: &mut O,
+                                name: &str,
+                                expected: &[u64],
+                                set: &PuzzleSet)
+                                -> AppResult<usize> {
+    let actual = set.entries().iter().map(|e| e.puzzle.fingerprint()).collect::<Vec<_>>();
+    let mut num_failure = 0;
+    for (i, (e, a)) in expected.iter().zip(&actual).enumerate() {
+        if e != a {
+            try!(writeln!(out,
+                          "{} #{}: MISMATCH (expected {:016x}, found {:016x})",
+                          name,
+                          i + 1,
+                          e,
+                          a));
+            num_failure += 1;
+        }
+    }
+    if expected.len() != actual.len() {
+        try!(writeln!(out,
+                      "{}: MISMATCH (expected {} puzzles, found {})",
+                      name,
+                      expected.len(),
+                      actual.len()));
+        num_failure += 1;
+    }
+    if num_failure == 0 {
+        try!(writeln!(out, "{}: ok ({} puzzles)", name, actual.len()));
+    }
+    Ok(num_failure)
+}
+
+fn invalid_manifest(msg: &str) -> AppError {
+    io::Error::new(io::ErrorKind::InvalidData, msg).into()
+}
+
+/// Orders the puzzles for learning: by the score of `rate`, except that a
+/// technique is introduced by the easiest puzzle needing it alone, before the
+/// puzzles needing it with other new techniques.
+fn progression(set: PuzzleSet) -> AppResult<PuzzleSet> {
+    let mut rated = vec![];
+    for entry in set.into_entries() {
+        // The lines of the solutions recorded in the pack are not given.
+        let puzzle = entry.puzzle.hints_only();
+        let (_, stats) = try!(solver::solve_with_stats(&puzzle, &SolveOptions::default()));
+        let techniques = try!(techniques(&puzzle, &stats));
+        rated.push((stats.score(), techniques, entry));
+    }
+    rated.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+    let mut introduced = HashSet::new();
+    let mut ordered = PuzzleSet::new();
+    while !rated.is_empty() {
+        // If every puzzle introduces several techniques, they are introduced
+        // together by the easiest.
+        let i = rated.iter()
+                     .position(|r| new_techniques(&r.1, &introduced).len() <= 1)
+                     .unwrap_or(0);
+        let (score, techniques, mut entry) = rated.remove(i);
+        let new = new_techniques(&techniques, &introduced);
+        let breakdown = techniques.iter()
+                                  .map(|&(ref name, n)| format!("{} {}", name, n))
+                                  .collect::<Vec<_>>();
+        entry.metadata.set("score", &format!("{:.1}", score));
+        entry.metadata.set("techniques", &breakdown.join(", "));
+        if !new.is_empty() {
+            entry.metadata.set("introduces", &new.join(", "));
+        }
+        introduced.extend(new);
+        ordered.push(entry);
+    }
+    Ok(ordered)
+}
+
+// Returns the techniques used to solve the puzzle from the easiest, with the
+// numbers of the passes, or of the branches for `branch`.
+fn techniques(puzzle: &Puzzle, stats: &SolverStats) -> AppResult<Vec<(String, u32)>> {
+    let (deduced, deductions) = try!(solver::trace_deductions(puzzle, &SolveOptions::default()));
+    let mut used = BTreeMap::new();
+    for d in &deductions {
+        let level = match d.step {
+            Step::Theorems => 0,
+            Step::Connect => 1,
+            Step::Probe { depth } => 1 + depth,
+        };
+        used.entry(level).or_insert((solve::step_name(d.step), 0)).1 += d.passes;
+    }
+    if deduced.has_unknown_edge() {
+        let _ = used.insert(u32::MAX, ("branch".to_owned(), stats.branches));
+    }
+    Ok(used.into_iter().map(|(_, t)| t).collect())
+}
+
+fn new_techniques(techniques: &[(String, u32)], introduced: &HashSet<String>) -> Vec<String> {
+    techniques.iter()
+              .map(|&(ref name, _)| name)
+              .filter(|&name| !introduced.contains(name))
+              .cloned()
+              .collect()
+}
+
+/// Reports how different the alternative solutions are from the first one,
+/// and the hints which kill the nearest alternative. Returns the number of
+/// the solutions.
+fn alternatives<O: Write>(out: &mut O, puzzle: &Puzzle, max_solutions: usize) -> AppResult<usize> {
+    let mut solutions = vec![];
+    let _ = try!(solver::for_each_solution(puzzle, &SolveOptions::default(), |s| {
+        solutions.push(LoopBits::from_puzzle(s));
+        solutions.len() < max_solutions
+    }));
+
+    match solutions.len() {
+        0 => try!(writeln!(out, "  no solution")),
+        1 => try!(writeln!(out, "  unique")),
+        n if n >= max_solutions => try!(writeln!(out, "  {} or more solutions", n)),
+        n => try!(writeln!(out, "  {} solutions", n)),
+    }
+    if solutions.len() < 2 {
+        return Ok(solutions.len());
+    }
+
+    let first = &solutions[0];
+    let mut nearest = 1;
+    for (i, alt) in solutions.iter().enumerate().skip(1) {
+        try!(writeln!(out,
+                      "  alternative {}: distance {:.3} ({} edges differ)",
+                      i,
+                      first.distance(alt),
+                      first.hamming_distance(alt)));
+        if first.distance(alt) < first.distance(&solutions[nearest]) {
+            nearest = i;
+        }
+    }
+
+    // A hint given by the first solution kills the alternative if the numbers
+    // of the lines around the cell differ.
+    let hints = puzzle.points()
+                      .filter(|&p| puzzle.hint(p).is_none())
+                      .filter(|&p| first.cell_lines(p) != solutions[nearest].cell_lines(p))
+                      .map(|p| format!("{} at {}", first.cell_lines(p), coord::format(p)))
+                      .collect::<Vec<_>>();
+    try!(writeln!(out, "  nearest alternative {}: add {}", nearest, hints.join(", ")));
+    Ok(solutions.len())
+}
+
+/// Reads a pack, or a puzzle in the format as a pack of it.
+fn read_as<T: Read>(format: Option<&PuzzleFormat>, input: &mut T) -> AppResult<PuzzleSet> {
+    let format = match format {
+        Some(format) => format,
+        None => return read(input),
+    };
+    let buf = try!(io_util::read_to_string(input));
+    let mut set = PuzzleSet::new();
+    set.push(Entry::new(try!(format.parse(&buf))));
+    Ok(set)
+}
+
+/// Reads a pack.
+pub fn read<T: Read>(input: &mut T) -> AppResult<PuzzleSet> {
+    let buf = try!(io_util::read_to_string(input));
+    parse(&buf)
+}
+
+/// Parses the pack, checking the version of its format if it has the
+/// envelope.
+pub fn parse(buf: &str) -> AppResult<PuzzleSet> {
+    try!(envelope::check_text(&envelope::PACK, buf));
+    Ok(try!(buf.parse::<PuzzleSet>()))
+}
+
+/// Writes the pack in the envelope.
+pub fn write<W: Write>(output: &mut W, set: &PuzzleSet) -> io::Result<()> {
+    try!(writeln!(output, "{}", envelope::header(&envelope::PACK)));
+    write!(output, "{}", set)
+}
+
+/// Creates the random number generator used by the commands. The same seed
+/// gives the same sequence on any platform; without a seed the generator is
+/// seeded by the OS.
+pub fn new_rng(seed: Option<u64>) -> AppResult<Isaac64Rng> {
+    match seed {
+        Some(seed) => Ok(seeded_rng(seed)),
+        None => Ok(seeded_rng(try!(OsRng::new()).next_u64())),
+    }
+}
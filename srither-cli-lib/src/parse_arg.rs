@@ -0,0 +1,2425 @@
+// Copyright (c) 2016 srither developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use std::{env, io, process};
+use std::io::prelude::*;
+use std::str::FromStr;
+use argparse::{ArgumentParser, IncrBy, List, Store, StoreOption, StoreTrue};
+
+use srither_core::format::{self, PuzzleFormat};
+use srither_core::geom::{Point, Size as GeomSize};
+use srither_core::orientation::Normalization;
+use srither_gen::{Difficulty, Symmetry};
+use srither_solver::{self as solver, Backend, Backtrack, HintLayout, RestartPolicy};
+use srither_testdata::Category;
+
+use coord::{self, CoordStyle};
+use pprint::{self, ColorPolicy, Config as PpConfig, Mode as PpMode};
+use size_spec;
+use theme::Theme;
+
+#[derive(Copy, Clone, Debug)]
+enum CommandType {
+    Solve,
+    Test,
+    Bench,
+    Pack,
+    Compare,
+    Counterexample,
+    Repl,
+    Rate,
+    Transform,
+    Soak,
+    Recheck,
+    Convert,
+    Render,
+    Export,
+    Play,
+    Checkpoint,
+    Generate,
+    History,
+    Edit,
+}
+
+impl CommandType {
+    fn setup_parser<'parser>(&'parser mut self,
+                             ap: &mut ArgumentParser<'parser>,
+                             color: &'parser mut ColorPolicy,
+                             coords: &'parser mut CoordStyle,
+                             verbosity: &'parser mut u32,
+                             history: &'parser mut Option<String>,
+                             args: &'parser mut Vec<String>) {
+        ap.set_description("Slither link solver - Command line interface");
+        let _ = ap.refer(color)
+                  .add_option(&["--color"],
+                              Store,
+                              "when to color the output (auto, always, never) [default: auto]")
+                  .metavar("WHEN");
+        let _ = ap.refer(coords)
+                  .add_option(&["--coords"],
+                              Store,
+                              "how to write and read the cells (rc0, rc1, xy0, xy1) \
+                               [default: rc0]")
+                  .metavar("STYLE");
+        let _ = ap.refer(verbosity)
+                  .add_option(&["-v", "--verbose"],
+                              IncrBy(1),
+                              "write diagnostics of the solver to stderr: step summaries (-v), \
+                               passes (-vv) and boards (-vvv)");
+        let _ = ap.refer(history)
+                  .add_option(&["--history"],
+                              StoreOption,
+                              "record the runs to the local history file, for `history` \
+                               [default: $SRITHER_HISTORY, or none]")
+                  .metavar("FILE");
+        let _ = ap.refer(self)
+                  .required()
+                  .add_argument("command",
+                                Store,
+                                "command to run (solve, test, bench, pack, compare, \
+                                 counterexample, repl, rate, transform, soak, recheck, \
+                                 convert, render, export, play, checkpoint, generate, history, \
+                                 edit)");
+        let _ = ap.refer(args)
+                  .add_argument("arguments", List, "arguments for command");
+        ap.stop_on_first_argument(true);
+    }
+}
+
+impl Default for CommandType {
+    fn default() -> CommandType {
+        CommandType::Solve
+    }
+}
+
+impl FromStr for CommandType {
+    type Err = ();
+
+    fn from_str(src: &str) -> Result<CommandType, ()> {
+        match src {
+            "solve" => Ok(CommandType::Solve),
+            "test" => Ok(CommandType::Test),
+            "bench" => Ok(CommandType::Bench),
+            "pack" => Ok(CommandType::Pack),
+            "compare" => Ok(CommandType::Compare),
+            "counterexample" => Ok(CommandType::Counterexample),
+            "repl" => Ok(CommandType::Repl),
+            "rate" => Ok(CommandType::Rate),
+            "transform" => Ok(CommandType::Transform),
+            "soak" => Ok(CommandType::Soak),
+            "recheck" => Ok(CommandType::Recheck),
+            "convert" => Ok(CommandType::Convert),
+            "render" => Ok(CommandType::Render),
+            "export" => Ok(CommandType::Export),
+            "play" => Ok(CommandType::Play),
+            "checkpoint" => Ok(CommandType::Checkpoint),
+            "generate" => Ok(CommandType::Generate),
+            "history" => Ok(CommandType::History),
+            "edit" => Ok(CommandType::Edit),
+            _ => Err(()),
+        }
+    }
+}
+
+trait SetupParser {
+    fn setup_parser<'parser>(&'parser mut self, ap: &mut ArgumentParser<'parser>);
+}
+
+/// The options of the commands processing the puzzles by the pipeline.
+#[derive(Copy, Clone, Debug)]
+struct PipelineArgs {
+    jobs: usize,
+    timeout: Option<u64>,
+    progress: bool,
+}
+
+impl PipelineArgs {
+    fn setup_parser<'parser>(&'parser mut self, ap: &mut ArgumentParser<'parser>) {
+        let _ = ap.refer(&mut self.jobs)
+                  .add_option(&["--jobs"],
+                              Store,
+                              "number of the puzzles processed in parallel [default: 1]")
+                  .metavar("N");
+        let _ = ap.refer(&mut self.timeout)
+                  .add_option(&["--timeout"],
+                              StoreOption,
+                              "give up each puzzle after the seconds.")
+                  .metavar("SECS");
+        let _ = ap.refer(&mut self.progress)
+                  .add_option(&["--progress"],
+                              StoreTrue,
+                              "show the progress on the standard error.");
+    }
+
+    fn config(&self, command: &str) -> PipelineConfig {
+        if self.jobs == 0 || self.timeout == Some(0) {
+            let _ = writeln!(&mut io::stderr(),
+                             "{}: --jobs and --timeout must be positive",
+                             command);
+            process::exit(2);
+        }
+        PipelineConfig {
+            jobs: self.jobs,
+            timeout: self.timeout,
+            progress: self.progress,
+        }
+    }
+}
+
+impl Default for PipelineArgs {
+    fn default() -> PipelineArgs {
+        PipelineArgs {
+            jobs: 1,
+            timeout: None,
+            progress: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct SolveArgs {
+    derive_all: bool,
+    output_mode: OutputModeArg,
+    theme: Option<String>,
+    width: Size,
+    height: Size,
+    cache_dir: Option<String>,
+    strategy_file: Option<String>,
+    profile_steps: bool,
+    count: bool,
+    max_guess_depth: Option<u32>,
+    max_solutions: Option<u64>,
+    solutions: Option<u64>,
+    restart: Option<u64>,
+    restart_seed: Option<u64>,
+    disabled_theorems: TheoremList,
+    effort: bool,
+    explain: bool,
+    normalize: Option<Normalization>,
+    resume_file: Option<String>,
+    slice: Option<u64>,
+    in_format: Option<PuzzleFormatArg>,
+    format: Format,
+    json: bool,
+    pipeline: PipelineArgs,
+    input_files: Vec<String>,
+}
+
+impl SetupParser for SolveArgs {
+    fn setup_parser<'parser>(&'parser mut self, ap: &mut ArgumentParser<'parser>) {
+        ap.set_description("Solve the given problem(s)");
+        let _ = ap.refer(&mut self.derive_all)
+                  .add_option(&["--all"], StoreTrue, "derive all solutions (if any).");
+        let _ = ap.refer(&mut self.output_mode)
+                  .add_option(&["--output-mode"],
+                              Store,
+                              "specify output mode (auto, pretty-color, pretty-contrast, \
+                               pretty-ascii, emoji, describe, raw, path, loopy-save, none) \
+                               [default: auto]");
+        let _ = ap.refer(&mut self.theme)
+                  .add_option(&["--theme"],
+                              StoreOption,
+                              "colors and glyphs of the pretty output, a built-in theme \
+                               (default, print, dark, high-contrast) or a theme file \
+                               [default: default]")
+                  .metavar("THEME");
+        let _ = ap.refer(&mut self.width)
+                  .add_option(&["--width"], Store, "specify cell width [default: 2]");
+        let _ = ap.refer(&mut self.height)
+                  .add_option(&["--height"], Store, "specify cell width [default: 1]");
+        let _ = ap.refer(&mut self.cache_dir)
+                  .add_option(&["--cache"],
+                              StoreOption,
+                              "cache solutions and ratings in the directory.")
+                  .metavar("DIR");
+        let _ = ap.refer(&mut self.strategy_file)
+                  .add_option(&["--strategy"],
+                              StoreOption,
+                              "solve with the deduction steps in the file.")
+                  .metavar("FILE");
+        let _ = ap.refer(&mut self.profile_steps)
+                  .add_option(&["--profile-steps"],
+                              StoreTrue,
+                              "print the time spent on each step of the solver.");
+        let _ = ap.refer(&mut self.count)
+                  .add_option(&["--count"],
+                              StoreTrue,
+                              "print the number of the solutions instead of them.");
+        let _ = ap.refer(&mut self.max_guess_depth)
+                  .add_option(&["--max-guess-depth"],
+                              StoreOption,
+                              "fail if more nested assumptions are needed.")
+                  .metavar("N");
+        let _ = ap.refer(&mut self.max_solutions)
+                  .add_option(&["--max-solutions"],
+                              StoreOption,
+                              "derive or count at most N solutions, which is required to \
+                               derive all solutions of a puzzle with too few hints.")
+                  .metavar("N");
+        let _ = ap.refer(&mut self.solutions)
+                  .add_option(&["--solutions"],
+                              StoreOption,
+                              "print the first N solutions as soon as each is found, for the \
+                               examples of a puzzle with many solutions.")
+                  .metavar("N");
+        let _ = ap.refer(&mut self.restart)
+                  .add_option(&["--restart"],
+                              StoreOption,
+                              "restart the search finding no solution in N nodes, doubling N \
+                               at each restart, for the puzzles the search gets stuck on.")
+                  .metavar("N");
+        let _ = ap.refer(&mut self.restart_seed)
+                  .add_option(&["--restart-seed"],
+                              StoreOption,
+                              "seed of the order of the cells branched on after the restarts \
+                               [default: 0]")
+                  .metavar("SEED");
+        let _ = ap.refer(&mut self.disabled_theorems)
+                  .add_option(&["--disable-theorem"],
+                              Store,
+                              "disable the theorems given by indices, ranges or group names \
+                               (e.g. 0-2,adjacent-3s).")
+                  .metavar("LIST");
+        let _ = ap.refer(&mut self.effort)
+                  .add_option(&["--effort"],
+                              StoreTrue,
+                              "print the number of the search nodes fixing each edge.");
+        let _ = ap.refer(&mut self.explain)
+                  .add_option(&["--explain"],
+                              StoreTrue,
+                              "print the course of the deduction in words.");
+        let _ = ap.refer(&mut self.normalize)
+                  .add_option(&["--normalize"],
+                              StoreOption,
+                              "solve the puzzles turned to the orientation (portrait, \
+                               canonical), and turn the solutions back.")
+                  .metavar("MODE");
+        let _ = ap.refer(&mut self.resume_file)
+                  .add_option(&["--resume-file"],
+                              StoreOption,
+                              "derive all solutions by iterative deepening, saving the state \
+                               of the search to the file and resuming from it.")
+                  .metavar("FILE");
+        let _ = ap.refer(&mut self.slice)
+                  .add_option(&["--slice"],
+                              StoreOption,
+                              "stop the search of --resume-file after the seconds.")
+                  .metavar("SECS");
+        let _ = ap.refer(&mut self.in_format)
+                  .add_option(&["--in-format"],
+                              StoreOption,
+                              "format of the puzzles (text, csv, json, puzzlink, loopy, \
+                               loopy-save, janko, compact) [default: detected from the input]")
+                  .metavar("FORMAT");
+        let _ = ap.refer(&mut self.format)
+                  .add_option(&["--format"], Store, "output format (text, json) [default: text]")
+                  .metavar("FORMAT");
+        let _ = ap.refer(&mut self.json)
+                  .add_option(&["--json"],
+                              StoreTrue,
+                              "print a JSON object per puzzle, the same as --format json.");
+        self.pipeline.setup_parser(ap);
+        let _ = ap.refer(&mut self.input_files)
+                  .add_argument("input_files", List, "puzzle files to solve.");
+    }
+}
+
+impl SolveArgs {
+    fn output_mode(&self) -> OutputMode {
+        let mut theme_name = "default";
+        let ppmode = match self.output_mode {
+            OutputModeArg::Auto => {
+                if pprint::is_pprintable() {
+                    PpMode::Color
+                } else {
+                    PpMode::Ascii
+                }
+            }
+            OutputModeArg::PrettyColor => PpMode::Color,
+            OutputModeArg::PrettyContrast => {
+                theme_name = "high-contrast";
+                PpMode::Color
+            }
+            OutputModeArg::PrettyAscii => PpMode::Ascii,
+            OutputModeArg::Emoji => PpMode::Emoji,
+            OutputModeArg::Describe => return OutputMode::Describe,
+            OutputModeArg::Raw => return OutputMode::Raw,
+            OutputModeArg::Path => return OutputMode::Path,
+            OutputModeArg::LoopySave => return OutputMode::LoopySave,
+            OutputModeArg::None => return OutputMode::None,
+        };
+        let theme_name = self.theme.as_ref().map(|s| &s[..]).unwrap_or(theme_name);
+        let theme = match Theme::find(theme_name) {
+            Ok(theme) => theme,
+            Err(e) => {
+                let _ = writeln!(&mut io::stderr(),
+                                 "Solve: cannot load the theme `{}`: {} (built-in: {})",
+                                 theme_name,
+                                 e,
+                                 Theme::names().join(", "));
+                process::exit(2);
+            }
+        };
+        OutputMode::Pretty(PpConfig {
+            mode: ppmode,
+            cell_width: self.width.0,
+            cell_height: self.height.0,
+            theme: theme,
+        })
+    }
+}
+
+impl Default for SolveArgs {
+    fn default() -> SolveArgs {
+        SolveArgs {
+            derive_all: false,
+            output_mode: OutputModeArg::Auto,
+            theme: None,
+            width: Size(2),
+            height: Size(1),
+            cache_dir: None,
+            strategy_file: None,
+            profile_steps: false,
+            count: false,
+            max_guess_depth: None,
+            max_solutions: None,
+            solutions: None,
+            restart: None,
+            restart_seed: None,
+            disabled_theorems: TheoremList(vec![]),
+            effort: false,
+            explain: false,
+            normalize: None,
+            resume_file: None,
+            slice: None,
+            in_format: None,
+            format: Format::Text,
+            json: false,
+            pipeline: PipelineArgs::default(),
+            input_files: vec![],
+        }
+    }
+}
+
+impl Into<Config> for SolveArgs {
+    fn into(self) -> Config {
+        if self.solutions == Some(0) {
+            let _ = writeln!(&mut io::stderr(), "Solve: --solutions must be positive");
+            process::exit(2);
+        }
+        if self.solutions.is_some() && (self.derive_all || self.count) {
+            let _ = writeln!(&mut io::stderr(),
+                             "Solve: --solutions cannot be used with --all or --count");
+            process::exit(2);
+        }
+        if self.slice == Some(0) {
+            let _ = writeln!(&mut io::stderr(), "Solve: --slice must be positive");
+            process::exit(2);
+        }
+        if self.restart == Some(0) {
+            let _ = writeln!(&mut io::stderr(), "Solve: --restart must be positive");
+            process::exit(2);
+        }
+        if self.restart_seed.is_some() && self.restart.is_none() {
+            let _ = writeln!(&mut io::stderr(), "Solve: --restart-seed needs --restart");
+            process::exit(2);
+        }
+        let seed = self.restart_seed.unwrap_or(0);
+        let restart = self.restart.map(|budget| {
+            RestartPolicy {
+                node_budget: budget,
+                seed: seed,
+            }
+        });
+        if self.slice.is_some() && self.resume_file.is_none() {
+            let _ = writeln!(&mut io::stderr(), "Solve: --slice needs --resume-file");
+            process::exit(2);
+        }
+        if self.resume_file.is_some() &&
+           (self.solutions.is_some() || self.normalize.is_some() || self.cache_dir.is_some() ||
+            self.restart.is_some() || self.profile_steps || self.effort || self.explain) {
+            let _ = writeln!(&mut io::stderr(),
+                             "Solve: --resume-file cannot be used with --solutions, \
+                              --normalize, --cache, --restart, --profile-steps, --effort or \
+                              --explain");
+            process::exit(2);
+        }
+        Config::Solve(SolveConfig {
+            derive_all: self.derive_all,
+            output_mode: self.output_mode(),
+            cache_dir: self.cache_dir,
+            strategy_file: self.strategy_file,
+            profile_steps: self.profile_steps,
+            count: self.count,
+            max_guess_depth: self.max_guess_depth,
+            max_solutions: self.max_solutions,
+            solutions: self.solutions,
+            restart: restart,
+            disabled_theorems: self.disabled_theorems.0,
+            effort: self.effort,
+            explain: self.explain,
+            normalize: self.normalize,
+            resume_file: self.resume_file,
+            slice: self.slice,
+            in_format: self.in_format.map(|f| f.0),
+            format: if self.json {
+                Format::Json
+            } else {
+                self.format
+            },
+            pipeline: self.pipeline.config("Solve"),
+            input_files: self.input_files,
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Size(usize);
+impl FromStr for Size {
+    type Err = ();
+
+    fn from_str(src: &str) -> Result<Size, ()> {
+        let val = usize::from_str(src);
+        if let Ok(v) = val {
+            if v != 0 {
+                return Ok(Size(v));
+            }
+        }
+        Err(())
+
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+enum OutputModeArg {
+    Auto,
+    PrettyColor,
+    PrettyContrast,
+    PrettyAscii,
+    Emoji,
+    Describe,
+    Raw,
+    Path,
+    LoopySave,
+    None,
+}
+
+impl FromStr for OutputModeArg {
+    type Err = ();
+
+    fn from_str(src: &str) -> Result<OutputModeArg, ()> {
+        match src {
+            "auto" => Ok(OutputModeArg::Auto),
+            "pretty-color" => Ok(OutputModeArg::PrettyColor),
+            "pretty-contrast" => Ok(OutputModeArg::PrettyContrast),
+            "pretty-ascii" => Ok(OutputModeArg::PrettyAscii),
+            "emoji" => Ok(OutputModeArg::Emoji),
+            "describe" => Ok(OutputModeArg::Describe),
+            "raw" => Ok(OutputModeArg::Raw),
+            "path" => Ok(OutputModeArg::Path),
+            "loopy-save" => Ok(OutputModeArg::LoopySave),
+            "none" => Ok(OutputModeArg::None),
+            _ => Err(()),
+        }
+    }
+}
+
+// The indices of the theorems given by `--disable-theorem`.
+#[derive(Clone, Debug)]
+struct TheoremList(Vec<usize>);
+
+impl FromStr for TheoremList {
+    type Err = ();
+
+    fn from_str(src: &str) -> Result<TheoremList, ()> {
+        solver::parse_theorems(src).map(TheoremList).ok_or(())
+    }
+}
+
+// The categories of the corpus given by `--corpus`, as names separated by
+// commas or `all`.
+#[derive(Clone, Debug)]
+struct CategoryList(Vec<Category>);
+
+impl FromStr for CategoryList {
+    type Err = ();
+
+    fn from_str(src: &str) -> Result<CategoryList, ()> {
+        if src == "all" {
+            return Ok(CategoryList(Category::all().to_vec()));
+        }
+        let mut categories = vec![];
+        for name in src.split(',') {
+            categories.push(try!(Category::from_name(name.trim()).ok_or(())));
+        }
+        Ok(CategoryList(categories))
+    }
+}
+
+// The layouts of the hints given by `--forbid`, as the string given and the
+// layouts parsed from it.
+#[derive(Clone, Debug)]
+struct LayoutList(String, Vec<HintLayout>);
+
+impl FromStr for LayoutList {
+    type Err = ();
+
+    fn from_str(src: &str) -> Result<LayoutList, ()> {
+        let mut layouts = vec![];
+        for layout in src.split(',') {
+            layouts.push(try!(layout.parse::<HintLayout>().map_err(|_| ())));
+        }
+        Ok(LayoutList(src.to_string(), layouts))
+    }
+}
+
+#[derive(Clone, Debug)]
+struct TestArgs {
+    derive_all: bool,
+    strategy_file: Option<String>,
+    disabled_theorems: TheoremList,
+    verify: bool,
+    corpus: CategoryList,
+    input_files: Vec<String>,
+}
+
+impl SetupParser for TestArgs {
+    fn setup_parser<'parser>(&'parser mut self, ap: &mut ArgumentParser<'parser>) {
+        ap.set_description("Test the given problem(s)");
+        let _ = ap.refer(&mut self.derive_all)
+                  .add_option(&["--all"], StoreTrue, "derive all solutions (if any).");
+        let _ = ap.refer(&mut self.strategy_file)
+                  .add_option(&["--strategy"],
+                              StoreOption,
+                              "solve with the deduction steps in the file.")
+                  .metavar("FILE");
+        let _ = ap.refer(&mut self.disabled_theorems)
+                  .add_option(&["--disable-theorem"],
+                              Store,
+                              "disable the theorems given by indices, ranges or group names \
+                               (e.g. 0-2,adjacent-3s).")
+                  .metavar("LIST");
+        let _ = ap.refer(&mut self.verify)
+                  .add_option(&["--verify"],
+                              StoreTrue,
+                              "fail if a solution has an edge neither line nor cross.");
+        let _ = ap.refer(&mut self.corpus)
+                  .add_option(&["--corpus"],
+                              Store,
+                              "also test the bundled puzzles of the categories (easy, hard, \
+                               pathological or all), checking their numbers of solutions.")
+                  .metavar("CATEGORIES");
+        let _ = ap.refer(&mut self.input_files)
+                  .add_argument("input_files", List, "puzzle files to solve.");
+    }
+}
+
+impl Default for TestArgs {
+    fn default() -> TestArgs {
+        TestArgs {
+            derive_all: false,
+            strategy_file: None,
+            disabled_theorems: TheoremList(vec![]),
+            verify: false,
+            corpus: CategoryList(vec![]),
+            input_files: vec![],
+        }
+    }
+}
+
+impl Into<Config> for TestArgs {
+    fn into(self) -> Config {
+        Config::Test(TestConfig {
+            derive_all: self.derive_all,
+            strategy_file: self.strategy_file,
+            disabled_theorems: self.disabled_theorems.0,
+            verify: self.verify,
+            corpus: self.corpus.0,
+            input_files: self.input_files,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+struct BenchArgs {
+    derive_all: bool,
+    only_hardest: Option<usize>,
+    corpus: CategoryList,
+    backtrack: BacktrackList,
+    input_files: Vec<String>,
+}
+
+impl SetupParser for BenchArgs {
+    fn setup_parser<'parser>(&'parser mut self, ap: &mut ArgumentParser<'parser>) {
+        ap.set_description("Bench the given problem(s)");
+        let _ = ap.refer(&mut self.derive_all)
+                  .add_option(&["--all"], StoreTrue, "derive all solutions (if any).");
+        let _ = ap.refer(&mut self.only_hardest)
+                  .add_option(&["--only-hardest"],
+                              StoreOption,
+                              "measure only hardest n problems.")
+                  .metavar("n");
+        let _ = ap.refer(&mut self.corpus)
+                  .add_option(&["--corpus"],
+                              Store,
+                              "also measure the bundled puzzles of the categories (easy, hard, \
+                               pathological or all).")
+                  .metavar("CATEGORIES");
+        let _ = ap.refer(&mut self.backtrack)
+                  .add_option(&["--backtrack"],
+                              Store,
+                              "comma-separated strategies keeping the branches to search, each \
+                               measured (auto, clone, snapshot) [default: auto]")
+                  .metavar("STRATEGIES");
+        let _ = ap.refer(&mut self.input_files)
+                  .add_argument("input_files", List, "puzzle files to solve.");
+    }
+}
+
+impl Default for BenchArgs {
+    fn default() -> BenchArgs {
+        BenchArgs {
+            derive_all: false,
+            only_hardest: None,
+            corpus: CategoryList(vec![]),
+            backtrack: BacktrackList(vec![Backtrack::Auto]),
+            input_files: vec![],
+        }
+    }
+}
+
+impl Into<Config> for BenchArgs {
+    fn into(self) -> Config {
+        Config::Bench(BenchConfig {
+            derive_all: self.derive_all,
+            only_hardest: self.only_hardest,
+            corpus: self.corpus.0,
+            backtrack: self.backtrack.0,
+            input_files: self.input_files,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+struct BacktrackList(Vec<Backtrack>);
+
+impl FromStr for BacktrackList {
+    type Err = ();
+
+    fn from_str(src: &str) -> Result<BacktrackList, ()> {
+        let mut list = vec![];
+        for name in src.split(',') {
+            let backtrack = match name.trim() {
+                "auto" => Backtrack::Auto,
+                "clone" => Backtrack::Clone,
+                "snapshot" => Backtrack::Snapshot,
+                _ => return Err(()),
+            };
+            list.push(backtrack);
+        }
+        Ok(BacktrackList(list))
+    }
+}
+
+#[derive(Clone, Debug)]
+struct PackArgs {
+    action: PackAction,
+    count: Option<usize>,
+    difficulty: Option<String>,
+    seed: Option<u64>,
+    max_solutions: usize,
+    normalize: Option<Normalization>,
+    in_format: Option<PuzzleFormatArg>,
+    manifest: Option<String>,
+    input_files: Vec<String>,
+}
+
+impl SetupParser for PackArgs {
+    fn setup_parser<'parser>(&'parser mut self, ap: &mut ArgumentParser<'parser>) {
+        ap.set_description("Sample, shuffle or find alternative solutions of puzzles in the \
+                            given pack file(s), or verify packs against a manifest");
+        let _ = ap.refer(&mut self.action)
+                  .required()
+                  .add_argument("action",
+                                Store,
+                                "action to run (sample, shuffle, alternatives, verify, \
+                                 progression)");
+        let _ = ap.refer(&mut self.count)
+                  .add_option(&["--count"],
+                              StoreOption,
+                              "number of puzzles to sample [default: all]")
+                  .metavar("N");
+        let _ = ap.refer(&mut self.difficulty)
+                  .add_option(&["--difficulty"],
+                              StoreOption,
+                              "take only puzzles with the given difficulty metadata")
+                  .metavar("DIFFICULTY");
+        let _ = ap.refer(&mut self.seed)
+                  .add_option(&["--seed"], StoreOption, "seed of the random number generator")
+                  .metavar("S");
+        let _ = ap.refer(&mut self.max_solutions)
+                  .add_option(&["--max-solutions"],
+                              Store,
+                              "number of solutions to find for alternatives [default: 100]")
+                  .metavar("N");
+        let _ = ap.refer(&mut self.normalize)
+                  .add_option(&["--normalize"],
+                              StoreOption,
+                              "turn the puzzles to the orientation (portrait, canonical), and \
+                               record it as orientation metadata")
+                  .metavar("MODE");
+        let _ = ap.refer(&mut self.in_format)
+                  .add_option(&["--in-format"],
+                              StoreOption,
+                              "read each file as a puzzle in the format (text, csv, json, \
+                               puzzlink, loopy, loopy-save, janko, compact) instead of a pack")
+                  .metavar("FORMAT");
+        let _ = ap.refer(&mut self.manifest)
+                  .add_option(&["--manifest"],
+                              StoreOption,
+                              "write the manifest of the built pack to the file, or read it \
+                               to verify")
+                  .metavar("FILE");
+        let _ = ap.refer(&mut self.input_files)
+                  .add_argument("input_files", List, "pack files to read.");
+    }
+}
+
+impl Default for PackArgs {
+    fn default() -> PackArgs {
+        PackArgs {
+            action: PackAction::Shuffle,
+            count: None,
+            difficulty: None,
+            seed: None,
+            max_solutions: 100,
+            normalize: None,
+            in_format: None,
+            manifest: None,
+            input_files: vec![],
+        }
+    }
+}
+
+impl Into<Config> for PackArgs {
+    fn into(self) -> Config {
+        Config::Pack(PackConfig {
+            action: self.action,
+            count: self.count,
+            difficulty: self.difficulty,
+            seed: self.seed,
+            max_solutions: self.max_solutions,
+            normalize: self.normalize,
+            in_format: self.in_format.map(|f| f.0),
+            manifest: self.manifest,
+            input_files: self.input_files,
+        })
+    }
+}
+
+/// The action of `pack`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PackAction {
+    /// Samples the puzzles.
+    Sample,
+    /// Shuffles the puzzles.
+    Shuffle,
+    /// Finds the alternative solutions of the puzzles.
+    Alternatives,
+    /// Verifies the packs against the manifest.
+    Verify,
+    /// Orders the puzzles for learning the techniques.
+    Progression,
+}
+
+impl FromStr for PackAction {
+    type Err = ();
+
+    fn from_str(src: &str) -> Result<PackAction, ()> {
+        match src {
+            "sample" => Ok(PackAction::Sample),
+            "shuffle" => Ok(PackAction::Shuffle),
+            "alternatives" => Ok(PackAction::Alternatives),
+            "verify" => Ok(PackAction::Verify),
+            "progression" => Ok(PackAction::Progression),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct CompareArgs {
+    backends: BackendList,
+    format: Format,
+    inputs: Vec<String>,
+}
+
+impl SetupParser for CompareArgs {
+    fn setup_parser<'parser>(&'parser mut self, ap: &mut ArgumentParser<'parser>) {
+        ap.set_description("Compare solutions and timings of the solver backends");
+        let _ = ap.refer(&mut self.backends)
+                  .add_option(&["--backends"],
+                              Store,
+                              "comma-separated backends to compare (native, simple, adaptive) \
+                               [default: native,simple]")
+                  .metavar("BACKENDS");
+        let _ = ap.refer(&mut self.format)
+                  .add_option(&["--format"], Store, "output format (text, json) [default: text]")
+                  .metavar("FORMAT");
+        let _ = ap.refer(&mut self.inputs)
+                  .add_argument("inputs", List, "puzzle files or directories to solve.");
+    }
+}
+
+impl Default for CompareArgs {
+    fn default() -> CompareArgs {
+        CompareArgs {
+            backends: BackendList(vec![Backend::Native, Backend::Simple]),
+            format: Format::Text,
+            inputs: vec![],
+        }
+    }
+}
+
+impl Into<Config> for CompareArgs {
+    fn into(self) -> Config {
+        Config::Compare(CompareConfig {
+            backends: self.backends.0,
+            format: self.format,
+            inputs: self.inputs,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+struct BackendList(Vec<Backend>);
+
+impl FromStr for BackendList {
+    type Err = ();
+
+    fn from_str(src: &str) -> Result<BackendList, ()> {
+        let mut backends = vec![];
+        for name in src.split(',') {
+            let backend = match name.trim() {
+                "native" => Backend::Native,
+                "simple" => Backend::Simple,
+                "adaptive" => Backend::Adaptive,
+                _ => return Err(()),
+            };
+            backends.push(backend);
+        }
+        Ok(BackendList(backends))
+    }
+}
+
+#[derive(Clone, Debug)]
+struct CounterexampleArgs {
+    input_files: Vec<String>,
+}
+
+impl SetupParser for CounterexampleArgs {
+    fn setup_parser<'parser>(&'parser mut self, ap: &mut ArgumentParser<'parser>) {
+        ap.set_description("Search counterexamples of the given theorem(s)");
+        let _ = ap.refer(&mut self.input_files)
+                  .add_argument("input_files", List, "theorem files to check.");
+    }
+}
+
+impl Default for CounterexampleArgs {
+    fn default() -> CounterexampleArgs {
+        CounterexampleArgs { input_files: vec![] }
+    }
+}
+
+impl Into<Config> for CounterexampleArgs {
+    fn into(self) -> Config {
+        Config::Counterexample(CounterexampleConfig { input_files: self.input_files })
+    }
+}
+
+#[derive(Clone, Debug)]
+struct ReplArgs {
+    input_file: String,
+}
+
+impl SetupParser for ReplArgs {
+    fn setup_parser<'parser>(&'parser mut self, ap: &mut ArgumentParser<'parser>) {
+        ap.set_description("Inspect the given puzzle interactively");
+        let _ = ap.refer(&mut self.input_file)
+                  .required()
+                  .add_argument("input_file", Store, "puzzle file to inspect.");
+    }
+}
+
+impl Default for ReplArgs {
+    fn default() -> ReplArgs {
+        ReplArgs { input_file: String::new() }
+    }
+}
+
+impl Into<Config> for ReplArgs {
+    fn into(self) -> Config {
+        Config::Repl(ReplConfig { input_file: self.input_file })
+    }
+}
+
+#[derive(Clone, Debug)]
+struct PlayArgs {
+    input_file: Option<String>,
+    save_file: Option<String>,
+    replay_file: Option<String>,
+    hint_policy: HintPolicyArg,
+    learn_times: u32,
+    precompute: bool,
+}
+
+impl SetupParser for PlayArgs {
+    fn setup_parser<'parser>(&'parser mut self, ap: &mut ArgumentParser<'parser>) {
+        ap.set_description("Play the given puzzle, saving the progress as it goes");
+        let _ = ap.refer(&mut self.save_file)
+                  .add_option(&["--save"],
+                              StoreOption,
+                              "log of the changes to resume and append to [default: \
+                               INPUT_FILE.play]")
+                  .metavar("FILE");
+        let _ = ap.refer(&mut self.replay_file)
+                  .add_option(&["--replay"], StoreOption, "replay the changes in the log.")
+                  .metavar("SAVE");
+        let _ = ap.refer(&mut self.hint_policy)
+                  .add_option(&["--hint-policy"],
+                              Store,
+                              "how the `hint` command chooses the hints (easiest, reinforce: \
+                               the steps newly learned in the session first) [default: \
+                               reinforce]")
+                  .metavar("POLICY");
+        let _ = ap.refer(&mut self.learn_times)
+                  .add_option(&["--learn-times"],
+                              Store,
+                              "times to apply a step to learn it for the reinforce policy \
+                               [default: 3]")
+                  .metavar("N");
+        let _ = ap.refer(&mut self.precompute)
+                  .add_option(&["--precompute"],
+                              StoreTrue,
+                              "solve the puzzle and deduce the order of its edges at the start, \
+                               answering `hint` and `check` from them");
+        let _ = ap.refer(&mut self.input_file)
+                  .add_argument("input_file", StoreOption, "puzzle file to play.");
+    }
+}
+
+impl Default for PlayArgs {
+    fn default() -> PlayArgs {
+        PlayArgs {
+            input_file: None,
+            save_file: None,
+            replay_file: None,
+            hint_policy: HintPolicyArg::Reinforce,
+            learn_times: 3,
+            precompute: false,
+        }
+    }
+}
+
+impl Into<Config> for PlayArgs {
+    fn into(self) -> Config {
+        Config::Play(PlayConfig {
+            input_file: self.input_file,
+            save_file: self.save_file,
+            replay_file: self.replay_file,
+            hint_policy: self.hint_policy,
+            learn_times: self.learn_times,
+            precompute: self.precompute,
+        })
+    }
+}
+
+/// How `hint` of `play` chooses the hints.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum HintPolicyArg {
+    /// The easiest steps first.
+    Easiest,
+    /// The steps newly learned in the session first.
+    Reinforce,
+}
+
+impl FromStr for HintPolicyArg {
+    type Err = ();
+
+    fn from_str(src: &str) -> Result<HintPolicyArg, ()> {
+        match src {
+            "easiest" => Ok(HintPolicyArg::Easiest),
+            "reinforce" => Ok(HintPolicyArg::Reinforce),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct RateArgs {
+    pack_file: String,
+    calibration_file: Option<String>,
+    annotate: bool,
+    theorem_set: u32,
+    disabled_theorems: TheoremList,
+    cache_dir: Option<String>,
+    format: Format,
+    pipeline: PipelineArgs,
+}
+
+impl SetupParser for RateArgs {
+    fn setup_parser<'parser>(&'parser mut self, ap: &mut ArgumentParser<'parser>) {
+        ap.set_description("Rate the difficulty of the puzzles in the pack file");
+        let _ = ap.refer(&mut self.pack_file)
+                  .required()
+                  .add_option(&["--pack"], Store, "pack file to rate.")
+                  .metavar("FILE");
+        let _ = ap.refer(&mut self.calibration_file)
+                  .add_option(&["--calibration"],
+                              StoreOption,
+                              "label the difficulty by the scores in the file (created from the \
+                               pack if missing).")
+                  .metavar("FILE");
+        let _ = ap.refer(&mut self.annotate)
+                  .add_option(&["--annotate"],
+                              StoreTrue,
+                              "print the pack with the ratings as metadata.");
+        let _ = ap.refer(&mut self.theorem_set)
+                  .add_option(&["--theorem-set"],
+                              Store,
+                              "version of the theorem set to rate with (defaults to the latest).")
+                  .metavar("VERSION");
+        let _ = ap.refer(&mut self.disabled_theorems)
+                  .add_option(&["--disable-theorem"],
+                              Store,
+                              "disable the theorems given by indices, ranges or group names \
+                               (e.g. 0-2,adjacent-3s).")
+                  .metavar("LIST");
+        let _ = ap.refer(&mut self.cache_dir)
+                  .add_option(&["--cache"],
+                              StoreOption,
+                              "cache solutions and ratings in the directory.")
+                  .metavar("DIR");
+        let _ = ap.refer(&mut self.format)
+                  .add_option(&["--format"], Store, "output format (text, json) [default: text]")
+                  .metavar("FORMAT");
+        self.pipeline.setup_parser(ap);
+    }
+}
+
+impl Default for RateArgs {
+    fn default() -> RateArgs {
+        RateArgs {
+            pack_file: String::new(),
+            calibration_file: None,
+            annotate: false,
+            theorem_set: solver::THEOREM_SET_VERSION,
+            disabled_theorems: TheoremList(vec![]),
+            cache_dir: None,
+            format: Format::Text,
+            pipeline: PipelineArgs::default(),
+        }
+    }
+}
+
+impl Into<Config> for RateArgs {
+    fn into(self) -> Config {
+        Config::Rate(RateConfig {
+            pack_file: self.pack_file,
+            calibration_file: self.calibration_file,
+            annotate: self.annotate,
+            theorem_set: self.theorem_set,
+            disabled_theorems: self.disabled_theorems.0,
+            cache_dir: self.cache_dir,
+            format: self.format,
+            pipeline: self.pipeline.config("Rate"),
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+struct TransformArgs {
+    ops: TransformOpList,
+    input_files: Vec<String>,
+}
+
+impl SetupParser for TransformArgs {
+    fn setup_parser<'parser>(&'parser mut self, ap: &mut ArgumentParser<'parser>) {
+        ap.set_description("Rotate, flip or crop the given puzzle(s)");
+        let _ = ap.refer(&mut self.ops)
+                  .required()
+                  .add_option(&["--ops"],
+                              Store,
+                              "comma-separated operations applied in order (rotate90, \
+                               flip-h, flip-v, crop:ROW,COL,ROWS,COLUMNS)")
+                  .metavar("OPS");
+        let _ = ap.refer(&mut self.input_files)
+                  .add_argument("input_files", List, "puzzle files to transform.");
+    }
+}
+
+impl Default for TransformArgs {
+    fn default() -> TransformArgs {
+        TransformArgs {
+            ops: TransformOpList(vec![]),
+            input_files: vec![],
+        }
+    }
+}
+
+impl Into<Config> for TransformArgs {
+    fn into(self) -> Config {
+        Config::Transform(TransformConfig {
+            ops: self.ops.0,
+            input_files: self.input_files,
+        })
+    }
+}
+
+/// An operation of `transform`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum TransformOp {
+    /// Rotates the puzzle by 90 degrees.
+    Rotate90,
+    /// Flips the puzzle horizontally.
+    FlipH,
+    /// Flips the puzzle vertically.
+    FlipV,
+    /// Crops the area of the size at the point.
+    Crop(Point, GeomSize),
+}
+
+#[derive(Clone, Debug)]
+struct TransformOpList(Vec<TransformOp>);
+
+impl FromStr for TransformOpList {
+    type Err = ();
+
+    fn from_str(src: &str) -> Result<TransformOpList, ()> {
+        let mut ops = vec![];
+        let mut words = src.split(',').map(|w| w.trim());
+        while let Some(word) = words.next() {
+            let op = match word {
+                "rotate90" => TransformOp::Rotate90,
+                "flip-h" => TransformOp::FlipH,
+                "flip-v" => TransformOp::FlipV,
+                _ if word.starts_with("crop:") => {
+                    // The arguments of crop are also separated by commas.
+                    let mut args = vec![&word["crop:".len()..]];
+                    args.extend(words.by_ref().take(3));
+                    let args = try!(args.iter()
+                                        .map(|a| a.parse::<i32>().map_err(|_| ()))
+                                        .collect::<Result<Vec<_>, ()>>());
+                    if args.len() != 4 || args[0] < 0 || args[1] < 0 || args[2] <= 0 ||
+                       args[3] <= 0 {
+                        return Err(());
+                    }
+                    TransformOp::Crop(Point(args[0], args[1]), GeomSize(args[2], args[3]))
+                }
+                _ => return Err(()),
+            };
+            ops.push(op);
+        }
+        Ok(TransformOpList(ops))
+    }
+}
+
+#[derive(Clone, Debug)]
+struct ConvertArgs {
+    from: Option<PuzzleFormatArg>,
+    to: PuzzleFormatArg,
+    pipeline: PipelineArgs,
+    input_files: Vec<String>,
+}
+
+impl SetupParser for ConvertArgs {
+    fn setup_parser<'parser>(&'parser mut self, ap: &mut ArgumentParser<'parser>) {
+        ap.set_description("Convert the given puzzle(s) between the formats");
+        let _ = ap.refer(&mut self.from)
+                  .add_option(&["--from"],
+                              StoreOption,
+                              "format of the input (text, csv, json, puzzlink, loopy, \
+                               loopy-save, janko, compact) [default: detected from the input]")
+                  .metavar("FORMAT");
+        let _ = ap.refer(&mut self.to)
+                  .required()
+                  .add_option(&["--to"],
+                              Store,
+                              "format of the output (text, csv, json, puzzlink, loopy, \
+                               loopy-save, janko, compact)")
+                  .metavar("FORMAT");
+        self.pipeline.setup_parser(ap);
+        let _ = ap.refer(&mut self.input_files)
+                  .add_argument("input_files", List, "puzzle files to convert.");
+    }
+}
+
+impl Default for ConvertArgs {
+    fn default() -> ConvertArgs {
+        ConvertArgs {
+            from: None,
+            to: PuzzleFormatArg(format::text()),
+            pipeline: PipelineArgs::default(),
+            input_files: vec![],
+        }
+    }
+}
+
+impl Into<Config> for ConvertArgs {
+    fn into(self) -> Config {
+        Config::Convert(ConvertConfig {
+            from: self.from.map(|f| f.0),
+            to: self.to.0,
+            pipeline: self.pipeline.config("Convert"),
+            input_files: self.input_files,
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+struct PuzzleFormatArg(&'static PuzzleFormat);
+
+impl FromStr for PuzzleFormatArg {
+    type Err = ();
+
+    fn from_str(src: &str) -> Result<PuzzleFormatArg, ()> {
+        format::find(src).map(PuzzleFormatArg).ok_or(())
+    }
+}
+
+#[derive(Clone, Debug)]
+struct RenderArgs {
+    solve: bool,
+    format: ImageFormat,
+    delay: u32,
+    cell_size: u32,
+    dpi: Option<u32>,
+    output_file: Option<String>,
+    input_file: Option<String>,
+}
+
+impl SetupParser for RenderArgs {
+    fn setup_parser<'parser>(&'parser mut self, ap: &mut ArgumentParser<'parser>) {
+        ap.set_description("Render the given puzzle, or its solution, into a PNG image, or the \
+                            course of solving it into an animation");
+        let _ = ap.refer(&mut self.solve)
+                  .add_option(&["--solve"],
+                              StoreTrue,
+                              "solve the puzzle and render its solution");
+        let _ = ap.refer(&mut self.format)
+                  .add_option(&["--format"],
+                              Store,
+                              "image format (png, gif, apng), where gif and apng animate the \
+                               edges fixed by each pass of the solver [default: png]")
+                  .metavar("FORMAT");
+        let _ = ap.refer(&mut self.delay)
+                  .add_option(&["--delay"],
+                              Store,
+                              "delay between the frames of the animation in milliseconds, from \
+                               10 to 10000 [default: 500]")
+                  .metavar("MS");
+        let _ = ap.refer(&mut self.cell_size)
+                  .add_option(&["--cell-size"],
+                              Store,
+                              "size of a cell in pixels, at least 8 [default: 32]")
+                  .metavar("PX");
+        let _ = ap.refer(&mut self.dpi)
+                  .add_option(&["--dpi"],
+                              StoreOption,
+                              "resolution recorded in the image for printing, in dots per inch")
+                  .metavar("DPI");
+        let _ = ap.refer(&mut self.output_file)
+                  .add_option(&["--output"],
+                              StoreOption,
+                              "file to write the image to [default: the standard output]")
+                  .metavar("FILE");
+        let _ = ap.refer(&mut self.input_file)
+                  .add_argument("input_file", StoreOption, "puzzle file to render.");
+    }
+}
+
+impl Default for RenderArgs {
+    fn default() -> RenderArgs {
+        RenderArgs {
+            solve: false,
+            format: ImageFormat::Png,
+            delay: 500,
+            cell_size: 32,
+            dpi: None,
+            output_file: None,
+            input_file: None,
+        }
+    }
+}
+
+impl Into<Config> for RenderArgs {
+    fn into(self) -> Config {
+        if self.cell_size < 8 {
+            let _ = writeln!(&mut io::stderr(),
+                             "Render: cell size too small `{}` (expected 8 or more)",
+                             self.cell_size);
+            process::exit(2);
+        }
+        if self.dpi == Some(0) {
+            let _ = writeln!(&mut io::stderr(), "Render: resolution must be positive");
+            process::exit(2);
+        }
+        if self.delay < 10 || self.delay > 10000 {
+            let _ = writeln!(&mut io::stderr(),
+                             "Render: invalid delay `{}` (expected 10 to 10000)",
+                             self.delay);
+            process::exit(2);
+        }
+        Config::Render(RenderConfig {
+            solve: self.solve,
+            format: self.format,
+            delay: self.delay,
+            cell_size: self.cell_size,
+            dpi: self.dpi,
+            output_file: self.output_file,
+            input_file: self.input_file,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+struct ExportArgs {
+    pdf: bool,
+    per_page: usize,
+    output_file: Option<String>,
+    input_files: Vec<String>,
+}
+
+impl SetupParser for ExportArgs {
+    fn setup_parser<'parser>(&'parser mut self, ap: &mut ArgumentParser<'parser>) {
+        ap.set_description("Export the given puzzles as printable sheets, with their answers at \
+                            the end");
+        let _ = ap.refer(&mut self.pdf)
+                  .add_option(&["--pdf"], StoreTrue, "export a PDF document of pages of A4");
+        let _ = ap.refer(&mut self.per_page)
+                  .add_option(&["--per-page"],
+                              Store,
+                              "number of the puzzles on a page [default: 4]")
+                  .metavar("N");
+        let _ = ap.refer(&mut self.output_file)
+                  .add_option(&["--output"],
+                              StoreOption,
+                              "file to write the document to [default: the standard output]")
+                  .metavar("FILE");
+        let _ = ap.refer(&mut self.input_files)
+                  .add_argument("input_files", List, "pack files to export.");
+    }
+}
+
+impl Default for ExportArgs {
+    fn default() -> ExportArgs {
+        ExportArgs {
+            pdf: false,
+            per_page: 4,
+            output_file: None,
+            input_files: vec![],
+        }
+    }
+}
+
+impl Into<Config> for ExportArgs {
+    fn into(self) -> Config {
+        if !self.pdf {
+            let _ = writeln!(&mut io::stderr(), "Export: no format given (expected `--pdf`)");
+            process::exit(2);
+        }
+        if self.per_page == 0 {
+            let _ = writeln!(&mut io::stderr(),
+                             "Export: number of the puzzles on a page must be positive");
+            process::exit(2);
+        }
+        Config::Export(ExportConfig {
+            format: ExportFormat::Pdf,
+            per_page: self.per_page,
+            output_file: self.output_file,
+            input_files: self.input_files,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+struct SoakArgs {
+    minutes: u64,
+    max_size: String,
+    seed: Option<u64>,
+    replay: Option<u64>,
+}
+
+impl SetupParser for SoakArgs {
+    fn setup_parser<'parser>(&'parser mut self, ap: &mut ArgumentParser<'parser>) {
+        ap.set_description("Solve random puzzles and check the solutions until the time is up");
+        let _ = ap.refer(&mut self.minutes)
+                  .add_option(&["--minutes"], Store, "minutes to run [default: 1]")
+                  .metavar("M");
+        let _ = ap.refer(&mut self.max_size)
+                  .add_option(&["--max-size"],
+                              Store,
+                              "maximum size of the puzzles, as COLUMNSxROWS or a preset \
+                               (small, medium, ...) [default: 6x6]")
+                  .metavar("SIZE");
+        let _ = ap.refer(&mut self.seed)
+                  .add_option(&["--seed"], StoreOption, "seed of the random number generator")
+                  .metavar("S");
+        let _ = ap.refer(&mut self.replay)
+                  .add_option(&["--replay"],
+                              StoreOption,
+                              "check only the puzzle of the seed reported by a failure")
+                  .metavar("SEED");
+    }
+}
+
+impl Default for SoakArgs {
+    fn default() -> SoakArgs {
+        SoakArgs {
+            minutes: 1,
+            max_size: "6x6".to_owned(),
+            seed: None,
+            replay: None,
+        }
+    }
+}
+
+impl Into<Config> for SoakArgs {
+    fn into(self) -> Config {
+        Config::Soak(SoakConfig {
+            minutes: self.minutes,
+            max_size: size_or_exit("Soak", &self.max_size),
+            seed: self.seed,
+            replay: self.replay,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+struct RecheckArgs {
+    pipeline: PipelineArgs,
+    input_files: Vec<String>,
+}
+
+impl SetupParser for RecheckArgs {
+    fn setup_parser<'parser>(&'parser mut self, ap: &mut ArgumentParser<'parser>) {
+        ap.set_description("Check that the solutions recorded in the given pack file(s) are \
+                            derived from their hints again");
+        self.pipeline.setup_parser(ap);
+        let _ = ap.refer(&mut self.input_files)
+                  .add_argument("input_files", List, "pack files to check.");
+    }
+}
+
+impl Default for RecheckArgs {
+    fn default() -> RecheckArgs {
+        RecheckArgs {
+            pipeline: PipelineArgs::default(),
+            input_files: vec![],
+        }
+    }
+}
+
+impl Into<Config> for RecheckArgs {
+    fn into(self) -> Config {
+        Config::Recheck(RecheckConfig {
+            pipeline: self.pipeline.config("Recheck"),
+            input_files: self.input_files,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+struct CheckpointArgs {
+    strategy_file: Option<String>,
+    theorem_set: u32,
+    disabled_theorems: TheoremList,
+    compare_file: Option<String>,
+    input_file: String,
+}
+
+impl SetupParser for CheckpointArgs {
+    fn setup_parser<'parser>(&'parser mut self, ap: &mut ArgumentParser<'parser>) {
+        ap.set_description("Print the board after each pass of the deduction, or compare it \
+                            with the boards printed by another build or configuration");
+        let _ = ap.refer(&mut self.strategy_file)
+                  .add_option(&["--strategy"],
+                              StoreOption,
+                              "deduce with the deduction steps in the file.")
+                  .metavar("FILE");
+        let _ = ap.refer(&mut self.theorem_set)
+                  .add_option(&["--theorem-set"],
+                              Store,
+                              "version of the theorem set to deduce with (defaults to the \
+                               latest).")
+                  .metavar("VERSION");
+        let _ = ap.refer(&mut self.disabled_theorems)
+                  .add_option(&["--disable-theorem"],
+                              Store,
+                              "disable the theorems given by indices, ranges or group names \
+                               (e.g. 0-2,adjacent-3s).")
+                  .metavar("LIST");
+        let _ = ap.refer(&mut self.compare_file)
+                  .add_option(&["--compare"],
+                              StoreOption,
+                              "report the first pass diverging from the boards in the file.")
+                  .metavar("FILE");
+        let _ = ap.refer(&mut self.input_file)
+                  .required()
+                  .add_argument("input_file", Store, "puzzle file to deduce.");
+    }
+}
+
+impl Default for CheckpointArgs {
+    fn default() -> CheckpointArgs {
+        CheckpointArgs {
+            strategy_file: None,
+            theorem_set: solver::THEOREM_SET_VERSION,
+            disabled_theorems: TheoremList(vec![]),
+            compare_file: None,
+            input_file: "".to_owned(),
+        }
+    }
+}
+
+impl Into<Config> for CheckpointArgs {
+    fn into(self) -> Config {
+        Config::Checkpoint(CheckpointConfig {
+            strategy_file: self.strategy_file,
+            theorem_set: self.theorem_set,
+            disabled_theorems: self.disabled_theorems.0,
+            compare_file: self.compare_file,
+            input_file: self.input_file,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+struct GenerateArgs {
+    size: Option<String>,
+    count: u64,
+    seed: Option<u64>,
+    hint_density: f64,
+    min_loop_length: f64,
+    max_loop_length: f64,
+    symmetry: SymmetryArg,
+    difficulty: Option<DifficultyArg>,
+    mask_file: Option<String>,
+    forbid: Option<LayoutList>,
+    book_dir: Option<String>,
+    dry_run: bool,
+}
+
+impl SetupParser for GenerateArgs {
+    fn setup_parser<'parser>(&'parser mut self, ap: &mut ArgumentParser<'parser>) {
+        ap.set_description("Generate puzzles with a unique solution, which are the same for the \
+                            same seed and options");
+        let _ = ap.refer(&mut self.size)
+                  .add_option(&["--size"],
+                              StoreOption,
+                              "size of the puzzles, as COLUMNSxROWS or a preset (small, \
+                               medium, ...) [default: 10x10, or the size of the mask]")
+                  .metavar("SIZE");
+        let _ = ap.refer(&mut self.count)
+                  .add_option(&["--count"], Store, "number of puzzles to generate [default: 1]")
+                  .metavar("N");
+        let _ = ap.refer(&mut self.seed)
+                  .add_option(&["--seed"],
+                              StoreOption,
+                              "seed of the first puzzle, incremented for each puzzle \
+                               [default: random]")
+                  .metavar("S");
+        let _ = ap.refer(&mut self.hint_density)
+                  .add_option(&["--hint-density"],
+                              Store,
+                              "minimum ratio of the cells with hints, from 0 to 1 [default: 0]")
+                  .metavar("D");
+        let _ = ap.refer(&mut self.min_loop_length)
+                  .add_option(&["--min-loop-length"],
+                              Store,
+                              "minimum length of the loop, as the ratio to the longest loop \
+                               from 0 to 1 [default: 0]")
+                  .metavar("R");
+        let _ = ap.refer(&mut self.max_loop_length)
+                  .add_option(&["--max-loop-length"],
+                              Store,
+                              "maximum length of the loop, as the ratio to the longest loop \
+                               from 0 to 1 [default: 1]")
+                  .metavar("R");
+        let _ = ap.refer(&mut self.symmetry)
+                  .add_option(&["--symmetry"],
+                              Store,
+                              "symmetry of the hints (none, rotate180, mirror-left-right, \
+                               mirror-top-bottom) [default: none]")
+                  .metavar("SYMMETRY");
+        let _ = ap.refer(&mut self.difficulty)
+                  .add_option(&["--difficulty"],
+                              StoreOption,
+                              "difficulty of the puzzles by the deduction steps needed (easy, \
+                               medium, hard, expert) [default: any]")
+                  .metavar("DIFFICULTY");
+        let _ = ap.refer(&mut self.mask_file)
+                  .add_option(&["--mask"],
+                              StoreOption,
+                              "place the hints only in the cells of `#` in the file of the rows \
+                               of `#` and `.`")
+                  .metavar("FILE");
+        let _ = ap.refer(&mut self.forbid)
+                  .add_option(&["--forbid"],
+                              StoreOption,
+                              "reject the puzzles with the layouts of the hints separated by \
+                               commas, as the rows of `0`-`4` and `_` separated by `/`, in any \
+                               rotation (e.g. 00,33/33)")
+                  .metavar("LAYOUTS");
+        let _ = ap.refer(&mut self.book_dir)
+                  .add_option(&["--book"],
+                              StoreOption,
+                              "write the puzzles ramping from easy to expert to numbered files \
+                               in DIR, and their solutions to DIR/answers.txt")
+                  .metavar("DIR");
+        let _ = ap.refer(&mut self.dry_run)
+                  .add_option(&["--dry-run"],
+                              StoreTrue,
+                              "print the plan with the solver calls and the time estimated by \
+                               timing sample puzzles, without generating the puzzles");
+    }
+}
+
+impl Default for GenerateArgs {
+    fn default() -> GenerateArgs {
+        GenerateArgs {
+            size: None,
+            count: 1,
+            seed: None,
+            hint_density: 0.0,
+            min_loop_length: 0.0,
+            max_loop_length: 1.0,
+            symmetry: SymmetryArg(Symmetry::None),
+            difficulty: None,
+            mask_file: None,
+            forbid: None,
+            book_dir: None,
+            dry_run: false,
+        }
+    }
+}
+
+impl Into<Config> for GenerateArgs {
+    fn into(self) -> Config {
+        if !(self.hint_density >= 0.0 && self.hint_density <= 1.0) {
+            let _ = writeln!(&mut io::stderr(),
+                             "Generate: hint density out of range `{}` (expected 0 to 1)",
+                             self.hint_density);
+            process::exit(2);
+        }
+        if !(self.min_loop_length >= 0.0 && self.min_loop_length <= self.max_loop_length &&
+             self.max_loop_length <= 1.0) {
+            let _ = writeln!(&mut io::stderr(),
+                             "Generate: loop length out of range `{}` to `{}` (expected 0 to 1)",
+                             self.min_loop_length,
+                             self.max_loop_length);
+            process::exit(2);
+        }
+        if self.book_dir.is_some() && self.difficulty.is_some() {
+            let _ = writeln!(&mut io::stderr(),
+                             "Generate: --book and --difficulty cannot be used together");
+            process::exit(2);
+        }
+        Config::Generate(GenerateConfig {
+            size: self.size.map(|s| size_or_exit("Generate", &s)),
+            count: self.count,
+            seed: self.seed,
+            hint_density: self.hint_density,
+            loop_length: (self.min_loop_length, self.max_loop_length),
+            symmetry: self.symmetry.0,
+            difficulty: self.difficulty.map(|d| d.0),
+            mask_file: self.mask_file,
+            forbid: self.forbid.as_ref().map(|l| l.0.clone()),
+            forbidden_layouts: self.forbid.map_or(vec![], |l| l.1),
+            book_dir: self.book_dir,
+            dry_run: self.dry_run,
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+struct DifficultyArg(Difficulty);
+
+impl FromStr for DifficultyArg {
+    type Err = ();
+
+    fn from_str(src: &str) -> Result<DifficultyArg, ()> {
+        Difficulty::from_name(src).map(DifficultyArg).ok_or(())
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+struct SymmetryArg(Symmetry);
+
+impl FromStr for SymmetryArg {
+    type Err = ();
+
+    fn from_str(src: &str) -> Result<SymmetryArg, ()> {
+        Symmetry::from_name(src).map(SymmetryArg).ok_or(())
+    }
+}
+
+#[derive(Clone, Debug)]
+struct EditArgs {
+    input_file: Option<String>,
+    size: Option<String>,
+    output_file: Option<String>,
+    debounce_ms: u64,
+}
+
+impl SetupParser for EditArgs {
+    fn setup_parser<'parser>(&'parser mut self, ap: &mut ArgumentParser<'parser>) {
+        ap.set_description("Edit the hints of a puzzle, checking if its solution is unique as \
+                            the hints change");
+        let _ = ap.refer(&mut self.size)
+                  .add_option(&["--size"],
+                              StoreOption,
+                              "size of the blank board to start from, as COLUMNSxROWS or a \
+                               preset (small, medium, ...) [default: 10x10]")
+                  .metavar("SIZE");
+        let _ = ap.refer(&mut self.output_file)
+                  .add_option(&["-o", "--output"],
+                              StoreOption,
+                              "write the puzzle to FILE at the end")
+                  .metavar("FILE");
+        let _ = ap.refer(&mut self.debounce_ms)
+                  .add_option(&["--debounce"],
+                              Store,
+                              "milliseconds to wait for the edits to pause before counting the \
+                               solutions [default: 300]")
+                  .metavar("MS");
+        let _ = ap.refer(&mut self.input_file)
+                  .add_argument("input_file", StoreOption, "puzzle file to start from.");
+    }
+}
+
+impl Default for EditArgs {
+    fn default() -> EditArgs {
+        EditArgs {
+            input_file: None,
+            size: None,
+            output_file: None,
+            debounce_ms: 300,
+        }
+    }
+}
+
+impl Into<Config> for EditArgs {
+    fn into(self) -> Config {
+        if self.input_file.is_some() && self.size.is_some() {
+            let _ = writeln!(&mut io::stderr(),
+                             "Edit: --size cannot be used with a puzzle file");
+            process::exit(2);
+        }
+        Config::Edit(EditConfig {
+            input_file: self.input_file,
+            size: size_or_exit("Edit", self.size.as_ref().map_or("10x10", |s| s)),
+            output_file: self.output_file,
+            debounce_ms: self.debounce_ms,
+        })
+    }
+}
+
+// Parses the size given to the command, and exits with the reason like the
+// argument parser if it is invalid.
+fn size_or_exit(command: &str, s: &str) -> GeomSize {
+    match size_spec::parse(s) {
+        Ok(size) => size,
+        Err(e) => {
+            let _ = writeln!(&mut io::stderr(), "{}: {}", command, e);
+            process::exit(2);
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct HistoryArgs {
+    summary: bool,
+    command: Option<String>,
+    puzzle: Option<String>,
+    last: Option<usize>,
+}
+
+impl SetupParser for HistoryArgs {
+    fn setup_parser<'parser>(&'parser mut self, ap: &mut ArgumentParser<'parser>) {
+        ap.set_description("Show the runs recorded to the history file given by `--history` \
+                            before the command");
+        let _ = ap.refer(&mut self.summary)
+                  .add_option(&["--summary"],
+                              StoreTrue,
+                              "summarize the runs and the durations of each command");
+        let _ = ap.refer(&mut self.command)
+                  .add_option(&["--command"], StoreOption, "show only the runs of the command")
+                  .metavar("COMMAND");
+        let _ = ap.refer(&mut self.puzzle)
+                  .add_option(&["--puzzle"],
+                              StoreOption,
+                              "show only the runs of the puzzle of the fingerprint in hex")
+                  .metavar("FINGERPRINT");
+        let _ = ap.refer(&mut self.last)
+                  .add_option(&["--last"], StoreOption, "show only the last N runs")
+                  .metavar("N");
+    }
+}
+
+impl Default for HistoryArgs {
+    fn default() -> HistoryArgs {
+        HistoryArgs {
+            summary: false,
+            command: None,
+            puzzle: None,
+            last: None,
+        }
+    }
+}
+
+impl Into<Config> for HistoryArgs {
+    fn into(self) -> Config {
+        let puzzle = self.puzzle.map(|fp| {
+            match u64::from_str_radix(&fp, 16) {
+                Ok(fp) => fp,
+                Err(_) => {
+                    let _ = writeln!(&mut io::stderr(), "History: invalid fingerprint `{}`", fp);
+                    process::exit(2);
+                }
+            }
+        });
+        Config::History(HistoryConfig {
+            summary: self.summary,
+            command: self.command,
+            puzzle: puzzle,
+            last: self.last,
+        })
+    }
+}
+
+/// The configuration of a command.
+#[derive(Clone, Debug)]
+pub enum Config {
+    /// The configuration of `solve`.
+    Solve(SolveConfig),
+    /// The configuration of `test`.
+    Test(TestConfig),
+    /// The configuration of `bench`.
+    Bench(BenchConfig),
+    /// The configuration of `pack`.
+    Pack(PackConfig),
+    /// The configuration of `compare`.
+    Compare(CompareConfig),
+    /// The configuration of `counterexample`.
+    Counterexample(CounterexampleConfig),
+    /// The configuration of `repl`.
+    Repl(ReplConfig),
+    /// The configuration of `rate`.
+    Rate(RateConfig),
+    /// The configuration of `transform`.
+    Transform(TransformConfig),
+    /// The configuration of `soak`.
+    Soak(SoakConfig),
+    /// The configuration of `recheck`.
+    Recheck(RecheckConfig),
+    /// The configuration of `convert`.
+    Convert(ConvertConfig),
+    /// The configuration of `render`.
+    Render(RenderConfig),
+    /// The configuration of `export`.
+    Export(ExportConfig),
+    /// The configuration of `play`.
+    Play(PlayConfig),
+    /// The configuration of `checkpoint`.
+    Checkpoint(CheckpointConfig),
+    /// The configuration of `generate`.
+    Generate(GenerateConfig),
+    /// The configuration of `history`.
+    History(HistoryConfig),
+    /// The configuration of `edit`.
+    Edit(EditConfig),
+}
+
+/// The options of `solve`.
+#[derive(Clone, Debug)]
+pub struct SolveConfig {
+    /// Derives all the solutions.
+    pub derive_all: bool,
+    /// How the solutions are printed.
+    pub output_mode: OutputMode,
+    /// The directory caching the solutions.
+    pub cache_dir: Option<String>,
+    /// The file of the deduction steps to solve with.
+    pub strategy_file: Option<String>,
+    /// Prints the time spent on each step of the solver.
+    pub profile_steps: bool,
+    /// Prints the number of the solutions instead of them.
+    pub count: bool,
+    /// Fails if more nested assumptions are needed.
+    pub max_guess_depth: Option<u32>,
+    /// Derives or counts at most this many solutions.
+    pub max_solutions: Option<u64>,
+    /// Prints the first this many solutions as soon as each is found.
+    pub solutions: Option<u64>,
+    /// Restarts the search finding no solution in the budget of the nodes.
+    pub restart: Option<RestartPolicy>,
+    /// The indices of the disabled theorems.
+    pub disabled_theorems: Vec<usize>,
+    /// Prints the number of the search nodes fixing each edge.
+    pub effort: bool,
+    /// Prints the course of the deduction in words.
+    pub explain: bool,
+    /// Solves the puzzles turned to the orientation, and turns the solutions back.
+    pub normalize: Option<Normalization>,
+    /// The file saving the state of the enumeration by iterative deepening,
+    /// resumed if it exists.
+    pub resume_file: Option<String>,
+    /// Stops the enumeration of `resume_file` after this many seconds.
+    pub slice: Option<u64>,
+    /// The format of the puzzles, or detected from the input if none.
+    pub in_format: Option<&'static PuzzleFormat>,
+    /// The format of the report.
+    pub format: Format,
+    /// The options of the pipeline.
+    pub pipeline: PipelineConfig,
+    /// The input files, or the standard input if empty.
+    pub input_files: Vec<String>,
+}
+
+/// The options of `test`.
+#[derive(Clone, Debug)]
+pub struct TestConfig {
+    /// Derives all the solutions.
+    pub derive_all: bool,
+    /// The file of the deduction steps to solve with.
+    pub strategy_file: Option<String>,
+    /// The indices of the disabled theorems.
+    pub disabled_theorems: Vec<usize>,
+    /// Fails if a solution has an edge neither line nor cross.
+    pub verify: bool,
+    /// The categories of the bundled puzzles to test as well.
+    pub corpus: Vec<Category>,
+    /// The input files, or the standard input if empty.
+    pub input_files: Vec<String>,
+}
+
+/// The options of `bench`.
+#[derive(Clone, Debug)]
+pub struct BenchConfig {
+    /// Derives all the solutions.
+    pub derive_all: bool,
+    /// Measures only the hardest this many puzzles.
+    pub only_hardest: Option<usize>,
+    /// The categories of the bundled puzzles to measure as well.
+    pub corpus: Vec<Category>,
+    /// The strategies of the search to measure each puzzle with.
+    pub backtrack: Vec<Backtrack>,
+    /// The input files, or the standard input if empty.
+    pub input_files: Vec<String>,
+}
+
+/// The options of `pack`.
+#[derive(Clone, Debug)]
+pub struct PackConfig {
+    /// The action to run.
+    pub action: PackAction,
+    /// The number of the puzzles to sample, or all if none.
+    pub count: Option<usize>,
+    /// Takes only the puzzles with the difficulty metadata.
+    pub difficulty: Option<String>,
+    /// The seed of the random number generator, or random if none.
+    pub seed: Option<u64>,
+    /// The number of the solutions to find for `alternatives`.
+    pub max_solutions: usize,
+    /// The orientation to turn the puzzles to, recorded as the metadata.
+    pub normalize: Option<Normalization>,
+    /// Reads each file as a puzzle in the format instead of a pack.
+    pub in_format: Option<&'static PuzzleFormat>,
+    /// The manifest to write the built pack to, or to verify against.
+    pub manifest: Option<String>,
+    /// The pack files, or the standard input if empty.
+    pub input_files: Vec<String>,
+}
+
+/// The options of `compare`.
+#[derive(Clone, Debug)]
+pub struct CompareConfig {
+    /// The backends of the solver to compare.
+    pub backends: Vec<Backend>,
+    /// The format of the report.
+    pub format: Format,
+    /// The puzzle files or directories to solve.
+    pub inputs: Vec<String>,
+}
+
+/// The options of `counterexample`.
+#[derive(Clone, Debug)]
+pub struct CounterexampleConfig {
+    /// The theorem files to check, or the standard input if empty.
+    pub input_files: Vec<String>,
+}
+
+/// The options of `repl`.
+#[derive(Clone, Debug)]
+pub struct ReplConfig {
+    /// The puzzle file to inspect.
+    pub input_file: String,
+}
+
+/// The options of `play`.
+#[derive(Clone, Debug)]
+pub struct PlayConfig {
+    /// The puzzle file to play.
+    pub input_file: Option<String>,
+    /// The log of the changes to resume and append to, or `INPUT_FILE.play` if none.
+    pub save_file: Option<String>,
+    /// The log of the changes to replay instead of playing.
+    pub replay_file: Option<String>,
+    /// How `hint` chooses the hints.
+    pub hint_policy: HintPolicyArg,
+    /// The times to apply a step to learn it for the reinforce policy.
+    pub learn_times: u32,
+    /// Answers `hint` and `check` from the deduction precomputed at the start.
+    pub precompute: bool,
+}
+
+/// The options of `generate`.
+#[derive(Clone, Debug)]
+pub struct GenerateConfig {
+    /// The size of the puzzles, or of the mask if none.
+    pub size: Option<GeomSize>,
+    /// The number of the puzzles to generate.
+    pub count: u64,
+    /// The seed of the first puzzle, incremented for each puzzle, or random if none.
+    pub seed: Option<u64>,
+    /// The minimum ratio of the cells with hints.
+    pub hint_density: f64,
+    /// The minimum and the maximum length of the loop, as the ratios to the longest loop.
+    pub loop_length: (f64, f64),
+    /// The symmetry of the hints.
+    pub symmetry: Symmetry,
+    /// The difficulty of the puzzles, or any if none.
+    pub difficulty: Option<Difficulty>,
+    /// The file of the cells where the hints may be placed.
+    pub mask_file: Option<String>,
+    /// The forbidden layouts of the hints as given, recorded as the metadata.
+    pub forbid: Option<String>,
+    /// The layouts of the hints the puzzles must not have.
+    pub forbidden_layouts: Vec<HintLayout>,
+    /// The directory to write the puzzles ramping from easy to expert to.
+    pub book_dir: Option<String>,
+    /// Prints the plan with the estimated time instead of generating the puzzles.
+    pub dry_run: bool,
+}
+
+/// The options of `edit`.
+#[derive(Clone, Debug)]
+pub struct EditConfig {
+    /// The puzzle file to start from, or a blank board if none.
+    pub input_file: Option<String>,
+    /// The size of the blank board to start from.
+    pub size: GeomSize,
+    /// The file to write the puzzle to at the end.
+    pub output_file: Option<String>,
+    /// The time to wait for the edits to pause before counting the solutions.
+    pub debounce_ms: u64,
+}
+
+/// The options of `history`.
+#[derive(Clone, Debug)]
+pub struct HistoryConfig {
+    /// Summarizes the runs of each command.
+    pub summary: bool,
+    /// Shows only the runs of the command.
+    pub command: Option<String>,
+    /// Shows only the runs of the puzzle of the fingerprint.
+    pub puzzle: Option<u64>,
+    /// Shows only the last this many runs.
+    pub last: Option<usize>,
+}
+
+/// The options of `checkpoint`.
+#[derive(Clone, Debug)]
+pub struct CheckpointConfig {
+    /// The file of the deduction steps to deduce with.
+    pub strategy_file: Option<String>,
+    /// The version of the theorem set.
+    pub theorem_set: u32,
+    /// The indices of the disabled theorems.
+    pub disabled_theorems: Vec<usize>,
+    /// The file of the boards to compare the passes with.
+    pub compare_file: Option<String>,
+    /// The puzzle file to deduce.
+    pub input_file: String,
+}
+
+/// The options of `transform`.
+#[derive(Clone, Debug)]
+pub struct TransformConfig {
+    /// The operations applied in order.
+    pub ops: Vec<TransformOp>,
+    /// The input files, or the standard input if empty.
+    pub input_files: Vec<String>,
+}
+
+/// The options of `soak`.
+#[derive(Copy, Clone, Debug)]
+pub struct SoakConfig {
+    /// The minutes to run.
+    pub minutes: u64,
+    /// The maximum size of the puzzles.
+    pub max_size: GeomSize,
+    /// The seed of the random number generator, or random if none.
+    pub seed: Option<u64>,
+    /// Checks only the puzzle of the seed reported by a failure.
+    pub replay: Option<u64>,
+}
+
+/// The options of `recheck`.
+#[derive(Clone, Debug)]
+pub struct RecheckConfig {
+    /// The options of the pipeline.
+    pub pipeline: PipelineConfig,
+    /// The pack files, or the standard input if empty.
+    pub input_files: Vec<String>,
+}
+
+/// The options of `convert`.
+#[derive(Clone, Debug)]
+pub struct ConvertConfig {
+    /// The format of the input, or detected from the input if none.
+    pub from: Option<&'static PuzzleFormat>,
+    /// The format of the output.
+    pub to: &'static PuzzleFormat,
+    /// The options of the pipeline.
+    pub pipeline: PipelineConfig,
+    /// The input files, or the standard input if empty.
+    pub input_files: Vec<String>,
+}
+
+/// The options of `render`.
+#[derive(Clone, Debug)]
+pub struct RenderConfig {
+    /// Renders the solution of the puzzle.
+    pub solve: bool,
+    /// The format of the image.
+    pub format: ImageFormat,
+    /// The delay between the frames of the animation in milliseconds.
+    pub delay: u32,
+    /// The size of a cell in pixels.
+    pub cell_size: u32,
+    /// The resolution recorded in the image, in dots per inch.
+    pub dpi: Option<u32>,
+    /// The file to write the image to, or the standard output if none.
+    pub output_file: Option<String>,
+    /// The puzzle file, or the standard input if none.
+    pub input_file: Option<String>,
+}
+
+/// The options of `export`.
+#[derive(Clone, Debug)]
+pub struct ExportConfig {
+    /// The format of the document.
+    pub format: ExportFormat,
+    /// The number of the puzzles on a page of the PDF document.
+    pub per_page: usize,
+    /// The file to write the document to, or the standard output if none.
+    pub output_file: Option<String>,
+    /// The pack files, or the standard input if empty.
+    pub input_files: Vec<String>,
+}
+
+/// The options of `rate`.
+#[derive(Clone, Debug)]
+pub struct RateConfig {
+    /// The pack file to rate.
+    pub pack_file: String,
+    /// The file of the scores labeling the difficulty, created from the pack if missing.
+    pub calibration_file: Option<String>,
+    /// Prints the pack with the ratings as the metadata.
+    pub annotate: bool,
+    /// The version of the theorem set.
+    pub theorem_set: u32,
+    /// The indices of the disabled theorems.
+    pub disabled_theorems: Vec<usize>,
+    /// The directory caching the ratings.
+    pub cache_dir: Option<String>,
+    /// The format of the report.
+    pub format: Format,
+    /// The options of the pipeline.
+    pub pipeline: PipelineConfig,
+}
+
+/// The options of the pipeline processing the puzzles of a pack in parallel.
+#[derive(Copy, Clone, Debug)]
+pub struct PipelineConfig {
+    /// The number of the puzzles processed in parallel.
+    pub jobs: usize,
+    /// The seconds to give up each puzzle after.
+    pub timeout: Option<u64>,
+    /// Shows the progress on the standard error.
+    pub progress: bool,
+}
+
+/// The format of the reports printed by the commands.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Format {
+    /// Text for humans.
+    Text,
+    /// JSON.
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = ();
+
+    fn from_str(src: &str) -> Result<Format, ()> {
+        match src {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            _ => Err(()),
+        }
+    }
+}
+
+/// The format of the images written by `render`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ImageFormat {
+    /// A PNG image.
+    Png,
+    /// An animated GIF.
+    Gif,
+    /// An animated PNG.
+    Apng,
+}
+
+impl FromStr for ImageFormat {
+    type Err = ();
+
+    fn from_str(src: &str) -> Result<ImageFormat, ()> {
+        match src {
+            "png" => Ok(ImageFormat::Png),
+            "gif" => Ok(ImageFormat::Gif),
+            "apng" => Ok(ImageFormat::Apng),
+            _ => Err(()),
+        }
+    }
+}
+
+/// The format of the documents written by `export`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ExportFormat {
+    /// A PDF document.
+    Pdf,
+}
+
+/// How `solve` prints the solutions.
+#[derive(Copy, Clone, Debug)]
+pub enum OutputMode {
+    /// Pretty printed.
+    Pretty(PpConfig),
+    /// Described in words.
+    Describe,
+    /// In the text format.
+    Raw,
+    /// As the ordered path of the loop.
+    Path,
+    /// As a save file of Loopy.
+    LoopySave,
+    /// Not printed.
+    None,
+}
+
+/// The command to run, with the options given before it not applied by
+/// `Config::parse` itself.
+#[derive(Clone, Debug)]
+pub struct Invocation {
+    /// The name of the command, e.g. `solve`.
+    pub command: String,
+    /// The configuration of the command.
+    pub config: Config,
+    /// The history file to record the run to, if given.
+    pub history_file: Option<String>,
+}
+
+impl Config {
+    /// Parses the command line, and exits with the usage on errors.
+    pub fn parse() -> Invocation {
+        let mut command = CommandType::default();
+        let mut color = ColorPolicy::Auto;
+        let mut coords = CoordStyle::RowColumn0;
+        let mut verbosity = 0;
+        let mut history = env::var("SRITHER_HISTORY").ok().and_then(|s| {
+            if s.is_empty() {
+                None
+            } else {
+                Some(s)
+            }
+        });
+        let mut args = vec![];
+        {
+            let mut ap = ArgumentParser::new();
+            command.setup_parser(&mut ap,
+                                 &mut color,
+                                 &mut coords,
+                                 &mut verbosity,
+                                 &mut history,
+                                 &mut args);
+            ap.parse_args_or_exit();
+        }
+        pprint::set_color_policy(color);
+        coord::set_style(coords);
+        solver::set_verbosity(verbosity);
+
+        args.insert(0, format!("{:?}", command));
+
+        let config = match command {
+            CommandType::Solve => Self::parse_subcommand::<SolveArgs>(args),
+            CommandType::Test => Self::parse_subcommand::<TestArgs>(args),
+            CommandType::Bench => Self::parse_subcommand::<BenchArgs>(args),
+            CommandType::Pack => Self::parse_subcommand::<PackArgs>(args),
+            CommandType::Compare => Self::parse_subcommand::<CompareArgs>(args),
+            CommandType::Counterexample => {
+                Self::parse_subcommand::<CounterexampleArgs>(args)
+            }
+            CommandType::Repl => Self::parse_subcommand::<ReplArgs>(args),
+            CommandType::Rate => Self::parse_subcommand::<RateArgs>(args),
+            CommandType::Transform => Self::parse_subcommand::<TransformArgs>(args),
+            CommandType::Soak => Self::parse_subcommand::<SoakArgs>(args),
+            CommandType::Recheck => Self::parse_subcommand::<RecheckArgs>(args),
+            CommandType::Convert => Self::parse_subcommand::<ConvertArgs>(args),
+            CommandType::Render => Self::parse_subcommand::<RenderArgs>(args),
+            CommandType::Export => Self::parse_subcommand::<ExportArgs>(args),
+            CommandType::Play => Self::parse_subcommand::<PlayArgs>(args),
+            CommandType::Checkpoint => Self::parse_subcommand::<CheckpointArgs>(args),
+            CommandType::Generate => Self::parse_subcommand::<GenerateArgs>(args),
+            CommandType::History => Self::parse_subcommand::<HistoryArgs>(args),
+            CommandType::Edit => Self::parse_subcommand::<EditArgs>(args),
+        };
+        Invocation {
+            command: format!("{:?}", command).to_lowercase(),
+            config: config,
+            history_file: history,
+        }
+    }
+
+    fn parse_subcommand<T>(args: Vec<String>) -> Config
+        where T: SetupParser + Default + Into<Config>
+    {
+        let mut sub_args = T::default();
+        {
+            let mut ap = ArgumentParser::new();
+            sub_args.setup_parser(&mut ap);
+            if let Err(x) = ap.parse(args, &mut io::stdout(), &mut io::stderr()) {
+                process::exit(x);
+            }
+        }
+        sub_args.into()
+    }
+}
@@ -0,0 +1,182 @@
+// Copyright (c) 2016 srither developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! The PDF backend of the layouts, writing the shapes as vector paths.
+//!
+//! The pages take the coordinates in points from their top-left corner, the
+//! same as the layouts, and flip them into the coordinates of PDF. The texts
+//! are written in Helvetica, one of the standard fonts every reader has, so
+//! that no font is embedded; they are limited to ASCII.
+
+use std::fmt::Write;
+
+use layout::{Layout, Pos, Shape};
+
+/// The size of a page of A4, in points.
+pub const A4: (f64, f64) = (595.28, 841.89);
+
+/// A page of the document, drawn by the layouts and the texts.
+#[derive(Clone, Debug)]
+pub struct Page {
+    width: f64,
+    height: f64,
+    content: String,
+}
+
+impl Page {
+    /// Creates an empty page of the size in points.
+    pub fn new(size: (f64, f64)) -> Page {
+        let mut content = String::new();
+        // Round joins and caps, so that a stroke of a point is a dot.
+        content.push_str("1 j 1 J\n");
+        Page {
+            width: size.0,
+            height: size.1,
+            content: content,
+        }
+    }
+
+    /// Draws the layout with its top-left corner at the position.
+    pub fn draw(&mut self, layout: &Layout, origin: Pos) {
+        let (x, y) = self.flip(origin);
+        let _ = writeln!(self.content, "q 1 0 0 -1 {:.2} {:.2} cm", x, y);
+        for shape in &layout.shapes {
+            match *shape {
+                Shape::Dot(center, radius) => self.stroke(&[center, center], radius * 2.0),
+                Shape::Stroke(ref points, width) => self.stroke(points, width),
+            }
+        }
+        self.content.push_str("Q\n");
+    }
+
+    /// Writes the text with the left end of its baseline at the position.
+    pub fn text(&mut self, text: &str, pos: Pos, size: f64) {
+        let (x, y) = self.flip(pos);
+        let mut escaped = String::new();
+        for c in text.chars() {
+            match c {
+                '(' | ')' | '\\' => {
+                    escaped.push('\\');
+                    escaped.push(c);
+                }
+                ' '...'~' => escaped.push(c),
+                _ => escaped.push('?'),
+            }
+        }
+        let _ = writeln!(self.content,
+                         "BT /F1 {:.2} Tf {:.2} {:.2} Td ({}) Tj ET",
+                         size,
+                         x,
+                         y,
+                         escaped);
+    }
+
+    fn stroke(&mut self, points: &[Pos], width: f64) {
+        let _ = write!(self.content, "{:.2} w", width);
+        for (i, &(x, y)) in points.iter().enumerate() {
+            let op = if i == 0 { "m" } else { "l" };
+            let _ = write!(self.content, " {:.2} {:.2} {}", x, y, op);
+        }
+        self.content.push_str(" S\n");
+    }
+
+    fn flip(&self, pos: Pos) -> Pos {
+        (pos.0, self.height - pos.1)
+    }
+}
+
+/// Encodes the pages into a PDF document.
+pub fn encode(pages: &[Page]) -> Vec<u8> {
+    // The objects are numbered from 1: the catalog, the page tree, the font,
+    // and then each page followed by its content.
+    let mut objects = vec![];
+    objects.push("<< /Type /Catalog /Pages 2 0 R >>".to_owned());
+    let kids = (0..pages.len()).map(|i| format!("{} 0 R", 4 + 2 * i)).collect::<Vec<_>>();
+    objects.push(format!("<< /Type /Pages /Kids [{}] /Count {} >>",
+                         kids.join(" "),
+                         pages.len()));
+    objects.push("<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".to_owned());
+    for (i, page) in pages.iter().enumerate() {
+        objects.push(format!("<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] \
+                              /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+                             page.width,
+                             page.height,
+                             5 + 2 * i));
+        objects.push(format!("<< /Length {} >>\nstream\n{}endstream",
+                             page.content.len(),
+                             page.content));
+    }
+
+    let mut pdf = String::new();
+    pdf.push_str("%PDF-1.4\n");
+    let mut offsets = vec![];
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        let _ = write!(pdf, "{} 0 obj\n{}\nendobj\n", i + 1, object);
+    }
+    let xref = pdf.len();
+    let _ = write!(pdf, "xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        let _ = write!(pdf, "{:010} 00000 n \n", offset);
+    }
+    let _ = write!(pdf,
+                   "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+                   objects.len() + 1,
+                   xref);
+    pdf.into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use layout::{Layout, Shape};
+    use super::{Page, encode};
+
+    #[test]
+    fn xref() {
+        let layout = Layout {
+            width: 10.0,
+            height: 10.0,
+            shapes: vec![Shape::Stroke(vec![(1.0, 2.0), (3.0, 4.0)], 0.5)],
+        };
+        let mut first = Page::new((200.0, 100.0));
+        first.draw(&layout, (10.0, 20.0));
+        first.text("(a\\b) \u{3042}", (5.0, 50.0), 12.0);
+        let pages = [first, Page::new((100.0, 200.0))];
+        let pdf = String::from_utf8(encode(&pages)).unwrap();
+        assert!(pdf.starts_with("%PDF-1.4\n1 0 obj\n<< /Type /Catalog /Pages 2 0 R >>"));
+        assert!(pdf.ends_with("\n%%EOF\n"));
+
+        // The table of the offsets follows `startxref`, with the free entry
+        // and an entry for each object.
+        let lines = pdf.lines().collect::<Vec<_>>();
+        let n = lines.len();
+        assert_eq!(("startxref", "%%EOF"), (lines[n - 3], lines[n - 1]));
+        assert_eq!("trailer", lines[n - 5]);
+        assert_eq!("<< /Size 8 /Root 1 0 R >>", lines[n - 4]);
+        let xref = lines[n - 2].parse::<usize>().unwrap();
+        let table = pdf[xref..].lines().collect::<Vec<_>>();
+        assert_eq!(("xref", "0 8", "0000000000 65535 f "), (table[0], table[1], table[2]));
+        for i in 1..8 {
+            let entry = table[2 + i];
+            assert_eq!(20, entry.len() + 1);
+            assert!(entry.ends_with(" 00000 n "));
+            let offset = entry[..10].parse::<usize>().unwrap();
+            assert!(pdf[offset..].starts_with(&format!("{} 0 obj\n", i)));
+        }
+        assert_eq!("trailer", table[10]);
+
+        // The content of the first page, with the coordinates flipped, and
+        // its length.
+        let content = "1 j 1 J\nq 1 0 0 -1 10.00 80.00 cm\n0.50 w 1.00 2.00 m 3.00 4.00 l S\n\
+                       Q\nBT /F1 12.00 Tf 5.00 50.00 Td (\\(a\\\\b\\) ?) Tj ET\n";
+        let stream = format!("<< /Length {} >>\nstream\n{}endstream", content.len(), content);
+        assert!(pdf.contains(&stream));
+        assert!(pdf.contains("/Kids [4 0 R 6 0 R] /Count 2"));
+        assert!(pdf.contains("/MediaBox [0 0 100.00 200.00]"));
+    }
+}
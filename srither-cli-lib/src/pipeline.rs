@@ -0,0 +1,292 @@
+// Copyright (c) 2016 srither developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Parallel processing of the puzzles of a pack, shared by the commands.
+//!
+//! The items are processed by the given number of worker threads, and their
+//! results are emitted in the order of the items, each as soon as the ones
+//! before it are emitted. An item taking longer than the timeout is reported
+//! as timed out, and its worker moves on to the next item. The work of the
+//! item is given a flag, which is set when it times out, so that the search
+//! ends. The worker waits for it to end, and discards its result, so no more
+//! items than the jobs are processed at a time.
+
+use std::{cmp, io, thread};
+use std::io::prelude::*;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::Duration;
+
+use error::AppResult;
+use parse_arg::PipelineConfig;
+
+/// The result of an item.
+#[derive(Clone, Debug)]
+pub enum Outcome<T> {
+    Done(T),
+    TimedOut,
+}
+
+impl<T> Outcome<T> {
+    /// Returns the result of the item of the index, or an error if it has
+    /// timed out, for the commands needing the results of all the items.
+    pub fn done(self, index: usize) -> AppResult<T> {
+        match self {
+            Outcome::Done(result) => Ok(result),
+            Outcome::TimedOut => {
+                let msg = format!("puzzle #{} timed out", index + 1);
+                Err(io::Error::new(io::ErrorKind::TimedOut, msg).into())
+            }
+        }
+    }
+}
+
+// The width of the progress bar in characters.
+const PROGRESS_WIDTH: usize = 30;
+
+/// Processes the items by `work` in parallel, and calls `emit` with the
+/// index and the result of each item in order. Stops at the first error of
+/// `emit`. The progress is drawn on `err`.
+///
+/// `work` is given the flag set when the item times out, which is passed to
+/// the solver by `SolveOptions::stop`.
+pub fn run<I, T, F, E, W>(config: &PipelineConfig,
+                          items: Vec<I>,
+                          work: F,
+                          mut emit: E,
+                          err: &mut W)
+                          -> AppResult<()>
+    where I: Send + 'static,
+          T: Send + 'static,
+          F: Fn(I, Arc<AtomicBool>) -> T + Send + Sync + 'static,
+          E: FnMut(usize, Outcome<T>) -> AppResult<()>,
+          W: Write
+{
+    let num_items = items.len();
+    let queue = Arc::new(Mutex::new(items.into_iter().enumerate()));
+    let work = Arc::new(work);
+    let (tx, rx) = mpsc::channel();
+    for _ in 0..cmp::max(1, cmp::min(config.jobs, num_items)) {
+        let queue = queue.clone();
+        let work = work.clone();
+        let tx = tx.clone();
+        let timeout = config.timeout;
+        let _ = thread::spawn(move || {
+            loop {
+                let next = queue.lock().unwrap().next();
+                let (i, item) = match next {
+                    Some(next) => next,
+                    None => break,
+                };
+                let outcome = match timeout {
+                    Some(secs) => run_with_timeout(&work, item, secs),
+                    None => Outcome::Done(work(item, Arc::new(AtomicBool::new(false)))),
+                };
+                // The receiver is gone once `emit` fails.
+                if tx.send((i, outcome)).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+    drop(tx);
+
+    let mut pending = (0..num_items).map(|_| None).collect::<Vec<_>>();
+    let mut num_done = 0;
+    let mut next = 0;
+    for (i, outcome) in rx {
+        pending[i] = Some(outcome);
+        num_done += 1;
+        if config.progress {
+            print_progress(err, num_done, num_items);
+        }
+        while let Some(outcome) = pending.get_mut(next).and_then(|o| o.take()) {
+            try!(emit(next, outcome));
+            next += 1;
+        }
+    }
+    if next < num_items {
+        // A worker has panicked, which is reported on the standard error.
+        let msg = format!("item #{} is not processed", next + 1);
+        return Err(io::Error::new(io::ErrorKind::Other, msg).into());
+    }
+    Ok(())
+}
+
+// Runs the work of the item in its own thread, and gives it up after the
+// seconds, setting the flag to stop the work and waiting for it to end.
+fn run_with_timeout<I, T, F>(work: &Arc<F>, item: I, secs: u64) -> Outcome<T>
+    where I: Send + 'static,
+          T: Send + 'static,
+          F: Fn(I, Arc<AtomicBool>) -> T + Send + Sync + 'static
+{
+    let (tx, rx) = mpsc::channel();
+    let work = work.clone();
+    let stop = Arc::new(AtomicBool::new(false));
+    let flag = stop.clone();
+    let _ = thread::spawn(move || {
+        let _ = tx.send(work(item, flag));
+    });
+    match rx.recv_timeout(Duration::from_secs(secs)) {
+        Ok(result) => Outcome::Done(result),
+        Err(RecvTimeoutError::Timeout) => {
+            stop.store(true, Ordering::SeqCst);
+            let _ = rx.recv();
+            Outcome::TimedOut
+        }
+        // The thread has panicked, which is reported as the worker does.
+        Err(RecvTimeoutError::Disconnected) => panic!("the work of an item panicked"),
+    }
+}
+
+// Draws the progress bar over itself on the sink of the standard error.
+fn print_progress<W: Write>(err: &mut W, done: usize, total: usize) {
+    let filled = if total == 0 {
+        PROGRESS_WIDTH
+    } else {
+        done * PROGRESS_WIDTH / total
+    };
+    let bar = (0..PROGRESS_WIDTH)
+                  .map(|i| if i < filled { '#' } else { '.' })
+                  .collect::<String>();
+    let end = if done == total { "\n" } else { "" };
+    let _ = write!(err, "\r[{}] {}/{}{}", bar, done, total, end);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cmp, io, thread};
+    use std::sync::{Arc, Mutex};
+    use std::sync::atomic::Ordering;
+    use std::time::Duration;
+
+    use parse_arg::PipelineConfig;
+    use super::{Outcome, run};
+
+    fn config(jobs: usize, timeout: Option<u64>) -> PipelineConfig {
+        PipelineConfig {
+            jobs: jobs,
+            timeout: timeout,
+            progress: false,
+        }
+    }
+
+    #[test]
+    fn ordered() {
+        // The later items are done first.
+        let mut emitted = vec![];
+        let mut err = vec![];
+        run(&config(3, None),
+            (0..6).collect(),
+            |i: u64, _| {
+                thread::sleep(Duration::from_millis(60 - 10 * i));
+                i * 10
+            },
+            |i, outcome| {
+                emitted.push((i, outcome.done(i).unwrap()));
+                Ok(())
+            },
+            &mut err)
+            .unwrap();
+        assert_eq!((0..6).map(|i| (i as usize, i * 10)).collect::<Vec<_>>(),
+                   emitted);
+        assert!(err.is_empty());
+
+        let mut err = vec![];
+        run(&PipelineConfig { progress: true, ..config(2, None) },
+            vec![1, 2],
+            |i: u32, _| i,
+            |_, _| Ok(()),
+            &mut err)
+            .unwrap();
+        assert!(String::from_utf8(err).unwrap().ends_with("] 2/2\n"));
+    }
+
+    #[test]
+    fn timeout() {
+        // The items of the odd indices run until they are stopped. The
+        // running works are counted, with the most of them at a time.
+        let running = Arc::new(Mutex::new((0, 0)));
+        let counter = running.clone();
+        let mut outcomes = vec![];
+        run(&config(2, Some(1)),
+            (0..4).collect(),
+            move |i: usize, stop| {
+                {
+                    let mut count = counter.lock().unwrap();
+                    count.0 += 1;
+                    count.1 = cmp::max(count.0, count.1);
+                }
+                while i % 2 == 1 && !stop.load(Ordering::SeqCst) {
+                    thread::sleep(Duration::from_millis(10));
+                }
+                counter.lock().unwrap().0 -= 1;
+                i
+            },
+            |i, outcome| {
+                outcomes.push(match outcome {
+                    Outcome::Done(n) => Some(n),
+                    Outcome::TimedOut => {
+                        assert!(outcome.done(i).is_err());
+                        None
+                    }
+                });
+                Ok(())
+            },
+            &mut vec![])
+            .unwrap();
+        assert_eq!(vec![Some(0), None, Some(2), None], outcomes);
+        // The works timed out have ended when the pipeline returns.
+        let count = *running.lock().unwrap();
+        assert_eq!(0, count.0);
+        assert!(count.1 <= 2);
+    }
+
+    #[test]
+    fn emit_error() {
+        let mut emitted = vec![];
+        let result = run(&config(2, None),
+                         (0..10).collect(),
+                         |i: usize, _| i,
+                         |i, _| {
+                             emitted.push(i);
+                             if i == 2 {
+                                 Err(io::Error::new(io::ErrorKind::Other, "emit").into())
+                             } else {
+                                 Ok(())
+                             }
+                         },
+                         &mut vec![]);
+        assert_eq!("IO error: emit", result.unwrap_err().to_string());
+        assert_eq!(vec![0, 1, 2], emitted);
+    }
+
+    #[test]
+    fn not_processed() {
+        // The panic of the work ends the only worker, with or without the
+        // timeout.
+        for &timeout in &[None, Some(10)] {
+            let mut emitted = vec![];
+            let result = run(&config(1, timeout),
+                             (0..4).collect(),
+                             |i: usize, _| {
+                                 assert!(i != 1, "the work panics");
+                                 i
+                             },
+                             |i, _| {
+                                 emitted.push(i);
+                                 Ok(())
+                             },
+                             &mut vec![]);
+            assert_eq!("IO error: item #2 is not processed",
+                       result.unwrap_err().to_string());
+            assert_eq!(vec![0], emitted);
+        }
+    }
+}
@@ -0,0 +1,546 @@
+// Copyright (c) 2016 srither developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Plays a puzzle interactively.
+
+use std::{cmp, io, thread};
+use std::fs::{File, OpenOptions};
+use std::io::prelude::*;
+use std::path::Path;
+use std::time::Duration;
+use time;
+
+use srither_core::geom::{Geom, Move, Point};
+use srither_core::annotation::{Annotation, Color, Target};
+use srither_core::puzzle::{Edge, Puzzle};
+use srither_solver::{self as solver, EasiestFirst, Hint, HintCache, HintPolicy, Reinforce,
+                     SolveOptions};
+
+use coord;
+use envelope;
+use error::{AppError, AppResult};
+use io_util;
+use parse_arg::{HintPolicyArg, PlayConfig};
+use pprint::{self, Config as PpConfig, Mode as PpMode};
+use solve;
+use theme::Theme;
+
+// `R C` is replaced by the names of the coordinates in the style given by
+// `--coords`.
+const HELP: &'static str = "\
+commands:
+  line R C SIDE     draw a line on the side (top, bottom, left, right) of the cell
+  cross R C SIDE    put a cross on the side of the cell
+  clear R C SIDE    clear the side of the cell
+  undo              undo the last change
+  show              print the board
+  check             compare the lines with the solution
+  hint              show an edge to fix next, and the step deducing it
+  help              print this message
+  quit              exit";
+
+
+// The longest pause between the changes shown by the replay.
+const MAX_REPLAY_PAUSE_MS: u64 = 1000;
+
+// A change of an edge, as recorded in the log: the horizontal edge above
+// the point or the vertical edge on the left of it.
+#[derive(Copy, Clone, Debug)]
+struct Change {
+    time_ms: u64,
+    horizontal: bool,
+    point: Point,
+    edge: Option<Edge>,
+}
+
+/// Reads the commands from `input`, and writes the board and the answers to
+/// `out`. Returns the board at the end.
+pub fn run<I, O>(config: PlayConfig, input: &mut I, out: &mut O) -> AppResult<Puzzle>
+    where I: BufRead,
+          O: Write
+{
+    if let Some(ref path) = config.replay_file {
+        return replay(out, path);
+    }
+    let file = match config.input_file {
+        Some(ref file) => file,
+        None => return Err(invalid_log("no puzzle to play")),
+    };
+    let save = config.save_file.clone().unwrap_or(format!("{}.play", file));
+
+    let buf = try!(io_util::read_file(file));
+    let mut puzzle = try!(Puzzle::parse_any(&buf)).0.hints_only();
+
+    // The progress is resumed from the log, and the changes are appended to it.
+    let mut history = vec![];
+    if Path::new(&save).exists() {
+        let (hints, changes) = try!(read_log(&save));
+        if hints != puzzle {
+            return Err(invalid_log("the log is of another puzzle"));
+        }
+        for change in changes {
+            history.push(apply(&mut puzzle, change));
+        }
+        try!(writeln!(out, "resumed {} changes from {}", history.len(), save));
+    }
+    let mut log = try!(OpenOptions::new().append(true).create(true).open(&save));
+    if try!(log.metadata()).len() == 0 {
+        try!(writeln!(log, "{}", envelope::header(&envelope::PLAY_LOG)));
+        try!(writeln!(log, "# hints: {}", hint_rows(&puzzle)));
+        try!(log.sync_data());
+    }
+    try!(show(out, &puzzle));
+
+    // The steps the player applies are remembered for this session only.
+    let mut policy: Box<HintPolicy> = match config.hint_policy {
+        HintPolicyArg::Easiest => Box::new(EasiestFirst),
+        HintPolicyArg::Reinforce => Box::new(Reinforce::new(config.learn_times)),
+    };
+    let mut shown: Option<Hint> = None;
+    // The hints and the checks are answered from the cache if precomputed,
+    // instead of solving the puzzle for each of them.
+    let mut cache = if config.precompute {
+        Some(try!(HintCache::new(&puzzle, &SolveOptions::default())))
+    } else {
+        None
+    };
+
+    try!(prompt(out));
+    for line in input.lines() {
+        let line = try!(line);
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        let edge = match words.get(0).cloned() {
+            Some("line") => Some(Some(Edge::Line)),
+            Some("cross") => Some(Some(Edge::Cross)),
+            Some("clear") => Some(None),
+            _ => None,
+        };
+        let change = match (words.get(0).cloned(), edge) {
+            (Some(cmd), Some(edge)) => {
+                match parse_side(&puzzle, &words[1..]) {
+                    Some((horizontal, point)) => {
+                        Some(Change {
+                            time_ms: now_ms(),
+                            horizontal: horizontal,
+                            point: point,
+                            edge: edge,
+                        })
+                    }
+                    None => {
+                        try!(writeln!(out,
+                                      "usage: {} {} top|bottom|left|right",
+                                      cmd,
+                                      coord::style().names()));
+                        None
+                    }
+                }
+            }
+            (Some("undo"), _) => {
+                match history.pop() {
+                    Some(mut change) => {
+                        change.time_ms = now_ms();
+                        let _ = apply(&mut puzzle, change);
+                        try!(append(&mut log, change));
+                        try!(show(out, &puzzle));
+                    }
+                    None => try!(writeln!(out, "no change to undo")),
+                }
+                None
+            }
+            (Some("show"), _) => {
+                try!(show(out, &puzzle));
+                None
+            }
+            (Some("check"), _) => {
+                try!(check(out, &puzzle, cache.as_ref()));
+                None
+            }
+            (Some("hint"), _) => {
+                shown = try!(hint(out, &puzzle, &mut *policy, cache.as_mut()));
+                None
+            }
+            (Some("help"), _) => {
+                try!(writeln!(out, "{}", HELP.replace("R C", coord::style().names())));
+                None
+            }
+            (Some("quit"), _) | (Some("exit"), _) => return Ok(puzzle),
+            (Some(cmd), _) => {
+                try!(writeln!(out, "unknown command `{}` (type `help` for commands)", cmd));
+                None
+            }
+            (None, _) => None,
+        };
+        if let Some(change) = change {
+            credit(&puzzle, change, shown, &mut *policy, cache.as_mut());
+            history.push(apply(&mut puzzle, change));
+            try!(append(&mut log, change));
+            try!(show(out, &puzzle));
+        }
+        try!(prompt(out));
+    }
+
+    Ok(puzzle)
+}
+
+/// Shows the changes in the log one by one, pausing as long as the player
+/// did, up to a second.
+fn replay<O: Write>(out: &mut O, path: &str) -> AppResult<Puzzle> {
+    let (mut puzzle, changes) = try!(read_log(path));
+    try!(show(out, &puzzle));
+    let start = changes.first().map_or(0, |c| c.time_ms);
+    let mut prev = start;
+    for change in changes {
+        let pause = cmp::min(change.time_ms.saturating_sub(prev), MAX_REPLAY_PAUSE_MS);
+        thread::sleep(Duration::from_millis(pause));
+        prev = change.time_ms;
+
+        let _ = apply(&mut puzzle, change);
+        let secs = (change.time_ms - start) / 1000;
+        try!(writeln!(out, "[{:02}:{:02}] {}", secs / 60, secs % 60, describe(change)));
+        try!(show(out, &puzzle));
+    }
+    Ok(puzzle)
+}
+
+// Sets the edge of the change, and returns the change undoing it.
+fn apply(puzzle: &mut Puzzle, change: Change) -> Change {
+    let mut undo = change;
+    if change.horizontal {
+        undo.edge = puzzle.edge_h(change.point);
+        puzzle.set_edge_h(change.point, change.edge);
+    } else {
+        undo.edge = puzzle.edge_v(change.point);
+        puzzle.set_edge_v(change.point, change.edge);
+    }
+    undo
+}
+
+// Appends the change to the log, and syncs it so that it survives crashes.
+fn append(log: &mut File, change: Change) -> AppResult<()> {
+    let edge = match change.edge {
+        Some(Edge::Line) => "line",
+        Some(Edge::Cross) => "cross",
+        None => "clear",
+    };
+    try!(writeln!(log,
+                  "{} {} {} {} {}",
+                  change.time_ms,
+                  if change.horizontal { "h" } else { "v" },
+                  change.point.0,
+                  change.point.1,
+                  edge));
+    try!(log.sync_data());
+    Ok(())
+}
+
+fn read_log(path: &str) -> AppResult<(Puzzle, Vec<Change>)> {
+    let buf = try!(io_util::read_file(path));
+    let mut lines = buf.lines().collect::<Vec<_>>();
+    // The last line is incomplete if the player crashed while writing it.
+    if !buf.ends_with('\n') {
+        let _ = lines.pop();
+    }
+
+    let mut lines = lines.into_iter().map(|l| l.trim()).filter(|l| !l.is_empty());
+    match lines.next().and_then(|line| envelope::parse(&envelope::PLAY_LOG, line)) {
+        Some(envelope) => try!(envelope::check(&envelope::PLAY_LOG, &envelope)),
+        None => return Err(invalid_log("not a play log")),
+    }
+    let puzzle = match lines.next() {
+        Some(line) if line.starts_with("# hints:") => {
+            let rows = line["# hints:".len()..].trim().replace('/', "\n");
+            try!(rows.parse::<Puzzle>())
+        }
+        _ => return Err(invalid_log("the log has no hints")),
+    };
+
+    let mut changes = vec![];
+    for line in lines {
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        if words.len() != 5 {
+            return Err(invalid_log("invalid change in the log"));
+        }
+        let horizontal = match words[1] {
+            "h" => true,
+            "v" => false,
+            _ => return Err(invalid_log("invalid change in the log")),
+        };
+        let edge = match words[4] {
+            "line" => Some(Edge::Line),
+            "cross" => Some(Edge::Cross),
+            "clear" => None,
+            _ => return Err(invalid_log("invalid change in the log")),
+        };
+        let (point, time_ms) = match (words[2].parse(), words[3].parse(), words[0].parse()) {
+            (Ok(r), Ok(c), Ok(t)) => (Point(r, c), t),
+            _ => return Err(invalid_log("invalid change in the log")),
+        };
+        let size = (puzzle.row() + 1, puzzle.column() + 1);
+        if point.0 < 0 || point.1 < 0 || point.0 >= size.0 || point.1 >= size.1 ||
+           (horizontal && point.1 == puzzle.column()) ||
+           (!horizontal && point.0 == puzzle.row()) {
+            return Err(invalid_log("invalid change in the log"));
+        }
+        changes.push(Change {
+            time_ms: time_ms,
+            horizontal: horizontal,
+            point: point,
+            edge: edge,
+        });
+    }
+    Ok((puzzle, changes))
+}
+
+/// Writes the hints as the rows separated by `/`.
+pub fn hint_rows(puzzle: &Puzzle) -> String {
+    let rows = (0..puzzle.row()).map(|r| {
+        (0..puzzle.column())
+            .map(|c| {
+                match puzzle.hint(Point(r, c)) {
+                    Some(n) => (b'0' + n) as char,
+                    None => '_',
+                }
+            })
+            .collect::<String>()
+    });
+    rows.collect::<Vec<_>>().join("/")
+}
+
+fn parse_side(puzzle: &Puzzle, args: &[&str]) -> Option<(bool, Point)> {
+    if args.len() != 3 {
+        return None;
+    }
+    let p = match coord::parse(&args[..2]) {
+        Some(p) if puzzle.contains(p) => p,
+        _ => return None,
+    };
+    match args[2] {
+        "top" => Some((true, p)),
+        "bottom" => Some((true, p + Move::DOWN)),
+        "left" => Some((false, p)),
+        "right" => Some((false, p + Move::RIGHT)),
+        _ => None,
+    }
+}
+
+fn describe(change: Change) -> String {
+    let edge = match change.edge {
+        Some(Edge::Line) => "line",
+        Some(Edge::Cross) => "cross",
+        None => "clear",
+    };
+    let dir = if change.horizontal {
+        "horizontal"
+    } else {
+        "vertical"
+    };
+    format!("{} the {} edge at {}", edge, dir, coord::format(change.point))
+}
+
+// Shows the hint chosen by the policy among the edges deduced from the
+// correct edges of the player, and returns it.
+fn hint<O: Write>(out: &mut O,
+                  puzzle: &Puzzle,
+                  policy: &mut HintPolicy,
+                  cache: Option<&mut HintCache>)
+                  -> AppResult<Option<Hint>> {
+    let (hints, wrong) = match deducible(puzzle, cache) {
+        Ok(deducible) => deducible,
+        Err(e) => {
+            try!(writeln!(out, "cannot solve the puzzle: {}", e));
+            return Ok(None);
+        }
+    };
+    match wrong {
+        0 => {}
+        1 => try!(writeln!(out, "1 edge is wrong, which is ignored")),
+        _ => try!(writeln!(out, "{} edges are wrong, which are ignored", wrong)),
+    }
+    match policy.choose(&hints) {
+        Some(i) => {
+            let h = hints[i];
+            let change = Change {
+                time_ms: 0,
+                horizontal: h.horizontal,
+                point: h.point,
+                edge: Some(h.edge),
+            };
+            try!(writeln!(out, "hint: {} (by {})", describe(change), solve::step_name(h.step)));
+            Ok(Some(h))
+        }
+        None => {
+            try!(writeln!(out, "no edge is deduced without guessing"));
+            Ok(None)
+        }
+    }
+}
+
+// Tells the policy the step the player has applied by the change, if the
+// change fixes an edge deducible from the board and is not the hint shown.
+fn credit(puzzle: &Puzzle,
+          change: Change,
+          shown: Option<Hint>,
+          policy: &mut HintPolicy,
+          cache: Option<&mut HintCache>) {
+    let edge = match change.edge {
+        Some(edge) => edge,
+        None => return,
+    };
+    let same = |h: &Hint| h.horizontal == change.horizontal && h.point == change.point;
+    if shown.as_ref().map_or(false, |h| same(h)) {
+        return;
+    }
+    if let Ok((hints, _)) = deducible(puzzle, cache) {
+        if let Some(h) = hints.iter().find(|h| same(h) && h.edge == edge) {
+            policy.applied(h);
+        }
+    }
+}
+
+// Returns the edges deduced from the edges of the player agreeing with the
+// solution, and the number of the other edges.
+fn deducible(puzzle: &Puzzle, cache: Option<&mut HintCache>) -> AppResult<(Vec<Hint>, usize)> {
+    if let Some(cache) = cache {
+        return Ok((try!(cache.hints(puzzle)), cache.mistakes(puzzle)));
+    }
+    let mut board = puzzle.hints_only();
+    let solution = try!(solver::solve(&board));
+    let mut wrong = 0;
+    for r in 0..(puzzle.row() + 1) {
+        for c in 0..(puzzle.column() + 1) {
+            let p = Point(r, c);
+            if c < puzzle.column() {
+                match puzzle.edge_h(p) {
+                    Some(e) if Some(e) == solution.edge_h(p) => board.set_edge_h(p, Some(e)),
+                    Some(_) => wrong += 1,
+                    None => {}
+                }
+            }
+            if r < puzzle.row() {
+                match puzzle.edge_v(p) {
+                    Some(e) if Some(e) == solution.edge_v(p) => board.set_edge_v(p, Some(e)),
+                    Some(_) => wrong += 1,
+                    None => {}
+                }
+            }
+        }
+    }
+    Ok((try!(solver::hints(&board, &SolveOptions::default())), wrong))
+}
+
+// Compares the edges set by the player with the solution.
+fn check<O: Write>(out: &mut O, puzzle: &Puzzle, cache: Option<&HintCache>) -> AppResult<()> {
+    let solution = match cache {
+        Some(cache) => cache.solution().clone(),
+        None => {
+            match solver::solve(&puzzle.hints_only()) {
+                Ok(solution) => solution,
+                Err(e) => {
+                    try!(writeln!(out, "cannot solve the puzzle: {}", e));
+                    return Ok(());
+                }
+            }
+        }
+    };
+
+    // The wrong edges are highlighted on the board, with the hints of the
+    // cells on their sides.
+    let (mut wrong, mut left) = (0, 0);
+    let mut marked = puzzle.clone();
+    {
+        let mut compare = |target: Target, played: Option<Edge>, answer: Option<Edge>| {
+            match (played, answer) {
+                (Some(e), Some(a)) if e != a => {
+                    wrong += 1;
+                    mark_wrong(&mut marked, target, e);
+                }
+                (None, Some(Edge::Line)) => left += 1,
+                _ => {}
+            }
+        };
+        for r in 0..(puzzle.row() + 1) {
+            for c in 0..(puzzle.column() + 1) {
+                let p = Point(r, c);
+                if c < puzzle.column() {
+                    compare(Target::EdgeH(p), puzzle.edge_h(p), solution.edge_h(p));
+                }
+                if r < puzzle.row() {
+                    compare(Target::EdgeV(p), puzzle.edge_v(p), solution.edge_v(p));
+                }
+            }
+        }
+    }
+    match (wrong, left) {
+        (0, 0) => try!(writeln!(out, "solved!")),
+        (0, _) => try!(writeln!(out, "no mistakes, {} lines left", left)),
+        (1, _) => try!(writeln!(out, "1 edge is wrong")),
+        _ => try!(writeln!(out, "{} edges are wrong", wrong)),
+    }
+    if wrong > 0 {
+        try!(show(out, &marked));
+    }
+    Ok(())
+}
+
+// Highlights the wrong edge in red, and the hints of the cells on its sides
+// in yellow.
+fn mark_wrong(puzzle: &mut Puzzle, target: Target, edge: Edge) {
+    let cells = match target {
+        Target::EdgeH(p) => [p + Move::UP, p],
+        Target::EdgeV(p) => [p + Move::LEFT, p],
+        Target::Cell(_) => unreachable!(),
+    };
+    let label = match edge {
+        Edge::Line => "a line where the solution has a cross",
+        Edge::Cross => "a cross where the solution has a line",
+    };
+    puzzle.annotations_mut().set(target,
+                                 Annotation {
+                                     label: label.to_owned(),
+                                     color: Some(Color::Red),
+                                 });
+    for &p in &cells {
+        if puzzle.contains(p) && puzzle.hint(p).is_some() {
+            puzzle.annotations_mut().set(Target::Cell(p),
+                                         Annotation {
+                                             label: String::new(),
+                                             color: Some(Color::Yellow),
+                                         });
+        }
+    }
+}
+
+fn show<O: Write>(out: &mut O, puzzle: &Puzzle) -> AppResult<()> {
+    let mode = if pprint::is_pprintable() {
+        PpMode::Color
+    } else {
+        PpMode::Ascii
+    };
+    let conf = PpConfig {
+        mode: mode,
+        cell_width: 2,
+        cell_height: 1,
+        theme: Theme::default(),
+    };
+    pprint::print(out, &conf, puzzle)
+}
+
+fn prompt<O: Write>(out: &mut O) -> AppResult<()> {
+    try!(write!(out, "> "));
+    try!(out.flush());
+    Ok(())
+}
+
+fn now_ms() -> u64 {
+    let now = time::get_time();
+    now.sec as u64 * 1000 + now.nsec as u64 / 1000000
+}
+
+fn invalid_log(msg: &str) -> AppError {
+    io::Error::new(io::ErrorKind::InvalidData, msg).into()
+}
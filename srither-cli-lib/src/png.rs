@@ -0,0 +1,433 @@
+// Copyright (c) 2016 srither developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! The PNG backend of the layouts, rasterizing the shapes into a grayscale
+//! image and encoding it without any external library.
+//!
+//! The shapes are antialiased by the distance from each pixel to them. The
+//! rows are filtered by the row above, so that the rows of the same pixels
+//! become zeros, and compressed by the runs of the same bytes in a block of
+//! the fixed Huffman codes of deflate, which is enough for the images of
+//! boards, mostly white.
+//!
+//! The animations are written in APNG, whose first frame is also the image
+//! shown by the readers without the support of the animations.
+
+use std::cmp;
+
+use layout::{Layout, Pos, Shape};
+
+// The pixel value of the background, darkened by the shapes to black.
+const WHITE: u8 = 255;
+
+/// A grayscale image of 8 bits per pixel.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl Image {
+    /// Rasterizes the layout into an image of its size rounded up.
+    pub fn render(layout: &Layout) -> Image {
+        let width = layout.width.ceil() as usize;
+        let height = layout.height.ceil() as usize;
+        let mut image = Image {
+            width: width,
+            height: height,
+            pixels: vec![WHITE; width * height],
+        };
+        for shape in &layout.shapes {
+            match *shape {
+                Shape::Dot(center, radius) => image.fill(center, center, radius),
+                Shape::Stroke(ref points, width) => {
+                    for pair in points.windows(2) {
+                        image.fill(pair[0], pair[1], width / 2.0);
+                    }
+                }
+            }
+        }
+        image
+    }
+
+    // Darkens the pixels within the radius of the segment, antialiased by
+    // the coverage of a pixel estimated from the distance.
+    fn fill(&mut self, a: Pos, b: Pos, radius: f64) {
+        let x0 = clamp((a.0.min(b.0) - radius - 1.0).floor(), self.width);
+        let x1 = clamp((a.0.max(b.0) + radius + 1.0).ceil(), self.width);
+        let y0 = clamp((a.1.min(b.1) - radius - 1.0).floor(), self.height);
+        let y1 = clamp((a.1.max(b.1) + radius + 1.0).ceil(), self.height);
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let d = distance((x as f64 + 0.5, y as f64 + 0.5), a, b);
+                let coverage = (radius + 0.5 - d).max(0.0).min(1.0);
+                let value = (WHITE as f64 * (1.0 - coverage)).round() as u8;
+                let pixel = &mut self.pixels[y * self.width + x];
+                *pixel = cmp::min(*pixel, value);
+            }
+        }
+    }
+
+    /// The width in pixels.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// The height in pixels.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The pixels, row by row from the top.
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Encodes the image in PNG, with the resolution in dots per inch if
+    /// any.
+    pub fn encode(&self, dpi: Option<u32>) -> Vec<u8> {
+        let mut png = self.header(dpi, None);
+        chunk(&mut png, b"IDAT", &self.compress());
+        chunk(&mut png, b"IEND", &[]);
+        png
+    }
+
+    // The signature and the chunks before the image data, with the control
+    // of the animation of the number of the frames if any.
+    fn header(&self, dpi: Option<u32>, frames: Option<usize>) -> Vec<u8> {
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+
+        let mut header = vec![];
+        push_u32(&mut header, self.width as u32);
+        push_u32(&mut header, self.height as u32);
+        // 8 bits, grayscale, deflate, adaptive filters, no interlace.
+        header.extend_from_slice(&[8, 0, 0, 0, 0]);
+        chunk(&mut png, b"IHDR", &header);
+
+        if let Some(frames) = frames {
+            let mut actl = vec![];
+            push_u32(&mut actl, frames as u32);
+            // Played forever.
+            push_u32(&mut actl, 0);
+            chunk(&mut png, b"acTL", &actl);
+        }
+
+        if let Some(dpi) = dpi {
+            let per_meter = (dpi as f64 / 0.0254).round() as u32;
+            let mut phys = vec![];
+            push_u32(&mut phys, per_meter);
+            push_u32(&mut phys, per_meter);
+            // The unit is the meter.
+            phys.push(1);
+            chunk(&mut png, b"pHYs", &phys);
+        }
+        png
+    }
+
+    // The filtered rows compressed into a zlib stream.
+    fn compress(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity((self.width + 1) * self.height);
+        for y in 0..self.height {
+            let row = &self.pixels[y * self.width..(y + 1) * self.width];
+            if y == 0 {
+                // No filter.
+                data.push(0);
+                data.extend_from_slice(row);
+            } else {
+                // The difference from the row above.
+                let above = &self.pixels[(y - 1) * self.width..y * self.width];
+                data.push(2);
+                data.extend(row.iter().zip(above).map(|(&p, &q)| p.wrapping_sub(q)));
+            }
+        }
+        zlib(&data)
+    }
+}
+
+/// Encodes the frames of the same size with their delays in milliseconds in
+/// APNG, played forever.
+pub fn encode_animation(frames: &[(Image, u32)], dpi: Option<u32>) -> Vec<u8> {
+    let mut png = frames[0].0.header(dpi, Some(frames.len()));
+    // The sequence numbers of the controls and the data of the frames.
+    let mut seq = 0;
+    for (i, &(ref image, delay)) in frames.iter().enumerate() {
+        let mut fctl = vec![];
+        push_u32(&mut fctl, seq);
+        push_u32(&mut fctl, image.width as u32);
+        push_u32(&mut fctl, image.height as u32);
+        push_u32(&mut fctl, 0);
+        push_u32(&mut fctl, 0);
+        // The delay as the fraction of a second, in milliseconds.
+        push_u16(&mut fctl, delay as u16);
+        push_u16(&mut fctl, 1000);
+        // Neither disposed nor blended, as the frames are whole.
+        fctl.extend_from_slice(&[0, 0]);
+        chunk(&mut png, b"fcTL", &fctl);
+        seq += 1;
+
+        if i == 0 {
+            chunk(&mut png, b"IDAT", &image.compress());
+        } else {
+            let mut fdat = vec![];
+            push_u32(&mut fdat, seq);
+            fdat.extend_from_slice(&image.compress());
+            chunk(&mut png, b"fdAT", &fdat);
+            seq += 1;
+        }
+    }
+    chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn clamp(v: f64, max: usize) -> usize {
+    if v < 0.0 {
+        0
+    } else {
+        cmp::min(v as usize, max)
+    }
+}
+
+// The distance from the point to the segment.
+fn distance(p: Pos, a: Pos, b: Pos) -> f64 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let len2 = dx * dx + dy * dy;
+    let t = if len2 == 0.0 {
+        0.0
+    } else {
+        (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / len2).max(0.0).min(1.0)
+    };
+    let (ex, ey) = (a.0 + t * dx - p.0, a.1 + t * dy - p.1);
+    (ex * ex + ey * ey).sqrt()
+}
+
+fn push_u16(buf: &mut Vec<u8>, n: u16) {
+    buf.extend_from_slice(&[(n >> 8) as u8, n as u8]);
+}
+
+fn push_u32(buf: &mut Vec<u8>, n: u32) {
+    buf.extend_from_slice(&[(n >> 24) as u8, (n >> 16) as u8, (n >> 8) as u8, n as u8]);
+}
+
+fn chunk(png: &mut Vec<u8>, kind: &[u8], data: &[u8]) {
+    push_u32(png, data.len() as u32);
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    push_u32(png, crc);
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &b in data {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                0xedb88320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &x in data {
+        a = (a + x as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+// The bits of deflate, packed from the least significant bit of each byte.
+struct BitWriter {
+    bytes: Vec<u8>,
+    bits: u32,
+    len: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u32, len: u32) {
+        self.bits |= value << self.len;
+        self.len += len;
+        while self.len >= 8 {
+            self.bytes.push(self.bits as u8);
+            self.bits >>= 8;
+            self.len -= 8;
+        }
+    }
+
+    // Writes a Huffman code, whose bits are packed from the most
+    // significant one.
+    fn write_code(&mut self, code: u32, len: u32) {
+        let mut reversed = 0;
+        for i in 0..len {
+            reversed |= ((code >> i) & 1) << (len - 1 - i);
+        }
+        self.write(reversed, len);
+    }
+
+    fn write_literal(&mut self, v: u32) {
+        match v {
+            0...143 => self.write_code(0x30 + v, 8),
+            144...255 => self.write_code(0x190 + v - 144, 9),
+            256...279 => self.write_code(v - 256, 7),
+            _ => self.write_code(0xc0 + v - 280, 8),
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.len > 0 {
+            self.bytes.push(self.bits as u8);
+        }
+        self.bytes
+    }
+}
+
+// The base lengths and the extra bits of the length codes from 257.
+static LENGTH_BASES: [u32; 29] = [3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43,
+                                  51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258];
+static LENGTH_EXTRA: [u32; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4,
+                                  4, 4, 5, 5, 5, 5, 0];
+
+// The longest match of deflate.
+const MAX_RUN: usize = 258;
+
+// Compresses the data into a zlib stream of a single block of the fixed
+// codes, where the runs of a byte repeated are the matches of the distance 1.
+fn zlib(data: &[u8]) -> Vec<u8> {
+    let mut w = BitWriter {
+        bytes: vec![0x78, 0x01],
+        bits: 0,
+        len: 0,
+    };
+    // The final block of the fixed codes.
+    w.write(1, 1);
+    w.write(1, 2);
+    let mut i = 0;
+    while i < data.len() {
+        let mut run = 0;
+        if i > 0 {
+            while run < MAX_RUN && i + run < data.len() && data[i + run] == data[i - 1] {
+                run += 1;
+            }
+        }
+        if run >= 3 {
+            let code = LENGTH_BASES.iter().rposition(|&base| base as usize <= run).unwrap();
+            w.write_literal(257 + code as u32);
+            w.write(run as u32 - LENGTH_BASES[code], LENGTH_EXTRA[code]);
+            // The distance code 0 for the distance 1.
+            w.write_code(0, 5);
+            i += run;
+        } else {
+            w.write_literal(data[i] as u32);
+            i += 1;
+        }
+    }
+    w.write_literal(256);
+    let mut stream = w.finish();
+    push_u32(&mut stream, adler32(data));
+    stream
+}
+
+#[cfg(test)]
+mod tests {
+    use layout::Layout;
+    use super::{Image, adler32, crc32, encode_animation, zlib};
+
+    fn u32_at(buf: &[u8], i: usize) -> u32 {
+        (buf[i] as u32) << 24 | (buf[i + 1] as u32) << 16 | (buf[i + 2] as u32) << 8 |
+        buf[i + 3] as u32
+    }
+
+    // Splits the PNG after the signature into the kinds and the data of the
+    // chunks, checking their CRCs.
+    fn chunks(png: &[u8]) -> Vec<(String, Vec<u8>)> {
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+        let mut chunks = vec![];
+        let mut i = 8;
+        while i < png.len() {
+            let len = u32_at(png, i) as usize;
+            let body = &png[i + 4..i + 8 + len];
+            assert_eq!(crc32(body), u32_at(png, i + 8 + len));
+            chunks.push((String::from_utf8(body[..4].to_vec()).unwrap(), body[4..].to_vec()));
+            i += 12 + len;
+        }
+        assert_eq!(png.len(), i);
+        chunks
+    }
+
+    fn blank(width: f64, height: f64) -> Image {
+        Image::render(&Layout {
+            width: width,
+            height: height,
+            shapes: vec![],
+        })
+    }
+
+    #[test]
+    fn checksums() {
+        assert_eq!(0xcbf43926, crc32(b"123456789"));
+        assert_eq!(0xae426082, crc32(b"IEND"));
+        assert_eq!(0x11e60398, adler32(b"Wikipedia"));
+        assert_eq!(1, adler32(b""));
+    }
+
+    #[test]
+    fn compress() {
+        // The streams are inflated back by zlib.
+        assert_eq!(vec![0x78, 0x01, 0x63, 0x60, 0x04, 0x03, 0xa6, 0xc4, 0xa4, 0x64, 0x00, 0x02,
+                        0x8a, 0x01, 0x2f],
+                   zlib(b"\x00\x01\x01\x01\x01\x01\x01\x02abc"));
+        // A run longer than the longest match.
+        let mut data = vec![0; 300];
+        data.push(7);
+        assert_eq!(vec![0x78, 0x01, 0x63, 0x18, 0x05, 0x44, 0x03, 0x76, 0x00, 0x01, 0x34, 0x00,
+                        0x08],
+                   zlib(&data));
+    }
+
+    #[test]
+    fn encode() {
+        let png = blank(3.0, 2.0).encode(Some(254));
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR\x00\x00\x00\x03\
+                                  \x00\x00\x00\x02\x08\x00\x00\x00\x00\xb8\x1f\x39\xc6"));
+        assert!(png.ends_with(b"\x00\x00\x00\x00IEND\xae\x42\x60\x82"));
+        let chunks = chunks(&png);
+        let kinds = chunks.iter().map(|c| &c.0[..]).collect::<Vec<_>>();
+        assert_eq!(vec!["IHDR", "pHYs", "IDAT", "IEND"], kinds);
+        // 254 dots per inch are 10000 per meter.
+        assert_eq!(b"\x00\x00\x27\x10\x00\x00\x27\x10\x01", &chunks[1].1[..]);
+        // The rows of white, the second filtered by the first.
+        let data = b"\x00\xff\xff\xff\x02\x00\x00\x00";
+        assert_eq!(zlib(data), chunks[2].1);
+        assert_eq!(adler32(data), u32_at(&chunks[2].1, chunks[2].1.len() - 4));
+    }
+
+    #[test]
+    fn animation() {
+        let frames = [(blank(2.0, 2.0), 500), (blank(2.0, 2.0), 250), (blank(2.0, 2.0), 250)];
+        let chunks = chunks(&encode_animation(&frames, None));
+        let kinds = chunks.iter().map(|c| &c.0[..]).collect::<Vec<_>>();
+        assert_eq!(vec!["IHDR", "acTL", "fcTL", "IDAT", "fcTL", "fdAT", "fcTL", "fdAT", "IEND"],
+                   kinds);
+        // Three frames played forever.
+        assert_eq!(b"\x00\x00\x00\x03\x00\x00\x00\x00", &chunks[1].1[..]);
+        // The sequence numbers of the controls and the data of the frames.
+        let seqs = chunks.iter()
+                         .filter(|c| c.0 == "fcTL" || c.0 == "fdAT")
+                         .map(|c| u32_at(&c.1, 0))
+                         .collect::<Vec<_>>();
+        assert_eq!(vec![0, 1, 2, 3, 4], seqs);
+        // The delay of 500/1000 seconds.
+        assert_eq!(b"\x01\xf4\x03\xe8\x00\x00", &chunks[2].1[20..]);
+    }
+}
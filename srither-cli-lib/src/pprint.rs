@@ -0,0 +1,662 @@
+// Copyright (c) 2016 srither developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use std::{env, io, iter};
+use std::cell::RefCell;
+use std::io::prelude::*;
+use std::rc::Rc;
+use std::str::FromStr;
+use std::sync::atomic::{ATOMIC_USIZE_INIT, AtomicUsize, Ordering};
+use term::{self, Terminal, color};
+use term::color::Color;
+use srither_core::annotation::{Color as Highlight, Target};
+use srither_core::puzzle::{Edge, Puzzle, Side};
+use srither_core::geom::{Geom, Move, Point};
+
+use coord;
+use error::AppResult;
+use theme::{Style, Theme};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Mode {
+    Color,
+    Ascii,
+    // The cells only, as the emoji and the full-width characters, for
+    // sharing in chats and forums.
+    Emoji,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Config {
+    pub mode: Mode,
+    pub cell_width: usize,
+    pub cell_height: usize,
+    pub theme: Theme,
+}
+
+fn side_to_style(ty: Option<Side>, theme: &Theme) -> Style {
+    match ty {
+        Some(Side::In) => theme.inside,
+        Some(Side::Out) => theme.outside,
+        None => theme.unknown,
+    }
+}
+
+fn highlight_to_color(highlight: Highlight) -> Color {
+    match highlight {
+        Highlight::Red => color::RED,
+        Highlight::Green => color::GREEN,
+        Highlight::Blue => color::BLUE,
+        Highlight::Yellow => color::YELLOW,
+        Highlight::Magenta => color::MAGENTA,
+        Highlight::Cyan => color::CYAN,
+    }
+}
+
+// The marker of the highlight written without colors.
+fn highlight_to_marker(highlight: Highlight) -> char {
+    match highlight {
+        Highlight::Red => '!',
+        Highlight::Green => '=',
+        Highlight::Blue => '#',
+        Highlight::Yellow => '*',
+        Highlight::Magenta => '%',
+        Highlight::Cyan => '^',
+    }
+}
+
+// Marks the target highlighted without colors: the edges are drawn with the
+// marker, and the blanks of the cells around their hints are filled with it.
+fn mark(highlight: Highlight, target: Target, s: &str) -> String {
+    let marker = highlight_to_marker(highlight);
+    match target {
+        Target::Cell(_) => s.chars().map(|c| if c == ' ' { marker } else { c }).collect(),
+        Target::EdgeH(_) | Target::EdgeV(_) => s.chars().map(|_| marker).collect(),
+    }
+}
+
+#[cfg(unix)]
+fn isatty_stdout() -> bool {
+    extern crate libc;
+    unsafe { libc::isatty(libc::STDOUT_FILENO) != 0 }
+}
+#[cfg(windows)]
+fn isatty_stdout() -> bool {
+    extern crate kernel32;
+    extern crate winapi;
+    unsafe {
+        let handle = winapi::winbase::STD_OUTPUT_HANDLE as *mut winapi::c_void;
+        let mut out = 0;
+        kernel32::GetConsoleMode(handle, &mut out) != 0
+    }
+}
+
+/// When the output is colored.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ColorPolicy {
+    /// Colored if the standard output is a terminal.
+    Auto,
+    /// Always colored.
+    Always,
+    /// Never colored.
+    Never,
+}
+
+impl FromStr for ColorPolicy {
+    type Err = ();
+
+    fn from_str(src: &str) -> Result<ColorPolicy, ()> {
+        match src {
+            "auto" => Ok(ColorPolicy::Auto),
+            "always" => Ok(ColorPolicy::Always),
+            "never" => Ok(ColorPolicy::Never),
+            _ => Err(()),
+        }
+    }
+}
+
+static COLOR_POLICY: AtomicUsize = ATOMIC_USIZE_INIT;
+
+/// Sets the color policy of the commands, given by `--color`.
+pub fn set_color_policy(policy: ColorPolicy) {
+    let n = match policy {
+        ColorPolicy::Auto => 0,
+        ColorPolicy::Always => 1,
+        ColorPolicy::Never => 2,
+    };
+    COLOR_POLICY.store(n, Ordering::Relaxed);
+}
+
+fn color_policy() -> ColorPolicy {
+    match COLOR_POLICY.load(Ordering::Relaxed) {
+        1 => ColorPolicy::Always,
+        2 => ColorPolicy::Never,
+        _ => ColorPolicy::Auto,
+    }
+}
+
+// Returns true if the terminal of stdout has the capabilities to color the
+// text. Some terminals, e.g. `screen` without its terminfo entry, or `less`
+// with `TERM=dumb`, do not.
+fn supports_color() -> bool {
+    if env::var("TERM").map(|t| t == "dumb").unwrap_or(false) {
+        return false;
+    }
+    term::stdout().map(|t| t.supports_color()).unwrap_or(false)
+}
+
+/// Returns true if the output should be colored by the color policy.
+pub fn is_pprintable() -> bool {
+    match color_policy() {
+        ColorPolicy::Always => true,
+        ColorPolicy::Never => false,
+        ColorPolicy::Auto => isatty_stdout() && supports_color(),
+    }
+}
+
+trait Printer {
+    fn is_colored(&self) -> bool;
+    fn write_pretty(&mut self, side: Option<Side>, s: &str) -> AppResult<()>;
+    fn write_highlight(&mut self, highlight: Highlight, s: &str) -> AppResult<()>;
+    fn write_plain(&mut self, s: &str) -> AppResult<()>;
+
+    // Writes the target highlighted with the color of its annotation, if any,
+    // or with the marker of the color if the text is not colored.
+    fn write_target(&mut self,
+                    puzzle: &Puzzle,
+                    target: Target,
+                    side: Option<Side>,
+                    s: &str)
+                    -> AppResult<()> {
+        match puzzle.annotations().get(target).and_then(|a| a.color) {
+            Some(highlight) if self.is_colored() => self.write_highlight(highlight, s),
+            Some(highlight) => self.write_plain(&mark(highlight, target, s)),
+            None => self.write_pretty(side, s),
+        }
+    }
+}
+
+// The sink shared by the printer and the terminal coloring the text written
+// to it.
+struct Shared<'a, W: Write + 'a>(Rc<RefCell<&'a mut W>>);
+
+impl<'a, W: Write> Write for Shared<'a, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.borrow_mut().flush()
+    }
+}
+
+type SharedTerminal<'a, W> = Terminal<Output = Shared<'a, W>> + 'a;
+
+// Returns the terminal coloring the text written to the sink, as
+// `term::stdout` does for stdout.
+#[cfg(not(windows))]
+fn terminal<'a, W: Write>(out: Shared<'a, W>) -> Option<Box<SharedTerminal<'a, W>>> {
+    let term = match term::TerminfoTerminal::new(out) {
+        Some(term) => term,
+        None => return None,
+    };
+    let term: Box<SharedTerminal<'a, W>> = Box::new(term);
+    Some(term)
+}
+#[cfg(windows)]
+fn terminal<'a, W: Write>(out: Shared<'a, W>) -> Option<Box<SharedTerminal<'a, W>>> {
+    let term = match term::WinConsole::new(out) {
+        Ok(term) => term,
+        Err(_) => return None,
+    };
+    let term: Box<SharedTerminal<'a, W>> = Box::new(term);
+    Some(term)
+}
+
+// Writes to the sink, coloring the text while the terminal can. If the
+// terminal has no color capability, or setting the colors fails in the middle
+// (e.g. the terminal is gone or stdout is redirected), the text is written
+// plain from then on instead of garbling the output or failing.
+struct WritePrinter<'a, W: Write + 'a> {
+    term: Option<Box<SharedTerminal<'a, W>>>,
+    out: Shared<'a, W>,
+    theme: Theme,
+}
+
+impl<'a, W: Write> WritePrinter<'a, W> {
+    fn new(out: &'a mut W, mode: Mode, theme: Theme) -> WritePrinter<'a, W> {
+        let out = Rc::new(RefCell::new(out));
+        let term = if mode != Mode::Ascii {
+            terminal(Shared(out.clone())).and_then(|t| if t.supports_color() {
+                Some(t)
+            } else {
+                None
+            })
+        } else {
+            None
+        };
+        WritePrinter {
+            term: term,
+            out: Shared(out),
+            theme: theme,
+        }
+    }
+
+    // Sets the colors of the text written next, or resets them if `None`.
+    fn set_style(&mut self, style: Option<Style>) {
+        let ok = match self.term {
+            Some(ref mut t) => {
+                t.reset()
+                 .and_then(|()| {
+                     match style {
+                         Some(ref style) => t.fg(style.fg).and_then(|()| t.bg(style.bg)),
+                         None => Ok(()),
+                     }
+                 })
+                 .is_ok()
+            }
+            None => return,
+        };
+        if !ok {
+            if let Some(mut t) = self.term.take() {
+                let _ = t.reset();
+            }
+        }
+    }
+
+    fn write_str(&mut self, s: &str) -> AppResult<()> {
+        match self.term {
+            Some(ref mut t) => try!(t.write_all(s.as_bytes())),
+            None => try!(self.out.write_all(s.as_bytes())),
+        }
+        Ok(())
+    }
+}
+
+impl<'a, W: Write> Printer for WritePrinter<'a, W> {
+    fn is_colored(&self) -> bool {
+        self.term.is_some()
+    }
+    fn write_pretty(&mut self, side: Option<Side>, s: &str) -> AppResult<()> {
+        let style = side_to_style(side, &self.theme);
+        self.set_style(Some(style));
+        self.write_str(s)
+    }
+    fn write_highlight(&mut self, highlight: Highlight, s: &str) -> AppResult<()> {
+        self.set_style(Some(Style {
+            fg: color::BLACK,
+            bg: highlight_to_color(highlight),
+        }));
+        self.write_str(s)
+    }
+    fn write_plain(&mut self, s: &str) -> AppResult<()> {
+        self.set_style(None);
+        self.write_str(s)
+    }
+}
+
+struct Table {
+    label_row: LabelRow,
+    edge_row: EdgeRow,
+    cell_row: CellRow,
+}
+
+impl Table {
+    fn new(conf: &Config) -> Table {
+        Table {
+            label_row: LabelRow::new(conf),
+            edge_row: EdgeRow::new(conf),
+            cell_row: CellRow::new(conf),
+        }
+    }
+
+    fn pprint<P>(&self, printer: &mut P, puzzle: &Puzzle) -> AppResult<()>
+        where P: Printer
+    {
+        let row = puzzle.row();
+        try!(self.label_row.pprint(printer, puzzle));
+        for y in 0..row {
+            try!(self.edge_row.pprint(printer, puzzle, y));
+            try!(self.cell_row.pprint(printer, puzzle, y));
+        }
+        try!(self.edge_row.pprint(printer, puzzle, row));
+        try!(self.label_row.pprint(printer, puzzle));
+        try!(pprint_labels(printer, puzzle));
+        Ok(())
+    }
+}
+
+struct LabelRow {
+    space_left: String,
+    space_cross: String,
+    label: Label,
+}
+
+impl LabelRow {
+    fn new(conf: &Config) -> LabelRow {
+        LabelRow {
+            space_left: format!("{:1$}", "", conf.cell_width),
+            space_cross: format!("{:1$}", "", 1),
+            label: Label::new(conf),
+        }
+    }
+
+    fn pprint<P>(&self, printer: &mut P, puzzle: &Puzzle) -> AppResult<()>
+        where P: Printer
+    {
+        try!(printer.write_plain(&self.space_left));
+        for x in 0..puzzle.column() {
+            try!(printer.write_plain(&self.space_cross));
+            try!(self.label.pprint(printer, x, true));
+        }
+        try!(printer.write_plain("\n"));
+        Ok(())
+    }
+}
+
+struct EdgeRow {
+    space_left: String,
+    corner: Corner,
+    edge_h: EdgeH,
+}
+
+impl EdgeRow {
+    fn new(conf: &Config) -> EdgeRow {
+        EdgeRow {
+            space_left: format!("{:1$}", "", conf.cell_width),
+            corner: Corner::new(conf),
+            edge_h: EdgeH::new(conf),
+        }
+    }
+
+    fn pprint<P>(&self, printer: &mut P, puzzle: &Puzzle, y: i32) -> AppResult<()>
+        where P: Printer
+    {
+        let col = puzzle.column();
+        try!(printer.write_plain(&self.space_left));
+        for x in 0..col {
+            try!(self.corner.pprint(printer, puzzle, Point(y, x)));
+            try!(self.edge_h.pprint(printer, puzzle, Point(y, x)));
+        }
+        try!(self.corner.pprint(printer, puzzle, Point(y, col)));
+        try!(printer.write_plain("\n"));
+        Ok(())
+    }
+}
+
+struct CellRow {
+    cell_height: usize,
+    edge_v: EdgeV,
+    label: Label,
+    cell: Cell,
+}
+
+impl CellRow {
+    fn new(conf: &Config) -> CellRow {
+        CellRow {
+            cell_height: conf.cell_height,
+            edge_v: EdgeV::new(conf),
+            label: Label::new(conf),
+            cell: Cell::new(conf),
+        }
+    }
+
+    fn pprint<P>(&self, printer: &mut P, puzzle: &Puzzle, y: i32) -> AppResult<()>
+        where P: Printer
+    {
+        let col = puzzle.column();
+        for i in 0..self.cell_height {
+            let num_line = (self.cell_height - 1) / 2 == i;
+            try!(self.label.pprint(printer, y, num_line));
+            for x in 0..col {
+                try!(self.edge_v.pprint(printer, puzzle, Point(y, x)));
+                try!(self.cell.pprint(printer, puzzle, Point(y, x), num_line));
+            }
+            try!(self.edge_v.pprint(printer, puzzle, Point(y, col)));
+            try!(self.label.pprint(printer, y, num_line));
+            try!(printer.write_plain("\n"));
+        }
+        Ok(())
+    }
+}
+
+struct Corner {
+    str_empty: String,
+    str_h: String,
+    str_v: String,
+    str_corner: String,
+}
+
+impl Corner {
+    fn new(conf: &Config) -> Corner {
+        let glyphs = conf.theme.glyphs;
+        Corner {
+            str_empty: glyphs.corner_empty.to_string(),
+            str_h: glyphs.line_h.to_string(),
+            str_v: glyphs.line_v.to_string(),
+            str_corner: glyphs.corner.to_string(),
+        }
+    }
+
+    fn pprint<P>(&self, printer: &mut P, puzzle: &Puzzle, p: Point) -> AppResult<()>
+        where P: Printer
+    {
+        let l = p + Move::LEFT;
+        let u = p + Move::UP;
+        let eh_p = puzzle.edge_h(p);
+        let eh_l = puzzle.edge_h(l);
+        let ev_p = puzzle.edge_v(p);
+        let ev_u = puzzle.edge_v(u);
+
+        let is_same_all = (eh_p == Some(Edge::Cross)) && (eh_l == Some(Edge::Cross)) &&
+                          (ev_p == Some((Edge::Cross))) &&
+                          (ev_u == Some((Edge::Cross)));
+
+        let side = if is_same_all {
+            puzzle.side(p)
+        } else {
+            None
+        };
+        let is_h = eh_p == Some(Edge::Line) && eh_l == Some(Edge::Line);
+        let is_v = ev_p == Some(Edge::Line) && ev_u == Some(Edge::Line);
+
+        let s = if is_same_all {
+            &self.str_empty
+        } else if is_h {
+            &self.str_h
+        } else if is_v {
+            &self.str_v
+        } else {
+            &self.str_corner
+        };
+        try!(printer.write_pretty(side, s));
+        Ok(())
+    }
+}
+
+struct EdgeH {
+    str_cross: String,
+    str_line: String,
+    str_unknown: String,
+}
+
+impl EdgeH {
+    fn new(conf: &Config) -> EdgeH {
+        EdgeH {
+            str_cross: iter::repeat(conf.theme.glyphs.cross).take(conf.cell_width).collect(),
+            str_line: iter::repeat(conf.theme.glyphs.line_h).take(conf.cell_width).collect(),
+            str_unknown: iter::repeat(conf.theme.glyphs.unknown_h)
+                             .take(conf.cell_width)
+                             .collect(),
+        }
+    }
+
+    fn pprint<P>(&self, printer: &mut P, puzzle: &Puzzle, p: Point) -> AppResult<()>
+        where P: Printer
+    {
+        let (s, side) = match puzzle.edge_h(p) {
+            Some(Edge::Cross) => (&self.str_cross, puzzle.side(p)),
+            Some(Edge::Line) => (&self.str_line, None),
+            None => (&self.str_unknown, None),
+        };
+        try!(printer.write_target(puzzle, Target::EdgeH(p), side, s));
+        Ok(())
+    }
+}
+
+struct Label {
+    width: usize,
+    order: i32,
+    space: String,
+}
+
+impl Label {
+    fn new(conf: &Config) -> Label {
+        Label {
+            width: conf.cell_width,
+            order: 10i32.pow(conf.cell_width as u32),
+            space: format!("{:1$}", "", conf.cell_width),
+        }
+    }
+
+    fn pprint<P>(&self, printer: &mut P, n: i32, num_line: bool) -> AppResult<()>
+        where P: Printer
+    {
+        if num_line {
+            let n = n + coord::style().origin();
+            try!(printer.write_plain(&format!("{:^1$}", n % self.order, self.width)));
+        } else {
+            try!(printer.write_plain(&self.space));
+        }
+        Ok(())
+    }
+}
+
+struct EdgeV {
+    str_cross: String,
+    str_line: String,
+    str_unknown: String,
+}
+
+impl EdgeV {
+    fn new(conf: &Config) -> EdgeV {
+        let glyphs = conf.theme.glyphs;
+        EdgeV {
+            str_cross: glyphs.cross.to_string(),
+            str_line: glyphs.line_v.to_string(),
+            str_unknown: glyphs.unknown_v.to_string(),
+        }
+    }
+
+    fn pprint<P>(&self, printer: &mut P, puzzle: &Puzzle, p: Point) -> AppResult<()>
+        where P: Printer
+    {
+        let (s, side) = match puzzle.edge_v(p) {
+            Some(Edge::Cross) => (&self.str_cross, puzzle.side(p)),
+            Some(Edge::Line) => (&self.str_line, None),
+            None => (&self.str_unknown, None),
+        };
+        try!(printer.write_target(puzzle, Target::EdgeV(p), side, s));
+        Ok(())
+    }
+}
+
+struct Cell {
+    nums: [String; 5],
+    space: String,
+}
+
+impl Cell {
+    fn new(conf: &Config) -> Cell {
+        Cell {
+            nums: [format!("{:^1$}", 0, conf.cell_width),
+                   format!("{:^1$}", 1, conf.cell_width),
+                   format!("{:^1$}", 2, conf.cell_width),
+                   format!("{:^1$}", 3, conf.cell_width),
+                   format!("{:^1$}", 4, conf.cell_width)],
+            space: format!("{:^1$}", "", conf.cell_width),
+        }
+    }
+
+    fn pprint<P>(&self, printer: &mut P, puzzle: &Puzzle, p: Point, num_line: bool) -> AppResult<()>
+        where P: Printer
+    {
+        let side = puzzle.side(p);
+        let s = match puzzle.hint(p) {
+            Some(x) if num_line => &self.nums[x as usize],
+            _ => &self.space,
+        };
+        try!(printer.write_target(puzzle, Target::Cell(p), side, s));
+        Ok(())
+    }
+}
+
+/// Writes the labels of the annotations below the puzzle, one per line.
+fn pprint_labels<P>(printer: &mut P, puzzle: &Puzzle) -> AppResult<()>
+    where P: Printer
+{
+    for (target, annotation) in puzzle.annotations().iter() {
+        if annotation.label.is_empty() {
+            continue;
+        }
+        let (name, p) = match target {
+            Target::Cell(p) => ("cell", p),
+            Target::EdgeH(p) => ("edge-h", p),
+            Target::EdgeV(p) => ("edge-v", p),
+        };
+        let line = format!("{} {}: {}\n", name, coord::format(p), annotation.label);
+        try!(match annotation.color {
+            Some(highlight) => printer.write_highlight(highlight, &line),
+            None => printer.write_plain(&line),
+        });
+    }
+    Ok(())
+}
+
+// The cells of the emoji mode, each as wide as two ASCII characters, so the
+// rows line up in the fonts of the chats: the inside and the outside shaded
+// black and white, the hints inside as the keycaps and the hints outside as
+// the full-width digits.
+const EMOJI_INSIDE: &'static str = "\u{2b1b}";
+const EMOJI_OUTSIDE: &'static str = "\u{2b1c}";
+const EMOJI_UNKNOWN: &'static str = "\u{2754}";
+const EMOJI_KEYCAPS: [&'static str; 5] = ["0\u{fe0f}\u{20e3}",
+                                          "1\u{fe0f}\u{20e3}",
+                                          "2\u{fe0f}\u{20e3}",
+                                          "3\u{fe0f}\u{20e3}",
+                                          "4\u{fe0f}\u{20e3}"];
+const EMOJI_DIGITS: [&'static str; 5] = ["\u{ff10}",
+                                         "\u{ff11}",
+                                         "\u{ff12}",
+                                         "\u{ff13}",
+                                         "\u{ff14}"];
+
+fn print_emoji<W: Write>(out: &mut W, puzzle: &Puzzle) -> AppResult<()> {
+    for r in 0..puzzle.row() {
+        let mut line = String::new();
+        for c in 0..puzzle.column() {
+            let p = Point(r, c);
+            line.push_str(match (puzzle.hint(p), puzzle.side(p)) {
+                (Some(x), Some(Side::In)) => EMOJI_KEYCAPS[x as usize],
+                (Some(x), _) => EMOJI_DIGITS[x as usize],
+                (None, Some(Side::In)) => EMOJI_INSIDE,
+                (None, Some(Side::Out)) => EMOJI_OUTSIDE,
+                (None, None) => EMOJI_UNKNOWN,
+            });
+        }
+        try!(writeln!(out, "{}", line));
+    }
+    Ok(())
+}
+
+pub fn print<W: Write>(out: &mut W, conf: &Config, puzzle: &Puzzle) -> AppResult<()> {
+    if conf.mode == Mode::Emoji {
+        return print_emoji(out, puzzle);
+    }
+    let table = Table::new(conf);
+    table.pprint(&mut WritePrinter::new(out, conf.mode, conf.theme), puzzle)
+}
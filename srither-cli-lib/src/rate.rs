@@ -0,0 +1,271 @@
+// Copyright (c) 2016 srither developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Rates the difficulty of the puzzles of a pack.
+
+use std::io;
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::Path;
+
+use srither_core::puzzle::Puzzle;
+use srither_solver::{self as solver, SolveOptions, SolverStats};
+
+use cache::Cache;
+use envelope;
+use error::{AppError, AppResult};
+use io_util;
+use json::Json;
+use pack;
+use parse_arg::{Format, RateConfig};
+use pipeline;
+
+/// The rating of a puzzle of the pack.
+#[derive(Copy, Clone, Debug)]
+pub struct Rating {
+    /// The difficulty score by the solver.
+    pub score: f64,
+    /// The percentile of the score in the pack.
+    pub pack_percentile: f64,
+    /// The percentile of the score in the calibration file, if given.
+    pub calibrated_percentile: Option<f64>,
+    /// The difficulty label of the percentile.
+    pub difficulty: &'static str,
+}
+
+/// Rates the puzzles of the pack, and writes the ratings, or the annotated
+/// pack, to `out`. The progress is drawn on `err`.
+pub fn run<O, E>(config: RateConfig, out: &mut O, err: &mut E) -> AppResult<Vec<Rating>>
+    where O: Write,
+          E: Write
+{
+    let buf = try!(io_util::read_file(&config.pack_file));
+    let mut set = try!(pack::parse(&buf));
+
+    let options = SolveOptions {
+        theorem_set: config.theorem_set,
+        disabled_theorems: config.disabled_theorems.clone(),
+        ..SolveOptions::default()
+    };
+    // The cached statistics are not of the solver without the disabled
+    // theorems.
+    let cache = match config.cache_dir {
+        Some(ref dir) if config.disabled_theorems.is_empty() => Some(try!(Cache::open(dir))),
+        _ => None,
+    };
+    let puzzles = set.entries().iter().map(|e| e.puzzle.clone()).collect();
+    let mut scores = vec![];
+    try!(pipeline::run(&config.pipeline,
+                       puzzles,
+                       move |puzzle, stop| {
+                           let options = SolveOptions { stop: Some(stop), ..options.clone() };
+                           solve(&puzzle, &options, cache.as_ref())
+                       },
+                       |i, outcome| {
+                           // The percentiles need the scores of all the puzzles.
+                           let (_, stats) = try!(try!(outcome.done(i)));
+                           scores.push(stats.score());
+                           Ok(())
+                       },
+                       err));
+
+    // The scores of the calibration file are the reference of the difficulty
+    // labels, so that the labels of different packs are comparable.
+    let calibration = match config.calibration_file {
+        Some(ref path) => {
+            Some(try!(load_calibration(Path::new(path), config.theorem_set, &scores, err)))
+        }
+        None => None,
+    };
+
+    let ratings = scores.iter()
+                        .map(|&score| {
+                            let pack = percentile(&scores, score);
+                            let calibrated = calibration.as_ref().map(|c| percentile(c, score));
+                            Rating {
+                                score: score,
+                                pack_percentile: pack,
+                                calibrated_percentile: calibrated,
+                                difficulty: difficulty(calibrated.unwrap_or(pack)),
+                            }
+                        })
+                        .collect::<Vec<_>>();
+
+    if config.annotate {
+        for (entry, rating) in set.entries_mut().iter_mut().zip(&ratings) {
+            let p = rating.calibrated_percentile.unwrap_or(rating.pack_percentile);
+            entry.metadata.set("score", &format!("{:.1}", rating.score));
+            entry.metadata.set("percentile", &format!("{:.1}", p));
+            entry.metadata.set("difficulty", rating.difficulty);
+            entry.metadata.set("theorem-set", &config.theorem_set.to_string());
+        }
+        try!(pack::write(out, &set));
+        return Ok(ratings);
+    }
+
+    if config.format == Format::Json {
+        let puzzles = set.entries()
+                         .iter()
+                         .zip(&ratings)
+                         .enumerate()
+                         .map(|(i, (entry, rating))| {
+                             Json::object(vec![("index", (i + 1).into()),
+                                               ("title", entry.metadata.get("title").into()),
+                                               ("score", rating.score.into()),
+                                               ("pack_percentile",
+                                                rating.pack_percentile.into()),
+                                               ("calibrated_percentile",
+                                                rating.calibrated_percentile.into()),
+                                               ("difficulty", rating.difficulty.into())])
+                         })
+                         .collect();
+        try!(writeln!(out,
+                      "{}",
+                      Json::object(vec![("theorem_set", (config.theorem_set as u64).into()),
+                                        ("puzzles", Json::Array(puzzles))])));
+        return Ok(ratings);
+    }
+
+    try!(writeln!(out, "theorem set: version {}", config.theorem_set));
+    try!(writeln!(out,
+                  "{:>4} {:>10} {:>7} {:>7} {:<10} {}",
+                  "#",
+                  "score",
+                  "pack%",
+                  "calib%",
+                  "difficulty",
+                  "title"));
+    for (i, (entry, rating)) in set.entries().iter().zip(&ratings).enumerate() {
+        let calib = rating.calibrated_percentile
+                          .map(|p| format!("{:.1}", p))
+                          .unwrap_or("-".to_owned());
+        try!(writeln!(out,
+                      "{:>4} {:>10.1} {:>7.1} {:>7} {:<10} {}",
+                      i + 1,
+                      rating.score,
+                      rating.pack_percentile,
+                      calib,
+                      rating.difficulty,
+                      entry.metadata.get("title").unwrap_or("")));
+    }
+
+    Ok(ratings)
+}
+
+/// Solves the puzzle for the statistics rating it, reading them from the
+/// cache if stored with the theorem set.
+fn solve(puzzle: &Puzzle,
+         options: &SolveOptions,
+         cache: Option<&Cache>)
+         -> AppResult<(Puzzle, SolverStats)> {
+    if let Some(stats) = cache.and_then(|c| c.get_stats(puzzle, options.theorem_set)) {
+        return Ok(stats);
+    }
+    let (solution, stats) = try!(solver::solve_with_stats(puzzle, options));
+    if let Some(cache) = cache {
+        try!(cache.put_stats(puzzle, options.theorem_set, &solution, &stats));
+    }
+    Ok((solution, stats))
+}
+
+/// Returns the percentage of the scores below the score, counting the equal
+/// scores as half.
+fn percentile(scores: &[f64], score: f64) -> f64 {
+    if scores.is_empty() {
+        return 0.0;
+    }
+    let below = scores.iter().filter(|&&s| s < score).count();
+    let equal = scores.iter().filter(|&&s| s == score).count();
+    100.0 * (below as f64 + equal as f64 / 2.0) / scores.len() as f64
+}
+
+fn difficulty(percentile: f64) -> &'static str {
+    if percentile < 25.0 {
+        "easy"
+    } else if percentile < 50.0 {
+        "medium"
+    } else if percentile < 75.0 {
+        "hard"
+    } else {
+        "expert"
+    }
+}
+
+/// Loads the scores in the calibration file, or creates the file from the
+/// scores if it does not exist. The scores depend on the theorem set, so the
+/// file must be created with the same version of the theorem set. Files
+/// without the version are created before the theorem set is versioned.
+///
+/// The scores may also change with the version of srither, e.g. by a fix of
+/// the solver, so a file written by another version is warned about on
+/// `err`. The version is in the envelope, or on the `# version:` line of the
+/// files of the format `v1`.
+fn load_calibration<E>(path: &Path,
+                       theorem_set: u32,
+                       scores: &[f64],
+                       err: &mut E)
+                       -> AppResult<Vec<f64>>
+    where E: Write
+{
+    if !path.exists() {
+        let mut f = try!(File::create(path));
+        try!(writeln!(f, "{}", envelope::header(&envelope::CALIBRATION)));
+        try!(writeln!(f, "# theorem-set: {}", theorem_set));
+        for score in scores {
+            try!(writeln!(f, "{}", score));
+        }
+        return Ok(scores.to_owned());
+    }
+
+    let buf = try!(io_util::read_file(path));
+    let envelope = try!(envelope::check_text(&envelope::CALIBRATION, &buf));
+    let mut written = envelope.and_then(|e| e.version);
+    let mut calibration = vec![];
+    let mut version = 1;
+    for line in buf.lines().map(|l| l.trim()) {
+        if line.starts_with("# version:") {
+            written = Some(line["# version:".len()..].trim().to_owned());
+            continue;
+        }
+        if line.starts_with("# theorem-set:") {
+            version = match line["# theorem-set:".len()..].trim().parse() {
+                Ok(version) => version,
+                Err(_) => return Err(invalid_calibration("invalid theorem set version")),
+            };
+            continue;
+        }
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match line.parse() {
+            Ok(score) => calibration.push(score),
+            Err(_) => return Err(invalid_calibration("invalid calibration data")),
+        }
+    }
+    if version != theorem_set {
+        let msg = format!("calibration file uses theorem set version {}, not {}",
+                          version,
+                          theorem_set);
+        return Err(invalid_calibration(&msg));
+    }
+    match written {
+        Some(ref written) if written != env!("CARGO_PKG_VERSION") => {
+            try!(writeln!(err,
+                          "calibration file is written by srither {}, and the scores of srither \
+                           {} may differ; remove it to calibrate again",
+                          written,
+                          env!("CARGO_PKG_VERSION")));
+        }
+        _ => {}
+    }
+    Ok(calibration)
+}
+
+fn invalid_calibration(msg: &str) -> AppError {
+    io::Error::new(io::ErrorKind::InvalidData, msg).into()
+}
@@ -0,0 +1,119 @@
+// Copyright (c) 2016 srither developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Checks the solutions recorded with the puzzles against the solver.
+
+use std::io::prelude::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use srither_core::loop_bits::LoopBits;
+use srither_core::puzzle::Puzzle;
+use srither_core::puzzle_set::PuzzleSet;
+use srither_solver::{self as solver, SolveOptions};
+
+use error::AppResult;
+use io_util;
+use pack;
+use parse_arg::RecheckConfig;
+use pipeline::{self, Outcome};
+
+/// The results of `recheck`.
+#[derive(Clone, Debug)]
+pub struct RecheckReport {
+    /// The result of each puzzle in order, as reported.
+    pub results: Vec<&'static str>,
+    /// The number of the puzzles failing the check.
+    pub failures: usize,
+}
+
+/// Checks the solutions recorded in the packs of the input files, or of
+/// `input` if none, and writes the results to `out`.
+pub fn run<I, O, E>(config: RecheckConfig,
+                    input: &mut I,
+                    out: &mut O,
+                    err: &mut E)
+                    -> AppResult<RecheckReport>
+    where I: Read,
+          O: Write,
+          E: Write
+{
+    let mut set = PuzzleSet::new();
+    if config.input_files.is_empty() {
+        set.extend(try!(pack::read(input)).into_entries());
+    } else {
+        for file in &config.input_files {
+            let mut f = try!(io_util::open(file));
+            set.extend(try!(pack::read(&mut f)).into_entries());
+        }
+    }
+
+    let titles = set.entries()
+                    .iter()
+                    .map(|e| e.metadata.get("title").unwrap_or("").to_owned())
+                    .collect::<Vec<_>>();
+    let puzzles = set.into_entries().into_iter().map(|e| e.puzzle).collect();
+    let mut num_failure = 0;
+    let mut results = vec![];
+    try!(pipeline::run(&config.pipeline,
+                       puzzles,
+                       |puzzle, stop| recheck(&puzzle, &stop),
+                       |i, outcome| {
+                           let (ok, result) = match outcome {
+                               Outcome::Done(result) => try!(result),
+                               Outcome::TimedOut => (false, "TIMEOUT"),
+                           };
+                           if !ok {
+                               num_failure += 1;
+                           }
+                           try!(writeln!(out, "#{} {}: {}", i + 1, titles[i], result));
+                           results.push(result);
+                           Ok(())
+                       },
+                       err));
+
+    try!(writeln!(out, ""));
+    try!(writeln!(out, "{} puzzles, {} failures", titles.len(), num_failure));
+    Ok(RecheckReport {
+        results: results,
+        failures: num_failure,
+    })
+}
+
+/// Solves the hints of the puzzle again and compares the solution with the
+/// lines recorded in the puzzle. Returns whether they agree, and the result
+/// to report. Puzzles without lines have nothing to check. The search ends
+/// once `stop` is set.
+fn recheck(recorded: &Puzzle, stop: &AtomicBool) -> AppResult<(bool, &'static str)> {
+    let lines = LoopBits::from_puzzle(recorded);
+    if lines.count() == 0 {
+        return Ok((true, "no recorded solution"));
+    }
+
+    let puzzle = recorded.hints_only();
+    let mut solutions = vec![];
+    let stopped = || stop.load(Ordering::SeqCst);
+    let count = try!(solver::for_each_solution_until(&puzzle,
+                                                     &SolveOptions::default(),
+                                                     stopped,
+                                                     |s| {
+                                                         solutions.push(LoopBits::from_puzzle(s));
+                                                         solutions.len() < 2
+                                                     }));
+    // The result of the puzzle timed out is discarded.
+    if count.is_none() {
+        return Ok((false, "TIMEOUT"));
+    }
+    let result = match solutions.len() {
+        0 => (false, "MISMATCH (no solution)"),
+        1 if solutions[0] == lines => (true, "ok"),
+        1 => (false, "MISMATCH (different solution)"),
+        _ if solutions.contains(&lines) => (false, "NOT UNIQUE"),
+        _ => (false, "MISMATCH (not unique, different solutions)"),
+    };
+    Ok(result)
+}
@@ -0,0 +1,112 @@
+// Copyright (c) 2016 srither developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Renders a puzzle as an image.
+
+use std::io;
+use std::fs::File;
+use std::io::prelude::*;
+
+use srither_core::puzzle::Puzzle;
+use srither_core::puzzle_set::PuzzleSet;
+use srither_solver::{self as solver, SolveOptions};
+
+use envelope;
+use error::AppResult;
+use gif;
+use history;
+use io_util;
+use layout::Layout;
+use parse_arg::{ImageFormat, RenderConfig};
+use png::{self, Image};
+
+// The last frame of an animation is held for the delays of this many frames.
+const LAST_FRAME_HOLD: u32 = 4;
+
+/// Renders the puzzle of the input file, or of `input` if none, and writes
+/// the image to the output file, or to `out` if none. Returns the encoded
+/// image.
+pub fn run<I, O>(config: RenderConfig, input: &mut I, out: &mut O) -> AppResult<Vec<u8>>
+    where I: Read,
+          O: Write
+{
+    let buf = match config.input_file {
+        Some(ref path) => try!(io_util::read_file(path)),
+        None => try!(io_util::read_to_string(input)),
+    };
+    let mut puzzle = match Puzzle::parse_any(&buf) {
+        Ok((puzzle, _)) => puzzle,
+        Err(e) => {
+            // A file of a puzzle with the headers of metadata, e.g. written
+            // by `generate`.
+            try!(envelope::check_text(&envelope::PACK, &buf));
+            match buf.parse::<PuzzleSet>() {
+                Ok(ref set) if set.entries().len() == 1 => set.entries()[0].puzzle.clone(),
+                Ok(set) => {
+                    let msg = format!("{} puzzles found, expected one to render",
+                                      set.entries().len());
+                    return Err(io::Error::new(io::ErrorKind::InvalidInput, msg).into());
+                }
+                Err(_) => return Err(e.into()),
+            }
+        }
+    };
+    history::note(&puzzle);
+
+    let image = match config.format {
+        ImageFormat::Png => {
+            if config.solve {
+                puzzle = try!(solver::solve(&puzzle.hints_only()));
+            }
+            let cell = config.cell_size as f64;
+            Image::render(&Layout::new(&puzzle, cell)).encode(config.dpi)
+        }
+        ImageFormat::Gif => {
+            let frames = try!(frames(&config, &puzzle));
+            // The sizes of GIF are of 16 bits.
+            if frames[0].0.width() > 0xffff || frames[0].0.height() > 0xffff {
+                let msg = "the image is too large for GIF";
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, msg).into());
+            }
+            gif::encode(&frames)
+        }
+        ImageFormat::Apng => png::encode_animation(&try!(frames(&config, &puzzle)), config.dpi),
+    };
+    match config.output_file {
+        Some(ref path) => try!(try!(File::create(path)).write_all(&image)),
+        None => try!(out.write_all(&image)),
+    }
+    Ok(image)
+}
+
+/// Renders the frames of solving the puzzle with their delays: the hints,
+/// the board after each pass of the deduction fixing some lines, and the
+/// solution, found by guessing if the deduction does not reach it.
+fn frames(config: &RenderConfig, puzzle: &Puzzle) -> AppResult<Vec<(Image, u32)>> {
+    let hints = puzzle.hints_only();
+    let mut boards = vec![hints.clone()];
+    for checkpoint in try!(solver::trace_passes(&hints, &SolveOptions::default())) {
+        boards.push(checkpoint.board);
+    }
+    boards.push(try!(solver::solve(&hints)));
+
+    let cell = config.cell_size as f64;
+    let mut frames = Vec::<(Image, u32)>::new();
+    for board in &boards {
+        let image = Image::render(&Layout::new(board, cell));
+        // The passes fixing only crosses, which are not drawn.
+        if frames.last().map_or(false, |&(ref last, _)| *last == image) {
+            continue;
+        }
+        frames.push((image, config.delay));
+    }
+    if let Some(last) = frames.last_mut() {
+        last.1 = config.delay * LAST_FRAME_HOLD;
+    }
+    Ok(frames)
+}
@@ -0,0 +1,264 @@
+// Copyright (c) 2016 srither developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Explores the deductions of a puzzle interactively.
+
+use std::io::prelude::*;
+
+use srither_core::annotation::{Annotation, Color, Target};
+use srither_core::geom::{Geom, Point};
+use srither_core::puzzle::{Puzzle, Side};
+use srither_solver::{self as solver, BorderPolicy};
+
+use coord;
+use error::AppResult;
+use io_util;
+use parse_arg::ReplConfig;
+use pprint::{self, Config as PpConfig, Mode as PpMode};
+use theme::Theme;
+
+// `R C` is replaced by the names of the coordinates in the style given by
+// `--coords`.
+const HELP: &'static str = "\
+commands:
+  show              print the puzzle
+  why R C           explain the cell at R C
+  assume R C in|out assume the side of the cell and deduce the consequences
+  retract           retract the last assumption
+  stack             print the assumptions
+  help              print this message
+  quit              exit";
+
+// An assumption and the puzzle deduced under it and the ones before it.
+struct Assumption {
+    point: Point,
+    side: Side,
+    puzzle: Puzzle,
+}
+
+/// Reads the commands from `input`, and writes the answers to `out`. Returns
+/// the puzzle deduced under the assumptions left at the end.
+pub fn run<I, O>(config: ReplConfig, input: &mut I, out: &mut O) -> AppResult<Puzzle>
+    where I: BufRead,
+          O: Write
+{
+    let buf = try!(io_util::read_file(&config.input_file));
+    let base = try!(buf.parse::<Puzzle>());
+    let mut stack: Vec<Assumption> = vec![];
+
+    try!(prompt(out));
+    for line in input.lines() {
+        let line = try!(line);
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        let puzzle = stack.last().map(|a| &a.puzzle).unwrap_or(&base).clone();
+        match words.get(0).cloned() {
+            Some("show") => try!(write!(out, "{}", puzzle)),
+            Some("why") => {
+                match parse_point(&puzzle, &words[1..]) {
+                    Some(p) => try!(why(out, &puzzle, p)),
+                    None => try!(writeln!(out, "usage: why {}", coord::style().names())),
+                }
+            }
+            Some("assume") => {
+                let side = match words.get(3).cloned() {
+                    Some("in") => Some(Side::In),
+                    Some("out") => Some(Side::Out),
+                    _ => None,
+                };
+                let p = if words.len() == 4 {
+                    parse_point(&puzzle, &words[1..3])
+                } else {
+                    None
+                };
+                match (p, side) {
+                    (Some(p), Some(side)) => {
+                        if let Some(a) = try!(assume(out, &puzzle, p, side, stack.len() + 1)) {
+                            stack.push(a);
+                        }
+                    }
+                    _ => try!(writeln!(out, "usage: assume {} in|out", coord::style().names())),
+                }
+            }
+            Some("retract") => {
+                match stack.pop() {
+                    Some(a) => {
+                        try!(writeln!(out, "retracted {}", describe(&a)));
+                        try!(write!(out, "{}", stack.last().map(|a| &a.puzzle).unwrap_or(&base)));
+                    }
+                    None => try!(writeln!(out, "no assumption to retract")),
+                }
+            }
+            Some("stack") => {
+                if stack.is_empty() {
+                    try!(writeln!(out, "no assumption"));
+                }
+                for (i, a) in stack.iter().enumerate() {
+                    try!(writeln!(out, "{}: {}", i + 1, describe(a)));
+                }
+            }
+            Some("help") => try!(writeln!(out, "{}", HELP.replace("R C", coord::style().names()))),
+            Some("quit") | Some("exit") => return Ok(puzzle),
+            Some(cmd) => {
+                try!(writeln!(out, "unknown command `{}` (type `help` for commands)", cmd))
+            }
+            None => {}
+        }
+        try!(prompt(out));
+    }
+
+    Ok(stack.last().map(|a| &a.puzzle).unwrap_or(&base).clone())
+}
+
+fn prompt<O: Write>(out: &mut O) -> AppResult<()> {
+    try!(write!(out, "> "));
+    try!(out.flush());
+    Ok(())
+}
+
+fn parse_point(puzzle: &Puzzle, args: &[&str]) -> Option<Point> {
+    coord::parse(args).and_then(|p| if puzzle.contains(p) {
+        Some(p)
+    } else {
+        None
+    })
+}
+
+fn describe(a: &Assumption) -> String {
+    let side = match a.side {
+        Side::In => "inside",
+        Side::Out => "outside",
+    };
+    format!("{} is {}", coord::format(a.point), side)
+}
+
+// Deduces the consequences of the assumption, and prints them highlighted.
+// Returns `None` if the assumption leads to a contradiction.
+fn assume<O: Write>(out: &mut O,
+                    puzzle: &Puzzle,
+                    p: Point,
+                    side: Side,
+                    depth: usize)
+                    -> AppResult<Option<Assumption>> {
+    match puzzle.side(p) {
+        Some(s) if s == side => {
+            try!(writeln!(out, "the cell is already known to be so"));
+            return Ok(None);
+        }
+        Some(_) => {
+            try!(writeln!(out, "contradiction: the cell is already known to be the opposite"));
+            return Ok(None);
+        }
+        None => {}
+    }
+
+    let mut assumed = puzzle.clone();
+    assumed.set_side(p, Some(side));
+    let deduced = match solver::deduce(&assumed, BorderPolicy::default()) {
+        Ok(deduced) => deduced,
+        Err(e) => {
+            try!(writeln!(out, "contradiction: {}", e));
+            return Ok(None);
+        }
+    };
+
+    let a = Assumption {
+        point: p,
+        side: side,
+        puzzle: deduced,
+    };
+    let mut shown = a.puzzle.clone();
+    let mut num_changed = 0;
+    {
+        let mut mark = |target: Target| {
+            shown.annotations_mut().set(target,
+                                        Annotation {
+                                            label: String::new(),
+                                            color: Some(Color::Cyan),
+                                        });
+            num_changed += 1;
+        };
+        for q in puzzle.points() {
+            if q != p && puzzle.side(q) != a.puzzle.side(q) {
+                mark(Target::Cell(q));
+            }
+        }
+        for r in 0..(puzzle.row() + 1) {
+            for c in 0..(puzzle.column() + 1) {
+                let q = Point(r, c);
+                if c < puzzle.column() && puzzle.edge_h(q) != a.puzzle.edge_h(q) {
+                    mark(Target::EdgeH(q));
+                }
+                if r < puzzle.row() && puzzle.edge_v(q) != a.puzzle.edge_v(q) {
+                    mark(Target::EdgeV(q));
+                }
+            }
+        }
+    }
+    shown.annotations_mut().set(Target::Cell(p),
+                                Annotation {
+                                    label: format!("assumption {}", depth),
+                                    color: Some(Color::Yellow),
+                                });
+
+    let mode = if pprint::is_pprintable() {
+        PpMode::Color
+    } else {
+        PpMode::Ascii
+    };
+    let conf = PpConfig {
+        mode: mode,
+        cell_width: 2,
+        cell_height: 1,
+        theme: Theme::default(),
+    };
+    try!(pprint::print(out, &conf, &shown));
+    try!(writeln!(out, "assumption {}: {}, {} consequences", depth, describe(&a), num_changed));
+    Ok(Some(a))
+}
+
+fn why<O: Write>(out: &mut O, puzzle: &Puzzle, p: Point) -> AppResult<()> {
+    let e = match solver::explain_cell(puzzle, p) {
+        Ok(e) => e,
+        Err(e) => {
+            try!(writeln!(out, "cannot explain the cell: {}", e));
+            return Ok(());
+        }
+    };
+
+    let hint = e.hint.map(|n| n.to_string()).unwrap_or("none".to_owned());
+    let side = match e.side {
+        Some(Side::In) => "inside",
+        Some(Side::Out) => "outside",
+        None => "unknown",
+    };
+    try!(writeln!(out, "hint: {}, side: {}", hint, side));
+    try!(writeln!(out,
+                  "lines: {}, crosses: {}, unknown: {}",
+                  e.lines,
+                  e.crosses,
+                  e.unknowns()));
+
+    if e.hint.is_some() {
+        let status = if e.possibilities == 0 {
+            "violated"
+        } else if e.is_satisfied() {
+            "satisfied"
+        } else {
+            "unsatisfied"
+        };
+        try!(writeln!(out, "status: {} ({} possibilities left)", status, e.possibilities));
+    }
+
+    if e.theorems.is_empty() {
+        try!(writeln!(out, "theorems: none"));
+    } else {
+        let theorems = e.theorems.iter().map(|i| format!("#{}", i)).collect::<Vec<_>>();
+        try!(writeln!(out, "theorems: {}", theorems.join(", ")));
+    }
+    Ok(())
+}
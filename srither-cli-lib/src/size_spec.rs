@@ -0,0 +1,153 @@
+// Copyright (c) 2016 srither developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Board sizes given on the command line.
+//!
+//! A size is written as `COLUMNSxROWS` like `10x18`, as a single number for
+//! a square board, or as the name of a preset.
+
+use std::fmt;
+use std::error::Error;
+
+use srither_core::geom::Size;
+
+/// The named sizes, with the numbers of the columns and the rows.
+pub const PRESETS: &'static [(&'static str, i32, i32)] = &[("small", 5, 5),
+                                                          ("medium", 10, 10),
+                                                          ("large", 20, 20),
+                                                          ("nikoli-10x10", 10, 10),
+                                                          ("nikoli-10x18", 10, 18),
+                                                          ("nikoli-36x20", 36, 20)];
+
+/// The largest number of the columns or the rows accepted.
+const MAX_LEN: i32 = 1000;
+
+#[derive(Clone, Debug)]
+pub struct ParseSizeError {
+    kind: SizeErrorKind,
+    input: String,
+}
+
+#[derive(Copy, Clone, Debug)]
+enum SizeErrorKind {
+    UnknownPreset,
+    InvalidNumber,
+    OutOfRange,
+}
+
+impl ParseSizeError {
+    fn new(kind: SizeErrorKind, input: &str) -> ParseSizeError {
+        ParseSizeError {
+            kind: kind,
+            input: input.to_owned(),
+        }
+    }
+}
+
+impl Error for ParseSizeError {
+    fn description(&self) -> &str {
+        match self.kind {
+            SizeErrorKind::UnknownPreset => "unknown size preset",
+            SizeErrorKind::InvalidNumber => "invalid number in size",
+            SizeErrorKind::OutOfRange => "size out of range",
+        }
+    }
+}
+
+impl fmt::Display for ParseSizeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "{} `{}`", self.description(), self.input));
+        match self.kind {
+            SizeErrorKind::UnknownPreset => {
+                let names = PRESETS.iter().map(|p| p.0).collect::<Vec<_>>();
+                write!(f, " (expected COLUMNSxROWS or one of {})", names.join(", "))
+            }
+            SizeErrorKind::InvalidNumber => write!(f, " (expected COLUMNSxROWS, e.g. 10x18)"),
+            SizeErrorKind::OutOfRange => {
+                write!(f, " (columns and rows must be 1 to {})", MAX_LEN)
+            }
+        }
+    }
+}
+
+/// Parses the size of a board. The returned size is in the order of the
+/// rows and the columns, like the other sizes.
+pub fn parse(s: &str) -> Result<Size, ParseSizeError> {
+    let s = s.trim();
+    if let Some(&(_, col, row)) = PRESETS.iter().find(|p| p.0 == s) {
+        return Ok(Size(row, col));
+    }
+
+    let (col, row) = match s.find('x') {
+        Some(idx) => (&s[..idx], &s[idx + 1..]),
+        None => (s, s),
+    };
+    let is_number = |t: &str| !t.is_empty() && t.chars().all(|c| c.is_digit(10));
+    if !is_number(col) || !is_number(row) {
+        let kind = if s.chars().any(|c| c.is_digit(10)) {
+            SizeErrorKind::InvalidNumber
+        } else {
+            SizeErrorKind::UnknownPreset
+        };
+        return Err(ParseSizeError::new(kind, s));
+    }
+    match (col.parse::<i32>(), row.parse::<i32>()) {
+        (Ok(col), Ok(row)) if 0 < col && col <= MAX_LEN && 0 < row && row <= MAX_LEN => {
+            Ok(Size(row, col))
+        }
+        _ => Err(ParseSizeError::new(SizeErrorKind::OutOfRange, s)),
+    }
+}
+
+/// Writes the size in the form parsed by `parse`.
+pub fn to_string(size: Size) -> String {
+    format!("{}x{}", size.1, size.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use srither_core::geom::Size;
+    use super::{PRESETS, parse, to_string};
+
+    fn check_error(input: &str, msg: &str) {
+        assert_eq!(msg, parse(input).unwrap_err().to_string());
+    }
+
+    #[test]
+    fn sizes() {
+        assert_eq!(Size(18, 10), parse("10x18").unwrap());
+        assert_eq!(Size(7, 7), parse(" 7 ").unwrap());
+        assert_eq!(Size(1, 1000), parse("1000x1").unwrap());
+        for &(name, col, row) in PRESETS {
+            let size = parse(name).unwrap();
+            assert_eq!(Size(row, col), size);
+            assert_eq!(size, parse(&to_string(size)).unwrap());
+        }
+        assert_eq!(Size(20, 36), parse("nikoli-36x20").unwrap());
+    }
+
+    #[test]
+    fn errors() {
+        check_error("huge",
+                    "unknown size preset `huge` (expected COLUMNSxROWS or one of small, \
+                     medium, large, nikoli-10x10, nikoli-10x18, nikoli-36x20)");
+        check_error("x", "unknown size preset `x` (expected COLUMNSxROWS or one of small, \
+                          medium, large, nikoli-10x10, nikoli-10x18, nikoli-36x20)");
+        for &input in &["10x", "x18", "10x18x2", "-3", "10 x 18", "1.5"] {
+            check_error(input,
+                        &format!("invalid number in size `{}` \
+                                  (expected COLUMNSxROWS, e.g. 10x18)",
+                                 input));
+        }
+        for &input in &["0", "10x0", "1001x1", "99999999999"] {
+            check_error(input,
+                        &format!("size out of range `{}` (columns and rows must be 1 to 1000)",
+                                 input));
+        }
+    }
+}
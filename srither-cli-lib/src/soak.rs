@@ -0,0 +1,203 @@
+// Copyright (c) 2016 srither developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Checks the solver on random puzzles for a while.
+
+use std::cmp;
+use std::io::prelude::*;
+use rand::Rng;
+use time;
+
+use srither_core::geom::{Geom, Size};
+use srither_core::loop_bits::LoopBits;
+use srither_core::puzzle::Puzzle;
+use srither_gen::random_loop;
+use srither_solver::{self as solver, SolveOptions};
+use srither_testdata as testdata;
+
+use error::AppResult;
+use pack;
+use parse_arg::SoakConfig;
+use size_spec;
+
+/// The maximum number of the solutions derived from a puzzle. Puzzles with
+/// more solutions are checked only partially.
+const MAX_SOLUTIONS: u64 = 1000;
+
+/// The results of `soak`.
+#[derive(Copy, Clone, Debug)]
+pub struct SoakReport {
+    /// The number of the puzzles checked.
+    pub puzzles: usize,
+    /// The number of the puzzles failing the checks.
+    pub failures: usize,
+}
+
+/// Checks the solver on the puzzles of the corpus and on random puzzles
+/// until the time is up, or on the puzzle of the seed to replay, and writes
+/// the failures to `out`.
+pub fn run<O: Write>(config: SoakConfig, out: &mut O) -> AppResult<SoakReport> {
+    if let Some(seed) = config.replay {
+        let result = soak_one(out, seed, config.max_size, true);
+        let failures = match result {
+            Ok(()) => {
+                try!(writeln!(out, "seed {}: ok", seed));
+                0
+            }
+            Err(msg) => {
+                try!(writeln!(out, "seed {}: FAILED: {}", seed, msg));
+                1
+            }
+        };
+        return Ok(SoakReport {
+            puzzles: 1,
+            failures: failures,
+        });
+    }
+
+    let mut rng = try!(pack::new_rng(config.seed));
+    let deadline = time::precise_time_ns() + config.minutes * 60 * 1000000000;
+    let mut num_puzzle = 0;
+    let mut num_failure = 0;
+
+    // The puzzles of the corpus are checked first, with the known numbers of
+    // the solutions.
+    for entry in testdata::entries() {
+        let expected = cmp::min(entry.num_solutions(), MAX_SOLUTIONS);
+        match check_solutions(out, &entry.puzzle(), None, false) {
+            Ok(count) if count == expected => {}
+            Ok(count) => {
+                try!(writeln!(out,
+                              "corpus {}: FAILED: {} solutions, but {} expected",
+                              entry.name(),
+                              count,
+                              expected));
+                num_failure += 1;
+            }
+            Err(msg) => {
+                try!(writeln!(out, "corpus {}: FAILED: {}", entry.name(), msg));
+                num_failure += 1;
+            }
+        }
+        num_puzzle += 1;
+    }
+
+    while time::precise_time_ns() < deadline {
+        let seed = rng.gen::<u64>();
+        if let Err(msg) = soak_one(out, seed, config.max_size, false) {
+            try!(writeln!(out, "seed {}: FAILED: {}", seed, msg));
+            try!(writeln!(out,
+                          "  reproduce with `soak --replay {} --max-size {}`",
+                          seed,
+                          size_spec::to_string(config.max_size)));
+            num_failure += 1;
+        }
+        num_puzzle += 1;
+    }
+
+    try!(writeln!(out, "{} puzzles, {} failures", num_puzzle, num_failure));
+    Ok(SoakReport {
+        puzzles: num_puzzle,
+        failures: num_failure,
+    })
+}
+
+/// Generates a random puzzle from the seed, solves it and checks the
+/// invariants of the solver and of the puzzle format. The puzzle and the
+/// number of its solutions are written to `out` if verbose, and the errors
+/// writing them fail the check.
+fn soak_one<O: Write>(out: &mut O, seed: u64, max_size: Size, verbose: bool) -> Result<(), String> {
+    let mut rng = match pack::new_rng(Some(seed)) {
+        Ok(rng) => rng,
+        Err(e) => return Err(format!("cannot create the random number generator: {}", e)),
+    };
+    let size = Size(rng.gen_range(1, max_size.0 + 1), rng.gen_range(1, max_size.1 + 1));
+    let answer = random_loop(&mut rng, size);
+    let hint_ratio = rng.gen_range(0.5, 1.0);
+    let mut puzzle = Puzzle::new(size);
+    for p in answer.points() {
+        if rng.gen::<f64>() < hint_ratio {
+            puzzle.set_hint(p, answer.hint(p));
+        }
+    }
+    if verbose {
+        try!(write!(out, "{}", puzzle).map_err(|e| e.to_string()));
+    }
+
+    // The sides are not written in the text format.
+    let mut lines = answer.clone();
+    for p in lines.points() {
+        lines.set_side(p, None);
+    }
+    for p in &[&puzzle, &lines] {
+        match p.to_string().parse::<Puzzle>() {
+            Ok(ref q) if q == *p => {}
+            _ => return Err(format!("puzzle does not round-trip:\n{}", p)),
+        }
+    }
+
+    let _ = try!(check_solutions(out, &puzzle, Some(&answer), verbose));
+    Ok(())
+}
+
+/// Checks the solutions of the puzzle, and returns the number of them up to
+/// `MAX_SOLUTIONS`. The solutions must be valid, must include the answer if
+/// given, and must be as many as those of the transposed puzzle.
+fn check_solutions<O: Write>(out: &mut O,
+                             puzzle: &Puzzle,
+                             answer: Option<&Puzzle>,
+                             verbose: bool)
+                             -> Result<u64, String> {
+    let answer_bits = answer.map(LoopBits::from_puzzle);
+    let mut found = false;
+    let mut error = None;
+    let mut num_solution = 0;
+    let count = try!(solver::for_each_solution(puzzle, &SolveOptions::default(), |s| {
+                    if !solver::verify(puzzle, s) {
+                        error = Some(format!("invalid solution:\n{}", s));
+                        return false;
+                    }
+                    if Some(LoopBits::from_puzzle(s)) == answer_bits {
+                        found = true;
+                    }
+                    num_solution += 1;
+                    num_solution < MAX_SOLUTIONS
+                })
+                    .map_err(|e| format!("solver error: {}", e)));
+    if let Some(msg) = error {
+        return Err(msg);
+    }
+    if let Some(answer) = answer {
+        if count < MAX_SOLUTIONS && !found {
+            return Err(format!("the generated loop is not a solution:\n{}", answer));
+        }
+    }
+    if verbose {
+        try!(writeln!(out, "{} solutions", count).map_err(|e| e.to_string()));
+    }
+
+    // The number of the solutions does not depend on the orientation.
+    if count < MAX_SOLUTIONS {
+        let rotated = puzzle.rotate90().flip_h();
+        let mut num_rotated = 0;
+        let rotated_count = try!(solver::for_each_solution(&rotated,
+                                                           &SolveOptions::default(),
+                                                           |_| {
+                                                               num_rotated += 1;
+                                                               num_rotated < MAX_SOLUTIONS
+                                                           })
+                                     .map_err(|e| format!("solver error: {}", e)));
+        if rotated_count != count {
+            return Err(format!("{} solutions, but {} solutions when transposed",
+                               count,
+                               rotated_count));
+        }
+    }
+
+    Ok(count)
+}
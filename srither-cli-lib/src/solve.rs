@@ -0,0 +1,920 @@
+// Copyright (c) 2016 srither developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Solves the puzzles.
+
+use std::fs::{self, File};
+use std::io;
+use std::io::prelude::*;
+use std::path::Path;
+use std::sync::atomic::Ordering;
+
+use srither_core::format::loopy::LoopySave;
+use srither_core::geom::{Geom, Point};
+use srither_core::orientation::{self, Orientation};
+use srither_core::puzzle::{Edge, Puzzle, Side};
+use srither_core::puzzle_set::PuzzleSet;
+use srither_solver::{Deduction, Deepening, SearchEffort, SearchNode, SolveOptions, Solutions,
+                     SolverStats, Step, Strategy, self as solver};
+use time;
+
+use cache::Cache;
+use coord;
+use describe;
+use envelope;
+use error::{AppError, AppResult};
+use history;
+use io_util;
+use json::Json;
+use parse_arg::{Format, OutputMode, SolveConfig};
+use pipeline::{self, Outcome};
+use pprint;
+
+/// Solves the puzzles of the input files, or of `input` if none, and writes
+/// their solutions and reports to `out`. Returns the results of the puzzles
+/// in order.
+pub fn run<I, O, E>(config: SolveConfig,
+                    input: &mut I,
+                    out: &mut O,
+                    err: &mut E)
+                    -> AppResult<Vec<Solved>>
+    where I: Read,
+          O: Write,
+          E: Write
+{
+    let mut options = try!(solve_options(config.strategy_file.as_ref()));
+    options.max_depth = config.max_guess_depth;
+    options.max_solutions = config.max_solutions;
+    options.restart = config.restart;
+    options.disabled_theorems = config.disabled_theorems.clone();
+    let mut results = vec![];
+    if config.input_files.is_empty() {
+        results.extend(try!(solve(&config, &options, input, out, err)));
+    } else {
+        for file in &config.input_files {
+            let mut f = try!(io_util::open(file));
+            results.extend(try!(solve(&config, &options, &mut f, out, err)));
+        }
+    }
+
+    Ok(results)
+}
+
+/// Returns the options to solve with the strategy in the file, if any.
+pub fn solve_options(strategy_file: Option<&String>) -> AppResult<SolveOptions> {
+    let mut options = SolveOptions::default();
+    if let Some(file) = strategy_file {
+        let buf = try!(io_util::read_file(file));
+        options.strategy = Some(try!(buf.parse::<Strategy>()));
+    }
+    Ok(options)
+}
+
+fn solve<T, O, E>(config: &SolveConfig,
+                  options: &SolveOptions,
+                  input: &mut T,
+                  out: &mut O,
+                  err: &mut E)
+                  -> AppResult<Vec<Solved>>
+    where T: Read,
+          O: Write,
+          E: Write
+{
+    let buf = try!(io_util::read_to_string(input));
+    let original = match config.in_format {
+        Some(format) => try!(format.parse(&buf)),
+        None => {
+            match Puzzle::parse_any(&buf) {
+                Ok((puzzle, _)) => puzzle,
+                Err(e) => {
+                    // A file of several puzzles with the headers of metadata.
+                    try!(envelope::check_text(&envelope::PACK, &buf));
+                    match buf.parse::<PuzzleSet>() {
+                        Ok(_) if config.resume_file.is_some() => {
+                            let msg = "--resume-file takes a single puzzle, not a pack";
+                            return Err(io::Error::new(io::ErrorKind::InvalidInput, msg).into());
+                        }
+                        Ok(set) => return solve_set(config, options, &set, out, err),
+                        Err(_) => return Err(e.into()),
+                    }
+                }
+            }
+        }
+    };
+    Ok(vec![try!(solve_puzzle(config, options, &original, out))])
+}
+
+/// Solves the puzzles of the set by the pipeline, and writes them in order.
+/// In text, the solutions of each puzzle follow its headers, separated by an
+/// empty line as in the set.
+fn solve_set<O, E>(config: &SolveConfig,
+                   options: &SolveOptions,
+                   set: &PuzzleSet,
+                   out: &mut O,
+                   err: &mut E)
+                   -> AppResult<Vec<Solved>>
+    where O: Write,
+          E: Write
+{
+    for entry in set.entries() {
+        history::note(&entry.puzzle);
+    }
+    let puzzles = set.entries().iter().map(|e| e.puzzle.clone()).collect();
+    let (shared, options) = (config.clone(), options.clone());
+    let mut num_timed_out = 0;
+    let mut results = vec![];
+    try!(pipeline::run(&config.pipeline,
+                       puzzles,
+                       move |puzzle, stop| {
+                           let options = SolveOptions { stop: Some(stop), ..options.clone() };
+                           let mut solutions = vec![];
+                           let solved = derive(&shared, &options, &puzzle, |s| {
+                               solutions.push(s.clone());
+                               Ok(())
+                           });
+                           solved.map(|solved| (solutions, solved))
+                       },
+                       |i, outcome| {
+                           let entry = &set.entries()[i];
+                           if config.format == Format::Text {
+                               if i > 0 {
+                                   try!(writeln!(out, ""));
+                               }
+                               for &(ref key, ref value) in entry.metadata.iter() {
+                                   try!(writeln!(out, "# {}: {}", key, value));
+                               }
+                           }
+                           // The other puzzles are still solved, and the command
+                           // fails at the end.
+                           let (solutions, mut solved) = match outcome {
+                               Outcome::Done(result) => try!(result),
+                               Outcome::TimedOut => {
+                                   match config.format {
+                                       Format::Text => try!(writeln!(out, "timed out")),
+                                       Format::Json => {
+                                           let json = Json::object(vec![("timed_out",
+                                                                         true.into())]);
+                                           try!(writeln!(out, "{}", json))
+                                       }
+                                   }
+                                   num_timed_out += 1;
+                                   return Ok(());
+                               }
+                           };
+                           for solution in &solutions {
+                               try!(output(out, config, solution));
+                           }
+                           try!(report(out, config, &entry.puzzle, &solved));
+                           solved.solutions = solutions;
+                           results.push(solved);
+                           Ok(())
+                       },
+                       err));
+    if num_timed_out > 0 {
+        let msg = format!("{} puzzles timed out", num_timed_out);
+        return Err(io::Error::new(io::ErrorKind::TimedOut, msg).into());
+    }
+    Ok(results)
+}
+
+fn solve_puzzle<O: Write>(config: &SolveConfig,
+                          options: &SolveOptions,
+                          original: &Puzzle,
+                          out: &mut O)
+                          -> AppResult<Solved> {
+    history::note(original);
+    if let Some(ref file) = config.resume_file {
+        return resume(config, options, original, file, out);
+    }
+    let solved = try!(derive(config, options, original, |s| output(out, config, s)));
+    try!(report(out, config, original, &solved));
+    Ok(solved)
+}
+
+/// The results of solving a puzzle.
+#[derive(Clone, Debug)]
+pub struct Solved {
+    /// The number of the solutions, with `--count`.
+    pub count: Option<u64>,
+    /// The solutions, in the orientation of the puzzle.
+    pub solutions: Vec<Puzzle>,
+    /// The statistics of the solver and the total time in nanoseconds, with
+    /// `--profile-steps`.
+    pub profile: Option<(SolverStats, u64)>,
+    /// The search nodes fixing each edge, with `--effort`.
+    pub effort: Option<SearchEffort>,
+    /// The board deduced without guessing and the deductions, with
+    /// `--explain`.
+    pub explanation: Option<(Puzzle, Vec<Deduction>)>,
+    /// The state of the search left unfinished, with `--resume-file`.
+    pub progress: Option<Deepening>,
+    /// The statistics of the solver and the total time in nanoseconds,
+    /// unless the solutions are read from the cache or only the first ones
+    /// are derived.
+    pub stats: Option<(SolverStats, u64)>,
+    /// Whether the puzzle has a single solution, in the JSON reports, or
+    /// none if it is not known.
+    pub unique: Option<bool>,
+}
+
+/// Solves the puzzle, and calls `on_solution` with each solution as soon as
+/// it is derived.
+fn derive<F>(config: &SolveConfig,
+             options: &SolveOptions,
+             original: &Puzzle,
+             mut on_solution: F)
+             -> AppResult<Solved>
+    where F: FnMut(&Puzzle) -> AppResult<()>
+{
+    let mut solved = Solved {
+        count: None,
+        solutions: vec![],
+        profile: None,
+        effort: None,
+        explanation: None,
+        progress: None,
+        stats: None,
+        unique: None,
+    };
+
+    // The normalized puzzle is solved and cached, and its solutions are turned
+    // back to the orientation of the input.
+    let (puzzle, orientation) = match config.normalize {
+        Some(normalization) => orientation::normalize(original, normalization),
+        None => (original.clone(), Orientation::identity()),
+    };
+    let restore = orientation.inverse();
+
+    if config.count {
+        solved.count = Some(try!(solver::for_each_solution(&puzzle, options, |_| true)));
+        return Ok(solved);
+    }
+    if let Some(max) = config.solutions {
+        let solutions = try!(first_solutions(options, &puzzle, max, |s| {
+            on_solution(&restore.apply(s))
+        }));
+        solved.solutions = solutions.iter().map(|s| restore.apply(s)).collect();
+        // Fewer solutions than the number are all of them.
+        let all = if max > 1 { Some(solutions.len()) } else { None };
+        solved.unique = uniqueness(config, options, &puzzle, all);
+        return Ok(solved);
+    }
+
+    // The solutions limited by `--max-solutions` may not be all of them, so
+    // they are not cached.
+    let cache = match config.cache_dir {
+        Some(ref dir) if config.max_solutions.is_none() => Some(try!(Cache::open(dir))),
+        _ => None,
+    };
+    // The cached solutions have no profile, may need deeper guesses, and are
+    // not derived without the disabled theorems.
+    let cached = if config.profile_steps || config.max_guess_depth.is_some() || config.effort ||
+                    config.explain || !config.disabled_theorems.is_empty() {
+        None
+    } else {
+        cache.as_ref().and_then(|c| c.get(&puzzle, config.derive_all))
+    };
+    if let Some(solutions) = cached {
+        for solution in solutions.iter().map(|s| restore.apply(s)) {
+            try!(on_solution(&solution));
+            solved.solutions.push(solution);
+        }
+        let all = if config.derive_all { Some(solutions.len()) } else { None };
+        solved.unique = uniqueness(config, options, &puzzle, all);
+        return Ok(solved);
+    }
+
+    let start = time::precise_time_ns();
+    let mut solutions = vec![];
+    let stats = if config.derive_all {
+        let mut it = try!(Solutions::with_options(&puzzle, options));
+        for solution in it.by_ref() {
+            try!(on_solution(&restore.apply(&solution)));
+            solutions.push(solution);
+        }
+        it.stats().clone()
+    } else {
+        let (solution, stats) = try!(solver::solve_with_stats(&puzzle, options));
+        try!(on_solution(&restore.apply(&solution)));
+        solutions.push(solution);
+        stats
+    };
+    let total = time::precise_time_ns() - start;
+    if config.profile_steps {
+        solved.profile = Some((stats.clone(), total));
+    }
+    solved.stats = Some((stats, total));
+    let all = if config.derive_all { Some(solutions.len()) } else { None };
+    solved.unique = uniqueness(config, options, &puzzle, all);
+    if config.effort {
+        solved.effort = Some(try!(solver::search_effort(original, options)).1);
+    }
+    if config.explain {
+        solved.explanation = Some(try!(solver::trace_deductions(original, options)));
+    }
+    solved.solutions = solutions.iter().map(|s| restore.apply(s)).collect();
+
+    // The search stopped by the options may have missed solutions, and its
+    // result is discarded.
+    if options.stop.as_ref().map_or(false, |s| s.load(Ordering::SeqCst)) {
+        return Ok(solved);
+    }
+    if let Some(ref cache) = cache {
+        try!(cache.put(&puzzle, config.derive_all, &solutions));
+        // The statistics rate the puzzle by the default deductions, as
+        // `rate` does, so the others are not cached.
+        let default_steps = options.backend == SolveOptions::default().backend &&
+                            options.strategy.is_none() &&
+                            options.variant_steps.is_empty() &&
+                            options.max_depth.is_none() &&
+                            options.disabled_theorems.is_empty() &&
+                            options.restart.is_none();
+        if !config.derive_all && default_steps {
+            if let Some(&(ref stats, _)) = solved.stats.as_ref() {
+                try!(cache.put_stats(&puzzle, options.theorem_set, &solutions[0], stats));
+            }
+        }
+    }
+
+    Ok(solved)
+}
+
+/// Returns whether the puzzle has a single solution for the JSON report, by
+/// the number of the solutions if all of them are derived, or by searching
+/// for a second one. Returns none if the search fails.
+fn uniqueness(config: &SolveConfig,
+              options: &SolveOptions,
+              puzzle: &Puzzle,
+              all_solutions: Option<usize>)
+              -> Option<bool> {
+    if config.format != Format::Json {
+        return None;
+    }
+    match all_solutions {
+        // A single solution may be cut by `--max-solutions`.
+        Some(n) if n != 1 || options.max_solutions.map_or(true, |max| max > 1) => Some(n == 1),
+        _ => {
+            let options = SolveOptions { max_solutions: Some(2), ..options.clone() };
+            solver::for_each_solution(puzzle, &options, |_| true).ok().map(|n| n == 1)
+        }
+    }
+}
+
+/// Passes the first solutions up to the number to `on_solution` as soon as
+/// each is found by the search, and returns them. They are examples of the
+/// solutions, so they are not cached.
+fn first_solutions<F>(options: &SolveOptions,
+                      puzzle: &Puzzle,
+                      max: u64,
+                      mut on_solution: F)
+                      -> AppResult<Vec<Puzzle>>
+    where F: FnMut(&Puzzle) -> AppResult<()>
+{
+    let mut solutions = vec![];
+    let mut result = Ok(());
+    let mut found = 0;
+    let _ = try!(solver::for_each_solution(puzzle, options, |s| {
+        result = on_solution(s);
+        solutions.push(s.clone());
+        found += 1;
+        result.is_ok() && found < max
+    }));
+    try!(result);
+    Ok(solutions)
+}
+
+/// Searches the puzzle by iterative deepening from the state saved in the
+/// file, if any, until all the nodes are searched or the slice is over, and
+/// saves the state again. The solutions are printed only when the search is
+/// finished, and the progress otherwise.
+fn resume<O: Write>(config: &SolveConfig,
+                    options: &SolveOptions,
+                    puzzle: &Puzzle,
+                    file: &str,
+                    out: &mut O)
+                    -> AppResult<Solved> {
+    let mut state = if Path::new(file).exists() {
+        try!(read_state(file, puzzle))
+    } else {
+        Deepening::new()
+    };
+    let deadline = config.slice.map(|secs| time::precise_time_ns() + secs * 1_000_000_000);
+    let _ = try!(state.run(puzzle, options, || {
+        deadline.map_or(false, |deadline| time::precise_time_ns() >= deadline)
+    }));
+    try!(write_state(file, puzzle, &state));
+
+    let mut solved = Solved {
+        count: None,
+        solutions: vec![],
+        profile: None,
+        effort: None,
+        explanation: None,
+        progress: None,
+        stats: None,
+        unique: None,
+    };
+    if !state.is_finished(options) {
+        match config.format {
+            Format::Text => {
+                try!(writeln!(out,
+                              "# unfinished: bound {}, {} nodes searched, {} pending, {} in the \
+                               frontier, {} solutions so far",
+                              state.bound,
+                              state.nodes,
+                              state.pending.len(),
+                              state.frontier.len(),
+                              state.solutions.len()))
+            }
+            Format::Json => {
+                let json = Json::object(vec![("finished", false.into()),
+                                             ("bound", state.bound.into()),
+                                             ("nodes", state.nodes.into()),
+                                             ("pending", state.pending.len().into()),
+                                             ("frontier", state.frontier.len().into()),
+                                             ("solutions_found", state.solutions.len().into())]);
+                try!(writeln!(out, "{}", json))
+            }
+        }
+        solved.progress = Some(state);
+        return Ok(solved);
+    }
+
+    let solutions = try!(state.solutions(puzzle, options));
+    solved.unique = uniqueness(config, options, puzzle, Some(solutions.len()));
+    if config.count {
+        solved.count = Some(solutions.len() as u64);
+    } else {
+        for solution in &solutions {
+            try!(output(out, config, solution));
+        }
+        solved.solutions = solutions;
+    }
+    try!(report(out, config, puzzle, &solved));
+    Ok(solved)
+}
+
+// The state is written as the lines of the nodes, each the list of the kind
+// (`pending`, `frontier` or `solution`) and the assumptions `in:R,C` or
+// `out:R,C` of the cells, following the headers of the puzzle and the round.
+fn write_state(file: &str, puzzle: &Puzzle, state: &Deepening) -> AppResult<()> {
+    // The state is replaced at once, so an interrupted write keeps the old one.
+    let tmp = format!("{}.tmp", file);
+    {
+        let mut f = try!(File::create(&tmp));
+        try!(writeln!(f, "{}", envelope::header(&envelope::DEEPENING)));
+        try!(writeln!(f, "# puzzle: {:016x}", puzzle.fingerprint()));
+        try!(writeln!(f, "# bound: {}", state.bound));
+        try!(writeln!(f, "# nodes: {}", state.nodes));
+        for &(kind, nodes) in &[("pending", &state.pending),
+                                ("frontier", &state.frontier),
+                                ("solution", &state.solutions)] {
+            for node in nodes {
+                try!(write!(f, "{}", kind));
+                for &(p, side) in node {
+                    let side = if side == Side::In { "in" } else { "out" };
+                    try!(write!(f, " {}:{},{}", side, p.0, p.1));
+                }
+                try!(writeln!(f, ""));
+            }
+        }
+        try!(f.sync_data());
+    }
+    try!(fs::rename(&tmp, file));
+    Ok(())
+}
+
+fn read_state(file: &str, puzzle: &Puzzle) -> AppResult<Deepening> {
+    let buf = try!(io_util::read_file(file));
+    let mut lines = buf.lines().map(|l| l.trim()).filter(|l| !l.is_empty());
+    match lines.next().and_then(|line| envelope::parse(&envelope::DEEPENING, line)) {
+        Some(envelope) => try!(envelope::check(&envelope::DEEPENING, &envelope)),
+        None => return Err(invalid_state("not a state of the search")),
+    }
+    let fingerprint = try!(state_header(&mut lines, "puzzle"));
+    if u64::from_str_radix(fingerprint, 16).ok() != Some(puzzle.fingerprint()) {
+        return Err(invalid_state("the state is of another puzzle"));
+    }
+    let mut state = Deepening::default();
+    state.bound = try!(try!(state_header(&mut lines, "bound"))
+                           .parse()
+                           .map_err(|_| invalid_state("invalid bound")));
+    state.nodes = try!(try!(state_header(&mut lines, "nodes"))
+                           .parse()
+                           .map_err(|_| invalid_state("invalid nodes")));
+
+    for line in lines {
+        let mut words = line.split_whitespace();
+        let nodes = match words.next() {
+            Some("pending") => &mut state.pending,
+            Some("frontier") => &mut state.frontier,
+            Some("solution") => &mut state.solutions,
+            _ => return Err(invalid_state("invalid node in the state")),
+        };
+        let mut node = SearchNode::new();
+        for word in words {
+            match parse_assumption(puzzle, word) {
+                Some(assumption) => node.push(assumption),
+                None => return Err(invalid_state("invalid node in the state")),
+            }
+        }
+        nodes.push(node);
+    }
+    Ok(state)
+}
+
+// Reads the value of the header `# NAME: VALUE` on the next line.
+fn state_header<'a, I>(lines: &mut I, name: &str) -> AppResult<&'a str>
+    where I: Iterator<Item = &'a str>
+{
+    let prefix = format!("# {}:", name);
+    match lines.next() {
+        Some(line) if line.starts_with(&prefix) => Ok(line[prefix.len()..].trim()),
+        _ => Err(invalid_state(&format!("the state has no {}", name))),
+    }
+}
+
+// Parses the assumption `in:R,C` or `out:R,C` of a cell in the puzzle.
+fn parse_assumption(puzzle: &Puzzle, word: &str) -> Option<(Point, Side)> {
+    let (side, cell) = if word.starts_with("in:") {
+        (Side::In, &word[3..])
+    } else if word.starts_with("out:") {
+        (Side::Out, &word[4..])
+    } else {
+        return None;
+    };
+    let mut coords = cell.split(',').map(|n| n.parse::<i32>());
+    match (coords.next(), coords.next(), coords.next()) {
+        (Some(Ok(r)), Some(Ok(c)), None) if puzzle.contains(Point(r, c)) => {
+            Some((Point(r, c), side))
+        }
+        _ => None,
+    }
+}
+
+fn invalid_state(msg: &str) -> AppError {
+    io::Error::new(io::ErrorKind::InvalidData, msg).into()
+}
+
+/// Prints the results of solving the puzzle after its solutions.
+fn report<W: Write>(out: &mut W,
+                    config: &SolveConfig,
+                    original: &Puzzle,
+                    solved: &Solved)
+                    -> AppResult<()> {
+    match config.format {
+        Format::Text => {
+            if let Some(count) = solved.count {
+                try!(writeln!(out, "{}", count));
+            }
+            if let Some((ref stats, total)) = solved.profile {
+                try!(print_profile(out, stats, total));
+            }
+            if let Some(ref effort) = solved.effort {
+                try!(print_effort(out, original, effort));
+            }
+            if let Some((ref deduced, ref deductions)) = solved.explanation {
+                try!(print_explanation(out, original, deduced, deductions));
+            }
+        }
+        Format::Json => {
+            if let Some(count) = solved.count {
+                try!(writeln!(out, "{}", Json::object(vec![("count", count.into())])));
+                return Ok(());
+            }
+            try!(writeln!(out, "{}", solutions_json(original, solved)));
+        }
+    }
+    Ok(())
+}
+
+fn output<W: Write>(out: &mut W, config: &SolveConfig, solution: &Puzzle) -> AppResult<()> {
+    // The solutions are written in the JSON object instead.
+    if config.format == Format::Json {
+        return Ok(());
+    }
+    match config.output_mode {
+        OutputMode::Pretty(conf) => {
+            try!(pprint::print(out, &conf, solution));
+        }
+        OutputMode::Describe => try!(describe::print(out, solution)),
+        OutputMode::Raw => {
+            try!(write!(out, "{}", solution.to_string()));
+        }
+        OutputMode::Path => try!(print_path(out, solution)),
+        OutputMode::LoopySave => try!(write!(out, "{}", LoopySave(solution))),
+        OutputMode::None => {}
+    }
+
+    Ok(())
+}
+
+/// Prints the vertices of the loop in the order of traversal, one per line
+/// as `x y` (the column and the row of the corner), and the first one again
+/// to close the polyline. The solutions are separated by empty lines.
+fn print_path<W: Write>(out: &mut W, solution: &Puzzle) -> AppResult<()> {
+    match solution.loop_path() {
+        Some(path) => {
+            for p in path.iter().chain(path.first()) {
+                try!(writeln!(out, "{} {}", p.1, p.0));
+            }
+        }
+        None => try!(writeln!(out, "# the lines are not a single loop")),
+    }
+    try!(writeln!(out, ""));
+    Ok(())
+}
+
+/// Prints the time spent on each step, from the most expensive one. The
+/// time not spent on any step is mostly spent on searching.
+fn print_profile<W: Write>(out: &mut W, stats: &SolverStats, total: u64) -> AppResult<()> {
+    let times = &stats.times;
+    let steps = times.pool + times.theorems + times.connect + times.probes;
+    let mut rows = vec![("theorem pool", times.pool),
+                        ("theorems", times.theorems),
+                        ("connectivity", times.connect),
+                        ("probing", times.probes),
+                        ("search and others", total.saturating_sub(steps))];
+    rows.sort_by(|a, b| b.1.cmp(&a.1));
+
+    try!(writeln!(out, "{:<20} {:>12} {:>7}", "step", "time", "%"));
+    for &(name, ns) in &rows {
+        try!(writeln!(out,
+                      "{:<20} {:>12} {:>7.1}",
+                      name,
+                      format_ns(ns),
+                      percent(ns, total)));
+    }
+    try!(writeln!(out, "{:<20} {:>12}", "total", format_ns(total)));
+
+    let names = ["hint 0", "hint 1", "hint 2", "hint 3", "hint 4", "no hint"];
+    let mut buckets = names.iter()
+                           .cloned()
+                           .zip(times.pool_buckets.iter().cloned())
+                           .collect::<Vec<_>>();
+    buckets.sort_by(|a, b| b.1.cmp(&a.1));
+
+    try!(writeln!(out, ""));
+    try!(writeln!(out, "{:<20} {:>12} {:>7}", "theorem bucket", "pool time", "%"));
+    for &(name, ns) in &buckets {
+        try!(writeln!(out,
+                      "{:<20} {:>12} {:>7.1}",
+                      name,
+                      format_ns(ns),
+                      percent(ns, times.pool)));
+    }
+
+    try!(writeln!(out, ""));
+    try!(writeln!(out,
+                  "guesses: {}, max depth: {}, restarts: {}",
+                  stats.guesses(),
+                  stats.max_depth,
+                  stats.restarts));
+    Ok(())
+}
+
+/// Prints the number of the search nodes fixing each edge on the lattice of
+/// the puzzle, with the hints in the cells. The numbers of 10 or more are
+/// written as `*`.
+fn print_effort<W: Write>(out: &mut W, puzzle: &Puzzle, effort: &SearchEffort) -> AppResult<()> {
+    let mark = |n: u32| {
+        match n {
+            0 => ' ',
+            n if n < 10 => (b'0' + n as u8) as char,
+            _ => '*',
+        }
+    };
+
+    try!(writeln!(out, "search nodes: {}", effort.nodes()));
+    for r in 0..(puzzle.row() + 1) {
+        let mut line = String::new();
+        for c in 0..puzzle.column() {
+            line.push('+');
+            line.push(mark(effort.edge_h(Point(r, c))));
+        }
+        line.push('+');
+        try!(writeln!(out, "{}", line));
+        if r == puzzle.row() {
+            break;
+        }
+
+        let mut line = String::new();
+        for c in 0..(puzzle.column() + 1) {
+            line.push(mark(effort.edge_v(Point(r, c))));
+            if c < puzzle.column() {
+                line.push(puzzle.hint(Point(r, c)).map_or(' ', |n| (b'0' + n) as char));
+            }
+        }
+        try!(writeln!(out, "{}", line));
+    }
+    Ok(())
+}
+
+/// Prints the deductions as a numbered list in words, and how the deduction
+/// ends. The hints completed by each deduction are listed up to a few.
+fn print_explanation<W: Write>(out: &mut W,
+                               puzzle: &Puzzle,
+                               deduced: &Puzzle,
+                               deductions: &[Deduction])
+                               -> AppResult<()> {
+    const MAX_HINTS: usize = 3;
+
+    for (i, d) in deductions.iter().enumerate() {
+        let mut line = format!("{}. {}", i + 1, step_in_words(d.step));
+        if d.passes > 1 {
+            line.push_str(&format!(" in {} passes", d.passes));
+        }
+        line.push_str(&format!(" fixes {} and {}",
+                               plural(d.lines, "line"),
+                               plural(d.crosses, "cross")));
+        if !d.completed.is_empty() {
+            let mut hints = d.completed
+                             .iter()
+                             .take(MAX_HINTS)
+                             .map(|&p| {
+                                 format!("the {} at {}", puzzle.hint(p).unwrap(), coord::format(p))
+                             })
+                             .collect::<Vec<_>>();
+            if d.completed.len() > MAX_HINTS {
+                let more = d.completed.len() - MAX_HINTS;
+                hints.push(if more == 1 {
+                    "1 more hint".to_owned()
+                } else {
+                    format!("{} more hints", more)
+                });
+            }
+            let last = hints.pop().unwrap();
+            if hints.is_empty() {
+                line.push_str(&format!(", completing {}", last));
+            } else {
+                line.push_str(&format!(", completing {} and {}", hints.join(", "), last));
+            }
+        }
+        try!(writeln!(out, "{}.", line));
+    }
+
+    let n = deductions.len() + 1;
+    if deduced.has_unknown_edge() {
+        let mut unknowns = 0;
+        for r in 0..(deduced.row() + 1) {
+            for c in 0..(deduced.column() + 1) {
+                let p = Point(r, c);
+                if c < deduced.column() && deduced.edge_h(p).is_none() {
+                    unknowns += 1;
+                }
+                if r < deduced.row() && deduced.edge_v(p).is_none() {
+                    unknowns += 1;
+                }
+            }
+        }
+        try!(writeln!(out,
+                      "{}. The rest needs guessing: {} unknown.",
+                      n,
+                      plural(unknowns, "edge is")));
+    } else {
+        try!(writeln!(out, "{}. The puzzle is solved.", n));
+    }
+    Ok(())
+}
+
+fn step_in_words(step: Step) -> String {
+    match step {
+        Step::Theorems => "Applying the theorems around the hints".to_owned(),
+        Step::Connect => "Analyzing the connectivity of the cells".to_owned(),
+        Step::Probe { depth: 1 } => {
+            "Assuming each unknown cell inside and outside".to_owned()
+        }
+        Step::Probe { depth } => {
+            format!("Assuming each unknown cell inside and outside, {} levels deep",
+                    depth)
+        }
+    }
+}
+
+fn plural(n: usize, noun: &str) -> String {
+    match (n, noun) {
+        (1, _) => format!("1 {}", noun),
+        (_, "cross") => format!("{} crosses", n),
+        (_, "edge is") => format!("{} edges are", n),
+        _ => format!("{} {}s", n, noun),
+    }
+}
+
+/// Returns the object written for each puzzle with `--format json`:
+/// `{"solutions": [raw puzzle strings], "size": {"rows": R, "columns": C},
+/// "hints": [[1, null, ...], ...], "solution_edges": [{"edge_h": [[true,
+/// false, ...], ...], "edge_v": [...]}, ...], "unique": true,
+/// "solve_time_ms": 1.5, "guesses": 0, "theorems_applied": 20, "profile":
+/// {...}, "effort": {...}, "deductions": [...]}`. `solution_edges` has the
+/// lines of each solution as `true`, and `unique`, `solve_time_ms`,
+/// `guesses` and `theorems_applied` are `null` if they are not known, e.g.
+/// for the cached solutions. `profile` is present only with
+/// `--profile-steps` and its times are in nanoseconds, `effort` is present
+/// only with `--effort` and has the number of the search nodes fixing each
+/// edge as `edge_h` and `edge_v`, arrays of the rows, and `deductions` is
+/// present only with `--explain`, with the hints completed by each
+/// deduction as pairs of the coordinates given by `--coords`.
+fn solutions_json(original: &Puzzle, solved: &Solved) -> Json {
+    let (row, column) = (original.row(), original.column());
+    let solutions = &solved.solutions;
+    let edges = solutions.iter()
+                         .map(|s| {
+                             let line_h = |p| s.edge_h(p) == Some(Edge::Line);
+                             let line_v = |p| s.edge_v(p) == Some(Edge::Line);
+                             Json::object(vec![("edge_h", rows_json(row + 1, column, line_h)),
+                                               ("edge_v", rows_json(row, column + 1, line_v))])
+                         })
+                         .collect();
+    let stats = solved.stats.as_ref();
+    let mut fields = vec![("solutions",
+                           Json::Array(solutions.iter().map(|s| s.to_string().into()).collect())),
+                          ("size",
+                           Json::object(vec![("rows", row.into()), ("columns", column.into())])),
+                          ("hints",
+                           rows_json(row, column, |p| original.hint(p).map(|n| n as u32))),
+                          ("solution_edges", Json::Array(edges)),
+                          ("unique", solved.unique.into()),
+                          ("solve_time_ms",
+                           stats.map(|&(_, total)| total as f64 / 1_000_000.0).into()),
+                          ("guesses", stats.map(|s| s.0.guesses()).into()),
+                          ("theorems_applied", stats.map(|s| s.0.theorems_applied).into())];
+    if let Some((ref stats, total)) = solved.profile {
+        let times = &stats.times;
+        let buckets = times.pool_buckets.iter().map(|&ns| ns.into()).collect();
+        fields.push(("profile",
+                     Json::object(vec![("total_ns", total.into()),
+                                       ("pool_ns", times.pool.into()),
+                                       ("theorems_ns", times.theorems.into()),
+                                       ("connect_ns", times.connect.into()),
+                                       ("probes_ns", times.probes.into()),
+                                       ("pool_buckets_ns", Json::Array(buckets)),
+                                       ("guesses", stats.guesses().into()),
+                                       ("max_depth", stats.max_depth.into()),
+                                       ("restarts", stats.restarts.into())])));
+    }
+    if let (Some(effort), Some(solution)) = (solved.effort.as_ref(), solutions.first()) {
+        let (row, column) = (solution.row(), solution.column());
+        fields.push(("effort",
+                     Json::object(vec![("nodes", effort.nodes().into()),
+                                       ("edge_h", rows_json(row + 1, column, |p| effort.edge_h(p))),
+                                       ("edge_v",
+                                        rows_json(row, column + 1, |p| effort.edge_v(p)))])));
+    }
+    if let Some((_, ref deductions)) = solved.explanation {
+        let deductions = deductions.iter().map(deduction_json).collect();
+        fields.push(("deductions", Json::Array(deductions)));
+    }
+    Json::object(fields)
+}
+
+// Returns the values of the points as an array of the rows.
+fn rows_json<T, F>(row: i32, column: i32, f: F) -> Json
+    where T: Into<Json>,
+          F: Fn(Point) -> T
+{
+    Json::Array((0..row)
+                    .map(|r| Json::Array((0..column).map(|c| f(Point(r, c)).into()).collect()))
+                    .collect())
+}
+
+/// Returns the short name of the step, e.g. `probe2`.
+pub fn step_name(step: Step) -> String {
+    match step {
+        Step::Theorems => "theorems".to_owned(),
+        Step::Connect => "connect".to_owned(),
+        Step::Probe { depth } => format!("probe{}", depth),
+    }
+}
+
+fn deduction_json(d: &Deduction) -> Json {
+    let step = step_name(d.step);
+    let style = coord::style();
+    let completed = d.completed
+                     .iter()
+                     .map(|&p| {
+                         let (a, b) = style.to_pair(p);
+                         Json::Array(vec![a.into(), b.into()])
+                     })
+                     .collect();
+    Json::object(vec![("step", step.into()),
+                      ("passes", d.passes.into()),
+                      ("lines", d.lines.into()),
+                      ("crosses", d.crosses.into()),
+                      ("completed", Json::Array(completed))])
+}
+
+fn percent(ns: u64, total: u64) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    100.0 * ns as f64 / total as f64
+}
+
+fn format_ns(ns: u64) -> String {
+    format!("{}.{:03} ms", ns / 1000000, ns / 1000 % 1000)
+}
@@ -0,0 +1,139 @@
+// Copyright (c) 2016 srither developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Tests the solver on the puzzles and their recorded solutions.
+
+use rustc_test::{DynTestFn, DynTestName, ShouldPanic, TestDesc, TestDescAndFn, self as test};
+
+use srither_core::puzzle::Puzzle;
+use srither_core::puzzle_set::PuzzleSet;
+use srither_solver::{SolveOptions, Solutions, self as solver};
+use srither_testdata::{self as testdata, Entry};
+
+use envelope;
+use error::AppResult;
+use io_util;
+use parse_arg::TestConfig;
+use solve;
+
+/// Runs the tests by the test harness, which reports to the standard output.
+pub fn run(config: TestConfig) -> AppResult<()> {
+    let derive_all = config.derive_all;
+    let verify = config.verify;
+    let mut options = try!(solve::solve_options(config.strategy_file.as_ref()));
+    options.disabled_theorems = config.disabled_theorems.clone();
+    let mut tests = vec![];
+    for input in config.input_files {
+        // Each puzzle of a file of several puzzles is a test of its own,
+        // named by the index and the title of the puzzle.
+        for (name, puzzle) in try!(read_puzzles(&input)) {
+            let options = options.clone();
+            tests.push(TestDescAndFn {
+                desc: TestDesc {
+                    name: DynTestName(name),
+                    ignore: false,
+                    should_panic: ShouldPanic::No,
+                },
+                testfn: DynTestFn(Box::new(move || {
+                    solve(&puzzle, derive_all, verify, &options).unwrap()
+                })),
+            });
+        }
+    }
+    for &category in &config.corpus {
+        for &entry in testdata::entries_of(category) {
+            let options = options.clone();
+            tests.push(TestDescAndFn {
+                desc: TestDesc {
+                    name: DynTestName(format!("corpus/{}/{}", category.name(), entry.name())),
+                    ignore: false,
+                    should_panic: ShouldPanic::No,
+                },
+                testfn: DynTestFn(Box::new(move || {
+                    solve_entry(entry, verify, &options).unwrap()
+                })),
+            });
+        }
+    }
+
+    test::test_main(&["".to_owned()], tests);
+
+    Ok(())
+}
+
+/// Reads the puzzles of the file with their names, which is the name of the
+/// file for a single puzzle.
+fn read_puzzles(file: &str) -> AppResult<Vec<(String, Puzzle)>> {
+    let buf = try!(io_util::read_file(file));
+    let set = match buf.parse::<Puzzle>() {
+        Ok(puzzle) => return Ok(vec![(file.to_owned(), puzzle)]),
+        Err(e) => {
+            try!(envelope::check_text(&envelope::PACK, &buf));
+            try!(buf.parse::<PuzzleSet>().map_err(|_| e))
+        }
+    };
+    let puzzles = set.into_entries()
+                     .into_iter()
+                     .enumerate()
+                     .map(|(i, entry)| {
+                         let name = match entry.metadata.get("title") {
+                             Some(title) => format!("{}#{} ({})", file, i + 1, title),
+                             None => format!("{}#{}", file, i + 1),
+                         };
+                         (name, entry.puzzle)
+                     })
+                     .collect();
+    Ok(puzzles)
+}
+
+// Solves the puzzle, never reading the solutions from a cache, as the test is
+// to run the solver.
+fn solve(puzzle: &Puzzle, derive_all: bool, verify: bool, options: &SolveOptions) -> AppResult<()> {
+    let solutions = if derive_all {
+        try!(Solutions::with_options(puzzle, options)).collect()
+    } else {
+        vec![try!(solver::solve_with_options(puzzle, options))]
+    };
+    if verify {
+        check_filled(&solutions);
+    }
+    let _ = test::black_box(solutions);
+
+    Ok(())
+}
+
+// Derives all solutions of the puzzle of the corpus, and panics unless their
+// number is the known one. One more solution than known is searched, so that
+// the puzzles without hints can be enumerated.
+fn solve_entry(entry: Entry, verify: bool, options: &SolveOptions) -> AppResult<()> {
+    let puzzle = entry.puzzle();
+    let options = SolveOptions {
+        max_solutions: Some(entry.num_solutions() + 1),
+        ..options.clone()
+    };
+    let solutions = try!(Solutions::with_options(&puzzle, &options)).collect::<Vec<_>>();
+    assert_eq!(entry.num_solutions(),
+               solutions.len() as u64,
+               "wrong number of solutions of {}",
+               entry.name());
+    if verify {
+        check_filled(&solutions);
+    }
+    let _ = test::black_box(solutions);
+    Ok(())
+}
+
+/// Panics if some edge of the solutions is left unknown, which makes the
+/// same solutions written differently.
+fn check_filled(solutions: &[Puzzle]) {
+    for solution in solutions {
+        assert!(!solution.has_unknown_edge(),
+                "solution has unknown edges:\n{}",
+                solution);
+    }
+}
@@ -0,0 +1,339 @@
+// Copyright (c) 2016 srither developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! The colors and the glyphs of the pretty printed puzzles.
+
+use std::io;
+use term::color::{self, Color};
+
+use error::{AppError, AppResult};
+use io_util;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Style {
+    pub fg: Color,
+    pub bg: Color,
+}
+
+/// The characters of the edges and the corners, repeated to the cell width
+/// for the horizontal edges.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Glyphs {
+    pub line_h: char,
+    pub line_v: char,
+    pub cross: char,
+    pub unknown_h: char,
+    pub unknown_v: char,
+    pub corner: char,
+    pub corner_empty: char,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Theme {
+    pub inside: Style,
+    pub outside: Style,
+    pub unknown: Style,
+    pub glyphs: Glyphs,
+}
+
+const ASCII: Glyphs = Glyphs {
+    line_h: '-',
+    line_v: '|',
+    cross: ' ',
+    unknown_h: '~',
+    unknown_v: '?',
+    corner: '+',
+    corner_empty: '.',
+};
+
+static NAMES: &'static [&'static str] = &["default", "print", "dark", "high-contrast"];
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme {
+            inside: style(color::BLACK, color::YELLOW),
+            outside: style(color::WHITE, color::BLACK),
+            unknown: style(color::BLACK, color::WHITE),
+            glyphs: ASCII,
+        }
+    }
+}
+
+impl Theme {
+    /// Returns the names of the built-in themes.
+    pub fn names() -> &'static [&'static str] {
+        NAMES
+    }
+
+    /// Returns the built-in theme of the name.
+    pub fn builtin(name: &str) -> Option<Theme> {
+        let theme = match name {
+            "default" => Theme::default(),
+            // For the light pages, with the inside in grey. The bright colors
+            // of this and the following themes need a terminal of 16 colors.
+            "print" => {
+                Theme {
+                    inside: style(color::BLACK, color::WHITE),
+                    outside: style(color::BLACK, color::BRIGHT_WHITE),
+                    unknown: style(color::BRIGHT_BLACK, color::BRIGHT_WHITE),
+                    glyphs: ASCII,
+                }
+            }
+            "dark" => {
+                Theme {
+                    inside: style(color::BRIGHT_WHITE, color::BLUE),
+                    outside: style(color::WHITE, color::BLACK),
+                    unknown: style(color::WHITE, color::BRIGHT_BLACK),
+                    glyphs: ASCII,
+                }
+            }
+            // Only black, white and bright yellow are used, so that the sides
+            // are told apart by the brightness alone.
+            "high-contrast" => {
+                Theme {
+                    inside: style(color::BLACK, color::BRIGHT_WHITE),
+                    outside: style(color::BRIGHT_WHITE, color::BLACK),
+                    unknown: style(color::BLACK, color::BRIGHT_YELLOW),
+                    glyphs: ASCII,
+                }
+            }
+            _ => return None,
+        };
+        Some(theme)
+    }
+
+    /// Returns the built-in theme of the name, or loads the theme in the file
+    /// of the path.
+    pub fn find(name_or_path: &str) -> AppResult<Theme> {
+        match Theme::builtin(name_or_path) {
+            Some(theme) => Ok(theme),
+            None => {
+                let buf = try!(io_util::read_file(name_or_path));
+                Theme::parse(&buf)
+            }
+        }
+    }
+
+    /// Parses the theme written in a subset of TOML: `key = "value"` lines
+    /// in the tables `[inside]`, `[outside]`, `[unknown]` and `[glyphs]`, and
+    /// the top-level `base`, the built-in theme to start from.
+    ///
+    /// ```toml
+    /// base = "dark"
+    ///
+    /// [inside]
+    /// fg = "black"
+    /// bg = "bright-cyan"
+    ///
+    /// [glyphs]
+    /// line-h = "="
+    /// ```
+    pub fn parse(s: &str) -> AppResult<Theme> {
+        let mut theme = Theme::default();
+        let mut table = String::new();
+        for (i, line) in s.lines().enumerate() {
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            let err = |msg: &str| invalid_theme(&format!("line {}: {}", i + 1, msg));
+
+            if line.starts_with('[') && line.ends_with(']') {
+                table = line[1..line.len() - 1].trim().to_owned();
+                if !["inside", "outside", "unknown", "glyphs"].contains(&&table[..]) {
+                    return Err(err(&format!("unknown table `{}`", table)));
+                }
+                continue;
+            }
+
+            let mut kv = line.splitn(2, '=');
+            let key = kv.next().unwrap().trim();
+            let value = match kv.next().and_then(|v| parse_string(v.trim())) {
+                Some(value) => value,
+                None => return Err(err("expected `key = \"value\"`")),
+            };
+            let ok = match (&table[..], key) {
+                ("", "base") => {
+                    // The base replaces all the keys before.
+                    match Theme::builtin(&value) {
+                        Some(base) => {
+                            theme = base;
+                            true
+                        }
+                        None => return Err(err(&format!("unknown theme `{}`", value))),
+                    }
+                }
+                ("inside", _) => set_style(&mut theme.inside, key, &value),
+                ("outside", _) => set_style(&mut theme.outside, key, &value),
+                ("unknown", _) => set_style(&mut theme.unknown, key, &value),
+                ("glyphs", _) => set_glyph(&mut theme.glyphs, key, &value),
+                _ => false,
+            };
+            if !ok {
+                return Err(err(&format!("invalid value `{}` of the key `{}`", value, key)));
+            }
+        }
+        Ok(theme)
+    }
+}
+
+fn style(fg: Color, bg: Color) -> Style {
+    Style { fg: fg, bg: bg }
+}
+
+fn set_style(style: &mut Style, key: &str, value: &str) -> bool {
+    let color = match parse_color(value) {
+        Some(color) => color,
+        None => return false,
+    };
+    match key {
+        "fg" => style.fg = color,
+        "bg" => style.bg = color,
+        _ => return false,
+    }
+    true
+}
+
+fn set_glyph(glyphs: &mut Glyphs, key: &str, value: &str) -> bool {
+    let mut chars = value.chars();
+    let c = match (chars.next(), chars.next()) {
+        (Some(c), None) => c,
+        _ => return false,
+    };
+    match key {
+        "line-h" => glyphs.line_h = c,
+        "line-v" => glyphs.line_v = c,
+        "cross" => glyphs.cross = c,
+        "unknown-h" => glyphs.unknown_h = c,
+        "unknown-v" => glyphs.unknown_v = c,
+        "corner" => glyphs.corner = c,
+        "corner-empty" => glyphs.corner_empty = c,
+        _ => return false,
+    }
+    true
+}
+
+// Parses the color of the name, e.g. `bright-red`, or of the number of the
+// 256 colors.
+fn parse_color(s: &str) -> Option<Color> {
+    let (bright, name) = if s.starts_with("bright-") {
+        (true, &s["bright-".len()..])
+    } else {
+        (false, s)
+    };
+    let color = match name {
+        "black" => color::BLACK,
+        "red" => color::RED,
+        "green" => color::GREEN,
+        "yellow" => color::YELLOW,
+        "blue" => color::BLUE,
+        "magenta" => color::MAGENTA,
+        "cyan" => color::CYAN,
+        "white" => color::WHITE,
+        _ if !bright => return s.parse::<u8>().ok().map(|n| n as Color),
+        _ => return None,
+    };
+    Some(if bright {
+        color + 8
+    } else {
+        color
+    })
+}
+
+fn strip_comment(line: &str) -> &str {
+    // `#` in the strings is not a comment.
+    let mut quoted = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '#' if !quoted => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+fn parse_string(s: &str) -> Option<String> {
+    if s.len() >= 2 && s.starts_with('"') && s.ends_with('"') {
+        Some(s[1..s.len() - 1].to_owned())
+    } else {
+        None
+    }
+}
+
+fn invalid_theme(msg: &str) -> AppError {
+    io::Error::new(io::ErrorKind::InvalidData, msg).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use term::color;
+    use super::{ASCII, Theme, style};
+
+    fn check_error(input: &str, msg: &str) {
+        assert_eq!(format!("IO error: {}", msg),
+                   Theme::parse(input).unwrap_err().to_string());
+    }
+
+    #[test]
+    fn builtin() {
+        for name in Theme::names() {
+            assert!(Theme::builtin(name).is_some());
+        }
+        assert_eq!(Some(Theme::default()), Theme::builtin("default"));
+        assert_eq!(None, Theme::builtin("light"));
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(Theme::default(), Theme::parse("").unwrap());
+        assert_eq!(Theme::builtin("dark").unwrap(),
+                   Theme::parse("# comment\n\nbase = \"dark\"  # the base\n").unwrap());
+
+        let theme = Theme::parse("[glyphs]\nline-h = \"=\"\ncorner = \"#\"\n\
+                                  [inside]\nfg = \"bright-cyan\"\nbg = \"208\"\n\
+                                  [ outside ]\nbg = \"magenta\"\n")
+                        .unwrap();
+        let mut expected = Theme::default();
+        expected.glyphs.line_h = '=';
+        expected.glyphs.corner = '#';
+        expected.inside = style(color::BRIGHT_CYAN, 208);
+        expected.outside.bg = color::MAGENTA;
+        assert_eq!(expected, theme);
+        assert_eq!(ASCII, Theme::default().glyphs);
+
+        // The later base replaces the former, and the tables after the base
+        // change it.
+        assert_eq!(Theme::builtin("print").unwrap(),
+                   Theme::parse("base = \"dark\"\nbase = \"print\"\n").unwrap());
+        let theme = Theme::parse("base = \"dark\"\n[inside]\nfg = \"red\"\n").unwrap();
+        assert_eq!(style(color::RED, color::BLUE), theme.inside);
+        assert_eq!(Theme::builtin("dark").unwrap().outside, theme.outside);
+    }
+
+    #[test]
+    fn errors() {
+        check_error("[colors]\n", "line 1: unknown table `colors`");
+        check_error("\n[]\n", "line 2: unknown table ``");
+        check_error("base = \"light\"", "line 1: unknown theme `light`");
+        check_error("base = dark", "line 1: expected `key = \"value\"`");
+        check_error("[inside]\nfg", "line 2: expected `key = \"value\"`");
+        check_error("[inside]\nfg = \"", "line 2: expected `key = \"value\"`");
+        check_error("fg = \"red\"", "line 1: invalid value `red` of the key `fg`");
+        check_error("[inside]\ncolor = \"red\"",
+                    "line 2: invalid value `red` of the key `color`");
+        check_error("[inside]\nfg = \"pink\"", "line 2: invalid value `pink` of the key `fg`");
+        check_error("[inside]\nfg = \"bright-8\"",
+                    "line 2: invalid value `bright-8` of the key `fg`");
+        check_error("[inside]\nfg = \"256\"", "line 2: invalid value `256` of the key `fg`");
+        check_error("[glyphs]\ncross = \"\"", "line 2: invalid value `` of the key `cross`");
+        check_error("[glyphs]\ncross = \"xx\"",
+                    "line 2: invalid value `xx` of the key `cross`");
+        check_error("[glyphs]\nfg = \"x\"", "line 2: invalid value `x` of the key `fg`");
+    }
+}
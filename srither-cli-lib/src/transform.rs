@@ -0,0 +1,64 @@
+// Copyright (c) 2016 srither developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Rotates and flips the puzzles.
+
+use std::io;
+use std::io::prelude::*;
+
+use srither_core::geom::{Geom, Move};
+use srither_core::puzzle::Puzzle;
+
+use error::AppResult;
+use io_util;
+use parse_arg::{TransformConfig, TransformOp};
+
+/// Transforms the puzzles of the input files, or of `input` if none, and
+/// writes them to `out`. Returns the transformed puzzles in order.
+pub fn run<I, O>(config: TransformConfig, input: &mut I, out: &mut O) -> AppResult<Vec<Puzzle>>
+    where I: Read,
+          O: Write
+{
+    let mut puzzles = vec![];
+    if config.input_files.is_empty() {
+        puzzles.push(try!(transform(&config, input, out)));
+    } else {
+        for file in &config.input_files {
+            let mut f = try!(io_util::open(file));
+            puzzles.push(try!(transform(&config, &mut f, out)));
+        }
+    }
+    Ok(puzzles)
+}
+
+fn transform<T, O>(config: &TransformConfig, input: &mut T, out: &mut O) -> AppResult<Puzzle>
+    where T: Read,
+          O: Write
+{
+    let buf = try!(io_util::read_to_string(input));
+    let mut puzzle = try!(buf.parse::<Puzzle>());
+
+    for &op in &config.ops {
+        puzzle = match op {
+            TransformOp::Rotate90 => puzzle.rotate90(),
+            TransformOp::FlipH => puzzle.flip_h(),
+            TransformOp::FlipV => puzzle.flip_v(),
+            TransformOp::Crop(origin, size) => {
+                if !puzzle.contains(origin + Move(size.0 - 1, size.1 - 1)) {
+                    let msg = format!("crop area is outside of the {}x{} puzzle",
+                                      puzzle.row(),
+                                      puzzle.column());
+                    return Err(io::Error::new(io::ErrorKind::InvalidInput, msg).into());
+                }
+                puzzle.crop(origin, size)
+            }
+        };
+    }
+    try!(write!(out, "{}", puzzle));
+    Ok(puzzle)
+}
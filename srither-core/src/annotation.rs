@@ -0,0 +1,158 @@
+// Copyright (c) 2016 srither-core developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Labels and colors attached to the cells and the edges of a puzzle.
+//!
+//! Annotations are only for display, e.g. to highlight the edges deduced by
+//! a theorem or the mistakes of a player. They are not written in the text
+//! format, and they are ignored by the solver and by the comparison of
+//! puzzles.
+
+use std::collections::BTreeMap;
+use std::collections::btree_map;
+
+use geom::Point;
+
+/// A cell or an edge to annotate.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Target {
+    /// The cell at the point.
+    Cell(Point),
+    /// The horizontal edge above the point.
+    EdgeH(Point),
+    /// The vertical edge on the left of the point.
+    EdgeV(Point),
+}
+
+/// A color to highlight a target.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Color {
+    /// Red.
+    Red,
+    /// Green.
+    Green,
+    /// Blue.
+    Blue,
+    /// Yellow.
+    Yellow,
+    /// Magenta.
+    Magenta,
+    /// Cyan.
+    Cyan,
+}
+
+/// An annotation of a target.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Annotation {
+    /// A free text, which may be empty.
+    pub label: String,
+    /// The color to highlight the target with.
+    pub color: Option<Color>,
+}
+
+/// Annotations of the targets of a puzzle, at most one for each target.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Annotations {
+    map: BTreeMap<Target, Annotation>,
+}
+
+impl Annotations {
+    /// Creates an empty set of annotations.
+    pub fn new() -> Annotations {
+        Annotations { map: BTreeMap::new() }
+    }
+
+    /// Returns the annotation of the target.
+    pub fn get(&self, target: Target) -> Option<&Annotation> {
+        self.map.get(&target)
+    }
+
+    /// Sets the annotation of the target, replacing the old one.
+    pub fn set(&mut self, target: Target, annotation: Annotation) {
+        let _ = self.map.insert(target, annotation);
+    }
+
+    /// Removes the annotation of the target.
+    pub fn remove(&mut self, target: Target) -> Option<Annotation> {
+        self.map.remove(&target)
+    }
+
+    /// Removes all annotations.
+    pub fn clear(&mut self) {
+        self.map.clear()
+    }
+
+    /// Returns true if there is no annotation.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns an iterator over the annotations, ordered by the targets.
+    pub fn iter(&self) -> Iter {
+        Iter { inner: self.map.iter() }
+    }
+}
+
+/// An iterator over the annotations.
+#[derive(Debug)]
+pub struct Iter<'a> {
+    inner: btree_map::Iter<'a, Target, Annotation>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (Target, &'a Annotation);
+
+    fn next(&mut self) -> Option<(Target, &'a Annotation)> {
+        self.inner.next().map(|(&t, a)| (t, a))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Annotation, Annotations, Color, Target};
+    use geom::Point;
+    use puzzle::Puzzle;
+
+    #[test]
+    fn ignored_by_comparison() {
+        let puzzle = "3_\n_1\n".parse::<Puzzle>().unwrap();
+        let mut annotated = puzzle.clone();
+        annotated.annotations_mut().set(Target::EdgeH(Point(0, 0)),
+                                        Annotation {
+                                            label: "theorem #3".to_owned(),
+                                            color: Some(Color::Red),
+                                        });
+        assert_eq!(puzzle, annotated);
+        assert_eq!(puzzle.to_string(), annotated.to_string());
+        assert!(puzzle.annotations().is_empty());
+        assert_eq!("theorem #3",
+                   annotated.annotations().get(Target::EdgeH(Point(0, 0))).unwrap().label);
+    }
+
+    #[test]
+    fn order() {
+        let mut annotations = Annotations::new();
+        let note = |label: &str| {
+            Annotation {
+                label: label.to_owned(),
+                color: None,
+            }
+        };
+        annotations.set(Target::EdgeV(Point(0, 0)), note("c"));
+        annotations.set(Target::Cell(Point(1, 0)), note("b"));
+        annotations.set(Target::Cell(Point(0, 1)), note("a"));
+        annotations.set(Target::Cell(Point(1, 0)), note("b2"));
+        let labels = annotations.iter().map(|(_, a)| &a.label[..]).collect::<Vec<_>>();
+        assert_eq!(vec!["a", "b2", "c"], labels);
+
+        assert_eq!("a", annotations.remove(Target::Cell(Point(0, 1))).unwrap().label);
+        assert_eq!(None, annotations.get(Target::Cell(Point(0, 1))));
+        annotations.clear();
+        assert!(annotations.is_empty());
+    }
+}
@@ -0,0 +1,93 @@
+// Copyright (c) 2016 srither-core developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Auxiliary clues of puzzle variants.
+//!
+//! A layer is a named list of optional numbers, such as the counts given
+//! outside of each row or column. srither does not interpret the layers;
+//! they are read by the solver steps of the variant which defines them (see
+//! `SolverStep` in srither-solver). The layers are compared by `==`, but
+//! they are not written in the text format and are dropped by the
+//! transformations of the puzzle.
+
+use std::collections::BTreeMap;
+use std::collections::btree_map;
+
+/// The layers of the auxiliary clues of a puzzle, by name.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ClueLayers {
+    map: BTreeMap<String, Vec<Option<u32>>>,
+}
+
+impl ClueLayers {
+    /// Creates an empty set of layers.
+    pub fn new() -> ClueLayers {
+        ClueLayers { map: BTreeMap::new() }
+    }
+
+    /// Returns the clues of the layer.
+    pub fn get(&self, name: &str) -> Option<&[Option<u32>]> {
+        self.map.get(name).map(|v| &v[..])
+    }
+
+    /// Sets the clues of the layer, replacing the old ones.
+    pub fn set(&mut self, name: &str, clues: Vec<Option<u32>>) {
+        let _ = self.map.insert(name.to_owned(), clues);
+    }
+
+    /// Removes the layer.
+    pub fn remove(&mut self, name: &str) -> Option<Vec<Option<u32>>> {
+        self.map.remove(name)
+    }
+
+    /// Returns true if there is no layer.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns an iterator over the layers, ordered by the names.
+    pub fn iter(&self) -> Iter {
+        Iter { inner: self.map.iter() }
+    }
+}
+
+/// An iterator over the layers.
+#[derive(Debug)]
+pub struct Iter<'a> {
+    inner: btree_map::Iter<'a, String, Vec<Option<u32>>>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (&'a str, &'a [Option<u32>]);
+
+    fn next(&mut self) -> Option<(&'a str, &'a [Option<u32>])> {
+        self.inner.next().map(|(k, v)| (&k[..], &v[..]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use puzzle::Puzzle;
+
+    #[test]
+    fn compared() {
+        let puzzle = "3_\n_1\n".parse::<Puzzle>().unwrap();
+        let mut layered = puzzle.clone();
+        layered.clue_layers_mut().set("rows", vec![Some(1), None]);
+        assert!(puzzle != layered);
+        assert_eq!(puzzle.to_string(), layered.to_string());
+        assert_eq!(layered, layered.hints_only());
+        assert_eq!(Some(&[Some(1), None][..]), layered.clue_layers().get("rows"));
+        let names = layered.clue_layers().iter().map(|(k, _)| k).collect::<Vec<_>>();
+        assert_eq!(vec!["rows"], names);
+
+        assert_eq!(Some(vec![Some(1), None]), layered.clue_layers_mut().remove("rows"));
+        assert!(layered.clue_layers().is_empty());
+        assert_eq!(puzzle, layered);
+    }
+}
@@ -0,0 +1,241 @@
+// Copyright (c) 2016 srither-core developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Compact single-line format, for sharing puzzles in URLs and chats.
+//!
+//! A puzzle is written as `COLUMNSxROWS-BODY`, e.g. `5x2-3a2c2b3`, of the
+//! characters safe in URLs only. The body gives the cells from the top-left
+//! one, row by row: `0`-`4` is a hint, `a`-`z` are 1 to 26 empty cells and
+//! `A`-`Z` are 27 to 52 empty cells. The cells after the end of the body are
+//! empty. Each puzzle has only one encoding, with the longest runs of the
+//! empty cells and without the empty cells at the end.
+
+use std::fmt;
+use std::error::Error;
+
+use geom::{Geom, Point, Size};
+use puzzle::Puzzle;
+
+/// An error type which is returned from parsing a compact string into
+/// puzzle.
+#[derive(Copy, Clone, Debug)]
+pub struct ParseCompactError {
+    kind: CompactErrorKind,
+}
+
+#[derive(Copy, Clone, Debug)]
+enum CompactErrorKind {
+    InvalidSize,
+    InvalidBody,
+    TooLong,
+}
+
+impl Error for ParseCompactError {
+    fn description(&self) -> &str {
+        match self.kind {
+            CompactErrorKind::InvalidSize => "invalid size found in compact string",
+            CompactErrorKind::InvalidBody => "invalid cell found in compact string",
+            CompactErrorKind::TooLong => "too many cells found in compact string",
+        }
+    }
+}
+
+impl fmt::Display for ParseCompactError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.description().fmt(f)
+    }
+}
+
+impl ParseCompactError {
+    fn invalid_size() -> ParseCompactError {
+        ParseCompactError { kind: CompactErrorKind::InvalidSize }
+    }
+    fn invalid_body() -> ParseCompactError {
+        ParseCompactError { kind: CompactErrorKind::InvalidBody }
+    }
+    fn too_long() -> ParseCompactError {
+        ParseCompactError { kind: CompactErrorKind::TooLong }
+    }
+}
+
+// The longest run of the empty cells written in a character.
+const MAX_RUN: usize = 52;
+
+// Returns the columns and the rows of the size part.
+fn parse_size(s: &str) -> Option<(i32, i32)> {
+    let mut parts = s.splitn(2, 'x').map(|n| n.parse::<i32>().ok());
+    match (parts.next(), parts.next()) {
+        (Some(Some(c)), Some(Some(r))) if c > 0 && r > 0 => Some((c, r)),
+        _ => None,
+    }
+}
+
+/// Returns true if the string is a compact string of a puzzle.
+pub fn detect(s: &str) -> bool {
+    let s = s.trim();
+    match s.find('-') {
+        Some(i) => {
+            parse_size(&s[..i]).is_some() &&
+            s[i + 1..].chars().all(|c| {
+                match c {
+                    '0'...'4' | 'a'...'z' | 'A'...'Z' => true,
+                    _ => false,
+                }
+            })
+        }
+        None => false,
+    }
+}
+
+/// Parses a compact string into a puzzle.
+pub fn parse(s: &str) -> Result<Puzzle, ParseCompactError> {
+    let s = s.trim();
+    let (size, body) = match s.find('-') {
+        Some(i) => (&s[..i], &s[i + 1..]),
+        None => return Err(ParseCompactError::invalid_size()),
+    };
+    let (column, row) = match parse_size(size) {
+        Some(size) => size,
+        None => return Err(ParseCompactError::invalid_size()),
+    };
+
+    let mut puzzle = Puzzle::new(Size(row, column));
+    let num_cells = (row * column) as usize;
+    let mut i = 0;
+    for c in body.chars() {
+        let (hint, skip) = match c {
+            '0'...'4' => (Some(c as u8 - b'0'), 1),
+            'a'...'z' => (None, (c as u8 - b'a') as usize + 1),
+            'A'...'Z' => (None, (c as u8 - b'A') as usize + 27),
+            _ => return Err(ParseCompactError::invalid_body()),
+        };
+        if i + skip > num_cells {
+            return Err(ParseCompactError::too_long());
+        }
+        puzzle.set_hint(point(i, column), hint);
+        i += skip;
+    }
+    Ok(puzzle)
+}
+
+fn point(i: usize, column: i32) -> Point {
+    Point(i as i32 / column, i as i32 % column)
+}
+
+/// A wrapper displaying the hints of a puzzle as a compact string.
+#[derive(Copy, Clone, Debug)]
+pub struct Compact<'a>(pub &'a Puzzle);
+
+impl<'a> fmt::Display for Compact<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Compact(puzzle) = *self;
+        try!(write!(f, "{}x{}-", puzzle.column(), puzzle.row()));
+
+        let num_cells = (puzzle.row() * puzzle.column()) as usize;
+        let mut empty = 0;
+        for i in 0..num_cells {
+            match puzzle.hint(point(i, puzzle.column())) {
+                Some(n) => {
+                    // The empty cells at the end are not written.
+                    while empty > 0 {
+                        let run = if empty > MAX_RUN {
+                            MAX_RUN
+                        } else {
+                            empty
+                        };
+                        try!(write!(f, "{}", run_char(run)));
+                        empty -= run;
+                    }
+                    try!(write!(f, "{}", n));
+                }
+                None => empty += 1,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn run_char(run: usize) -> char {
+    if run <= 26 {
+        (b'a' + run as u8 - 1) as char
+    } else {
+        (b'A' + run as u8 - 27) as char
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+    use std::iter;
+    use super::{Compact, ParseCompactError, detect, parse};
+    use geom::{Geom, Point, Size};
+    use puzzle::Puzzle;
+
+    fn check_error(input: &str, error: ParseCompactError) {
+        assert_eq!(parse(input).unwrap_err().description(), error.description());
+    }
+
+    #[test]
+    fn parse_string() {
+        let expected = "3_2__\n_2__3\n".parse::<Puzzle>().unwrap();
+        assert_eq!(expected, parse("5x2-3a2c2b3").unwrap());
+        assert_eq!(expected, parse(" 5x2-3a2c2b3\n").unwrap());
+
+        let puzzle = parse("30x3-Z0").unwrap();
+        assert_eq!(Size(3, 30), puzzle.size());
+        assert_eq!(Some(0), puzzle.hint(Point(1, 22)));
+        assert_eq!(Puzzle::new(Size(2, 2)), parse("2x2-").unwrap());
+    }
+
+    #[test]
+    fn round_trip() {
+        let inputs = ["3_2__\n_2__3",
+                      "0\n_\n_",
+                      "_\n_\n1",
+                      "__\n__",
+                      "1____\n_____\n_____\n_____\n_____\n____2",
+                      "______________________________\n______________________________\n\
+                       ______________________________\n_____________________________1"];
+        for input in &inputs {
+            let puzzle = input.parse::<Puzzle>().unwrap();
+            let s = Compact(&puzzle).to_string();
+            assert!(detect(&s));
+            assert_eq!(puzzle, parse(&s).unwrap());
+        }
+    }
+
+    #[test]
+    fn display() {
+        let puzzle = "3_2__\n_2__3".parse::<Puzzle>().unwrap();
+        assert_eq!("5x2-3a2c2b3", Compact(&puzzle).to_string());
+        let puzzle = "3_2__\n_____".parse::<Puzzle>().unwrap();
+        assert_eq!("5x2-3a2", Compact(&puzzle).to_string());
+        let empty = iter::repeat('_').take(59).collect::<String>();
+        let puzzle = format!("{}_", empty).parse::<Puzzle>().unwrap();
+        assert_eq!("60x1-", Compact(&puzzle).to_string());
+        let puzzle = format!("{}1", empty).parse::<Puzzle>().unwrap();
+        assert_eq!("60x1-Zg1", Compact(&puzzle).to_string());
+    }
+
+    #[test]
+    fn detection() {
+        assert!(detect("5x2-3a2c2b3\n"));
+        assert!(!detect("5x2t0:3a2c2b3"));
+        assert!(!detect("5x2-3a2c 2b3"));
+        assert!(!detect("3_2__\n_2__3\n"));
+    }
+
+    #[test]
+    fn parse_errors() {
+        check_error("5x2:3a2c2b3", ParseCompactError::invalid_size());
+        check_error("5x0-", ParseCompactError::invalid_size());
+        check_error("5-3", ParseCompactError::invalid_size());
+        check_error("5x2-3a2c5", ParseCompactError::invalid_body());
+        check_error("5x2-3a2c2b3a", ParseCompactError::too_long());
+    }
+}
@@ -0,0 +1,208 @@
+// Copyright (c) 2016 srither-core developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! CSV hint grid format.
+//!
+//! Each record is a row of the puzzle and each field is a cell, which is
+//! either empty (no hint) or a hint from 0 to 4, as in the native format.
+//! Records may be shorter than the widest one, as spreadsheets often drop
+//! trailing empty fields; missing fields are treated as empty cells.
+
+use std::fmt;
+use std::error::Error;
+
+use geom::{Geom, Point, Size};
+use puzzle::Puzzle;
+
+/// An error type which is returned from parsing a CSV string into puzzle.
+#[derive(Copy, Clone, Debug)]
+pub struct ParseCsvError {
+    kind: CsvErrorKind,
+}
+
+#[derive(Copy, Clone, Debug)]
+enum CsvErrorKind {
+    Empty,
+    InvalidHint,
+}
+
+impl Error for ParseCsvError {
+    fn description(&self) -> &str {
+        match self.kind {
+            CsvErrorKind::Empty => "cannot parse puzzle from empty CSV",
+            CsvErrorKind::InvalidHint => "invalid hint found in CSV",
+        }
+    }
+}
+
+impl fmt::Display for ParseCsvError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.description().fmt(f)
+    }
+}
+
+impl ParseCsvError {
+    fn empty() -> ParseCsvError {
+        ParseCsvError { kind: CsvErrorKind::Empty }
+    }
+    fn invalid_hint() -> ParseCsvError {
+        ParseCsvError { kind: CsvErrorKind::InvalidHint }
+    }
+}
+
+const DELIMITERS: &'static [char] = &[',', ';', '\t'];
+
+/// Returns true if the string has the delimiters or the quotes of CSV.
+pub fn detect(s: &str) -> bool {
+    s.contains(DELIMITERS) || s.contains('"')
+}
+
+/// Parses a CSV hint grid into a puzzle.
+///
+/// The delimiter is one of `,`, `;` or a tab, detected from the first record
+/// with more than one field, as a record of a single field has none.
+pub fn parse(s: &str) -> Result<Puzzle, ParseCsvError> {
+    let mut lines = s.lines().skip_while(|l| l.trim().is_empty()).collect::<Vec<_>>();
+    while lines.last().map(|l| l.trim().is_empty()) == Some(true) {
+        let _ = lines.pop();
+    }
+    if lines.is_empty() {
+        return Err(ParseCsvError::empty());
+    }
+
+    let delim = lines.iter()
+                     .filter_map(|l| DELIMITERS.iter().cloned().find(|&d| l.contains(d)))
+                     .next()
+                     .unwrap_or(',');
+
+    let mut rows = vec![];
+    for line in &lines {
+        let mut row = vec![];
+        for field in line.split(delim) {
+            row.push(try!(parse_field(field)));
+        }
+        rows.push(row);
+    }
+
+    let num_rows = rows.len();
+    let num_cols = rows.iter().map(|r| r.len()).max().unwrap();
+    let mut puzzle = Puzzle::new(Size(num_rows as i32, num_cols as i32));
+    for (r, row) in rows.into_iter().enumerate() {
+        for (c, hint) in row.into_iter().enumerate() {
+            puzzle.set_hint(Point(r as i32, c as i32), hint);
+        }
+    }
+    Ok(puzzle)
+}
+
+fn parse_field(field: &str) -> Result<Option<u8>, ParseCsvError> {
+    match field.trim().trim_matches('"').trim() {
+        "" => Ok(None),
+        "0" => Ok(Some(0)),
+        "1" => Ok(Some(1)),
+        "2" => Ok(Some(2)),
+        "3" => Ok(Some(3)),
+        "4" => Ok(Some(4)),
+        _ => Err(ParseCsvError::invalid_hint()),
+    }
+}
+
+/// A wrapper displaying the hints of a puzzle as a CSV grid.
+#[derive(Copy, Clone, Debug)]
+pub struct Csv<'a>(pub &'a Puzzle);
+
+impl<'a> fmt::Display for Csv<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Csv(puzzle) = *self;
+        for r in 0..puzzle.row() {
+            for c in 0..puzzle.column() {
+                if c > 0 {
+                    try!(write!(f, ","));
+                }
+                match puzzle.hint(Point(r, c)) {
+                    Some(n) => try!(write!(f, "{}", n)),
+                    // A blank single-column record would be an empty line,
+                    // which is skipped when it is the last one.
+                    None if puzzle.column() == 1 => try!(write!(f, "\"\"")),
+                    None => {}
+                }
+            }
+            try!(writeln!(f, ""));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+    use super::{Csv, ParseCsvError, parse};
+    use geom::{Geom, Point, Size};
+    use puzzle::Puzzle;
+
+    fn check_error(input: &str, error: ParseCsvError) {
+        assert_eq!(parse(input).unwrap_err().description(), error.description());
+    }
+
+    #[test]
+    fn parse_grid() {
+        let puzzle = parse("1,2,3,\n,,,0\n3,,,\n").unwrap();
+        let expected = "123_\n___0\n3___".parse::<Puzzle>().unwrap();
+        assert_eq!(expected, puzzle);
+    }
+
+    #[test]
+    fn infer_size() {
+        let puzzle = parse("\n1\n,,2\n\n").unwrap();
+        assert_eq!(Size(2, 3), puzzle.size());
+        assert_eq!(Some(1), puzzle.hint(Point(0, 0)));
+        assert_eq!(None, puzzle.hint(Point(0, 2)));
+        assert_eq!(Some(2), puzzle.hint(Point(1, 2)));
+
+        let puzzle = parse(",,\n,,\n,,\n").unwrap();
+        assert_eq!(Size(3, 3), puzzle.size());
+    }
+
+    #[test]
+    fn delimiters() {
+        let expected = "1_2\n_3_".parse::<Puzzle>().unwrap();
+        assert_eq!(expected, parse("1;;2\r\n;3;\r\n").unwrap());
+        assert_eq!(expected, parse("1\t\t2\n\t3\t\n").unwrap());
+        assert_eq!(expected, parse("\"1\", ,\"2\"\n,\"3\",\"\"\n").unwrap());
+
+        // The first record has a single field, so the delimiter is of the
+        // next one.
+        let expected = "1__\n_2_\n__3".parse::<Puzzle>().unwrap();
+        assert_eq!(expected, parse("1\n;2\n;;3\n").unwrap());
+        assert_eq!(expected, parse("1\n\t2\n\t\t3\n").unwrap());
+    }
+
+    #[test]
+    fn round_trip() {
+        let inputs = ["123___\n______\n3_____", "0\n_\n_", "_\n_\n1", "__\n__", "4"];
+        for input in &inputs {
+            let puzzle = input.parse::<Puzzle>().unwrap();
+            let csv = Csv(&puzzle).to_string();
+            assert_eq!(puzzle, parse(&csv).unwrap());
+        }
+    }
+
+    #[test]
+    fn display() {
+        let puzzle = "12_\n__3".parse::<Puzzle>().unwrap();
+        assert_eq!("1,2,\n,,3\n", Csv(&puzzle).to_string());
+    }
+
+    #[test]
+    fn parse_errors() {
+        check_error("", ParseCsvError::empty());
+        check_error(" \n\n", ParseCsvError::empty());
+        check_error("1,5", ParseCsvError::invalid_hint());
+        check_error("1,x\n", ParseCsvError::invalid_hint());
+    }
+}
@@ -0,0 +1,391 @@
+// Copyright (c) 2016 srither-core developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Problem files of janko.at.
+//!
+//! A file has the keys, e.g. `size 10` or `rows 5` and `cols 8`, and the
+//! sections `problem` and `solution` of the rows of the cells separated by
+//! spaces. Both the older style of the site, with a keyword per line ending at
+//! `end`, and the newer one, with `[problem]` headers and `key = value`
+//! lines, are read. The cells of the problem are `0`-`4` for a hint and `-` or
+//! `.` for none, and the cells of the solution are `x` inside the loop and `-`
+//! outside it, as the shaded cells of the other puzzles of the site.
+//!
+//! ```text
+//! begin
+//! puzzle slitherlink
+//! size 2
+//! problem
+//! 3 -
+//! - -
+//! solution
+//! x -
+//! - -
+//! end
+//! ```
+
+use std::fmt;
+use std::error::Error;
+
+use geom::{Geom, Point, Size};
+use puzzle::{Edge, Puzzle};
+
+/// An error type which is returned from parsing a janko.at problem file into
+/// puzzle.
+#[derive(Copy, Clone, Debug)]
+pub struct ParseJankoError {
+    kind: JankoErrorKind,
+}
+
+#[derive(Copy, Clone, Debug)]
+enum JankoErrorKind {
+    NotSlitherlink,
+    NoProblem,
+    InvalidSize,
+    InvalidHint,
+    InvalidSolution,
+    SizeMismatch,
+}
+
+impl Error for ParseJankoError {
+    fn description(&self) -> &str {
+        match self.kind {
+            JankoErrorKind::NotSlitherlink => "problem file is not of a Slitherlink puzzle",
+            JankoErrorKind::NoProblem => "no problem found in problem file",
+            JankoErrorKind::InvalidSize => "invalid size found in problem file",
+            JankoErrorKind::InvalidHint => "invalid hint found in problem file",
+            JankoErrorKind::InvalidSolution => "invalid cell found in solution",
+            JankoErrorKind::SizeMismatch => "rows of different size found in problem file",
+        }
+    }
+}
+
+impl fmt::Display for ParseJankoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.description().fmt(f)
+    }
+}
+
+impl ParseJankoError {
+    fn not_slitherlink() -> ParseJankoError {
+        ParseJankoError { kind: JankoErrorKind::NotSlitherlink }
+    }
+    fn no_problem() -> ParseJankoError {
+        ParseJankoError { kind: JankoErrorKind::NoProblem }
+    }
+    fn invalid_size() -> ParseJankoError {
+        ParseJankoError { kind: JankoErrorKind::InvalidSize }
+    }
+    fn invalid_hint() -> ParseJankoError {
+        ParseJankoError { kind: JankoErrorKind::InvalidHint }
+    }
+    fn invalid_solution() -> ParseJankoError {
+        ParseJankoError { kind: JankoErrorKind::InvalidSolution }
+    }
+    fn size_mismatch() -> ParseJankoError {
+        ParseJankoError { kind: JankoErrorKind::SizeMismatch }
+    }
+}
+
+// Returns the name of the section if the line starts one.
+fn section(line: &str) -> Option<&str> {
+    if line.starts_with('[') && line.ends_with(']') {
+        return Some(line[1..line.len() - 1].trim());
+    }
+    match line {
+        "begin" | "end" | "problem" | "solution" => Some(line),
+        _ => None,
+    }
+}
+
+/// Returns true if the string has the problem section of a problem file.
+pub fn detect(s: &str) -> bool {
+    s.lines().any(|l| section(l.trim()) == Some("problem"))
+}
+
+/// Parses a janko.at problem file into a puzzle.
+///
+/// The solution, if any, is parsed into the edges of the puzzle, all of which
+/// are fixed. The keys other than the size and the kind of the puzzle are
+/// ignored.
+pub fn parse(s: &str) -> Result<Puzzle, ParseJankoError> {
+    let mut current = "";
+    let mut size = (None, None);
+    let mut problem = vec![];
+    let mut solution = vec![];
+    for line in s.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(name) = section(line) {
+            current = name;
+            continue;
+        }
+
+        let cells = line.split_whitespace().collect::<Vec<_>>();
+        // A key ends the rows of the cells in the older style, e.g. `unit`.
+        let is_key = cells[0].len() > 1 && cells[0].chars().all(char::is_alphabetic);
+        match current {
+            "problem" if !is_key => {
+                problem.push(cells);
+                continue;
+            }
+            "solution" if !is_key => {
+                solution.push(cells);
+                continue;
+            }
+            _ => current = "",
+        }
+
+        let (key, value) = match line.find('=') {
+            Some(i) => (line[..i].trim(), line[i + 1..].trim()),
+            None => (cells[0], line[cells[0].len()..].trim()),
+        };
+        let num = || {
+            match value.parse::<i32>() {
+                Ok(n) if n > 0 => Ok(Some(n)),
+                _ => Err(ParseJankoError::invalid_size()),
+            }
+        };
+        match key {
+            "puzzle" => {
+                if value.to_lowercase() != "slitherlink" {
+                    return Err(ParseJankoError::not_slitherlink());
+                }
+            }
+            "size" => {
+                let n = try!(num());
+                size = (n, n);
+            }
+            "rows" => size.0 = try!(num()),
+            "cols" | "columns" => size.1 = try!(num()),
+            _ => {}
+        }
+    }
+
+    if problem.is_empty() {
+        return Err(ParseJankoError::no_problem());
+    }
+    let row = size.0.unwrap_or(problem.len() as i32);
+    let column = size.1.unwrap_or(problem[0].len() as i32);
+    let fits = |rows: &[Vec<&str>]| {
+        rows.len() as i32 == row && rows.iter().all(|r| r.len() as i32 == column)
+    };
+    if !fits(&problem) || !(solution.is_empty() || fits(&solution)) {
+        return Err(ParseJankoError::size_mismatch());
+    }
+
+    let mut puzzle = Puzzle::new(Size(row, column));
+    for (r, cells) in problem.iter().enumerate() {
+        for (c, cell) in cells.iter().enumerate() {
+            let hint = match *cell {
+                "-" | "." => None,
+                "0" | "1" | "2" | "3" | "4" => Some(cell.as_bytes()[0] - b'0'),
+                _ => return Err(ParseJankoError::invalid_hint()),
+            };
+            puzzle.set_hint(Point(r as i32, c as i32), hint);
+        }
+    }
+    if solution.is_empty() {
+        return Ok(puzzle);
+    }
+
+    let mut inside = vec![];
+    for cells in &solution {
+        for cell in cells {
+            match *cell {
+                "x" => inside.push(true),
+                "-" => inside.push(false),
+                _ => return Err(ParseJankoError::invalid_solution()),
+            }
+        }
+    }
+    // The cells out of the board are outside.
+    let is_inside = |p: Point| puzzle.contains(p) && inside[(p.0 * column + p.1) as usize];
+    let edge = |p: Point, q: Point| {
+        Some(if is_inside(p) != is_inside(q) {
+            Edge::Line
+        } else {
+            Edge::Cross
+        })
+    };
+    let mut solved = puzzle.clone();
+    for r in 0..(row + 1) {
+        for c in 0..(column + 1) {
+            let p = Point(r, c);
+            if c < column {
+                solved.set_edge_h(p, edge(Point(r - 1, c), p));
+            }
+            if r < row {
+                solved.set_edge_v(p, edge(Point(r, c - 1), p));
+            }
+        }
+    }
+    Ok(solved)
+}
+
+// Returns the cells inside the loop from the lines of the puzzle, if they
+// form a solution; the unknown edges are taken as crosses.
+fn solution(puzzle: &Puzzle) -> Option<Vec<bool>> {
+    let is_line = |e: Option<Edge>| e == Some(Edge::Line);
+    let mut inside = vec![];
+    let mut has_line = false;
+    for r in 0..puzzle.row() {
+        let mut side = false;
+        for c in 0..puzzle.column() {
+            let p = Point(r, c);
+            if is_line(puzzle.edge_v(p)) {
+                side = !side;
+                has_line = true;
+            }
+            inside.push(side);
+        }
+        if side != is_line(puzzle.edge_v(Point(r, puzzle.column()))) {
+            return None;
+        }
+    }
+    let at = |p: Point| puzzle.contains(p) && inside[(p.0 * puzzle.column() + p.1) as usize];
+    for r in 0..(puzzle.row() + 1) {
+        for c in 0..puzzle.column() {
+            let p = Point(r, c);
+            if is_line(puzzle.edge_h(p)) != (at(Point(r - 1, c)) != at(p)) {
+                return None;
+            }
+        }
+    }
+    if has_line {
+        Some(inside)
+    } else {
+        None
+    }
+}
+
+/// A wrapper displaying a puzzle as a janko.at problem file in the older
+/// style, with its solution if the lines of the puzzle form one.
+#[derive(Copy, Clone, Debug)]
+pub struct Janko<'a>(pub &'a Puzzle);
+
+impl<'a> fmt::Display for Janko<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Janko(puzzle) = *self;
+        try!(writeln!(f, "begin"));
+        try!(writeln!(f, "puzzle slitherlink"));
+        if puzzle.row() == puzzle.column() {
+            try!(writeln!(f, "size {}", puzzle.row()));
+        } else {
+            try!(writeln!(f, "rows {}", puzzle.row()));
+            try!(writeln!(f, "cols {}", puzzle.column()));
+        }
+
+        try!(writeln!(f, "problem"));
+        for r in 0..puzzle.row() {
+            let cells = (0..puzzle.column())
+                            .map(|c| {
+                                match puzzle.hint(Point(r, c)) {
+                                    Some(n) => n.to_string(),
+                                    None => "-".to_owned(),
+                                }
+                            })
+                            .collect::<Vec<_>>();
+            try!(writeln!(f, "{}", cells.join(" ")));
+        }
+
+        if let Some(inside) = solution(puzzle) {
+            try!(writeln!(f, "solution"));
+            for cells in inside.chunks(puzzle.column() as usize) {
+                let cells = cells.iter()
+                                 .map(|&i| if i { "x" } else { "-" })
+                                 .collect::<Vec<_>>();
+                try!(writeln!(f, "{}", cells.join(" ")));
+            }
+        }
+        writeln!(f, "end")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+    use super::{Janko, ParseJankoError, detect, parse};
+    use geom::{Geom, Point, Size};
+    use puzzle::Puzzle;
+
+    fn check_error(input: &str, error: ParseJankoError) {
+        assert_eq!(parse(input).unwrap_err().description(), error.description());
+    }
+
+    #[test]
+    fn parse_file() {
+        let expected = "3_2__\n_2__3\n".parse::<Puzzle>().unwrap();
+        let old = "begin\ntitle Slitherlink 1\npuzzle slitherlink\nrows 2\ncols 5\nproblem\n3 - \
+                   2 - -\n- 2 - - 3\nunit 20\nend\n";
+        assert_eq!(expected, parse(old).unwrap());
+        let new = "[setup]\npuzzle = Slitherlink\nrows = 2\ncols = 5\n\n[problem]\n3 - 2 . \
+                   -\n- 2 - - 3\n";
+        assert_eq!(expected, parse(new).unwrap());
+
+        let puzzle = parse("problem\n- - -\n- 1 -\n").unwrap();
+        assert_eq!(Size(2, 3), puzzle.size());
+        assert_eq!(Some(1), puzzle.hint(Point(1, 1)));
+    }
+
+    #[test]
+    fn solution() {
+        let expected = "+x+-+\nx2|3|\n+-+x+\n|3x |\n+-+-+\n".parse::<Puzzle>().unwrap();
+        let puzzle = parse("size 2\nproblem\n2 3\n3 -\nsolution\n- x\nx x\n").unwrap();
+        assert_eq!(expected, puzzle);
+
+        let s = Janko(&puzzle).to_string();
+        assert!(s.contains("solution\n- x\nx x\n"));
+        assert_eq!(puzzle, parse(&s).unwrap());
+
+        // The hints alone, or the lines not forming a loop, are not solutions.
+        let hints = puzzle.hints_only();
+        assert!(!Janko(&hints).to_string().contains("solution"));
+        let mut broken = puzzle.clone();
+        broken.set_edge_h(Point(2, 0), None);
+        assert!(!Janko(&broken).to_string().contains("solution"));
+    }
+
+    #[test]
+    fn round_trip() {
+        let inputs = ["3_2__\n_2__3", "0\n_\n_", "__\n__", "1____\n_____\n_____\n_____\n____2"];
+        for input in &inputs {
+            let puzzle = input.parse::<Puzzle>().unwrap();
+            assert_eq!(puzzle, parse(&Janko(&puzzle).to_string()).unwrap());
+        }
+    }
+
+    #[test]
+    fn display() {
+        let puzzle = "3_2__\n_2__3".parse::<Puzzle>().unwrap();
+        assert_eq!("begin\npuzzle slitherlink\nrows 2\ncols 5\nproblem\n3 - 2 - -\n- 2 - - \
+                    3\nend\n",
+                   Janko(&puzzle).to_string());
+    }
+
+    #[test]
+    fn detection() {
+        assert!(detect("begin\nproblem\n3 -\n"));
+        assert!(detect("[problem]\n3 -\n"));
+        assert!(!detect("3_2__\n_2__3\n"));
+    }
+
+    #[test]
+    fn parse_errors() {
+        check_error("puzzle sudoku\nproblem\n3 -\n", ParseJankoError::not_slitherlink());
+        check_error("size 2\n", ParseJankoError::no_problem());
+        check_error("size 0\nproblem\n3\n", ParseJankoError::invalid_size());
+        check_error("problem\n3 5\n", ParseJankoError::invalid_hint());
+        check_error("problem\n3 -\nsolution\nx o\n", ParseJankoError::invalid_solution());
+        check_error("size 2\nproblem\n3 -\n", ParseJankoError::size_mismatch());
+        check_error("problem\n3 -\n- - -\n", ParseJankoError::size_mismatch());
+        check_error("problem\n3 -\nsolution\nx\n", ParseJankoError::size_mismatch());
+    }
+}
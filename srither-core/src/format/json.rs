@@ -0,0 +1,358 @@
+// Copyright (c) 2016 srither-core developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! JSON hint grid format.
+//!
+//! A puzzle is an object whose `hints` is an array of the rows, each of them
+//! an array of numbers or `null` for the cells without hints:
+//! `{"rows": 2, "columns": 3, "hints": [[1, 2, null], [null, null, 3]]}`.
+//! `rows` and `columns` may be omitted, and must match `hints` if given. The
+//! other members are ignored.
+
+use std::fmt;
+use std::error::Error;
+use std::iter::Peekable;
+use std::str::Chars;
+
+use geom::{Geom, Point, Size};
+use puzzle::Puzzle;
+
+/// An error type which is returned from parsing a JSON string into puzzle.
+#[derive(Copy, Clone, Debug)]
+pub struct ParseJsonError {
+    kind: JsonErrorKind,
+}
+
+#[derive(Copy, Clone, Debug)]
+enum JsonErrorKind {
+    Syntax,
+    NoHints,
+    Empty,
+    LengthMismatch,
+    SizeMismatch,
+    InvalidHint,
+}
+
+impl Error for ParseJsonError {
+    fn description(&self) -> &str {
+        match self.kind {
+            JsonErrorKind::Syntax => "invalid JSON",
+            JsonErrorKind::NoHints => "JSON has no array of hints",
+            JsonErrorKind::Empty => "cannot parse puzzle from empty hints",
+            JsonErrorKind::LengthMismatch => "the length of rows are not same",
+            JsonErrorKind::SizeMismatch => "the size does not match the hints",
+            JsonErrorKind::InvalidHint => "invalid hint found in JSON",
+        }
+    }
+}
+
+impl fmt::Display for ParseJsonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.description().fmt(f)
+    }
+}
+
+impl ParseJsonError {
+    fn syntax() -> ParseJsonError {
+        ParseJsonError { kind: JsonErrorKind::Syntax }
+    }
+    fn no_hints() -> ParseJsonError {
+        ParseJsonError { kind: JsonErrorKind::NoHints }
+    }
+    fn empty() -> ParseJsonError {
+        ParseJsonError { kind: JsonErrorKind::Empty }
+    }
+    fn length_mismatch() -> ParseJsonError {
+        ParseJsonError { kind: JsonErrorKind::LengthMismatch }
+    }
+    fn size_mismatch() -> ParseJsonError {
+        ParseJsonError { kind: JsonErrorKind::SizeMismatch }
+    }
+    fn invalid_hint() -> ParseJsonError {
+        ParseJsonError { kind: JsonErrorKind::InvalidHint }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Value {
+    Null,
+    Bool,
+    Number(f64),
+    String,
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    fn get(&self, key: &str) -> Option<&Value> {
+        match *self {
+            Value::Object(ref members) => members.iter().find(|m| m.0 == key).map(|m| &m.1),
+            _ => None,
+        }
+    }
+}
+
+/// Returns true if the string is a JSON object.
+pub fn detect(s: &str) -> bool {
+    s.trim_left().starts_with('{')
+}
+
+/// Parses a JSON hint grid into a puzzle.
+pub fn parse(s: &str) -> Result<Puzzle, ParseJsonError> {
+    let mut chars = s.chars().peekable();
+    let value = try!(parse_value(&mut chars));
+    skip_ws(&mut chars);
+    if chars.peek().is_some() {
+        return Err(ParseJsonError::syntax());
+    }
+
+    let rows = match value.get("hints") {
+        Some(&Value::Array(ref rows)) => rows,
+        _ => return Err(ParseJsonError::no_hints()),
+    };
+    if rows.is_empty() {
+        return Err(ParseJsonError::empty());
+    }
+    let mut hints = vec![];
+    for row in rows {
+        let mut hint_row = vec![];
+        match *row {
+            Value::Array(ref cells) => {
+                for cell in cells {
+                    hint_row.push(try!(parse_hint(cell)));
+                }
+            }
+            _ => return Err(ParseJsonError::invalid_hint()),
+        }
+        hints.push(hint_row);
+    }
+
+    let column = hints[0].len();
+    if column == 0 {
+        return Err(ParseJsonError::empty());
+    }
+    if hints.iter().any(|r| r.len() != column) {
+        return Err(ParseJsonError::length_mismatch());
+    }
+    let size = Size(hints.len() as i32, column as i32);
+    for &(key, n) in &[("rows", size.0), ("columns", size.1)] {
+        match value.get(key) {
+            None => {}
+            Some(&Value::Number(m)) if m == n as f64 => {}
+            Some(_) => return Err(ParseJsonError::size_mismatch()),
+        }
+    }
+
+    let mut puzzle = Puzzle::new(size);
+    for (r, row) in hints.into_iter().enumerate() {
+        for (c, hint) in row.into_iter().enumerate() {
+            puzzle.set_hint(Point(r as i32, c as i32), hint);
+        }
+    }
+    Ok(puzzle)
+}
+
+fn parse_hint(cell: &Value) -> Result<Option<u8>, ParseJsonError> {
+    match *cell {
+        Value::Null => Ok(None),
+        Value::Number(n) if n >= 0.0 && n <= 4.0 && n.fract() == 0.0 => Ok(Some(n as u8)),
+        _ => Err(ParseJsonError::invalid_hint()),
+    }
+}
+
+fn skip_ws(chars: &mut Peekable<Chars>) {
+    while chars.peek().map_or(false, |c| c.is_whitespace()) {
+        let _ = chars.next();
+    }
+}
+
+fn expect(chars: &mut Peekable<Chars>, s: &str) -> Result<(), ParseJsonError> {
+    for c in s.chars() {
+        if chars.next() != Some(c) {
+            return Err(ParseJsonError::syntax());
+        }
+    }
+    Ok(())
+}
+
+fn parse_value(chars: &mut Peekable<Chars>) -> Result<Value, ParseJsonError> {
+    skip_ws(chars);
+    let c = match chars.peek() {
+        Some(&c) => c,
+        None => return Err(ParseJsonError::syntax()),
+    };
+    match c {
+        'n' => expect(chars, "null").map(|_| Value::Null),
+        't' => expect(chars, "true").map(|_| Value::Bool),
+        'f' => expect(chars, "false").map(|_| Value::Bool),
+        '"' => parse_string(chars).map(|_| Value::String),
+        '[' => {
+            let _ = chars.next();
+            let mut elems = vec![];
+            try!(parse_seq(chars, ']', |chars| {
+                elems.push(try!(parse_value(chars)));
+                Ok(())
+            }));
+            Ok(Value::Array(elems))
+        }
+        '{' => {
+            let _ = chars.next();
+            let mut members = vec![];
+            try!(parse_seq(chars, '}', |chars| {
+                skip_ws(chars);
+                let key = try!(parse_string(chars));
+                skip_ws(chars);
+                try!(expect(chars, ":"));
+                members.push((key, try!(parse_value(chars))));
+                Ok(())
+            }));
+            Ok(Value::Object(members))
+        }
+        '-' | '0'...'9' => {
+            let mut num = String::new();
+            while let Some(&c) = chars.peek() {
+                match c {
+                    '-' | '+' | '.' | 'e' | 'E' | '0'...'9' => num.push(c),
+                    _ => break,
+                }
+                let _ = chars.next();
+            }
+            num.parse().map(Value::Number).map_err(|_| ParseJsonError::syntax())
+        }
+        _ => Err(ParseJsonError::syntax()),
+    }
+}
+
+// Parses the elements separated by commas until the closing character.
+fn parse_seq<F>(chars: &mut Peekable<Chars>, close: char, mut f: F) -> Result<(), ParseJsonError>
+    where F: FnMut(&mut Peekable<Chars>) -> Result<(), ParseJsonError>
+{
+    skip_ws(chars);
+    if chars.peek() == Some(&close) {
+        let _ = chars.next();
+        return Ok(());
+    }
+    loop {
+        try!(f(chars));
+        skip_ws(chars);
+        match chars.next() {
+            Some(',') => {}
+            Some(c) if c == close => return Ok(()),
+            _ => return Err(ParseJsonError::syntax()),
+        }
+    }
+}
+
+fn parse_string(chars: &mut Peekable<Chars>) -> Result<String, ParseJsonError> {
+    try!(expect(chars, "\""));
+    let mut s = String::new();
+    loop {
+        match chars.next() {
+            Some('"') => return Ok(s),
+            Some('\\') => {
+                match chars.next() {
+                    Some('n') => s.push('\n'),
+                    Some('t') => s.push('\t'),
+                    Some('r') => s.push('\r'),
+                    Some('b') => s.push('\u{8}'),
+                    Some('f') => s.push('\u{c}'),
+                    Some('u') => {
+                        let hex = chars.by_ref().take(4).collect::<String>();
+                        let c = u32::from_str_radix(&hex, 16).ok().and_then(::std::char::from_u32);
+                        s.push(c.unwrap_or('\u{fffd}'));
+                    }
+                    Some(c) => s.push(c),
+                    None => return Err(ParseJsonError::syntax()),
+                }
+            }
+            Some(c) => s.push(c),
+            None => return Err(ParseJsonError::syntax()),
+        }
+    }
+}
+
+/// A wrapper displaying the hints of a puzzle as a JSON object.
+#[derive(Copy, Clone, Debug)]
+pub struct Json<'a>(pub &'a Puzzle);
+
+impl<'a> fmt::Display for Json<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Json(puzzle) = *self;
+        try!(write!(f,
+                    "{{\"rows\":{},\"columns\":{},\"hints\":[",
+                    puzzle.row(),
+                    puzzle.column()));
+        for r in 0..puzzle.row() {
+            if r > 0 {
+                try!(write!(f, ","));
+            }
+            try!(write!(f, "["));
+            for c in 0..puzzle.column() {
+                if c > 0 {
+                    try!(write!(f, ","));
+                }
+                match puzzle.hint(Point(r, c)) {
+                    Some(n) => try!(write!(f, "{}", n)),
+                    None => try!(write!(f, "null")),
+                }
+            }
+            try!(write!(f, "]"));
+        }
+        writeln!(f, "]}}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+    use super::{Json, ParseJsonError, parse};
+    use puzzle::Puzzle;
+
+    fn check_error(input: &str, error: ParseJsonError) {
+        assert_eq!(parse(input).unwrap_err().description(), error.description());
+    }
+
+    #[test]
+    fn parse_grid() {
+        let expected = "12_\n__3".parse::<Puzzle>().unwrap();
+        let input = r#" {"title": "a \"b\" c", "rows": 2, "columns": 3,
+                         "hints": [[1, 2, null], [null, null, 3.0]], "tags": [true, {}]} "#;
+        assert_eq!(expected, parse(input).unwrap());
+        assert_eq!(expected, parse(r#"{"hints":[[1,2,null],[null,null,3]]}"#).unwrap());
+    }
+
+    #[test]
+    fn round_trip() {
+        let inputs = ["123___\n______\n3_____", "0\n_\n_", "__\n__"];
+        for input in &inputs {
+            let puzzle = input.parse::<Puzzle>().unwrap();
+            let json = Json(&puzzle).to_string();
+            assert_eq!(puzzle, parse(&json).unwrap());
+        }
+    }
+
+    #[test]
+    fn display() {
+        let puzzle = "12_\n__3".parse::<Puzzle>().unwrap();
+        assert_eq!("{\"rows\":2,\"columns\":3,\"hints\":[[1,2,null],[null,null,3]]}\n",
+                   Json(&puzzle).to_string());
+    }
+
+    #[test]
+    fn parse_errors() {
+        check_error("", ParseJsonError::syntax());
+        check_error("{\"hints\": [[1]]} x", ParseJsonError::syntax());
+        check_error("{\"hints\": [[1,]]}", ParseJsonError::syntax());
+        check_error("{\"rows\": 1}", ParseJsonError::no_hints());
+        check_error("{\"hints\": []}", ParseJsonError::empty());
+        check_error("{\"hints\": [[1], [1, 2]]}", ParseJsonError::length_mismatch());
+        check_error("{\"rows\": 2, \"hints\": [[1]]}", ParseJsonError::size_mismatch());
+        check_error("{\"hints\": [[5]]}", ParseJsonError::invalid_hint());
+        check_error("{\"hints\": [[\"1\"]]}", ParseJsonError::invalid_hint());
+    }
+}
@@ -0,0 +1,576 @@
+// Copyright (c) 2016 srither-core developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Game IDs of Loopy in Simon Tatham's Portable Puzzle Collection.
+//!
+//! A puzzle is written as `COLUMNSxROWStGRID:BODY`, e.g. `5x2t0:3a2c2b3`,
+//! optionally with the difficulty after the grid type, e.g. `5x2t0dh:...`.
+//! The grid type `0` is the square grid, the only one supported; the other
+//! numbers are the other tilings of Loopy. The body gives the cells from the
+//! top-left one, row by row: `0`-`4` is a hint and `a`-`z` are 1 to 26 empty
+//! cells. The body must give all the cells.
+//!
+//! The save files of the puzzle collection are also read and written. A save
+//! file is a list of records `KEY:LENGTH:VALUE` on their lines, the keys
+//! padded by spaces to 8 characters: the name of the collection and of the
+//! game, the parameters (the part of the game ID before `:`), the body as
+//! `DESC`, and the moves from the start, of which the first `STATEPOS - 1`
+//! are made and the rest are undone. A move sets the edges by their indices
+//! and `y` (line), `n` (cross) or `u` (unknown), e.g. `3y4n`, and a move
+//! solving the puzzle starts with `S`. Loopy numbers the edges as they are
+//! met on the cells row by row, each cell meeting its top, right, bottom and
+//! left edges in order.
+
+use std::fmt;
+use std::error::Error;
+
+use geom::{Geom, Point, Size};
+use puzzle::{Edge, Puzzle};
+
+/// An error type which is returned from parsing a Loopy game ID into puzzle.
+#[derive(Copy, Clone, Debug)]
+pub struct ParseLoopyError {
+    kind: LoopyErrorKind,
+}
+
+#[derive(Copy, Clone, Debug)]
+enum LoopyErrorKind {
+    InvalidParams,
+    UnsupportedGrid,
+    InvalidBody,
+    TooShort,
+    TooLong,
+    InvalidSave,
+    UnsupportedGame,
+    InvalidMove,
+}
+
+impl Error for ParseLoopyError {
+    fn description(&self) -> &str {
+        match self.kind {
+            LoopyErrorKind::InvalidParams => "invalid parameters found in Loopy game ID",
+            LoopyErrorKind::UnsupportedGrid => "grid type other than square is not supported",
+            LoopyErrorKind::InvalidBody => "invalid cell found in Loopy game ID",
+            LoopyErrorKind::TooShort => "too few cells found in Loopy game ID",
+            LoopyErrorKind::TooLong => "too many cells found in Loopy game ID",
+            LoopyErrorKind::InvalidSave => "invalid record found in Loopy save file",
+            LoopyErrorKind::UnsupportedGame => "save file of a game other than Loopy",
+            LoopyErrorKind::InvalidMove => "invalid move found in Loopy save file",
+        }
+    }
+}
+
+impl fmt::Display for ParseLoopyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.description().fmt(f)
+    }
+}
+
+impl ParseLoopyError {
+    fn invalid_params() -> ParseLoopyError {
+        ParseLoopyError { kind: LoopyErrorKind::InvalidParams }
+    }
+    fn unsupported_grid() -> ParseLoopyError {
+        ParseLoopyError { kind: LoopyErrorKind::UnsupportedGrid }
+    }
+    fn invalid_body() -> ParseLoopyError {
+        ParseLoopyError { kind: LoopyErrorKind::InvalidBody }
+    }
+    fn too_short() -> ParseLoopyError {
+        ParseLoopyError { kind: LoopyErrorKind::TooShort }
+    }
+    fn too_long() -> ParseLoopyError {
+        ParseLoopyError { kind: LoopyErrorKind::TooLong }
+    }
+    fn invalid_save() -> ParseLoopyError {
+        ParseLoopyError { kind: LoopyErrorKind::InvalidSave }
+    }
+    fn unsupported_game() -> ParseLoopyError {
+        ParseLoopyError { kind: LoopyErrorKind::UnsupportedGame }
+    }
+    fn invalid_move() -> ParseLoopyError {
+        ParseLoopyError { kind: LoopyErrorKind::InvalidMove }
+    }
+}
+
+// The grid type of the square grid.
+const SQUARE: u32 = 0;
+// The name of the collection starting the save files.
+const SAVE_MAGIC: &'static str = "Simon Tatham's Portable Puzzle Collection";
+// The difficulty of the new games from the parameters of the save files
+// written, the easiest one.
+const DIFFICULTY: char = 'e';
+
+// The parameters of a game ID: the columns, the rows and the grid type.
+fn parse_params(s: &str) -> Option<(i32, i32, u32)> {
+    let x = match s.find('x') {
+        Some(x) => x,
+        None => return None,
+    };
+    let t = match s.find('t') {
+        Some(t) if t > x => t,
+        _ => return None,
+    };
+    // The difficulty, `d` and a letter, is only used to generate puzzles.
+    let grid = match s.find('d') {
+        Some(d) if d > t && d + 2 == s.len() => &s[t + 1..d],
+        Some(_) => return None,
+        None => &s[t + 1..],
+    };
+    let column = s[..x].parse::<i32>().ok();
+    let row = s[x + 1..t].parse::<i32>().ok();
+    match (column, row, grid.parse::<u32>().ok()) {
+        (Some(c), Some(r), Some(g)) if c > 0 && r > 0 => Some((c, r, g)),
+        _ => None,
+    }
+}
+
+/// Returns true if the string is a Loopy game ID.
+pub fn detect(s: &str) -> bool {
+    let s = s.trim();
+    match s.find(':') {
+        Some(i) => parse_params(&s[..i]).is_some() && !s.contains(char::is_whitespace),
+        None => false,
+    }
+}
+
+/// Parses a Loopy game ID into a puzzle.
+///
+/// The random seeds of Loopy, written after `#` instead of `:`, are not
+/// accepted, as they generate puzzles by the algorithm of Loopy.
+pub fn parse(s: &str) -> Result<Puzzle, ParseLoopyError> {
+    let s = s.trim();
+    let (params, body) = match s.find(':') {
+        Some(i) => (&s[..i], &s[i + 1..]),
+        None => return Err(ParseLoopyError::invalid_params()),
+    };
+    let (column, row, grid) = match parse_params(params) {
+        Some(params) => params,
+        None => return Err(ParseLoopyError::invalid_params()),
+    };
+    if grid != SQUARE {
+        return Err(ParseLoopyError::unsupported_grid());
+    }
+    parse_body(column, row, body)
+}
+
+// Parses the body of a game ID of the size.
+fn parse_body(column: i32, row: i32, body: &str) -> Result<Puzzle, ParseLoopyError> {
+    let mut puzzle = Puzzle::new(Size(row, column));
+    let num_cells = (row * column) as usize;
+    let mut i = 0;
+    for c in body.chars() {
+        if i >= num_cells {
+            return Err(ParseLoopyError::too_long());
+        }
+        let (hint, skip) = match c {
+            '0'...'4' => (Some(c as u8 - b'0'), 1),
+            'a'...'z' => (None, (c as u8 - b'a') as usize + 1),
+            _ => return Err(ParseLoopyError::invalid_body()),
+        };
+        if i + skip > num_cells {
+            return Err(ParseLoopyError::too_long());
+        }
+        puzzle.set_hint(point(i, column), hint);
+        i += skip;
+    }
+    if i < num_cells {
+        return Err(ParseLoopyError::too_short());
+    }
+    Ok(puzzle)
+}
+
+fn point(i: usize, column: i32) -> Point {
+    Point(i as i32 / column, i as i32 % column)
+}
+
+/// A wrapper displaying the hints of a puzzle as a Loopy game ID of the
+/// square grid.
+#[derive(Copy, Clone, Debug)]
+pub struct Loopy<'a>(pub &'a Puzzle);
+
+impl<'a> fmt::Display for Loopy<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Loopy(puzzle) = *self;
+        writeln!(f, "{}x{}t{}:{}", puzzle.column(), puzzle.row(), SQUARE, body(puzzle))
+    }
+}
+
+// The body of the game ID of the puzzle.
+fn body(puzzle: &Puzzle) -> String {
+    let mut body = String::new();
+    let num_cells = (puzzle.row() * puzzle.column()) as usize;
+    let mut empty = 0;
+    for i in 0..num_cells {
+        match puzzle.hint(point(i, puzzle.column())) {
+            Some(n) => {
+                if empty > 0 {
+                    body.push((b'a' + empty - 1) as char);
+                    empty = 0;
+                }
+                body.push((b'0' + n) as char);
+            }
+            None => {
+                empty += 1;
+                if empty == 26 {
+                    body.push('z');
+                    empty = 0;
+                }
+            }
+        }
+    }
+    if empty > 0 {
+        body.push((b'a' + empty - 1) as char);
+    }
+    body
+}
+
+// An edge of the square grid: the horizontal one above the point, or the
+// vertical one on the left of the point.
+#[derive(Copy, Clone, Debug)]
+enum GridEdge {
+    H(Point),
+    V(Point),
+}
+
+impl GridEdge {
+    fn get(self, puzzle: &Puzzle) -> Option<Edge> {
+        match self {
+            GridEdge::H(p) => puzzle.edge_h(p),
+            GridEdge::V(p) => puzzle.edge_v(p),
+        }
+    }
+
+    fn set(self, puzzle: &mut Puzzle, edge: Option<Edge>) {
+        match self {
+            GridEdge::H(p) => puzzle.set_edge_h(p, edge),
+            GridEdge::V(p) => puzzle.set_edge_v(p, edge),
+        }
+    }
+}
+
+// The edges of the puzzle in the order of the indices of Loopy.
+fn grid_edges(puzzle: &Puzzle) -> Vec<GridEdge> {
+    let mut edges = vec![];
+    for r in 0..puzzle.row() {
+        for c in 0..puzzle.column() {
+            // The top and the left edges are met on the cells above and on
+            // the left first, if any.
+            if r == 0 {
+                edges.push(GridEdge::H(Point(r, c)));
+            }
+            edges.push(GridEdge::V(Point(r, c + 1)));
+            edges.push(GridEdge::H(Point(r + 1, c)));
+            if c == 0 {
+                edges.push(GridEdge::V(Point(r, c)));
+            }
+        }
+    }
+    edges
+}
+
+/// Returns true if the string is a save file of the puzzle collection.
+pub fn detect_save(s: &str) -> bool {
+    s.trim_left().starts_with(&format!("SAVEFILE:{}:{}", SAVE_MAGIC.len(), SAVE_MAGIC))
+}
+
+/// Parses a save file of Loopy into a puzzle, with the edges set by the
+/// moves made.
+pub fn parse_save(s: &str) -> Result<Puzzle, ParseLoopyError> {
+    let records = try!(records(s));
+    let value = |key: &str| records.iter().find(|r| r.0 == key).map(|r| r.1);
+    if records.first() != Some(&("SAVEFILE", SAVE_MAGIC)) {
+        return Err(ParseLoopyError::invalid_save());
+    }
+    if value("GAME") != Some("Loopy") {
+        return Err(ParseLoopyError::unsupported_game());
+    }
+    // The current parameters are of the game, and the others are of the new
+    // games.
+    let (column, row, grid) = match value("CPARAMS").or(value("PARAMS")).and_then(parse_params) {
+        Some(params) => params,
+        None => return Err(ParseLoopyError::invalid_params()),
+    };
+    if grid != SQUARE {
+        return Err(ParseLoopyError::unsupported_grid());
+    }
+    let initial = match value("DESC") {
+        Some(body) => try!(parse_body(column, row, body)),
+        None => return Err(ParseLoopyError::invalid_save()),
+    };
+
+    let moves = records.iter()
+                       .filter(|r| r.0 == "MOVE" || r.0 == "SOLVE" || r.0 == "RESTART")
+                       .collect::<Vec<_>>();
+    let num_states = value("NSTATES").and_then(|n| n.parse::<usize>().ok());
+    let state_pos = value("STATEPOS").and_then(|n| n.parse::<usize>().ok());
+    let state_pos = match (num_states, state_pos) {
+        (Some(n), Some(pos)) if n == moves.len() + 1 && pos >= 1 && pos <= n => pos,
+        _ => return Err(ParseLoopyError::invalid_save()),
+    };
+    let edges = grid_edges(&initial);
+    let mut puzzle = initial.clone();
+    for &&(key, mv) in &moves[..state_pos - 1] {
+        if key == "RESTART" {
+            puzzle = initial.clone();
+        } else {
+            try!(apply_move(&mut puzzle, &edges, mv));
+        }
+    }
+    Ok(puzzle)
+}
+
+// Splits the save file into the records of the keys and the values.
+fn records(s: &str) -> Result<Vec<(&str, &str)>, ParseLoopyError> {
+    let mut records = vec![];
+    let mut rest = s.trim_left();
+    while !rest.is_empty() {
+        let mut fields = rest.splitn(3, ':');
+        let (key, len, tail) = match (fields.next(), fields.next(), fields.next()) {
+            (Some(key), Some(len), Some(tail)) => (key.trim_right(), len.parse::<usize>(), tail),
+            _ => return Err(ParseLoopyError::invalid_save()),
+        };
+        let len = match len {
+            Ok(len) if key.len() <= 8 && len <= tail.len() && tail.is_char_boundary(len) => len,
+            _ => return Err(ParseLoopyError::invalid_save()),
+        };
+        records.push((key, &tail[..len]));
+        rest = tail[len..].trim_left_matches('\n');
+    }
+    Ok(records)
+}
+
+// Sets the edges of the puzzle by the move.
+fn apply_move(puzzle: &mut Puzzle, edges: &[GridEdge], mv: &str) -> Result<(), ParseLoopyError> {
+    let mut rest = if mv.starts_with('S') { &mv[1..] } else { mv };
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_digit(10)).unwrap_or(rest.len());
+        let edge = rest[..digits].parse::<usize>().ok().and_then(|i| edges.get(i));
+        let state = match rest[digits..].chars().next() {
+            Some('y') => Some(Edge::Line),
+            Some('n') => Some(Edge::Cross),
+            Some('u') => None,
+            _ => return Err(ParseLoopyError::invalid_move()),
+        };
+        match edge {
+            Some(edge) => edge.set(puzzle, state),
+            None => return Err(ParseLoopyError::invalid_move()),
+        }
+        rest = &rest[digits + 1..];
+    }
+    Ok(())
+}
+
+/// A wrapper displaying a puzzle as a save file of Loopy, with the edges of
+/// the puzzle set by a move, which solves the puzzle if all the edges are
+/// set. Loopy opens the puzzles of at least 3x3 cells only.
+#[derive(Copy, Clone, Debug)]
+pub struct LoopySave<'a>(pub &'a Puzzle);
+
+impl<'a> fmt::Display for LoopySave<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let LoopySave(puzzle) = *self;
+        let params = format!("{}x{}t{}d{}",
+                             puzzle.column(),
+                             puzzle.row(),
+                             SQUARE,
+                             DIFFICULTY);
+        let mut mv = String::new();
+        for (i, edge) in grid_edges(puzzle).into_iter().enumerate() {
+            match edge.get(puzzle) {
+                Some(Edge::Line) => mv.push_str(&format!("{}y", i)),
+                Some(Edge::Cross) => mv.push_str(&format!("{}n", i)),
+                None => {}
+            }
+        }
+        let num_states = if mv.is_empty() { 1 } else { 2 };
+
+        try!(record(f, "SAVEFILE", SAVE_MAGIC));
+        try!(record(f, "VERSION", "1"));
+        try!(record(f, "GAME", "Loopy"));
+        try!(record(f, "PARAMS", &params));
+        try!(record(f, "CPARAMS", &params));
+        try!(record(f, "DESC", &body(puzzle)));
+        try!(record(f, "NSTATES", &num_states.to_string()));
+        try!(record(f, "STATEPOS", &num_states.to_string()));
+        if !mv.is_empty() {
+            if puzzle.has_unknown_edge() {
+                try!(record(f, "MOVE", &mv));
+            } else {
+                try!(record(f, "SOLVE", &format!("S{}", mv)));
+            }
+        }
+        Ok(())
+    }
+}
+
+// Writes a record of the save file.
+fn record(f: &mut fmt::Formatter, key: &str, value: &str) -> fmt::Result {
+    writeln!(f, "{:<8}:{}:{}", key, value.len(), value)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+    use super::{Loopy, LoopySave, ParseLoopyError, detect, detect_save, parse, parse_save};
+    use geom::{Geom, Point, Size};
+    use puzzle::{Edge, Puzzle};
+
+    fn check_error(input: &str, error: ParseLoopyError) {
+        assert_eq!(parse(input).unwrap_err().description(), error.description());
+    }
+
+    #[test]
+    fn parse_id() {
+        let expected = "3_2__\n_2__3\n".parse::<Puzzle>().unwrap();
+        assert_eq!(expected, parse("5x2t0:3a2c2b3").unwrap());
+        assert_eq!(expected, parse("5x2t0dh:3a2c2b3\n").unwrap());
+
+        let puzzle = parse("30x1t0:z0c").unwrap();
+        assert_eq!(Size(1, 30), puzzle.size());
+        assert_eq!(Some(0), puzzle.hint(Point(0, 26)));
+        assert_eq!(None, puzzle.hint(Point(0, 27)));
+    }
+
+    #[test]
+    fn round_trip() {
+        let inputs = ["3_2__\n_2__3",
+                      "0\n_\n_",
+                      "_\n_\n1",
+                      "__\n__",
+                      "1____\n_____\n_____\n_____\n_____\n____2",
+                      "______________________________\n______________________________"];
+        for input in &inputs {
+            let puzzle = input.parse::<Puzzle>().unwrap();
+            let id = Loopy(&puzzle).to_string();
+            assert_eq!(puzzle, parse(&id).unwrap());
+        }
+    }
+
+    #[test]
+    fn display() {
+        let puzzle = "3_2__\n_2__3".parse::<Puzzle>().unwrap();
+        assert_eq!("5x2t0:3a2c2b3\n", Loopy(&puzzle).to_string());
+    }
+
+    #[test]
+    fn detection() {
+        assert!(detect("7x7t0:a3b\n"));
+        assert!(detect("7x7t0de:a3b"));
+        assert!(detect("7x7t2:a3b"));
+        assert!(!detect("7x7t0#12345"));
+        assert!(!detect("https://puzz.link/p?slither/5/2/8cgc3"));
+        assert!(!detect("3_2__\n_2__3\n"));
+    }
+
+    // The saves below are laid out as Loopy writes a game entered by its ID,
+    // which has no seed. Loopy needs at least 3x3 cells, and a click sets one
+    // edge.
+
+    // A save of the game partly played, with four clicks made and the last
+    // one undone.
+    const PLAYED: &'static str = "\
+SAVEFILE:41:Simon Tatham's Portable Puzzle Collection
+VERSION :1:1
+GAME    :5:Loopy
+PARAMS  :7:3x3t0dn
+CPARAMS :7:3x3t0dn
+DESC    :8:b21a221a
+NSTATES :1:5
+STATEPOS:1:4
+MOVE    :2:0y
+MOVE    :2:3y
+MOVE    :2:1n
+MOVE    :3:21y
+";
+
+    // A save of the game solved by the `Solve` command, which sets every
+    // edge.
+    const SOLVED: &'static str = "\
+SAVEFILE:41:Simon Tatham's Portable Puzzle Collection
+VERSION :1:1
+GAME    :5:Loopy
+PARAMS  :7:3x3t0de
+CPARAMS :7:3x3t0de
+DESC    :8:b21a221a
+NSTATES :1:2
+STATEPOS:1:2
+SOLVE   :63:S0y1n2n3y4y5y6n7n8n9y10n11n12y13n14n15y16n17n18y19y20n21y22y23y
+";
+
+    #[test]
+    fn parse_saves() {
+        let mut expected = "__2\n1_2\n21_\n".parse::<Puzzle>().unwrap();
+        expected.set_edge_h(Point(0, 0), Some(Edge::Line));
+        expected.set_edge_v(Point(0, 0), Some(Edge::Line));
+        expected.set_edge_v(Point(0, 1), Some(Edge::Cross));
+        assert_eq!(expected, parse_save(PLAYED).unwrap());
+
+        let expected = "+-+-+x+\n| x |2x\n+x+x+-+\n|1x x2|\n+x+x+x+\n|2x1x |\n+-+-+-+\n"
+                           .parse::<Puzzle>()
+                           .unwrap();
+        assert_eq!(expected, parse_save(SOLVED).unwrap());
+
+        // A restart clears the edges.
+        let restarted = PLAYED.replace("MOVE    :2:3y", "RESTART :8:b21a221a");
+        let mut expected = "__2\n1_2\n21_\n".parse::<Puzzle>().unwrap();
+        expected.set_edge_v(Point(0, 1), Some(Edge::Cross));
+        assert_eq!(expected, parse_save(&restarted).unwrap());
+    }
+
+    #[test]
+    fn write_saves() {
+        let solution = parse_save(SOLVED).unwrap();
+        assert_eq!(SOLVED, LoopySave(&solution).to_string());
+
+        let partial = parse_save(PLAYED).unwrap();
+        let save = LoopySave(&partial).to_string();
+        assert!(save.ends_with("NSTATES :1:2\nSTATEPOS:1:2\nMOVE    :6:0y1n3y\n"));
+        assert_eq!(partial, parse_save(&save).unwrap());
+
+        let puzzle = "1____\n_____\n____2\n".parse::<Puzzle>().unwrap();
+        let save = LoopySave(&puzzle).to_string();
+        assert!(save.contains("DESC    :3:1m2\nNSTATES :1:1\nSTATEPOS:1:1\n"));
+        assert_eq!(puzzle, parse_save(&save).unwrap());
+        assert!(detect_save(&save));
+        assert!(!detect_save("5x2t0:3a2c2b3"));
+    }
+
+    #[test]
+    fn save_errors() {
+        let check = |input: &str, error: ParseLoopyError| {
+            assert_eq!(parse_save(input).unwrap_err().description(), error.description());
+        };
+        check(&SOLVED.replace("GAME    :5:Loopy", "GAME    :6:Bridges"),
+              ParseLoopyError::unsupported_game());
+        check(&SOLVED.replace("3x3t0de", "3x3t1de"),
+              ParseLoopyError::unsupported_grid());
+        check(&SOLVED.replace("NSTATES :1:2", "NSTATES :1:3"),
+              ParseLoopyError::invalid_save());
+        check(&SOLVED.replace("STATEPOS:1:2", "STATEPOS:1:0"),
+              ParseLoopyError::invalid_save());
+        check(&SOLVED.replace("SOLVE   :63:", "SOLVE   :65:"),
+              ParseLoopyError::invalid_save());
+        check(&SOLVED.replace("DESC    :8:b21a221a", "DESC    :8:b21a221b"),
+              ParseLoopyError::too_long());
+        check(&SOLVED.replace("23y", "24y"), ParseLoopyError::invalid_move());
+        check(&SOLVED.replace("23y", "23x"), ParseLoopyError::invalid_move());
+    }
+
+    #[test]
+    fn parse_errors() {
+        check_error("5x2:3a2c2b3", ParseLoopyError::invalid_params());
+        check_error("5x0t0:", ParseLoopyError::invalid_params());
+        check_error("5x2t0dhh:3a2c2b3", ParseLoopyError::invalid_params());
+        check_error("7x7t1:a", ParseLoopyError::unsupported_grid());
+        check_error("5x2t0:3a2c5b3", ParseLoopyError::invalid_body());
+        check_error("5x2t0:3a2c2b", ParseLoopyError::too_short());
+        check_error("5x2t0:3a2c2b3a", ParseLoopyError::too_long());
+        check_error("5x2t0:3a2c2d", ParseLoopyError::too_long());
+    }
+}
@@ -0,0 +1,366 @@
+// Copyright (c) 2016 srither-core developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Puzzle formats other than the native text format, and the registry of all
+//! formats.
+//!
+//! A format registered in `FORMATS` is available to all the commands reading
+//! or writing puzzles by its name, and to the detection of the format of the
+//! input.
+
+use std::fmt;
+use std::error::Error;
+
+use puzzle::{ParsePuzzleError, Puzzle};
+
+use self::compact::{Compact, ParseCompactError};
+use self::csv::{Csv, ParseCsvError};
+use self::janko::{Janko, ParseJankoError};
+use self::json::{Json, ParseJsonError};
+use self::loopy::{Loopy, LoopySave, ParseLoopyError};
+use self::puzzlink::{ParsePuzzlinkError, Puzzlink};
+
+pub mod compact;
+pub mod csv;
+pub mod janko;
+pub mod json;
+pub mod loopy;
+pub mod puzzlink;
+
+/// A format of puzzles, which detects, parses and emits them.
+pub trait PuzzleFormat: fmt::Debug + Sync {
+    /// Returns the name of the format, e.g. `csv`.
+    fn name(&self) -> &'static str;
+    /// Returns true if the string looks like a puzzle in the format. The
+    /// string may still fail to parse.
+    fn detect(&self, s: &str) -> bool;
+    /// Parses a puzzle in the format.
+    fn parse(&self, s: &str) -> Result<Puzzle, ParseFormatError>;
+    /// Returns the puzzle in the format, ending with a newline.
+    fn emit(&self, puzzle: &Puzzle) -> String;
+}
+
+/// An error type which is returned from parsing a string in a format into
+/// puzzle.
+#[derive(Copy, Clone, Debug)]
+pub struct ParseFormatError {
+    kind: FormatErrorKind,
+}
+
+#[derive(Copy, Clone, Debug)]
+enum FormatErrorKind {
+    Unknown,
+    Text(ParsePuzzleError),
+    Csv(ParseCsvError),
+    Json(ParseJsonError),
+    Puzzlink(ParsePuzzlinkError),
+    Loopy(ParseLoopyError),
+    Janko(ParseJankoError),
+    Compact(ParseCompactError),
+}
+
+impl From<ParsePuzzleError> for ParseFormatError {
+    fn from(err: ParsePuzzleError) -> ParseFormatError {
+        ParseFormatError { kind: FormatErrorKind::Text(err) }
+    }
+}
+
+impl From<ParseCsvError> for ParseFormatError {
+    fn from(err: ParseCsvError) -> ParseFormatError {
+        ParseFormatError { kind: FormatErrorKind::Csv(err) }
+    }
+}
+
+impl From<ParseJsonError> for ParseFormatError {
+    fn from(err: ParseJsonError) -> ParseFormatError {
+        ParseFormatError { kind: FormatErrorKind::Json(err) }
+    }
+}
+
+impl From<ParsePuzzlinkError> for ParseFormatError {
+    fn from(err: ParsePuzzlinkError) -> ParseFormatError {
+        ParseFormatError { kind: FormatErrorKind::Puzzlink(err) }
+    }
+}
+
+impl From<ParseLoopyError> for ParseFormatError {
+    fn from(err: ParseLoopyError) -> ParseFormatError {
+        ParseFormatError { kind: FormatErrorKind::Loopy(err) }
+    }
+}
+
+impl From<ParseJankoError> for ParseFormatError {
+    fn from(err: ParseJankoError) -> ParseFormatError {
+        ParseFormatError { kind: FormatErrorKind::Janko(err) }
+    }
+}
+
+impl From<ParseCompactError> for ParseFormatError {
+    fn from(err: ParseCompactError) -> ParseFormatError {
+        ParseFormatError { kind: FormatErrorKind::Compact(err) }
+    }
+}
+
+impl Error for ParseFormatError {
+    fn description(&self) -> &str {
+        use self::FormatErrorKind::*;
+        match self.kind {
+            Unknown => "cannot detect the format of puzzle",
+            Text(ref e) => e.description(),
+            Csv(ref e) => e.description(),
+            Json(ref e) => e.description(),
+            Puzzlink(ref e) => e.description(),
+            Loopy(ref e) => e.description(),
+            Janko(ref e) => e.description(),
+            Compact(ref e) => e.description(),
+        }
+    }
+    fn cause(&self) -> Option<&Error> {
+        use self::FormatErrorKind::*;
+        match self.kind {
+            Unknown => None,
+            Text(ref e) => Some(e),
+            Csv(ref e) => Some(e),
+            Json(ref e) => Some(e),
+            Puzzlink(ref e) => Some(e),
+            Loopy(ref e) => Some(e),
+            Janko(ref e) => Some(e),
+            Compact(ref e) => Some(e),
+        }
+    }
+}
+
+impl fmt::Display for ParseFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.description().fmt(f)
+    }
+}
+
+impl ParseFormatError {
+    fn unknown() -> ParseFormatError {
+        ParseFormatError { kind: FormatErrorKind::Unknown }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+struct TextFormat;
+
+impl PuzzleFormat for TextFormat {
+    fn name(&self) -> &'static str {
+        "text"
+    }
+    fn detect(&self, s: &str) -> bool {
+        // The hints and the lattice of lines and crosses.
+        s.chars().all(|c| "01234_-+|x \r\n".contains(c))
+    }
+    fn parse(&self, s: &str) -> Result<Puzzle, ParseFormatError> {
+        Ok(try!(s.parse::<Puzzle>()))
+    }
+    fn emit(&self, puzzle: &Puzzle) -> String {
+        puzzle.to_string()
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+struct CsvFormat;
+
+impl PuzzleFormat for CsvFormat {
+    fn name(&self) -> &'static str {
+        "csv"
+    }
+    fn detect(&self, s: &str) -> bool {
+        csv::detect(s)
+    }
+    fn parse(&self, s: &str) -> Result<Puzzle, ParseFormatError> {
+        Ok(try!(csv::parse(s)))
+    }
+    fn emit(&self, puzzle: &Puzzle) -> String {
+        Csv(puzzle).to_string()
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+struct JsonFormat;
+
+impl PuzzleFormat for JsonFormat {
+    fn name(&self) -> &'static str {
+        "json"
+    }
+    fn detect(&self, s: &str) -> bool {
+        json::detect(s)
+    }
+    fn parse(&self, s: &str) -> Result<Puzzle, ParseFormatError> {
+        Ok(try!(json::parse(s)))
+    }
+    fn emit(&self, puzzle: &Puzzle) -> String {
+        Json(puzzle).to_string()
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+struct PuzzlinkFormat;
+
+impl PuzzleFormat for PuzzlinkFormat {
+    fn name(&self) -> &'static str {
+        "puzzlink"
+    }
+    fn detect(&self, s: &str) -> bool {
+        puzzlink::detect(s)
+    }
+    fn parse(&self, s: &str) -> Result<Puzzle, ParseFormatError> {
+        Ok(try!(puzzlink::parse(s)))
+    }
+    fn emit(&self, puzzle: &Puzzle) -> String {
+        Puzzlink(puzzle).to_string()
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+struct LoopyFormat;
+
+impl PuzzleFormat for LoopyFormat {
+    fn name(&self) -> &'static str {
+        "loopy"
+    }
+    fn detect(&self, s: &str) -> bool {
+        loopy::detect(s)
+    }
+    fn parse(&self, s: &str) -> Result<Puzzle, ParseFormatError> {
+        Ok(try!(loopy::parse(s)))
+    }
+    fn emit(&self, puzzle: &Puzzle) -> String {
+        Loopy(puzzle).to_string()
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+struct LoopySaveFormat;
+
+impl PuzzleFormat for LoopySaveFormat {
+    fn name(&self) -> &'static str {
+        "loopy-save"
+    }
+    fn detect(&self, s: &str) -> bool {
+        loopy::detect_save(s)
+    }
+    fn parse(&self, s: &str) -> Result<Puzzle, ParseFormatError> {
+        Ok(try!(loopy::parse_save(s)))
+    }
+    fn emit(&self, puzzle: &Puzzle) -> String {
+        LoopySave(puzzle).to_string()
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+struct JankoFormat;
+
+impl PuzzleFormat for JankoFormat {
+    fn name(&self) -> &'static str {
+        "janko"
+    }
+    fn detect(&self, s: &str) -> bool {
+        janko::detect(s)
+    }
+    fn parse(&self, s: &str) -> Result<Puzzle, ParseFormatError> {
+        Ok(try!(janko::parse(s)))
+    }
+    fn emit(&self, puzzle: &Puzzle) -> String {
+        Janko(puzzle).to_string()
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+struct CompactFormat;
+
+impl PuzzleFormat for CompactFormat {
+    fn name(&self) -> &'static str {
+        "compact"
+    }
+    fn detect(&self, s: &str) -> bool {
+        compact::detect(s)
+    }
+    fn parse(&self, s: &str) -> Result<Puzzle, ParseFormatError> {
+        Ok(try!(compact::parse(s)))
+    }
+    fn emit(&self, puzzle: &Puzzle) -> String {
+        format!("{}\n", Compact(puzzle))
+    }
+}
+
+static TEXT: TextFormat = TextFormat;
+
+// The formats in the priority order of the detection. The text format comes
+// last, as its detector is the loosest.
+static FORMATS: &'static [&'static PuzzleFormat] = &[&PuzzlinkFormat,
+                                                     &LoopyFormat,
+                                                     &LoopySaveFormat,
+                                                     &CompactFormat,
+                                                     &JankoFormat,
+                                                     &JsonFormat,
+                                                     &CsvFormat,
+                                                     &TEXT];
+
+/// Returns all the registered formats, in the priority order of the
+/// detection.
+pub fn formats() -> &'static [&'static PuzzleFormat] {
+    FORMATS
+}
+
+/// Returns the first registered format whose detector accepts the string.
+pub fn detect(s: &str) -> Result<&'static PuzzleFormat, ParseFormatError> {
+    FORMATS.iter().find(|f| f.detect(s)).cloned().ok_or(ParseFormatError::unknown())
+}
+
+/// Returns the registered format of the name.
+pub fn find(name: &str) -> Option<&'static PuzzleFormat> {
+    FORMATS.iter().find(|f| f.name() == name).cloned()
+}
+
+/// Returns the native text format.
+pub fn text() -> &'static PuzzleFormat {
+    &TEXT
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+    use super::{detect, find, formats, text};
+    use puzzle::Puzzle;
+
+    #[test]
+    fn registry() {
+        assert_eq!("text", text().name());
+        assert_eq!("csv", find("csv").unwrap().name());
+        assert!(find("png").is_none());
+
+        let puzzle = "12_\n__3".parse::<Puzzle>().unwrap();
+        for format in formats() {
+            let s = format.emit(&puzzle);
+            assert!(s.ends_with('\n'));
+            assert_eq!(puzzle, format.parse(&s).unwrap());
+            for to in formats() {
+                assert_eq!(puzzle, to.parse(&to.emit(&format.parse(&s).unwrap())).unwrap());
+            }
+        }
+        assert!(text().parse("12_\n__").is_err());
+    }
+
+    #[test]
+    fn detection() {
+        let puzzle = "12_\n__3".parse::<Puzzle>().unwrap();
+        for format in formats() {
+            let (parsed, detected) = Puzzle::parse_any(&format.emit(&puzzle)).unwrap();
+            assert_eq!(puzzle, parsed);
+            assert_eq!(format.name(), detected.name());
+        }
+        assert_eq!("text", detect("+ +\n 1 \n+-+\n").unwrap().name());
+        assert_eq!("csv", detect("\"\"\n1\n").unwrap().name());
+        assert_eq!("cannot detect the format of puzzle",
+                   Puzzle::parse_any("<html></html>").unwrap_err().description());
+    }
+}
@@ -0,0 +1,232 @@
+// Copyright (c) 2016 srither-core developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! puzz.link URL format.
+//!
+//! A puzzle is written as `https://puzz.link/p?slither/COLUMNS/ROWS/BODY`.
+//! The body gives the cells from the top-left one, row by row: `0`-`4` is a
+//! hint, `5`-`9` and `a`-`e` are a hint followed by one or two empty cells,
+//! `g`-`z` are 1 to 20 empty cells and `.` is a hint without number. The
+//! cells after the end of the body are empty.
+
+use std::fmt;
+use std::error::Error;
+
+use geom::{Geom, Point, Size};
+use puzzle::Puzzle;
+
+/// An error type which is returned from parsing a puzz.link URL into puzzle.
+#[derive(Copy, Clone, Debug)]
+pub struct ParsePuzzlinkError {
+    kind: PuzzlinkErrorKind,
+}
+
+#[derive(Copy, Clone, Debug)]
+enum PuzzlinkErrorKind {
+    NotSlither,
+    InvalidSize,
+    InvalidBody,
+}
+
+impl Error for ParsePuzzlinkError {
+    fn description(&self) -> &str {
+        match self.kind {
+            PuzzlinkErrorKind::NotSlither => "URL is not of a Slither Link puzzle",
+            PuzzlinkErrorKind::InvalidSize => "invalid size found in URL",
+            PuzzlinkErrorKind::InvalidBody => "invalid cell found in URL",
+        }
+    }
+}
+
+impl fmt::Display for ParsePuzzlinkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.description().fmt(f)
+    }
+}
+
+impl ParsePuzzlinkError {
+    fn not_slither() -> ParsePuzzlinkError {
+        ParsePuzzlinkError { kind: PuzzlinkErrorKind::NotSlither }
+    }
+    fn invalid_size() -> ParsePuzzlinkError {
+        ParsePuzzlinkError { kind: PuzzlinkErrorKind::InvalidSize }
+    }
+    fn invalid_body() -> ParsePuzzlinkError {
+        ParsePuzzlinkError { kind: PuzzlinkErrorKind::InvalidBody }
+    }
+}
+
+const PREFIX: &'static str = "slither/";
+const URL_BASE: &'static str = "https://puzz.link/p?";
+
+/// Returns true if the string is a URL of a Slither Link puzzle.
+pub fn detect(s: &str) -> bool {
+    let s = s.trim();
+    s.contains(PREFIX) && !s.contains(char::is_whitespace)
+}
+
+/// Parses a puzz.link URL into a puzzle.
+///
+/// Only the part after `slither/` is read, so the URLs of the other sites
+/// with the same format are also accepted. The hints without number are
+/// parsed as empty cells.
+pub fn parse(s: &str) -> Result<Puzzle, ParsePuzzlinkError> {
+    let s = s.trim();
+    let start = match s.find(PREFIX) {
+        Some(i) => i + PREFIX.len(),
+        None => return Err(ParsePuzzlinkError::not_slither()),
+    };
+    let mut parts = s[start..].split('/');
+    let mut size = || {
+        parts.next()
+             .and_then(|n| n.parse::<i32>().ok())
+             .and_then(|n| if n > 0 { Some(n) } else { None })
+    };
+    let (column, row) = match (size(), size()) {
+        (Some(c), Some(r)) => (c, r),
+        _ => return Err(ParsePuzzlinkError::invalid_size()),
+    };
+    let body = parts.next().unwrap_or("");
+
+    let mut puzzle = Puzzle::new(Size(row, column));
+    let num_cells = (row * column) as usize;
+    let mut i = 0;
+    for c in body.chars() {
+        if i >= num_cells {
+            break;
+        }
+        let (hint, skip) = match c {
+            '0'...'4' => (Some(c as u8 - b'0'), 1),
+            '5'...'9' => (Some(c as u8 - b'5'), 2),
+            'a'...'e' => (Some(c as u8 - b'a'), 3),
+            'g'...'z' => (None, (c as u8 - b'f') as usize),
+            '.' => (None, 1),
+            _ => return Err(ParsePuzzlinkError::invalid_body()),
+        };
+        puzzle.set_hint(point(i, column), hint);
+        i += skip;
+    }
+    Ok(puzzle)
+}
+
+fn point(i: usize, column: i32) -> Point {
+    Point(i as i32 / column, i as i32 % column)
+}
+
+/// A wrapper displaying the hints of a puzzle as a puzz.link URL.
+#[derive(Copy, Clone, Debug)]
+pub struct Puzzlink<'a>(pub &'a Puzzle);
+
+impl<'a> fmt::Display for Puzzlink<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Puzzlink(puzzle) = *self;
+        try!(write!(f, "{}{}{}/{}/", URL_BASE, PREFIX, puzzle.column(), puzzle.row()));
+
+        let num_cells = (puzzle.row() * puzzle.column()) as usize;
+        let hint = |i: usize| {
+            if i < num_cells {
+                puzzle.hint(point(i, puzzle.column()))
+            } else {
+                Some(0)
+            }
+        };
+        let mut i = 0;
+        let mut empty = 0;
+        while i < num_cells {
+            match hint(i) {
+                Some(n) => {
+                    if empty > 0 {
+                        try!(write!(f, "{}", (b'f' + empty) as char));
+                        empty = 0;
+                    }
+                    // The empty cells following a hint are written with it.
+                    let (base, skip) = match (hint(i + 1), hint(i + 2)) {
+                        (None, None) => (b'a', 3),
+                        (None, Some(_)) => (b'5', 2),
+                        _ => (b'0', 1),
+                    };
+                    try!(write!(f, "{}", (base + n) as char));
+                    i += skip;
+                }
+                None => {
+                    empty += 1;
+                    if empty == 20 {
+                        try!(write!(f, "z"));
+                        empty = 0;
+                    }
+                    i += 1;
+                }
+            }
+        }
+        if empty > 0 {
+            try!(write!(f, "{}", (b'f' + empty) as char));
+        }
+        writeln!(f, "")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+    use super::{ParsePuzzlinkError, Puzzlink, detect, parse};
+    use geom::{Geom, Point, Size};
+    use puzzle::Puzzle;
+
+    fn check_error(input: &str, error: ParsePuzzlinkError) {
+        assert_eq!(parse(input).unwrap_err().description(), error.description());
+    }
+
+    #[test]
+    fn parse_url() {
+        let expected = "3_2__\n_2__3\n".parse::<Puzzle>().unwrap();
+        let puzzle = parse("https://puzz.link/p?slither/5/2/8cgc3").unwrap();
+        assert_eq!(expected, puzzle);
+        assert_eq!(expected, parse("http://pzv.jp/p.html?slither/5/2/8cgc3/").unwrap());
+
+        let puzzle = parse("https://puzz.link/p?slither/25/1/z0.").unwrap();
+        assert_eq!(Size(1, 25), puzzle.size());
+        assert_eq!(Some(0), puzzle.hint(Point(0, 20)));
+        assert_eq!(None, puzzle.hint(Point(0, 21)));
+    }
+
+    #[test]
+    fn round_trip() {
+        let inputs = ["3_2__\n_2__3",
+                      "0\n_\n_",
+                      "_\n_\n1",
+                      "__\n__",
+                      "1____\n_____\n_____\n_____\n_____\n____2"];
+        for input in &inputs {
+            let puzzle = input.parse::<Puzzle>().unwrap();
+            let url = Puzzlink(&puzzle).to_string();
+            assert_eq!(puzzle, parse(&url).unwrap());
+        }
+    }
+
+    #[test]
+    fn display() {
+        let puzzle = "3_2__\n_2__3".parse::<Puzzle>().unwrap();
+        assert_eq!("https://puzz.link/p?slither/5/2/8cgc3\n",
+                   Puzzlink(&puzzle).to_string());
+    }
+
+    #[test]
+    fn detection() {
+        assert!(detect("https://puzz.link/p?slither/5/2/8cgc3\n"));
+        assert!(!detect("https://puzz.link/p?slither/5/2/8cgc3\nhttps://puzz.link/p"));
+        assert!(!detect("3_2__\n_2__3\n"));
+    }
+
+    #[test]
+    fn parse_errors() {
+        check_error("https://puzz.link/p?nurikabe/5/2/", ParsePuzzlinkError::not_slither());
+        check_error("https://puzz.link/p?slither/5/", ParsePuzzlinkError::invalid_size());
+        check_error("https://puzz.link/p?slither/0/2/", ParsePuzzlinkError::invalid_size());
+        check_error("https://puzz.link/p?slither/2/2/f", ParsePuzzlinkError::invalid_body());
+    }
+}
@@ -7,6 +7,9 @@
 // modified, or distributed except according to those terms.
 
 //! Data structures and utility functions for Slither Link.
+//!
+//! The items of `prelude` are stable. The other modules may change between
+//! the versions.
 
 #![feature(associated_consts)]
 
@@ -29,7 +32,20 @@
 #![cfg_attr(feature="dev", warn(string_add_assign))]
 
 extern crate board_game_geom;
+#[cfg(feature = "serde")]
+extern crate serde;
 
 pub use board_game_geom as geom;
+pub mod annotation;
+pub mod clue_layer;
+pub mod format;
 pub mod lattice_parser;
+pub mod loop_bits;
+pub mod loop_path;
+pub mod orientation;
+pub mod prelude;
 pub mod puzzle;
+pub mod puzzle_set;
+
+#[cfg(feature = "serde")]
+mod serialize;
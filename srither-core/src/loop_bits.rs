@@ -0,0 +1,419 @@
+// Copyright (c) 2016 srither-core developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! A compact representation of the loop of a solution.
+
+use std::collections::VecDeque;
+
+use geom::{Geom, Move, Point, Size};
+use puzzle::{Edge, Puzzle};
+
+const WORD_BITS: usize = 64;
+
+/// A set of the edges drawn as lines, one bit for each edge.
+///
+/// The horizontal edges come first in row-major order, followed by the
+/// vertical edges in row-major order.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct LoopBits {
+    size: Size,
+    words: Vec<u64>,
+}
+
+impl LoopBits {
+    /// Creates an empty set of the edges of the puzzle of the size.
+    pub fn new(size: Size) -> LoopBits {
+        assert!(size.0 > 0 && size.1 > 0);
+        let len = edge_len(size);
+        LoopBits {
+            size: size,
+            words: vec![0; (len + WORD_BITS - 1) / WORD_BITS],
+        }
+    }
+
+    /// Creates a set of the lines of the puzzle. Unknown edges are not
+    /// included.
+    pub fn from_puzzle(puzzle: &Puzzle) -> LoopBits {
+        let mut bits = LoopBits::new(puzzle.size());
+        for r in 0..(puzzle.row() + 1) {
+            for c in 0..puzzle.column() {
+                let p = Point(r, c);
+                if puzzle.edge_h(p) == Some(Edge::Line) {
+                    bits.set_edge_h(p, true);
+                }
+            }
+        }
+        for r in 0..puzzle.row() {
+            for c in 0..(puzzle.column() + 1) {
+                let p = Point(r, c);
+                if puzzle.edge_v(p) == Some(Edge::Line) {
+                    bits.set_edge_v(p, true);
+                }
+            }
+        }
+        bits
+    }
+
+    /// Sets the edges of the puzzle to lines if they are in the set, or to
+    /// crosses otherwise.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the size of the puzzle differs from the set.
+    pub fn apply_to(&self, puzzle: &mut Puzzle) {
+        assert_eq!(self.size, puzzle.size());
+        let edge = |b| if b {
+            Some(Edge::Line)
+        } else {
+            Some(Edge::Cross)
+        };
+        for r in 0..(self.size.0 + 1) {
+            for c in 0..self.size.1 {
+                let p = Point(r, c);
+                puzzle.set_edge_h(p, edge(self.edge_h(p)));
+            }
+        }
+        for r in 0..self.size.0 {
+            for c in 0..(self.size.1 + 1) {
+                let p = Point(r, c);
+                puzzle.set_edge_v(p, edge(self.edge_v(p)));
+            }
+        }
+    }
+
+    /// Returns the size of the puzzle.
+    #[inline]
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    /// Returns the number of the edges of the puzzle.
+    #[inline]
+    pub fn edge_len(&self) -> usize {
+        edge_len(self.size)
+    }
+
+    /// Returns the number of the edges in the set.
+    pub fn count(&self) -> u32 {
+        self.words.iter().fold(0, |sum, w| sum + w.count_ones())
+    }
+
+    /// Returns the length of the longest single loop in the puzzle of the
+    /// size. The loop passes all the vertices of the grid if their number is
+    /// even, or all of them but one otherwise, as a loop alternates between
+    /// the two colors of the checkered vertices.
+    pub fn max_loop_len(size: Size) -> u32 {
+        let vertices = ((size.0 + 1) * (size.1 + 1)) as u32;
+        vertices - vertices % 2
+    }
+
+    /// Returns true if the upper edge of the cell is in the set.
+    #[inline]
+    pub fn edge_h(&self, p: Point) -> bool {
+        self.get(self.index_h(p))
+    }
+
+    /// Adds or removes the upper edge of the cell.
+    #[inline]
+    pub fn set_edge_h(&mut self, p: Point, line: bool) {
+        let i = self.index_h(p);
+        self.set(i, line)
+    }
+
+    /// Returns true if the left edge of the cell is in the set.
+    #[inline]
+    pub fn edge_v(&self, p: Point) -> bool {
+        self.get(self.index_v(p))
+    }
+
+    /// Adds or removes the left edge of the cell.
+    #[inline]
+    pub fn set_edge_v(&mut self, p: Point, line: bool) {
+        let i = self.index_v(p);
+        self.set(i, line)
+    }
+
+    /// Returns true if the edge of the index is in the set. The index is in
+    /// the order described in the type documentation.
+    #[inline]
+    pub fn get(&self, i: usize) -> bool {
+        assert!(i < self.edge_len());
+        self.words[i / WORD_BITS] & (1 << (i % WORD_BITS)) != 0
+    }
+
+    /// Adds or removes the edge of the index.
+    #[inline]
+    pub fn set(&mut self, i: usize, line: bool) {
+        assert!(i < self.edge_len());
+        let mask = 1 << (i % WORD_BITS);
+        if line {
+            self.words[i / WORD_BITS] |= mask;
+        } else {
+            self.words[i / WORD_BITS] &= !mask;
+        }
+    }
+
+    /// Returns the number of the edges in only one of the sets.
+    pub fn hamming_distance(&self, other: &LoopBits) -> u32 {
+        assert_eq!(self.size, other.size);
+        self.words
+            .iter()
+            .zip(&other.words)
+            .fold(0, |sum, (a, b)| sum + (a ^ b).count_ones())
+    }
+
+    /// Returns the Jaccard distance between the sets, which is 0 if the sets
+    /// are the same and 1 if they have no common edge.
+    pub fn distance(&self, other: &LoopBits) -> f64 {
+        let union = self.union(other).count();
+        if union == 0 {
+            return 0.0;
+        }
+        1.0 - self.intersection(other).count() as f64 / union as f64
+    }
+
+    /// Returns the number of the edges around the cell in the set.
+    pub fn cell_lines(&self, p: Point) -> u8 {
+        let edges = [self.edge_h(p),
+                     self.edge_h(Point(p.0 + 1, p.1)),
+                     self.edge_v(p),
+                     self.edge_v(Point(p.0, p.1 + 1))];
+        edges.iter().filter(|&&b| b).count() as u8
+    }
+
+    /// Returns the edges in either of the sets.
+    pub fn union(&self, other: &LoopBits) -> LoopBits {
+        assert_eq!(self.size, other.size);
+        LoopBits {
+            size: self.size,
+            words: self.words.iter().zip(&other.words).map(|(a, b)| a | b).collect(),
+        }
+    }
+
+    /// Returns the edges in both of the sets.
+    pub fn intersection(&self, other: &LoopBits) -> LoopBits {
+        assert_eq!(self.size, other.size);
+        LoopBits {
+            size: self.size,
+            words: self.words.iter().zip(&other.words).map(|(a, b)| a & b).collect(),
+        }
+    }
+
+    /// Returns true if all edges in the set are also in the other set.
+    pub fn is_subset(&self, other: &LoopBits) -> bool {
+        assert_eq!(self.size, other.size);
+        self.words.iter().zip(&other.words).all(|(a, b)| a & !b == 0)
+    }
+
+    /// Returns the distances from each cell to the nearest cell touching a
+    /// line, counted in steps between adjacent cells. The cells touching a
+    /// line are at the distance 0. Returns `None` if the set is empty.
+    pub fn distance_field(&self) -> Option<DistanceField> {
+        let size = self.size;
+        let index = |p: Point| (p.0 * size.1 + p.1) as usize;
+        let mut dists = vec![None; (size.0 * size.1) as usize];
+        let mut queue = VecDeque::new();
+        for r in 0..size.0 {
+            for c in 0..size.1 {
+                let p = Point(r, c);
+                if self.cell_lines(p) > 0 {
+                    dists[index(p)] = Some(0);
+                    queue.push_back(p);
+                }
+            }
+        }
+        if queue.is_empty() {
+            return None;
+        }
+
+        while let Some(p) = queue.pop_front() {
+            let d = dists[index(p)].unwrap();
+            for &m in &Move::ALL_DIRECTIONS {
+                let q = p + m;
+                if 0 <= q.0 && q.0 < size.0 && 0 <= q.1 && q.1 < size.1 &&
+                   dists[index(q)].is_none() {
+                    dists[index(q)] = Some(d + 1);
+                    queue.push_back(q);
+                }
+            }
+        }
+
+        Some(DistanceField {
+            size: size,
+            dists: dists.into_iter().map(|d| d.unwrap()).collect(),
+        })
+    }
+
+    fn index_h(&self, p: Point) -> usize {
+        assert!(0 <= p.0 && p.0 <= self.size.0 && 0 <= p.1 && p.1 < self.size.1);
+        (p.0 * self.size.1 + p.1) as usize
+    }
+
+    fn index_v(&self, p: Point) -> usize {
+        assert!(0 <= p.0 && p.0 < self.size.0 && 0 <= p.1 && p.1 <= self.size.1);
+        ((self.size.0 + 1) * self.size.1 + p.0 * (self.size.1 + 1) + p.1) as usize
+    }
+}
+
+/// The distances from the cells of a puzzle to the loop.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DistanceField {
+    size: Size,
+    dists: Vec<u32>,
+}
+
+impl DistanceField {
+    /// Returns the distance from the cell to the loop.
+    pub fn get(&self, p: Point) -> u32 {
+        assert!(0 <= p.0 && p.0 < self.size.0 && 0 <= p.1 && p.1 < self.size.1);
+        self.dists[(p.0 * self.size.1 + p.1) as usize]
+    }
+
+    /// Returns the largest distance, which is small if the loop visits the
+    /// whole board.
+    pub fn max(&self) -> u32 {
+        self.dists.iter().cloned().max().unwrap_or(0)
+    }
+
+    /// Returns the mean of the distances.
+    pub fn mean(&self) -> f64 {
+        let sum = self.dists.iter().fold(0, |sum, &d| sum + d);
+        sum as f64 / self.dists.len() as f64
+    }
+}
+
+fn edge_len(size: Size) -> usize {
+    ((size.0 + 1) * size.1 + size.0 * (size.1 + 1)) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LoopBits;
+    use geom::{Geom, Point, Size};
+    use puzzle::{Edge, Puzzle};
+
+    fn lines(input: &str) -> (Puzzle, LoopBits) {
+        let puzzle = input.parse::<Puzzle>().unwrap();
+        let bits = LoopBits::from_puzzle(&puzzle);
+        (puzzle, bits)
+    }
+
+    #[test]
+    fn max_loop_len() {
+        assert_eq!(4, LoopBits::max_loop_len(Size(1, 1)));
+        assert_eq!(12, LoopBits::max_loop_len(Size(1, 5)));
+        assert_eq!(8, LoopBits::max_loop_len(Size(2, 2)));
+        assert_eq!(20, LoopBits::max_loop_len(Size(3, 4)));
+
+        // The border of the comb passes all the vertices.
+        let (_, bits) = lines("
++-+-+-+
+|     |
++ +-+ +
+| | | |
++-+ +-+
+");
+        assert_eq!(LoopBits::max_loop_len(Size(2, 3)), bits.count());
+    }
+
+    #[test]
+    fn from_puzzle() {
+        let input = "
++-+-+ +
+|3  |
++-+ + +
+  |1|
++ +-+ +
+";
+        let (puzzle, bits) = lines(input);
+        assert_eq!(Size(2, 3), bits.size());
+        assert_eq!(17, bits.edge_len());
+        assert_eq!(8, bits.count());
+        assert_eq!(3, bits.cell_lines(Point(0, 0)));
+        assert_eq!(3, bits.cell_lines(Point(1, 1)));
+        assert_eq!(1, bits.cell_lines(Point(1, 2)));
+
+        for r in 0..(puzzle.row() + 1) {
+            for c in 0..puzzle.column() {
+                let p = Point(r, c);
+                assert_eq!(puzzle.edge_h(p) == Some(Edge::Line), bits.edge_h(p));
+            }
+        }
+        for r in 0..puzzle.row() {
+            for c in 0..(puzzle.column() + 1) {
+                let p = Point(r, c);
+                assert_eq!(puzzle.edge_v(p) == Some(Edge::Line), bits.edge_v(p));
+            }
+        }
+    }
+
+    #[test]
+    fn apply_to() {
+        let input = "
++-+-+
+|   |
++-+-+
+";
+        let (puzzle, bits) = lines(input);
+        let mut output = Puzzle::new(puzzle.size());
+        bits.apply_to(&mut output);
+        assert_eq!(bits, LoopBits::from_puzzle(&output));
+        assert_eq!(Some(Edge::Cross), output.edge_v(Point(0, 1)));
+        assert_eq!(Some(Edge::Line), output.edge_v(Point(0, 2)));
+    }
+
+    #[test]
+    fn set_operations() {
+        let mut a = LoopBits::new(Size(8, 8));
+        let mut b = LoopBits::new(Size(8, 8));
+        assert!(a.edge_len() > 64);
+        a.set_edge_h(Point(0, 0), true);
+        a.set_edge_v(Point(7, 8), true);
+        b.set_edge_v(Point(7, 8), true);
+        b.set_edge_h(Point(8, 7), true);
+
+        assert_eq!(2, a.hamming_distance(&b));
+        assert_eq!(0, a.hamming_distance(&a));
+        assert_eq!(3, a.union(&b).count());
+        assert_eq!(1, a.intersection(&b).count());
+        assert!(a.intersection(&b).is_subset(&a));
+        assert!(a.is_subset(&a.union(&b)));
+        assert!(!a.is_subset(&b));
+
+        assert_eq!(0.0, a.distance(&a));
+        assert!((a.distance(&b) - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(1.0, a.distance(&LoopBits::new(Size(8, 8))));
+        assert_eq!(0.0, LoopBits::new(Size(8, 8)).distance(&LoopBits::new(Size(8, 8))));
+
+        b.set_edge_h(Point(8, 7), false);
+        assert!(b.is_subset(&a));
+        assert!(!b.edge_h(Point(8, 7)));
+    }
+
+    #[test]
+    fn distance_field() {
+        let input = "
++-+ + + +
+| |
++-+ + + +
+
++ + + + +
+";
+        let (_, bits) = lines(input);
+        let field = bits.distance_field().unwrap();
+        assert_eq!(0, field.get(Point(0, 0)));
+        assert_eq!(0, field.get(Point(0, 1)));
+        assert_eq!(0, field.get(Point(1, 0)));
+        assert_eq!(1, field.get(Point(1, 1)));
+        assert_eq!(3, field.get(Point(1, 3)));
+        assert_eq!(3, field.max());
+        assert!((field.mean() - 9.0 / 8.0).abs() < 1e-9);
+
+        assert_eq!(None, LoopBits::new(Size(2, 2)).distance_field());
+    }
+}
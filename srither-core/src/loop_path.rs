@@ -0,0 +1,209 @@
+// Copyright (c) 2016 srither-core developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Loops traced along the lines, as sequences of vertices.
+//!
+//! The vertex `Point(r, c)` is the upper-left corner of the cell
+//! `Point(r, c)`, so the vertices of a puzzle range from `Point(0, 0)` to
+//! `Point(row, column)`. The orientation is the one seen on the screen,
+//! where the rows go down.
+
+use geom::{Move, Point};
+use loop_bits::LoopBits;
+
+/// The direction in which a loop goes around.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Orientation {
+    /// Clockwise on the screen.
+    Clockwise,
+    /// Counterclockwise on the screen.
+    CounterClockwise,
+}
+
+/// A closed loop, as the sequence of its vertices. The last vertex is
+/// connected to the first one and is not repeated.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Loop {
+    vertices: Vec<Point>,
+}
+
+impl Loop {
+    /// Returns the vertices of the loop in order.
+    pub fn vertices(&self) -> &[Point] {
+        &self.vertices
+    }
+
+    /// Returns the area surrounded by the loop, in cells. The area is
+    /// positive if the loop is counterclockwise, and negative otherwise.
+    pub fn signed_area(&self) -> i32 {
+        // The shoelace formula with `x = column` and `y = -row`.
+        let n = self.vertices.len();
+        let twice = (0..n).fold(0, |sum, i| {
+            let (p, q) = (self.vertices[i], self.vertices[(i + 1) % n]);
+            sum + q.1 * p.0 - p.1 * q.0
+        });
+        twice / 2
+    }
+
+    /// Returns the direction in which the loop goes around.
+    pub fn orientation(&self) -> Orientation {
+        if self.signed_area() > 0 {
+            Orientation::CounterClockwise
+        } else {
+            Orientation::Clockwise
+        }
+    }
+
+    /// Returns the loop going around in the opposite direction, starting
+    /// from the same vertex.
+    pub fn reversed(&self) -> Loop {
+        let mut vertices = self.vertices.clone();
+        vertices[1..].reverse();
+        Loop { vertices: vertices }
+    }
+
+    /// Returns the same loop starting from its first vertex in row-major
+    /// order, going around counterclockwise. Two loops drawn along the same
+    /// edges have the same canonical form.
+    pub fn canonical(&self) -> Loop {
+        let start = (0..self.vertices.len()).min_by_key(|&i| self.vertices[i]).unwrap_or(0);
+        let mut vertices = self.vertices[start..].to_vec();
+        vertices.extend_from_slice(&self.vertices[..start]);
+        let lp = Loop { vertices: vertices };
+        match lp.orientation() {
+            Orientation::CounterClockwise => lp,
+            Orientation::Clockwise => lp.reversed(),
+        }
+    }
+}
+
+/// Traces the loops drawn by the lines, in the order of their first
+/// vertices in row-major order. Each loop starts from its first vertex.
+///
+/// Returns `None` if the lines do not form loops, i.e. some vertex has one,
+/// three or four lines.
+pub fn extract(bits: &LoopBits) -> Option<Vec<Loop>> {
+    let size = bits.size();
+    let line = |p: Point, m: Move| {
+        let q = p + m;
+        if q.0 < 0 || q.1 < 0 || q.0 > size.0 || q.1 > size.1 {
+            return false;
+        }
+        if m.0 == 0 {
+            bits.edge_h(Point(p.0, if m.1 > 0 { p.1 } else { q.1 }))
+        } else {
+            bits.edge_v(Point(if m.0 > 0 { p.0 } else { q.0 }, p.1))
+        }
+    };
+
+    let mut loops = vec![];
+    let mut visited = vec![false; ((size.0 + 1) * (size.1 + 1)) as usize];
+    let index = |p: Point| (p.0 * (size.1 + 1) + p.1) as usize;
+    for r in 0..(size.0 + 1) {
+        for c in 0..(size.1 + 1) {
+            let start = Point(r, c);
+            let moves = Move::ALL_DIRECTIONS
+                            .iter()
+                            .cloned()
+                            .filter(|&m| line(start, m))
+                            .collect::<Vec<_>>();
+            match moves.len() {
+                0 => continue,
+                2 => {}
+                _ => return None,
+            }
+            if visited[index(start)] {
+                continue;
+            }
+
+            let mut vertices = vec![];
+            let (mut p, mut m) = (start, moves[0]);
+            loop {
+                visited[index(p)] = true;
+                vertices.push(p);
+                p = p + m;
+                if p == start {
+                    break;
+                }
+                let back = Move(-m.0, -m.1);
+                let next = Move::ALL_DIRECTIONS
+                               .iter()
+                               .cloned()
+                               .filter(|&n| n != back && line(p, n))
+                               .collect::<Vec<_>>();
+                if next.len() != 1 {
+                    return None;
+                }
+                m = next[0];
+            }
+            loops.push(Loop { vertices: vertices });
+        }
+    }
+    Some(loops)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Loop, Orientation, extract};
+    use geom::Point;
+    use loop_bits::LoopBits;
+    use puzzle::Puzzle;
+
+    fn loops(input: &str) -> Option<Vec<Loop>> {
+        extract(&LoopBits::from_puzzle(&input.parse::<Puzzle>().unwrap()))
+    }
+
+    #[test]
+    fn orientation() {
+        let input = "
++-+-+ +
+|3  |
++-+ + +
+  |1|
++ +-+ +
+";
+        let found = loops(input).unwrap();
+        assert_eq!(1, found.len());
+        let lp = &found[0];
+        assert_eq!(8, lp.vertices().len());
+        assert_eq!(Point(0, 0), lp.vertices()[0]);
+        assert_eq!(3, lp.signed_area().abs());
+
+        let rev = lp.reversed();
+        assert_eq!(Point(0, 0), rev.vertices()[0]);
+        assert_eq!(-lp.signed_area(), rev.signed_area());
+        assert!(lp.orientation() != rev.orientation());
+
+        let canonical = lp.canonical();
+        assert_eq!(Orientation::CounterClockwise, canonical.orientation());
+        assert_eq!(vec![Point(0, 0), Point(1, 0), Point(1, 1)],
+                   canonical.vertices()[..3].to_vec());
+        assert_eq!(canonical, rev.canonical());
+    }
+
+    #[test]
+    fn multiple_loops() {
+        let input = "
++-+ +-+
+| | | |
++-+ +-+
+";
+        let found = loops(input).unwrap();
+        assert_eq!(2, found.len());
+        assert_eq!(Point(0, 0), found[0].vertices()[0]);
+        assert_eq!(Point(0, 2), found[1].vertices()[0]);
+        assert!(found.iter().all(|lp| lp.signed_area().abs() == 1));
+
+        let branch = "
++-+-+
+| | |
++-+-+
+";
+        assert_eq!(None, loops(branch));
+    }
+}
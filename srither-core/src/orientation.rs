@@ -0,0 +1,270 @@
+// Copyright (c) 2016 srither-core developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Orientations of puzzles, and normalizing puzzles to an orientation.
+//!
+//! An orientation is written as the operations of `srither transform`
+//! separated by commas, such as `flip-h,rotate90`, or `identity`.
+
+use std::fmt;
+use std::error::Error;
+use std::str::FromStr;
+
+use geom::Geom;
+use puzzle::Puzzle;
+
+/// One of the eight orientations of a puzzle: an optional horizontal flip,
+/// followed by rotations by 90 degrees clockwise.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Orientation {
+    flip: bool,
+    rotations: u8,
+}
+
+impl Orientation {
+    /// Returns the orientation which keeps puzzles as they are.
+    pub fn identity() -> Orientation {
+        Orientation {
+            flip: false,
+            rotations: 0,
+        }
+    }
+
+    /// Returns all orientations, from the identity.
+    pub fn all() -> Vec<Orientation> {
+        let mut all = vec![];
+        for &flip in &[false, true] {
+            for rotations in 0..4 {
+                all.push(Orientation {
+                    flip: flip,
+                    rotations: rotations,
+                });
+            }
+        }
+        all
+    }
+
+    /// Returns true if the orientation keeps puzzles as they are.
+    pub fn is_identity(self) -> bool {
+        self == Orientation::identity()
+    }
+
+    /// Returns the orientation undoing this one.
+    pub fn inverse(self) -> Orientation {
+        if self.flip {
+            // A flip after rotations is a flip, which undoes itself.
+            self
+        } else {
+            Orientation {
+                flip: false,
+                rotations: (4 - self.rotations) % 4,
+            }
+        }
+    }
+
+    /// Returns the orientation applying this one and then `next`.
+    pub fn then(self, next: Orientation) -> Orientation {
+        let mut o = self;
+        if next.flip {
+            // A flip after rotations is the reversed rotations after a flip.
+            o = Orientation {
+                flip: !o.flip,
+                rotations: (4 - o.rotations) % 4,
+            };
+        }
+        Orientation {
+            flip: o.flip,
+            rotations: (o.rotations + next.rotations) % 4,
+        }
+    }
+
+    /// Returns the puzzle in this orientation. Unless the orientation is the
+    /// identity, the returned puzzle has no annotations and no clue layers,
+    /// as with the transformations of `Puzzle`.
+    pub fn apply(self, puzzle: &Puzzle) -> Puzzle {
+        let mut puzzle = if self.flip {
+            puzzle.flip_h()
+        } else {
+            puzzle.clone()
+        };
+        for _ in 0..self.rotations {
+            puzzle = puzzle.rotate90();
+        }
+        puzzle
+    }
+}
+
+impl fmt::Display for Orientation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_identity() {
+            return f.write_str("identity");
+        }
+        let mut ops = vec![];
+        if self.flip {
+            ops.push("flip-h");
+        }
+        for _ in 0..self.rotations {
+            ops.push("rotate90");
+        }
+        f.write_str(&ops.join(","))
+    }
+}
+
+/// An error type which is returned from parsing a string into orientation.
+#[derive(Copy, Clone, Debug)]
+pub struct ParseOrientationError;
+
+impl Error for ParseOrientationError {
+    fn description(&self) -> &str {
+        "unknown operation in orientation"
+    }
+}
+
+impl fmt::Display for ParseOrientationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.description().fmt(f)
+    }
+}
+
+impl FromStr for Orientation {
+    type Err = ParseOrientationError;
+
+    fn from_str(s: &str) -> Result<Orientation, ParseOrientationError> {
+        let flip = Orientation {
+            flip: true,
+            rotations: 0,
+        };
+        let rotate = |n| {
+            Orientation {
+                flip: false,
+                rotations: n,
+            }
+        };
+
+        let mut o = Orientation::identity();
+        for op in s.split(',').map(|op| op.trim()) {
+            o = match op {
+                "identity" => o,
+                "rotate90" => o.then(rotate(1)),
+                "flip-h" => o.then(flip),
+                // Flipping vertically is flipping horizontally and rotating
+                // by 180 degrees.
+                "flip-v" => o.then(flip).then(rotate(2)),
+                _ => return Err(ParseOrientationError),
+            };
+        }
+        Ok(o)
+    }
+}
+
+/// The orientation puzzles are normalized to.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Normalization {
+    /// Rotates the puzzles with more columns than rows by 90 degrees, so
+    /// that they have at least as many rows as columns.
+    Portrait,
+    /// Takes the orientation with at least as many rows as columns whose
+    /// text format is the smallest, so that the puzzles equal up to
+    /// rotations and flips are normalized to the same puzzle.
+    Canonical,
+}
+
+impl fmt::Display for Normalization {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Normalization::Portrait => f.write_str("portrait"),
+            Normalization::Canonical => f.write_str("canonical"),
+        }
+    }
+}
+
+impl FromStr for Normalization {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Normalization, ()> {
+        match s {
+            "portrait" => Ok(Normalization::Portrait),
+            "canonical" => Ok(Normalization::Canonical),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Returns the normalized puzzle and the orientation applied to it. Applying
+/// the inverse of the orientation to the puzzle or its solutions restores
+/// the original orientation.
+pub fn normalize(puzzle: &Puzzle, normalization: Normalization) -> (Puzzle, Orientation) {
+    match normalization {
+        Normalization::Portrait => {
+            if puzzle.row() >= puzzle.column() {
+                (puzzle.clone(), Orientation::identity())
+            } else {
+                let o = Orientation {
+                    flip: false,
+                    rotations: 1,
+                };
+                (o.apply(puzzle), o)
+            }
+        }
+        Normalization::Canonical => {
+            let mut best: Option<(String, Puzzle, Orientation)> = None;
+            for o in Orientation::all() {
+                let p = o.apply(puzzle);
+                if p.row() < p.column() {
+                    continue;
+                }
+                let s = p.to_string();
+                if best.as_ref().map_or(true, |b| s < b.0) {
+                    best = Some((s, p, o));
+                }
+            }
+            let (_, p, o) = best.unwrap();
+            (p, o)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Normalization, Orientation, normalize};
+    use geom::Geom;
+    use puzzle::Puzzle;
+
+    #[test]
+    fn orientation() {
+        let puzzle = "12_\n__3\n".parse::<Puzzle>().unwrap();
+        for o in Orientation::all() {
+            let transformed = o.apply(&puzzle);
+            assert_eq!(puzzle, o.inverse().apply(&transformed));
+            assert_eq!(o, o.to_string().parse().unwrap());
+        }
+        assert_eq!(puzzle.flip_v(),
+                   "flip-v".parse::<Orientation>().unwrap().apply(&puzzle));
+        assert_eq!(puzzle.flip_h().rotate90(),
+                   "flip-h,rotate90".parse::<Orientation>().unwrap().apply(&puzzle));
+        assert!("rotate45".parse::<Orientation>().is_err());
+    }
+
+    #[test]
+    fn normalized() {
+        let puzzle = "12_\n__3\n".parse::<Puzzle>().unwrap();
+        let (portrait, o) = normalize(&puzzle, Normalization::Portrait);
+        assert_eq!((3, 2), (portrait.row(), portrait.column()));
+        assert_eq!(puzzle, o.inverse().apply(&portrait));
+
+        let (canonical, o) = normalize(&puzzle, Normalization::Canonical);
+        assert_eq!(puzzle, o.inverse().apply(&canonical));
+        for o in Orientation::all() {
+            let (c, _) = normalize(&o.apply(&puzzle), Normalization::Canonical);
+            assert_eq!(canonical, c);
+        }
+        for &n in &[Normalization::Portrait, Normalization::Canonical] {
+            assert_eq!(Ok(n), n.to_string().parse());
+        }
+    }
+}
@@ -0,0 +1,26 @@
+// Copyright (c) 2016 srither-core developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! The stable surface of the crate.
+//!
+//! The items here are kept across the versions of the same major version, so
+//! that the users importing only them can upgrade without following the moves
+//! of the other items between the modules. The other modules may change in
+//! any version.
+//!
+//! ```
+//! use srither_core::prelude::*;
+//!
+//! let mut puzzle = "3_\n__".parse::<Puzzle>().unwrap();
+//! puzzle.set_hint(Point(1, 1), Some(2));
+//! assert_eq!(Size(2, 2), puzzle.size());
+//! assert_eq!(None, puzzle.edge_h(Point(0, 0)));
+//! ```
+
+pub use geom::{Geom, Point, Size};
+pub use puzzle::{Edge, Hint, ParsePuzzleError, Puzzle, Side};
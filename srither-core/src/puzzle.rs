@@ -8,11 +8,19 @@
 
 //! Slither link puzzle data structure.
 
+use std::cmp;
 use std::error::Error;
 use std::fmt;
 
-use geom::{Geom, Point, Size, Table};
+use annotation::Annotations;
+use clue_layer::ClueLayers;
+use format::{self, ParseFormatError, PuzzleFormat};
+use format::compact::{self, Compact, ParseCompactError};
+use format::puzzlink::{self, ParsePuzzlinkError, Puzzlink};
+use geom::{Geom, Move, Point, Size, Table};
 use lattice_parser::ParseLatticeError;
+use loop_bits::LoopBits;
+use loop_path;
 
 /// A hint of the slither link puzzle.
 pub type Hint = Option<u8>;
@@ -36,7 +44,9 @@ pub enum Edge {
 }
 
 /// Slither link puzzle data.
-#[derive(Clone, Debug, Eq, PartialEq)]
+///
+/// The annotations are not compared by `==`, but the clue layers are.
+#[derive(Clone, Debug)]
 pub struct Puzzle {
     size: Size,
     hint: Table<Hint>,
@@ -44,13 +54,26 @@ pub struct Puzzle {
     edge_v: Table<Option<Edge>>,
     edge_h: Table<Option<Edge>>,
     sum_of_hint: u32,
+    annotations: Annotations,
+    clue_layers: ClueLayers,
 }
 
+impl PartialEq for Puzzle {
+    fn eq(&self, other: &Puzzle) -> bool {
+        self.size == other.size && self.hint == other.hint && self.side == other.side &&
+        self.edge_v == other.edge_v && self.edge_h == other.edge_h &&
+        self.clue_layers == other.clue_layers
+    }
+}
+
+impl Eq for Puzzle {}
+
 impl Puzzle {
     /// Creates an empty slither link puzzle.
     #[inline]
     pub fn new(size: Size) -> Puzzle {
         assert!(size.0 > 0 && size.1 > 0);
+        check_size(size);
         let hint = vec![None; (size.0 * size.1) as usize];
         let side = vec![None; (size.0 * size.1) as usize];
         let edge_v = vec![None; (size.0 * (size.1 + 1)) as usize];
@@ -58,6 +81,52 @@ impl Puzzle {
         Puzzle::with_data(size, hint, side, edge_v, edge_h)
     }
 
+    /// Parses a puzzle in any registered format, and returns it with the
+    /// format. The formats are tried in the order of the registry, and the
+    /// first one whose detector accepts the string parses it.
+    pub fn parse_any(s: &str) -> Result<(Puzzle, &'static PuzzleFormat), ParseFormatError> {
+        let format = try!(format::detect(s));
+        Ok((try!(format.parse(s)), format))
+    }
+
+    /// Parses a puzz.link URL, or a pzv.jp URL of the same format, e.g.
+    /// `https://puzz.link/p?slither/5/2/8cgc3`, into a puzzle of its hints.
+    pub fn from_pzv_url(url: &str) -> Result<Puzzle, ParsePuzzlinkError> {
+        puzzlink::parse(url)
+    }
+
+    /// Returns the puzz.link URL of the hints of the puzzle.
+    pub fn to_pzv_url(&self) -> String {
+        Puzzlink(self).to_string().trim_right().to_owned()
+    }
+
+    /// Parses a compact string, e.g. `5x2-3a2c2b3`, into a puzzle of its
+    /// hints.
+    pub fn from_compact(s: &str) -> Result<Puzzle, ParseCompactError> {
+        compact::parse(s)
+    }
+
+    /// Returns the hints of the puzzle in a compact single-line string, of
+    /// the characters safe in URLs only.
+    pub fn to_compact(&self) -> String {
+        Compact(self).to_string()
+    }
+
+    /// Returns the vertices of the loop drawn by the lines in the order of
+    /// traversal, e.g. to plot the solution as a polyline, or `None` unless
+    /// the lines are a single loop.
+    ///
+    /// The vertex `Point(r, c)` is the upper-left corner of the cell
+    /// `Point(r, c)`. The loop starts from its first vertex in row-major
+    /// order and goes around counterclockwise, and the first vertex is not
+    /// repeated at the end.
+    pub fn loop_path(&self) -> Option<Vec<Point>> {
+        match loop_path::extract(&LoopBits::from_puzzle(self)) {
+            Some(ref loops) if loops.len() == 1 => Some(loops[0].canonical().vertices().to_owned()),
+            _ => None,
+        }
+    }
+
     #[inline]
     fn with_data(size: Size,
                  hint: Vec<Hint>,
@@ -66,6 +135,7 @@ impl Puzzle {
                  edge_h: Vec<Option<Edge>>)
                  -> Puzzle {
         assert!(size.0 > 0 && size.1 > 0);
+        check_size(size);
         let mut sum_of_hint = 0;
         for &h in &hint {
             if let Some(n) = h {
@@ -83,6 +153,8 @@ impl Puzzle {
             edge_v: edge_v,
             edge_h: edge_h,
             sum_of_hint: sum_of_hint,
+            annotations: Annotations::new(),
+            clue_layers: ClueLayers::new(),
         }
     }
 
@@ -139,6 +211,217 @@ impl Puzzle {
     pub fn set_edge_v(&mut self, p: Point, edge: Option<Edge>) {
         self.edge_v[p] = edge;
     }
+
+    /// Returns the annotations of the cells and the edges.
+    #[inline]
+    pub fn annotations(&self) -> &Annotations {
+        &self.annotations
+    }
+
+    /// Returns the mutable annotations of the cells and the edges.
+    #[inline]
+    pub fn annotations_mut(&mut self) -> &mut Annotations {
+        &mut self.annotations
+    }
+
+    /// Returns the auxiliary clues of the puzzle variant.
+    #[inline]
+    pub fn clue_layers(&self) -> &ClueLayers {
+        &self.clue_layers
+    }
+
+    /// Returns the mutable auxiliary clues of the puzzle variant.
+    #[inline]
+    pub fn clue_layers_mut(&mut self) -> &mut ClueLayers {
+        &mut self.clue_layers
+    }
+
+    /// Returns the puzzle with the hints and the clue layers only, without
+    /// the sides, the edges and the annotations.
+    pub fn hints_only(&self) -> Puzzle {
+        let mut puzzle = Puzzle::new(self.size);
+        puzzle.clue_layers = self.clue_layers.clone();
+        for r in 0..self.row() {
+            for c in 0..self.column() {
+                let p = Point(r, c);
+                puzzle.set_hint(p, self.hint(p));
+            }
+        }
+        puzzle
+    }
+
+    /// Sets all edges which are not lines to crosses.
+    ///
+    /// The solver may leave some edges off the loop unknown, depending on
+    /// how the puzzle is solved. Filling them makes the same solutions equal
+    /// and written in the same way.
+    pub fn fill_crosses(&mut self) {
+        for r in 0..(self.row() + 1) {
+            for c in 0..self.column() {
+                let p = Point(r, c);
+                if self.edge_h(p) != Some(Edge::Line) {
+                    self.set_edge_h(p, Some(Edge::Cross));
+                }
+            }
+        }
+        for r in 0..self.row() {
+            for c in 0..(self.column() + 1) {
+                let p = Point(r, c);
+                if self.edge_v(p) != Some(Edge::Line) {
+                    self.set_edge_v(p, Some(Edge::Cross));
+                }
+            }
+        }
+    }
+
+    /// Returns true if some edge is neither line nor cross.
+    pub fn has_unknown_edge(&self) -> bool {
+        for r in 0..(self.row() + 1) {
+            for c in 0..self.column() {
+                if self.edge_h(Point(r, c)).is_none() {
+                    return true;
+                }
+            }
+        }
+        for r in 0..self.row() {
+            for c in 0..(self.column() + 1) {
+                if self.edge_v(Point(r, c)).is_none() {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// Returns the puzzle rotated by 90 degrees clockwise.
+    ///
+    /// The puzzles returned by the transformations have no annotations and
+    /// no clue layers.
+    pub fn rotate90(&self) -> Puzzle {
+        let row = self.row();
+        self.transformed(Size(self.column(), row), |p| Point(row - 1 - p.1, p.0))
+    }
+
+    /// Returns the puzzle flipped horizontally.
+    pub fn flip_h(&self) -> Puzzle {
+        let col = self.column();
+        self.transformed(self.size, |p| Point(p.0, col - 1 - p.1))
+    }
+
+    /// Returns the puzzle flipped vertically.
+    pub fn flip_v(&self) -> Puzzle {
+        let row = self.row();
+        self.transformed(self.size, |p| Point(row - 1 - p.0, p.1))
+    }
+
+    /// Returns the area of the size from the origin. The edges on the border
+    /// of the area are kept.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the area is not contained in the puzzle.
+    pub fn crop(&self, origin: Point, size: Size) -> Puzzle {
+        assert!(size.0 > 0 && size.1 > 0);
+        assert!(self.contains(origin) && self.contains(origin + Move(size.0 - 1, size.1 - 1)));
+        self.transformed(size, |p| Point(p.0 + origin.0, p.1 + origin.1))
+    }
+
+    // Creates a puzzle of the size whose cell at `p` is the cell at `f(p)`
+    // of this puzzle. `f` must map adjacent cells to adjacent cells.
+    fn transformed<F>(&self, size: Size, f: F) -> Puzzle
+        where F: Fn(Point) -> Point
+    {
+        let mut puzzle = Puzzle::new(size);
+        for r in 0..size.0 {
+            for c in 0..size.1 {
+                let p = Point(r, c);
+                puzzle.set_hint(p, self.hint(f(p)));
+                puzzle.set_side(p, self.side(f(p)));
+            }
+        }
+        for r in 0..(size.0 + 1) {
+            for c in 0..size.1 {
+                let p = Point(r, c);
+                puzzle.set_edge_h(p, self.edge_between(f(p + Move::UP), f(p)));
+            }
+        }
+        for r in 0..size.0 {
+            for c in 0..(size.1 + 1) {
+                let p = Point(r, c);
+                puzzle.set_edge_v(p, self.edge_between(f(p + Move::LEFT), f(p)));
+            }
+        }
+        puzzle
+    }
+
+    // Returns the edge between the adjacent cells.
+    fn edge_between(&self, p0: Point, p1: Point) -> Option<Edge> {
+        if p0.0 == p1.0 {
+            self.edge_v(Point(p0.0, cmp::max(p0.1, p1.1)))
+        } else {
+            self.edge_h(Point(cmp::max(p0.0, p1.0), p0.1))
+        }
+    }
+
+    /// Returns the fingerprint of the puzzle, which identifies the puzzle
+    /// regardless of its rotation and reflection.
+    ///
+    /// The fingerprint is computed only from the hints. The puzzle is
+    /// transformed by each of the 8 rotations and reflections, and written in
+    /// the form of the rows of `0`-`4` and `_`, each followed by `\n`. The
+    /// lexicographically smallest string is the canonical form, and the
+    /// fingerprint is its 64-bit FNV-1a hash. This algorithm must not be
+    /// changed as the fingerprints are stored in files. It is usually
+    /// written in 16 lower hexadecimal digits.
+    pub fn fingerprint(&self) -> u64 {
+        let canonical = (0..8).map(|sym| self.hint_string(sym)).min().unwrap();
+
+        let mut hash = 0xcbf29ce484222325;
+        for b in canonical.bytes() {
+            hash ^= b as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+        hash
+    }
+
+    // Writes the hints transformed by the symmetry. Bit 0 of `sym` transposes
+    // the rows and the columns, and bits 1 and 2 flip them.
+    fn hint_string(&self, sym: u32) -> String {
+        let transpose = sym & 1 != 0;
+        let (flip_r, flip_c) = (sym & 2 != 0, sym & 4 != 0);
+        let (rows, cols) = if transpose {
+            (self.column(), self.row())
+        } else {
+            (self.row(), self.column())
+        };
+
+        let mut s = String::new();
+        for r in 0..rows {
+            for c in 0..cols {
+                let (r, c) = if transpose {
+                    (c, r)
+                } else {
+                    (r, c)
+                };
+                let r = if flip_r {
+                    self.row() - 1 - r
+                } else {
+                    r
+                };
+                let c = if flip_c {
+                    self.column() - 1 - c
+                } else {
+                    c
+                };
+                match self.hint(Point(r, c)) {
+                    Some(n) => s.push((b'0' + n) as char),
+                    None => s.push('_'),
+                }
+            }
+            s.push('\n');
+        }
+        s
+    }
 }
 
 impl Geom for Puzzle {
@@ -350,6 +633,22 @@ mod from_str_impl {
     }
 }
 
+// With the `paranoid` feature, panics if the cells and the edges of the size
+// cannot be numbered without overflow, instead of wrapping the indices around.
+#[cfg(feature = "paranoid")]
+fn check_size(size: Size) {
+    let num_points = size.0
+                         .checked_add(1)
+                         .and_then(|r| size.1.checked_add(1).and_then(|c| r.checked_mul(c)));
+    if num_points.is_none() {
+        panic!("too large puzzle: {} rows and {} columns", size.0, size.1);
+    }
+}
+
+#[cfg(not(feature = "paranoid"))]
+#[inline]
+fn check_size(_size: Size) {}
+
 mod display_impl {
     use super::{Edge, Puzzle};
     use std::fmt;
@@ -435,7 +734,7 @@ mod display_impl {
 mod tests {
     use std::fmt;
     use std::error::Error;
-    use super::{ParsePuzzleError, ParsePuzzleResult, Puzzle};
+    use super::{Edge, ParsePuzzleError, ParsePuzzleResult, Puzzle, Side};
     use geom::{Geom, Point, Size};
 
     fn check_error<T>(result: ParsePuzzleResult<T>, error: ParsePuzzleError)
@@ -444,6 +743,152 @@ mod tests {
         assert_eq!(result.unwrap_err().description(), error.description());
     }
 
+    #[test]
+    fn pzv_url() {
+        let puzzle = "3_2__\n_2__3\n".parse::<Puzzle>().unwrap();
+        assert_eq!("https://puzz.link/p?slither/5/2/8cgc3", puzzle.to_pzv_url());
+        for url in &["https://puzz.link/p?slither/5/2/8cgc3",
+                     "http://pzv.jp/p.html?slither/5/2/8cgc3/",
+                     "https://puzz.link/p?slither/5/2/8cgc3j"] {
+            assert_eq!(puzzle, Puzzle::from_pzv_url(url).unwrap());
+        }
+
+        let puzzle = Puzzle::from_pzv_url("https://puzz.link/p?slither/10/3/zk3.0c").unwrap();
+        assert_eq!(Size(3, 10), puzzle.size());
+        assert_eq!(Some(3), puzzle.hint(Point(2, 5)));
+        assert_eq!(None, puzzle.hint(Point(2, 6)));
+        assert_eq!(Some(0), puzzle.hint(Point(2, 7)));
+        assert_eq!(Some(2), puzzle.hint(Point(2, 8)));
+        assert_eq!(puzzle, Puzzle::from_pzv_url(&puzzle.to_pzv_url()).unwrap());
+        assert!(Puzzle::from_pzv_url("https://puzz.link/p?nurikabe/5/2/").is_err());
+    }
+
+    #[test]
+    fn compact() {
+        let puzzle = "3_2__\n_2__3\n".parse::<Puzzle>().unwrap();
+        assert_eq!("5x2-3a2c2b3", puzzle.to_compact());
+        assert_eq!(puzzle, Puzzle::from_compact("5x2-3a2c2b3").unwrap());
+
+        // The lines are not written.
+        let solved = "+-+\n|1|\n+-+\n".parse::<Puzzle>().unwrap();
+        assert_eq!("1x1-1", solved.to_compact());
+        assert_eq!(solved.hints_only(), Puzzle::from_compact("1x1-1").unwrap());
+        assert!(Puzzle::from_compact("1x1").is_err());
+    }
+
+    #[test]
+    fn loop_path() {
+        let solution = "+-+-+\n|2x2|\n+x+x+\n|2x2|\n+-+-+\n".parse::<Puzzle>().unwrap();
+        assert_eq!(Some(vec![Point(0, 0),
+                             Point(1, 0),
+                             Point(2, 0),
+                             Point(2, 1),
+                             Point(2, 2),
+                             Point(1, 2),
+                             Point(0, 2),
+                             Point(0, 1)]),
+                   solution.loop_path());
+
+        let two_loops = "+-+x+-+\n|1|0|1|\n+-+x+-+\n".parse::<Puzzle>().unwrap();
+        assert_eq!(None, two_loops.loop_path());
+        assert_eq!(None, "2_\n_2\n".parse::<Puzzle>().unwrap().loop_path());
+    }
+
+    #[test]
+    fn fingerprint() {
+        let puzzle = "3__\n_1_\n".parse::<Puzzle>().unwrap();
+        let variants = ["_3\n1_\n__\n", "__3\n_1_\n", "_1_\n3__\n", "__\n1_\n_3\n"];
+        for s in &variants {
+            let variant = s.parse::<Puzzle>().unwrap();
+            assert_eq!(puzzle.fingerprint(), variant.fingerprint());
+        }
+        assert!(puzzle.fingerprint() != "3__\n_2_\n".parse::<Puzzle>().unwrap().fingerprint());
+        assert!(puzzle.fingerprint() != "3__\n1__\n".parse::<Puzzle>().unwrap().fingerprint());
+
+        let mut solved = puzzle.clone();
+        solved.set_edge_h(Point(0, 0), Some(Edge::Line));
+        solved.set_side(Point(0, 0), Some(Side::In));
+        assert_eq!(puzzle.fingerprint(), solved.fingerprint());
+    }
+
+    #[test]
+    fn fingerprint_stability() {
+        // These values must not change across versions.
+        fn check(expected: u64, input: &str) {
+            assert_eq!(expected, input.parse::<Puzzle>().unwrap().fingerprint());
+        }
+        check(0xb10e60d71135df91, "3__\n_1_\n");
+        check(0x07fc1e07b4bd2c5f, "0\n");
+        check(0x1acb7064d3930137, "_2_2\n3___\n__1_\n");
+    }
+
+    #[test]
+    fn transform() {
+        let input = "
++-+-+ +
+|3  |
++-+ + +
+  |1|2
++ +-+ +
+";
+        let puzzle = input.parse::<Puzzle>().unwrap();
+
+        let hints = puzzle.hints_only();
+        assert_eq!(Some(3), hints.hint(Point(0, 0)));
+        assert_eq!(None, hints.edge_h(Point(0, 0)));
+        assert_eq!(hints, hints.rotate90().hints_only().rotate90().rotate90().rotate90());
+
+        let rotated = puzzle.rotate90();
+        assert_eq!(Size(3, 2), rotated.size());
+        assert_eq!(Some(3), rotated.hint(Point(0, 1)));
+        assert_eq!(Some(1), rotated.hint(Point(1, 0)));
+        assert_eq!(Some(2), rotated.hint(Point(2, 0)));
+        assert_eq!(Some(Edge::Line), rotated.edge_v(Point(0, 2)));
+        assert_eq!(Some(Edge::Line), rotated.edge_h(Point(0, 1)));
+        assert_eq!(None, rotated.edge_v(Point(2, 2)));
+        assert_eq!(puzzle.fingerprint(), rotated.fingerprint());
+        assert_eq!(puzzle, rotated.rotate90().rotate90().rotate90());
+        assert_eq!(puzzle.rotate90().rotate90(), puzzle.flip_h().flip_v());
+        assert_eq!(puzzle, puzzle.flip_h().flip_h());
+
+        let flipped = puzzle.flip_h();
+        assert_eq!(Some(3), flipped.hint(Point(0, 2)));
+        assert_eq!(Some(Edge::Line), flipped.edge_v(Point(0, 3)));
+        assert_eq!(None, flipped.edge_v(Point(0, 0)));
+
+        let cropped = puzzle.crop(Point(1, 1), Size(1, 2));
+        let expected = "
++ + +
+|1|2
++-+ +
+";
+        assert_eq!(expected.parse::<Puzzle>().unwrap(), cropped);
+    }
+
+    #[test]
+    #[should_panic]
+    fn crop_outside() {
+        let puzzle = "3__\n_1_\n".parse::<Puzzle>().unwrap();
+        let _ = puzzle.crop(Point(1, 1), Size(2, 2));
+    }
+
+    #[test]
+    fn fill_crosses() {
+        let input = "
++-+ +
+| |
++-+ +
+";
+        let mut puzzle = input.parse::<Puzzle>().unwrap();
+        assert!(puzzle.has_unknown_edge());
+        puzzle.fill_crosses();
+        assert!(!puzzle.has_unknown_edge());
+        assert_eq!(Some(Edge::Line), puzzle.edge_h(Point(0, 0)));
+        assert_eq!(Some(Edge::Line), puzzle.edge_v(Point(0, 1)));
+        assert_eq!(Some(Edge::Cross), puzzle.edge_h(Point(0, 1)));
+        assert_eq!(Some(Edge::Cross), puzzle.edge_v(Point(0, 2)));
+    }
+
     #[test]
     fn parse_pattern2() {
         let input = "123___
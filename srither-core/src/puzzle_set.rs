@@ -0,0 +1,363 @@
+// Copyright (c) 2016 srither-core developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! A collection of puzzles with metadata.
+//!
+//! In text form, puzzles are separated by empty lines or header lines. Header
+//! lines start with `#` and have the form `# key: value`; they give metadata
+//! to the puzzle following them. Header lines without `:` are comments.
+//!
+//! ```text
+//! # title: first
+//! # difficulty: easy
+//! 3__
+//! _1_
+//!
+//! # title: second
+//! _2_
+//! 3__
+//! ```
+//!
+//! As empty lines separate puzzles, rows without hints in the lattice format
+//! must contain at least one space (which is what `Display` writes).
+
+use std::{fmt, slice};
+use std::error::Error;
+use std::iter::FromIterator;
+use std::str::FromStr;
+
+use puzzle::{ParsePuzzleError, Puzzle};
+
+/// Metadata of a puzzle, which is an ordered list of key-value pairs.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Metadata {
+    entries: Vec<(String, String)>,
+}
+
+impl Metadata {
+    /// Creates empty metadata.
+    #[inline]
+    pub fn new() -> Metadata {
+        Metadata { entries: vec![] }
+    }
+
+    /// Returns true if the metadata has no entry.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Gets a value of the key.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|&&(ref k, _)| k == key)
+            .map(|&(_, ref v)| &v[..])
+    }
+
+    /// Sets a value of the key, replacing the old value if any.
+    pub fn set(&mut self, key: &str, value: &str) {
+        if let Some(e) = self.entries.iter_mut().find(|e| e.0 == key) {
+            e.1 = value.to_owned();
+            return;
+        }
+        self.entries.push((key.to_owned(), value.to_owned()));
+    }
+
+    /// Returns an iterator iterating key-value pairs in order.
+    #[inline]
+    pub fn iter(&self) -> slice::Iter<(String, String)> {
+        self.entries.iter()
+    }
+}
+
+/// A puzzle in a puzzle set.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Entry {
+    /// The metadata of the puzzle.
+    pub metadata: Metadata,
+    /// The puzzle.
+    pub puzzle: Puzzle,
+}
+
+impl Entry {
+    /// Creates an entry without metadata.
+    #[inline]
+    pub fn new(puzzle: Puzzle) -> Entry {
+        Entry {
+            metadata: Metadata::new(),
+            puzzle: puzzle,
+        }
+    }
+}
+
+/// A collection of puzzles.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PuzzleSet {
+    entries: Vec<Entry>,
+}
+
+impl PuzzleSet {
+    /// Creates an empty puzzle set.
+    #[inline]
+    pub fn new() -> PuzzleSet {
+        PuzzleSet { entries: vec![] }
+    }
+
+    /// Returns the number of the puzzles.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if the set has no puzzle.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Appends an entry.
+    #[inline]
+    pub fn push(&mut self, entry: Entry) {
+        self.entries.push(entry);
+    }
+
+    /// Returns the entries.
+    #[inline]
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Returns the mutable entries.
+    #[inline]
+    pub fn entries_mut(&mut self) -> &mut Vec<Entry> {
+        &mut self.entries
+    }
+
+    /// Converts the set into its entries.
+    #[inline]
+    pub fn into_entries(self) -> Vec<Entry> {
+        self.entries
+    }
+}
+
+impl FromIterator<Entry> for PuzzleSet {
+    fn from_iter<T>(iter: T) -> PuzzleSet
+        where T: IntoIterator<Item = Entry>
+    {
+        PuzzleSet { entries: iter.into_iter().collect() }
+    }
+}
+
+impl Extend<Entry> for PuzzleSet {
+    fn extend<T>(&mut self, iter: T)
+        where T: IntoIterator<Item = Entry>
+    {
+        self.entries.extend(iter)
+    }
+}
+
+/// An error type which is returned from parsing a string into puzzle set.
+#[derive(Copy, Clone, Debug)]
+pub struct ParsePuzzleSetError {
+    kind: PuzzleSetErrorKind,
+}
+
+#[derive(Copy, Clone, Debug)]
+enum PuzzleSetErrorKind {
+    Empty,
+    Puzzle(usize, ParsePuzzleError),
+}
+
+impl ParsePuzzleSetError {
+    fn empty() -> ParsePuzzleSetError {
+        ParsePuzzleSetError { kind: PuzzleSetErrorKind::Empty }
+    }
+    fn puzzle(idx: usize, err: ParsePuzzleError) -> ParsePuzzleSetError {
+        ParsePuzzleSetError { kind: PuzzleSetErrorKind::Puzzle(idx, err) }
+    }
+
+    /// Returns the index of the puzzle which cannot be parsed, if any.
+    pub fn index(&self) -> Option<usize> {
+        match self.kind {
+            PuzzleSetErrorKind::Empty => None,
+            PuzzleSetErrorKind::Puzzle(idx, _) => Some(idx),
+        }
+    }
+}
+
+impl Error for ParsePuzzleSetError {
+    fn description(&self) -> &str {
+        match self.kind {
+            PuzzleSetErrorKind::Empty => "no puzzle found in string",
+            PuzzleSetErrorKind::Puzzle(_, ref e) => e.description(),
+        }
+    }
+    fn cause(&self) -> Option<&Error> {
+        match self.kind {
+            PuzzleSetErrorKind::Empty => None,
+            PuzzleSetErrorKind::Puzzle(_, ref e) => Some(e),
+        }
+    }
+}
+
+impl fmt::Display for ParsePuzzleSetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            PuzzleSetErrorKind::Empty => self.description().fmt(f),
+            PuzzleSetErrorKind::Puzzle(idx, ref e) => write!(f, "puzzle #{}: {}", idx + 1, e),
+        }
+    }
+}
+
+impl FromStr for PuzzleSet {
+    type Err = ParsePuzzleSetError;
+
+    fn from_str(s: &str) -> Result<PuzzleSet, ParsePuzzleSetError> {
+        let mut set = PuzzleSet::new();
+        let mut metadata = Metadata::new();
+        let mut body = vec![];
+
+        for line in s.lines() {
+            let line = line.trim_right_matches('\r');
+            let is_header = line.trim_left().starts_with('#');
+            if (is_header || line.is_empty()) && !body.is_empty() {
+                try!(push_entry(&mut set, &mut metadata, &mut body));
+            }
+
+            if is_header {
+                let header = line.trim_left()[1..].trim();
+                if let Some(idx) = header.find(':') {
+                    let key = header[..idx].trim().to_lowercase();
+                    let value = header[idx + 1..].trim();
+                    metadata.set(&key, value);
+                }
+            } else if !line.trim().is_empty() || !body.is_empty() {
+                body.push(line);
+            }
+        }
+        if !body.is_empty() {
+            try!(push_entry(&mut set, &mut metadata, &mut body));
+        }
+
+        if set.is_empty() {
+            return Err(ParsePuzzleSetError::empty());
+        }
+        Ok(set)
+    }
+}
+
+fn push_entry(set: &mut PuzzleSet,
+              metadata: &mut Metadata,
+              body: &mut Vec<&str>)
+              -> Result<(), ParsePuzzleSetError> {
+    while body.last().map(|l| l.trim().is_empty()) == Some(true) {
+        let _ = body.pop();
+    }
+    let idx = set.len();
+    let puzzle = try!(body.join("\n")
+                          .parse::<Puzzle>()
+                          .map_err(|e| ParsePuzzleSetError::puzzle(idx, e)));
+    set.push(Entry {
+        metadata: metadata.clone(),
+        puzzle: puzzle,
+    });
+    *metadata = Metadata::new();
+    body.clear();
+    Ok(())
+}
+
+impl fmt::Display for PuzzleSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, entry) in self.entries.iter().enumerate() {
+            if i > 0 {
+                try!(writeln!(f, ""));
+            }
+            for &(ref key, ref value) in entry.metadata.iter() {
+                try!(writeln!(f, "# {}: {}", key, value));
+            }
+            try!(write!(f, "{}", entry.puzzle));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Entry, Metadata, PuzzleSet};
+    use geom::{Geom, Point, Size};
+    use puzzle::Puzzle;
+
+    #[test]
+    fn parse() {
+        let input = "
+# title: first
+# Difficulty:  easy
+# just a comment
+3__
+_1_
+
+_2_
+3__
+# title: third
++ + +
+ 0
++ + +
+";
+        let set = input.parse::<PuzzleSet>().unwrap();
+        assert_eq!(3, set.len());
+
+        let e = &set.entries()[0];
+        assert_eq!(Some("first"), e.metadata.get("title"));
+        assert_eq!(Some("easy"), e.metadata.get("difficulty"));
+        assert_eq!(Size(2, 3), e.puzzle.size());
+        assert_eq!(Some(3), e.puzzle.hint(Point(0, 0)));
+
+        let e = &set.entries()[1];
+        assert!(e.metadata.is_empty());
+        assert_eq!(Some(2), e.puzzle.hint(Point(0, 1)));
+
+        let e = &set.entries()[2];
+        assert_eq!(Some("third"), e.metadata.get("title"));
+        assert_eq!(Size(1, 2), e.puzzle.size());
+    }
+
+    #[test]
+    fn single_puzzle() {
+        let set = "3__\n_1_\n".parse::<PuzzleSet>().unwrap();
+        assert_eq!(1, set.len());
+        assert_eq!("3__\n_1_\n".parse::<Puzzle>().unwrap(),
+                   set.entries()[0].puzzle);
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut meta = Metadata::new();
+        meta.set("title", "a");
+        meta.set("difficulty", "hard");
+        meta.set("title", "b");
+        assert_eq!(Some("b"), meta.get("title"));
+
+        let set = vec![Entry {
+                           metadata: meta,
+                           puzzle: "3__\n___\n".parse().unwrap(),
+                       },
+                       Entry::new("_2\n__\n1_".parse().unwrap())]
+                      .into_iter()
+                      .collect::<PuzzleSet>();
+        assert_eq!(set, set.to_string().parse::<PuzzleSet>().unwrap());
+    }
+
+    #[test]
+    fn parse_error() {
+        assert!("".parse::<PuzzleSet>().is_err());
+        assert!("# title: only\n".parse::<PuzzleSet>().is_err());
+        let err = "12\n\n1x\n".parse::<PuzzleSet>().unwrap_err();
+        assert_eq!(Some(1), err.index());
+    }
+}
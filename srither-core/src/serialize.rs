@@ -0,0 +1,186 @@
+// Copyright (c) 2016 srither-core developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! The serialization of the puzzles by serde, with the `serde` feature.
+//!
+//! A puzzle is a struct of the fields `rows` and `columns`, and the rows of
+//! the cells `hints` and `sides`, of the horizontal edges `edges_h`, one more
+//! than the rows of the cells, and of the vertical edges `edges_v`, one more
+//! than the columns in each row. The unknown sides and edges, and the cells
+//! without hints are `None`, e.g. `null` in JSON. A side is `in` or `out`,
+//! and an edge is `line` or `cross`.
+//!
+//! The fields other than `rows`, `columns` and `hints` may be omitted on
+//! deserializing, and are unknown then, so the puzzles in JSON are read from
+//! and into the `json` format as their hints. The annotations and the clue
+//! layers are not serialized.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::de::{Error, MapVisitor, Visitor};
+
+use geom::{Geom, Point, Size};
+use puzzle::{Edge, Hint, Puzzle, Side};
+
+impl Serialize for Side {
+    fn serialize<S>(&self, serializer: &mut S) -> Result<(), S::Error>
+        where S: Serializer
+    {
+        serializer.serialize_str(match *self {
+            Side::In => "in",
+            Side::Out => "out",
+        })
+    }
+}
+
+impl Deserialize for Side {
+    fn deserialize<D>(deserializer: &mut D) -> Result<Side, D::Error>
+        where D: Deserializer
+    {
+        match &try!(String::deserialize(deserializer))[..] {
+            "in" => Ok(Side::In),
+            "out" => Ok(Side::Out),
+            s => Err(D::Error::unknown_variant(s)),
+        }
+    }
+}
+
+impl Serialize for Edge {
+    fn serialize<S>(&self, serializer: &mut S) -> Result<(), S::Error>
+        where S: Serializer
+    {
+        serializer.serialize_str(match *self {
+            Edge::Line => "line",
+            Edge::Cross => "cross",
+        })
+    }
+}
+
+impl Deserialize for Edge {
+    fn deserialize<D>(deserializer: &mut D) -> Result<Edge, D::Error>
+        where D: Deserializer
+    {
+        match &try!(String::deserialize(deserializer))[..] {
+            "line" => Ok(Edge::Line),
+            "cross" => Ok(Edge::Cross),
+            s => Err(D::Error::unknown_variant(s)),
+        }
+    }
+}
+
+static FIELDS: &'static [&'static str] = &["rows", "columns", "hints", "sides", "edges_h",
+                                           "edges_v"];
+
+type Rows<T> = Vec<Vec<T>>;
+
+// The rows of the values at the points of the size.
+fn rows<T, F>(size: Size, f: F) -> Rows<T>
+    where F: Fn(Point) -> T
+{
+    (0..size.0).map(|r| (0..size.1).map(|c| f(Point(r, c))).collect()).collect()
+}
+
+impl Serialize for Puzzle {
+    fn serialize<S>(&self, serializer: &mut S) -> Result<(), S::Error>
+        where S: Serializer
+    {
+        let size = self.size();
+        let mut state = try!(serializer.serialize_struct("Puzzle", FIELDS.len()));
+        try!(serializer.serialize_struct_elt(&mut state, "rows", size.0));
+        try!(serializer.serialize_struct_elt(&mut state, "columns", size.1));
+        try!(serializer.serialize_struct_elt(&mut state, "hints", rows(size, |p| self.hint(p))));
+        try!(serializer.serialize_struct_elt(&mut state, "sides", rows(size, |p| self.side(p))));
+        try!(serializer.serialize_struct_elt(&mut state,
+                                             "edges_h",
+                                             rows(Size(size.0 + 1, size.1),
+                                                  |p| self.edge_h(p))));
+        try!(serializer.serialize_struct_elt(&mut state,
+                                             "edges_v",
+                                             rows(Size(size.0, size.1 + 1),
+                                                  |p| self.edge_v(p))));
+        serializer.serialize_struct_end(state)
+    }
+}
+
+impl Deserialize for Puzzle {
+    fn deserialize<D>(deserializer: &mut D) -> Result<Puzzle, D::Error>
+        where D: Deserializer
+    {
+        deserializer.deserialize_struct("Puzzle", FIELDS, PuzzleVisitor)
+    }
+}
+
+struct PuzzleVisitor;
+
+fn fits<T>(rows: &Rows<T>, size: Size) -> bool {
+    rows.len() == size.0 as usize && rows.iter().all(|row| row.len() == size.1 as usize)
+}
+
+fn set_rows<T, F>(rows: Rows<T>, mut f: F)
+    where F: FnMut(Point, T)
+{
+    for (r, row) in rows.into_iter().enumerate() {
+        for (c, v) in row.into_iter().enumerate() {
+            f(Point(r as i32, c as i32), v);
+        }
+    }
+}
+
+impl Visitor for PuzzleVisitor {
+    type Value = Puzzle;
+
+    fn visit_map<V>(&mut self, mut visitor: V) -> Result<Puzzle, V::Error>
+        where V: MapVisitor
+    {
+        let mut row = None;
+        let mut column = None;
+        let mut hints = None;
+        let mut sides = None;
+        let mut edges_h = None;
+        let mut edges_v = None;
+        while let Some(key) = try!(visitor.visit_key::<String>()) {
+            match &key[..] {
+                "rows" => row = Some(try!(visitor.visit_value::<i32>())),
+                "columns" => column = Some(try!(visitor.visit_value::<i32>())),
+                "hints" => hints = Some(try!(visitor.visit_value::<Rows<Hint>>())),
+                "sides" => sides = Some(try!(visitor.visit_value::<Rows<Option<Side>>>())),
+                "edges_h" => edges_h = Some(try!(visitor.visit_value::<Rows<Option<Edge>>>())),
+                "edges_v" => edges_v = Some(try!(visitor.visit_value::<Rows<Option<Edge>>>())),
+                _ => return Err(V::Error::unknown_field(&key)),
+            }
+        }
+        try!(visitor.end());
+
+        let size = match (row, column) {
+            (Some(r), Some(c)) if r > 0 && c > 0 => Size(r, c),
+            (Some(_), Some(_)) => return Err(V::Error::invalid_value("the size is not positive")),
+            (None, _) => return Err(V::Error::missing_field("rows")),
+            (_, None) => return Err(V::Error::missing_field("columns")),
+        };
+        let hints = match hints {
+            Some(hints) => hints,
+            None => return Err(V::Error::missing_field("hints")),
+        };
+        if hints.iter().any(|row| row.iter().any(|&h| h.map_or(false, |n| n > 4))) {
+            return Err(V::Error::invalid_value("the hint is greater than 4"));
+        }
+        let size_h = Size(size.0 + 1, size.1);
+        let size_v = Size(size.0, size.1 + 1);
+        if !fits(&hints, size) || !sides.as_ref().map_or(true, |s| fits(s, size)) ||
+           !edges_h.as_ref().map_or(true, |e| fits(e, size_h)) ||
+           !edges_v.as_ref().map_or(true, |e| fits(e, size_v)) {
+            return Err(V::Error::invalid_value("the rows differ from the size of the puzzle"));
+        }
+
+        let mut puzzle = Puzzle::new(size);
+        set_rows(hints, |p, h| puzzle.set_hint(p, h));
+        set_rows(sides.unwrap_or(vec![]), |p, s| puzzle.set_side(p, s));
+        set_rows(edges_h.unwrap_or(vec![]), |p, e| puzzle.set_edge_h(p, e));
+        set_rows(edges_v.unwrap_or(vec![]), |p, e| puzzle.set_edge_v(p, e));
+        Ok(puzzle)
+    }
+}
@@ -0,0 +1,32 @@
+// Copyright (c) 2016 srither-gen developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Generates puzzles of a difficulty, and rates them by the solver.
+
+extern crate srither_core;
+extern crate srither_gen;
+extern crate srither_solver;
+
+use srither_core::geom::Size;
+use srither_gen::{Difficulty, Generator, Symmetry};
+use srither_solver::SolveOptions;
+
+fn main() {
+    let mut gen = Generator::new(Size(6, 6));
+    gen.set_symmetry(Symmetry::Rotate180);
+    gen.set_difficulty(Some(Difficulty::Easy));
+
+    // The same seed always generates the same puzzle.
+    for seed in 0..3 {
+        let puzzle = gen.generate(&mut srither_gen::seeded_rng(seed)).unwrap();
+        let (_, stats) = srither_solver::solve_with_stats(&puzzle, &SolveOptions::default())
+                             .unwrap();
+        println!("seed {}: score {:.1}", seed, stats.score());
+        print!("{}", puzzle);
+    }
+}
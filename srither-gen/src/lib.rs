@@ -0,0 +1,880 @@
+// Copyright (c) 2016 srither-gen developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Slither Link puzzle generator.
+
+#![warn(bad_style)]
+#![warn(missing_copy_implementations)]
+#![warn(missing_debug_implementations)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(trivial_numeric_casts)]
+#![warn(unused)]
+#![warn(unused_extern_crates)]
+#![warn(unused_import_braces)]
+#![warn(unused_qualifications)]
+#![warn(unused_results)]
+
+#![cfg_attr(feature="dev", feature(plugin))]
+#![cfg_attr(feature="dev", plugin(clippy))]
+#![cfg_attr(feature="dev", warn(mut_mut))]
+#![cfg_attr(feature="dev", warn(string_add))]
+#![cfg_attr(feature="dev", warn(string_add_assign))]
+
+extern crate rand;
+extern crate srither_core;
+extern crate srither_solver;
+
+use std::fmt;
+use std::error::Error as ErrorTrait;
+use std::str::FromStr;
+use rand::{Isaac64Rng, Rng, SeedableRng};
+
+use srither_core::geom::{Geom, Point, Size, Table};
+use srither_core::loop_bits::LoopBits;
+use srither_core::puzzle::Puzzle;
+use srither_solver::{self as solver, HintLayout, SolveOptions, Solutions, Step};
+
+pub use region::{random_loop, sprawling_loop};
+
+mod region;
+
+/// An error type which is returned from generating a puzzle.
+#[derive(Copy, Clone, Debug)]
+pub struct Error {
+    kind: ErrorKind,
+}
+
+#[derive(Copy, Clone, Debug)]
+enum ErrorKind {
+    NoUniqueLoop,
+    NoLoopOfLength,
+    NoPuzzleOfDifficulty,
+    NoPuzzleOfMask,
+    NoPuzzleWithoutLayouts,
+    InvalidMask,
+    InvalidLoop,
+    AmbiguousLoop,
+    Solver(solver::Error),
+}
+
+impl ErrorTrait for Error {
+    fn description(&self) -> &str {
+        match self.kind {
+            ErrorKind::NoUniqueLoop => "no loop with a unique solution found",
+            ErrorKind::NoLoopOfLength => "no loop of the length found",
+            ErrorKind::NoPuzzleOfDifficulty => "no puzzle of the difficulty found",
+            ErrorKind::NoPuzzleOfMask => "no puzzle unique by the hints of the mask found",
+            ErrorKind::NoPuzzleWithoutLayouts => "no puzzle without the forbidden layouts found",
+            ErrorKind::InvalidMask => "the mask is not rows of `#` and `.` of the same length",
+            ErrorKind::InvalidLoop => "the lines are not a single loop",
+            ErrorKind::AmbiguousLoop => "the loop is not the unique solution of its hints",
+            ErrorKind::Solver(ref e) => e.description(),
+        }
+    }
+    fn cause(&self) -> Option<&ErrorTrait> {
+        match self.kind {
+            ErrorKind::NoUniqueLoop |
+            ErrorKind::NoLoopOfLength |
+            ErrorKind::NoPuzzleOfDifficulty |
+            ErrorKind::NoPuzzleOfMask |
+            ErrorKind::NoPuzzleWithoutLayouts |
+            ErrorKind::InvalidMask |
+            ErrorKind::InvalidLoop |
+            ErrorKind::AmbiguousLoop => None,
+            ErrorKind::Solver(ref e) => Some(e),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.description().fmt(f)
+    }
+}
+
+impl From<solver::Error> for Error {
+    fn from(err: solver::Error) -> Error {
+        Error { kind: ErrorKind::Solver(err) }
+    }
+}
+
+impl Error {
+    fn no_unique_loop() -> Error {
+        Error { kind: ErrorKind::NoUniqueLoop }
+    }
+    fn no_loop_of_length() -> Error {
+        Error { kind: ErrorKind::NoLoopOfLength }
+    }
+    fn no_puzzle_of_difficulty() -> Error {
+        Error { kind: ErrorKind::NoPuzzleOfDifficulty }
+    }
+    fn no_puzzle_of_mask() -> Error {
+        Error { kind: ErrorKind::NoPuzzleOfMask }
+    }
+    fn no_puzzle_without_layouts() -> Error {
+        Error { kind: ErrorKind::NoPuzzleWithoutLayouts }
+    }
+    fn invalid_mask() -> Error {
+        Error { kind: ErrorKind::InvalidMask }
+    }
+    fn invalid_loop() -> Error {
+        Error { kind: ErrorKind::InvalidLoop }
+    }
+    fn ambiguous_loop() -> Error {
+        Error { kind: ErrorKind::AmbiguousLoop }
+    }
+}
+
+/// Generating puzzles result.
+pub type GenResult<T> = Result<T, Error>;
+
+// The number of the loops tried before giving up. Almost all loops have a
+// unique solution with all the hints.
+const MAX_ATTEMPTS: u32 = 100;
+
+// The number of the random loops drawn for a loop of the length before
+// giving up.
+const MAX_LOOP_SAMPLES: u32 = 1000;
+
+/// A symmetry of the cells with hints, as in the published puzzles.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Symmetry {
+    /// The hints are placed freely.
+    None,
+    /// The hints are placed symmetrically under the 180 degree rotation.
+    Rotate180,
+    /// The hints are placed symmetrically between the left and the right.
+    MirrorLeftRight,
+    /// The hints are placed symmetrically between the top and the bottom.
+    MirrorTopBottom,
+}
+
+static SYMMETRIES: &'static [Symmetry] = &[Symmetry::None,
+                                           Symmetry::Rotate180,
+                                           Symmetry::MirrorLeftRight,
+                                           Symmetry::MirrorTopBottom];
+
+impl Symmetry {
+    /// Returns all the symmetries.
+    pub fn all() -> &'static [Symmetry] {
+        SYMMETRIES
+    }
+
+    /// Returns the name of the symmetry, e.g. `rotate180`.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Symmetry::None => "none",
+            Symmetry::Rotate180 => "rotate180",
+            Symmetry::MirrorLeftRight => "mirror-left-right",
+            Symmetry::MirrorTopBottom => "mirror-top-bottom",
+        }
+    }
+
+    /// Returns the symmetry of the name.
+    pub fn from_name(name: &str) -> Option<Symmetry> {
+        SYMMETRIES.iter().find(|s| s.name() == name).cloned()
+    }
+
+    // Returns the cell which must have a hint together with `p`.
+    fn image(&self, p: Point, size: Size) -> Point {
+        match *self {
+            Symmetry::None => p,
+            Symmetry::Rotate180 => Point(size.0 - 1 - p.0, size.1 - 1 - p.1),
+            Symmetry::MirrorLeftRight => Point(p.0, size.1 - 1 - p.1),
+            Symmetry::MirrorTopBottom => Point(size.0 - 1 - p.0, p.1),
+        }
+    }
+}
+
+/// The difficulty of a puzzle, given by the strongest deduction step needed to
+/// solve it.
+///
+/// Unlike the percentiles of the scores by `srither rate`, the difficulty
+/// does not depend on the other puzzles.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Difficulty {
+    /// Solved by the theorems only.
+    Easy,
+    /// Solved by the theorems and the connectivity of the cells.
+    Medium,
+    /// Solved by assuming each cell inside and outside.
+    Hard,
+    /// Solved only by nested assumptions or branching.
+    Expert,
+}
+
+static DIFFICULTIES: &'static [Difficulty] = &[Difficulty::Easy,
+                                               Difficulty::Medium,
+                                               Difficulty::Hard,
+                                               Difficulty::Expert];
+
+impl Difficulty {
+    /// Returns all the difficulties, from the easiest.
+    pub fn all() -> &'static [Difficulty] {
+        DIFFICULTIES
+    }
+
+    /// Returns the name of the difficulty, e.g. `easy`.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+            Difficulty::Expert => "expert",
+        }
+    }
+
+    /// Returns the difficulty of the name.
+    pub fn from_name(name: &str) -> Option<Difficulty> {
+        DIFFICULTIES.iter().find(|d| d.name() == name).cloned()
+    }
+
+    /// Returns the difficulty of the puzzle, which should have a unique
+    /// solution.
+    pub fn of(puzzle: &Puzzle) -> GenResult<Difficulty> {
+        let (deduced, deductions) = try!(solver::trace_deductions(puzzle,
+                                                                  &SolveOptions::default()));
+        if deduced.has_unknown_edge() {
+            return Ok(Difficulty::Expert);
+        }
+        let level = |step| {
+            match step {
+                Step::Theorems => Difficulty::Easy,
+                Step::Connect => Difficulty::Medium,
+                Step::Probe { depth: 1 } => Difficulty::Hard,
+                Step::Probe { .. } => Difficulty::Expert,
+            }
+        };
+        Ok(deductions.iter().map(|d| level(d.step)).max().unwrap_or(Difficulty::Easy))
+    }
+}
+
+/// The cells where the hints may be placed, e.g. to draw a picture or
+/// initials by the hints.
+///
+/// The mask is parsed from the rows of `#`, the cells allowed to have hints,
+/// and `.`, the cells not.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HintMask {
+    cells: Table<bool>,
+}
+
+impl HintMask {
+    /// Returns the size of the mask.
+    pub fn size(&self) -> Size {
+        self.cells.size()
+    }
+
+    /// Returns true if the cell of the point may have a hint.
+    pub fn allows(&self, p: Point) -> bool {
+        self.cells[p]
+    }
+}
+
+impl FromStr for HintMask {
+    type Err = Error;
+
+    fn from_str(s: &str) -> GenResult<HintMask> {
+        let rows = s.lines().map(|l| l.trim_right()).filter(|l| !l.is_empty()).collect::<Vec<_>>();
+        let column = rows.first().map(|r| r.len()).unwrap_or(0);
+        if column == 0 || rows.iter().any(|r| r.len() != column) {
+            return Err(Error::invalid_mask());
+        }
+        let mut cells = vec![];
+        for c in rows.iter().flat_map(|r| r.chars()) {
+            match c {
+                '#' => cells.push(true),
+                '.' => cells.push(false),
+                _ => return Err(Error::invalid_mask()),
+            }
+        }
+        let size = Size(rows.len() as i32, column as i32);
+        Ok(HintMask { cells: Table::new(size, false, cells) })
+    }
+}
+
+/// The work to generate a puzzle, by the numbers of the solver calls, e.g. to
+/// estimate the time before generating large or hard puzzles.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Workload {
+    /// The uniqueness checks of a loop tried at most, one for all the hints
+    /// and one for each hint. The hints removed together with another one
+    /// under the symmetry are not checked again.
+    pub unique_checks: u64,
+    /// The ratings of the difficulty of a loop tried at most, one for each
+    /// hint removed and one for the puzzle made, if a difficulty is set.
+    pub difficulty_checks: u64,
+    /// The loops tried at least. Almost all loops give a puzzle without a
+    /// difficulty, a mask or forbidden layouts, but the others are tried
+    /// again until a puzzle is found.
+    pub min_attempts: u32,
+    /// The loops tried at most before giving up.
+    pub max_attempts: u32,
+}
+
+/// A generator of the puzzles with a unique solution.
+///
+/// A puzzle is made from a random loop with all the hints, and the hints are
+/// removed in random order as long as the solution stays unique. With a
+/// symmetry, the hints of the cells mapped to each other are removed together.
+/// With a difficulty, the hints making the puzzle harder than it are kept,
+/// and the loops are tried again until the puzzle is of the difficulty.
+/// With a mask, the hints out of the mask are removed first, and the loops
+/// are tried again until the hints in the mask give a unique solution.
+/// With a range of the length of the loop, the random loops out of the range
+/// are drawn again, before the hints are removed. With forbidden layouts, the
+/// puzzles whose hints are laid out as any of them are thrown away, and the
+/// loops are tried again.
+#[derive(Clone, Debug)]
+pub struct Generator {
+    size: Size,
+    hint_density: f64,
+    loop_length: (f64, f64),
+    symmetry: Symmetry,
+    difficulty: Option<Difficulty>,
+    mask: Option<HintMask>,
+    forbidden: Vec<HintLayout>,
+}
+
+impl Generator {
+    /// Creates a generator of the puzzles of the size, whose hints are
+    /// removed as many as possible.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the size is empty.
+    pub fn new(size: Size) -> Generator {
+        assert!(size.0 > 0 && size.1 > 0);
+        Generator {
+            size: size,
+            hint_density: 0.0,
+            loop_length: (0.0, 1.0),
+            symmetry: Symmetry::None,
+            difficulty: None,
+            mask: None,
+            forbidden: vec![],
+        }
+    }
+
+    /// Returns the size of the puzzles.
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    /// Returns the density of the hints.
+    pub fn hint_density(&self) -> f64 {
+        self.hint_density
+    }
+
+    /// Sets the density of the hints, which is the ratio of the cells with
+    /// hints from 0 to 1. The hints are no longer removed once the density
+    /// reaches it, but they may be denser if no more hints can be removed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the density is out of 0 to 1.
+    pub fn set_hint_density(&mut self, density: f64) {
+        assert!(density >= 0.0 && density <= 1.0);
+        self.hint_density = density;
+    }
+
+    /// Returns the range of the length of the loop, as the ratios to the
+    /// longest loop in the size.
+    pub fn loop_length(&self) -> (f64, f64) {
+        self.loop_length
+    }
+
+    /// Sets the range of the length of the loop, as the ratios from 0 to 1 to
+    /// the longest loop in the size given by `LoopBits::max_loop_len`, e.g.
+    /// `(0.6, 1.0)` for the sprawling loops. The default is `(0.0, 1.0)`.
+    ///
+    /// The random loops are drawn until one is in the range, and the
+    /// generation fails if none is found in some drawings, so a narrow range
+    /// may not be satisfied. With a minimum, the loops are drawn by
+    /// `sprawling_loop`, whose loops are long, instead of `random_loop`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the ratios are out of 0 to 1, or the minimum is greater than
+    /// the maximum.
+    pub fn set_loop_length(&mut self, min: f64, max: f64) {
+        assert!(min >= 0.0 && min <= max && max <= 1.0);
+        self.loop_length = (min, max);
+    }
+
+    /// Returns the symmetry of the hints.
+    pub fn symmetry(&self) -> Symmetry {
+        self.symmetry
+    }
+
+    /// Sets the symmetry of the hints. The default is `Symmetry::None`.
+    pub fn set_symmetry(&mut self, symmetry: Symmetry) {
+        self.symmetry = symmetry;
+    }
+
+    /// Returns the difficulty of the puzzles, if any.
+    pub fn difficulty(&self) -> Option<Difficulty> {
+        self.difficulty
+    }
+
+    /// Sets the difficulty of the puzzles. The default is `None`, which
+    /// generates puzzles of any difficulty.
+    pub fn set_difficulty(&mut self, difficulty: Option<Difficulty>) {
+        self.difficulty = difficulty;
+    }
+
+    /// Returns the mask of the hints, if any.
+    pub fn mask(&self) -> Option<&HintMask> {
+        self.mask.as_ref()
+    }
+
+    /// Sets the mask of the cells where the hints may be placed. The default
+    /// is `None`, which allows the hints in all the cells.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the size of the mask differs from that of the puzzles.
+    pub fn set_mask(&mut self, mask: Option<HintMask>) {
+        if let Some(ref mask) = mask {
+            assert_eq!(self.size, mask.size());
+        }
+        self.mask = mask;
+    }
+
+    /// Returns the layouts of the hints forbidden in the puzzles.
+    pub fn forbidden_layouts(&self) -> &[HintLayout] {
+        &self.forbidden
+    }
+
+    /// Sets the layouts of the hints forbidden in the puzzles, e.g. the
+    /// adjacent `0`s or a 2x2 block of `3`s, in any rotation or reflection.
+    /// The default is none.
+    pub fn set_forbidden_layouts(&mut self, layouts: Vec<HintLayout>) {
+        self.forbidden = layouts;
+    }
+
+    /// Returns the work to generate a puzzle.
+    ///
+    /// The numbers of the solver calls are the most ones, as fewer hints are
+    /// checked with a symmetry or a density.
+    pub fn workload(&self) -> Workload {
+        let removals = match self.mask {
+            Some(ref mask) => mask.cells.points().filter(|&p| mask.allows(p)).count() as u64,
+            None => (self.size.0 * self.size.1) as u64,
+        };
+        let retried = self.difficulty.is_some() || self.mask.is_some() ||
+                      !self.forbidden.is_empty();
+        Workload {
+            unique_checks: 1 + removals,
+            difficulty_checks: if self.difficulty.is_some() {
+                removals + 1
+            } else {
+                0
+            },
+            min_attempts: 1,
+            max_attempts: if retried {
+                MAX_ATTEMPTS
+            } else {
+                1
+            },
+        }
+    }
+
+    /// Generates a puzzle with a unique solution.
+    pub fn generate<R>(&self, rng: &mut R) -> GenResult<Puzzle>
+        where R: Rng
+    {
+        let num_cells = (self.size.0 * self.size.1) as usize;
+        let min_hints = (self.hint_density * num_cells as f64).ceil() as usize;
+
+        // The reason why the last loop is given up.
+        let mut failure = if self.mask.is_some() {
+            Error::no_puzzle_of_mask()
+        } else {
+            Error::no_unique_loop()
+        };
+        for _ in 0..MAX_ATTEMPTS {
+            let mut puzzle = try!(self.random_loop(rng)).hints_only();
+            if let Some(ref mask) = self.mask {
+                for p in puzzle.points() {
+                    if !mask.allows(p) {
+                        puzzle.set_hint(p, None);
+                    }
+                }
+            }
+            if !try!(is_unique(&puzzle)) {
+                continue;
+            }
+
+            let mut points = puzzle.points().collect::<Vec<_>>();
+            rng.shuffle(&mut points);
+            try!(remove_hints(&mut puzzle,
+                              &points,
+                              self.symmetry,
+                              min_hints,
+                              self.difficulty));
+            if let Some(difficulty) = self.difficulty {
+                if try!(Difficulty::of(&puzzle)) != difficulty {
+                    failure = Error::no_puzzle_of_difficulty();
+                    continue;
+                }
+            }
+            if self.forbidden.iter().any(|layout| layout.matches(&puzzle)) {
+                failure = Error::no_puzzle_without_layouts();
+                continue;
+            }
+            return Ok(puzzle);
+        }
+        Err(failure)
+    }
+
+    // Returns a random loop of the length in the range, drawing the loops
+    // until one is in the range. Any loop is in the default range, so the
+    // first one is taken and the puzzles of a seed are the same as before.
+    fn random_loop<R>(&self, rng: &mut R) -> GenResult<Puzzle>
+        where R: Rng
+    {
+        let max_len = LoopBits::max_loop_len(self.size) as f64;
+        let (min, max) = self.loop_length;
+        for _ in 0..MAX_LOOP_SAMPLES {
+            let answer = if min > 0.0 {
+                sprawling_loop(rng, self.size)
+            } else {
+                random_loop(rng, self.size)
+            };
+            let len = LoopBits::from_puzzle(&answer).count() as f64;
+            if len >= min * max_len && len <= max * max_len {
+                return Ok(answer);
+            }
+        }
+        Err(Error::no_loop_of_length())
+    }
+}
+
+/// Returns the random number generator of the seed.
+///
+/// The generation is deterministic given the random number generator, so
+/// the same seed and the same options always give the same puzzles, on any
+/// platform. `StdRng` is not used, as its algorithm depends on the word size
+/// of the platform.
+pub fn seeded_rng(seed: u64) -> Isaac64Rng {
+    SeedableRng::from_seed(&[seed][..])
+}
+
+/// Makes a puzzle whose solution is the loop, e.g. drawn by hand first.
+///
+/// All the hints of the loop are derived, and then removed as by
+/// `reduce_hints`. The lines of a solved puzzle are taken by
+/// `LoopBits::from_puzzle`. Returns an error if the lines are not a single
+/// loop, or the puzzle with all the hints has another solution.
+pub fn from_loop(lines: &LoopBits) -> GenResult<Puzzle> {
+    let mut answer = Puzzle::new(lines.size());
+    lines.apply_to(&mut answer);
+    for p in answer.points() {
+        answer.set_hint(p, Some(lines.cell_lines(p)));
+    }
+    let puzzle = answer.hints_only();
+    if !solver::verify(&puzzle, &answer) {
+        return Err(Error::invalid_loop());
+    }
+    if !try!(is_unique(&puzzle)) {
+        return Err(Error::ambiguous_loop());
+    }
+    Ok(reduce_hints(&puzzle))
+}
+
+/// Removes the hints of the puzzle one by one as long as the solution stays
+/// unique, and returns the puzzle none of whose hints can be removed any more.
+///
+/// The hints are tried in the row-major order, and the lines and the sides
+/// given to the puzzle are cleared. No hint is removed if the puzzle does not
+/// have a unique solution.
+pub fn reduce_hints(puzzle: &Puzzle) -> Puzzle {
+    let mut puzzle = puzzle.hints_only();
+    if is_unique(&puzzle).unwrap_or(false) {
+        let points = puzzle.points().collect::<Vec<_>>();
+        // The puzzle is solved already, so it is solved with fewer hints too.
+        let _ = remove_hints(&mut puzzle, &points, Symmetry::None, 0, None);
+    }
+    puzzle
+}
+
+// Removes the hints of the points in order, together with their images under
+// the symmetry, as long as the solution stays unique, more than `min_hints`
+// hints are left and the puzzle is not harder than `max_difficulty`.
+// Removing a hint never makes the solution unique again, so no hint left can
+// be removed.
+fn remove_hints(puzzle: &mut Puzzle,
+                points: &[Point],
+                symmetry: Symmetry,
+                min_hints: usize,
+                max_difficulty: Option<Difficulty>)
+                -> GenResult<()> {
+    let size = puzzle.size();
+    let mut num_hints = puzzle.points().filter(|&p| puzzle.hint(p).is_some()).count();
+    for &p in points {
+        if num_hints <= min_hints {
+            break;
+        }
+        if puzzle.hint(p).is_none() {
+            continue;
+        }
+        let q = symmetry.image(p, size);
+        let hints = (puzzle.hint(p), puzzle.hint(q));
+        puzzle.set_hint(p, None);
+        puzzle.set_hint(q, None);
+        let removable = match max_difficulty {
+            Some(max) => try!(is_unique(puzzle)) && try!(Difficulty::of(puzzle)) <= max,
+            None => try!(is_unique(puzzle)),
+        };
+        if removable {
+            num_hints -= if p == q || hints.1.is_none() { 1 } else { 2 };
+        } else {
+            puzzle.set_hint(p, hints.0);
+            puzzle.set_hint(q, hints.1);
+        }
+    }
+    Ok(())
+}
+
+fn is_unique(puzzle: &Puzzle) -> GenResult<bool> {
+    let options = SolveOptions { max_solutions: Some(2), ..SolveOptions::default() };
+    let mut it = try!(Solutions::with_options(puzzle, &options));
+    Ok(it.next().is_some() && it.next().is_none())
+}
+
+#[cfg(test)]
+mod tests {
+    use srither_core::geom::{Geom, Point, Size};
+    use srither_core::loop_bits::LoopBits;
+    use srither_core::puzzle::Puzzle;
+    use srither_solver::{self as solver, HintLayout, Solutions};
+    use super::{Difficulty, Generator, HintMask, MAX_ATTEMPTS, Symmetry, from_loop, random_loop,
+                reduce_hints, seeded_rng, sprawling_loop};
+
+    #[test]
+    fn random_loop_is_solution() {
+        let mut rng = seeded_rng(1);
+        for _ in 0..20 {
+            let answer = random_loop(&mut rng, Size(4, 5));
+            assert!(solver::verify(&answer.hints_only(), &answer));
+            let answer = sprawling_loop(&mut rng, Size(4, 5));
+            assert!(solver::verify(&answer.hints_only(), &answer));
+        }
+    }
+
+    #[test]
+    fn unique_solution() {
+        let mut rng = seeded_rng(2);
+        for &(size, density) in &[(Size(1, 1), 0.0), (Size(4, 4), 0.0), (Size(5, 3), 0.5)] {
+            let mut gen = Generator::new(size);
+            gen.set_hint_density(density);
+            let puzzle = gen.generate(&mut rng).unwrap();
+            assert_eq!(size, puzzle.size());
+            assert_eq!(1, Solutions::new(&puzzle).unwrap().count());
+
+            let num_hints = puzzle.points().filter(|&p| puzzle.hint(p).is_some()).count();
+            assert!(num_hints as f64 >= density * (size.0 * size.1) as f64);
+        }
+    }
+
+    #[test]
+    fn reproducible() {
+        let mut gen = Generator::new(Size(5, 5));
+        gen.set_symmetry(Symmetry::Rotate180);
+        let puzzle = gen.generate(&mut seeded_rng(7)).unwrap();
+        assert_eq!(puzzle, gen.generate(&mut seeded_rng(7)).unwrap());
+    }
+
+    #[test]
+    fn mask() {
+        let mask = "#.#.#\n.#.#.\n#.#.#\n.#.#.\n#.#.#\n".parse::<HintMask>().unwrap();
+        assert_eq!(Size(5, 5), mask.size());
+        assert!(mask.allows(Point(0, 0)) && !mask.allows(Point(0, 1)));
+        assert!(!mask.allows(Point(-1, 0)));
+
+        let mut gen = Generator::new(mask.size());
+        gen.set_mask(Some(mask.clone()));
+        let puzzle = gen.generate(&mut seeded_rng(3)).unwrap();
+        assert_eq!(1, Solutions::new(&puzzle).unwrap().count());
+        for p in puzzle.points() {
+            assert!(puzzle.hint(p).is_none() || mask.allows(p));
+        }
+
+        assert!("##\n#\n".parse::<HintMask>().is_err());
+        assert!("#x\n".parse::<HintMask>().is_err());
+        assert!("\n".parse::<HintMask>().is_err());
+    }
+
+    #[test]
+    fn symmetry_names() {
+        for &symmetry in Symmetry::all() {
+            assert_eq!(Some(symmetry), Symmetry::from_name(symmetry.name()));
+        }
+        assert_eq!(None, Symmetry::from_name("rotate90"));
+    }
+
+    #[test]
+    fn difficulty() {
+        for &difficulty in Difficulty::all() {
+            assert_eq!(Some(difficulty), Difficulty::from_name(difficulty.name()));
+        }
+
+        let puzzle = "22\n22\n".parse::<Puzzle>().unwrap();
+        assert_eq!(Difficulty::Easy, Difficulty::of(&puzzle).unwrap());
+
+        let mut rng = seeded_rng(8);
+        for &difficulty in &[Difficulty::Easy, Difficulty::Medium] {
+            let mut gen = Generator::new(Size(5, 5));
+            gen.set_difficulty(Some(difficulty));
+            let puzzle = gen.generate(&mut rng).unwrap();
+            assert_eq!(1, Solutions::new(&puzzle).unwrap().count());
+            assert_eq!(difficulty, Difficulty::of(&puzzle).unwrap());
+        }
+    }
+
+    #[test]
+    fn workload() {
+        let mut gen = Generator::new(Size(3, 4));
+        let workload = gen.workload();
+        assert_eq!(13, workload.unique_checks);
+        assert_eq!(0, workload.difficulty_checks);
+        assert_eq!((1, 1), (workload.min_attempts, workload.max_attempts));
+
+        gen.set_difficulty(Some(Difficulty::Hard));
+        let workload = gen.workload();
+        assert_eq!(13, workload.unique_checks);
+        assert_eq!(13, workload.difficulty_checks);
+        assert_eq!((1, MAX_ATTEMPTS), (workload.min_attempts, workload.max_attempts));
+
+        let mut gen = Generator::new(Size(2, 3));
+        gen.set_mask(Some("#.#\n.#.\n".parse::<HintMask>().unwrap()));
+        let workload = gen.workload();
+        assert_eq!(4, workload.unique_checks);
+        assert_eq!(MAX_ATTEMPTS, workload.max_attempts);
+    }
+
+    #[test]
+    fn loop_length() {
+        let size = Size(5, 5);
+        let max_len = LoopBits::max_loop_len(size) as f64;
+        let mut rng = seeded_rng(9);
+        for &(min, max) in &[(0.8, 1.0), (0.6, 0.7), (0.0, 0.3)] {
+            let mut gen = Generator::new(size);
+            gen.set_loop_length(min, max);
+            assert_eq!((min, max), gen.loop_length());
+            let puzzle = gen.generate(&mut rng).unwrap();
+            let len = LoopBits::from_puzzle(&solver::solve(&puzzle).unwrap()).count() as f64;
+            assert!(len >= min * max_len && len <= max * max_len);
+        }
+
+        // The default range does not change the puzzles of a seed.
+        let mut gen = Generator::new(size);
+        let puzzle = gen.generate(&mut seeded_rng(10)).unwrap();
+        gen.set_loop_length(0.0, 1.0);
+        assert_eq!(puzzle, gen.generate(&mut seeded_rng(10)).unwrap());
+    }
+
+    #[test]
+    fn forbidden_layouts() {
+        let layouts = vec!["00".parse::<HintLayout>().unwrap(),
+                           "3_/_3".parse::<HintLayout>().unwrap()];
+        let mut rng = seeded_rng(11);
+        let mut gen = Generator::new(Size(6, 6));
+        gen.set_forbidden_layouts(layouts.clone());
+        assert_eq!(&layouts[..], gen.forbidden_layouts());
+        assert_eq!(MAX_ATTEMPTS, gen.workload().max_attempts);
+        for _ in 0..5 {
+            let puzzle = gen.generate(&mut rng).unwrap();
+            assert_eq!(1, Solutions::new(&puzzle).unwrap().count());
+            assert!(layouts.iter().all(|layout| !layout.matches(&puzzle)));
+        }
+
+        // The only puzzle of a single cell with its hint is a `4`.
+        let mut gen = Generator::new(Size(1, 1));
+        gen.set_hint_density(1.0);
+        gen.set_forbidden_layouts(vec!["4".parse::<HintLayout>().unwrap()]);
+        assert!(gen.generate(&mut rng).is_err());
+    }
+
+    #[test]
+    fn keep_all_hints() {
+        let mut gen = Generator::new(Size(3, 3));
+        gen.set_hint_density(1.0);
+        let puzzle = gen.generate(&mut seeded_rng(3)).unwrap();
+        assert!(puzzle.points().all(|p| puzzle.hint(p).is_some()));
+    }
+
+    #[test]
+    fn symmetric_hints() {
+        let mut rng = seeded_rng(4);
+        for &symmetry in &[Symmetry::Rotate180,
+                           Symmetry::MirrorLeftRight,
+                           Symmetry::MirrorTopBottom] {
+            let size = Size(5, 4);
+            let mut gen = Generator::new(size);
+            gen.set_symmetry(symmetry);
+            let puzzle = gen.generate(&mut rng).unwrap();
+            assert_eq!(1, Solutions::new(&puzzle).unwrap().count());
+            for p in puzzle.points() {
+                let q = symmetry.image(p, size);
+                assert_eq!(puzzle.hint(p).is_some(), puzzle.hint(q).is_some());
+            }
+        }
+    }
+
+    #[test]
+    fn minimal_hints() {
+        let answer = random_loop(&mut seeded_rng(5), Size(4, 4));
+        let puzzle = reduce_hints(&answer);
+        assert!(solver::verify(&puzzle, &solver::solve(&puzzle).unwrap()));
+        assert_eq!(1, Solutions::new(&puzzle).unwrap().count());
+        for p in puzzle.points().filter(|&p| puzzle.hint(p).is_some()) {
+            assert_eq!(answer.hint(p), puzzle.hint(p));
+            let mut fewer = puzzle.clone();
+            fewer.set_hint(p, None);
+            assert!(Solutions::new(&fewer).unwrap().count() > 1);
+        }
+
+        // The hints of a puzzle with many solutions are kept.
+        let puzzle = "3__\n_1_\n".parse::<Puzzle>().unwrap();
+        assert_eq!(puzzle, reduce_hints(&puzzle));
+    }
+
+    #[test]
+    fn puzzle_of_loop() {
+        let mut rng = seeded_rng(6);
+        let mut made = 0;
+        for _ in 0..5 {
+            let answer = random_loop(&mut rng, Size(5, 5));
+            let lines = LoopBits::from_puzzle(&answer);
+            let puzzle = match from_loop(&lines) {
+                Ok(puzzle) => puzzle,
+                // Some loops are not unique even with all the hints.
+                Err(_) => continue,
+            };
+            let solution = solver::solve(&puzzle).unwrap();
+            assert_eq!(lines, LoopBits::from_puzzle(&solution));
+            assert_eq!(reduce_hints(&puzzle), puzzle);
+            made += 1;
+        }
+        assert!(made > 0);
+
+        // No line, and two loops.
+        let mut lines = LoopBits::new(Size(1, 3));
+        assert!(from_loop(&lines).is_err());
+        for &c in &[0, 2] {
+            lines.set_edge_h(Point(0, c), true);
+            lines.set_edge_h(Point(1, c), true);
+            lines.set_edge_v(Point(0, c), true);
+            lines.set_edge_v(Point(0, c + 1), true);
+        }
+        assert!(from_loop(&lines).is_err());
+    }
+}
@@ -0,0 +1,139 @@
+// Copyright (c) 2016 srither-gen developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use rand::Rng;
+
+use srither_core::geom::{Geom, Move, Point, Size};
+use srither_core::loop_bits::LoopBits;
+use srither_core::puzzle::{Edge, Puzzle, Side};
+
+/// Returns a solved puzzle whose loop surrounds a random region, with all the
+/// hints. The region is grown cell by cell, keeping both of the region and
+/// its outside connected and never touching itself at a corner, so that its
+/// border is a single loop.
+pub fn random_loop<R>(rng: &mut R, size: Size) -> Puzzle
+    where R: Rng
+{
+    grow_loop(rng, size, false)
+}
+
+/// Returns a solved puzzle whose loop surrounds a random sprawling region,
+/// with all the hints. The region is grown as by `random_loop` to all the
+/// cells it can reach, but from the cells touching it by one side only as
+/// long as any, so that it grows in thin branches and its border is long.
+pub fn sprawling_loop<R>(rng: &mut R, size: Size) -> Puzzle
+    where R: Rng
+{
+    grow_loop(rng, size, true)
+}
+
+fn grow_loop<R>(rng: &mut R, size: Size, sprawl: bool) -> Puzzle
+    where R: Rng
+{
+    let mut puzzle = Puzzle::new(size);
+    for p in puzzle.points() {
+        puzzle.set_side(p, Some(Side::Out));
+    }
+    let start = Point(rng.gen_range(0, size.0), rng.gen_range(0, size.1));
+    puzzle.set_side(start, Some(Side::In));
+
+    let steps = if sprawl {
+        size.0 * size.1
+    } else {
+        rng.gen_range(0, size.0 * size.1)
+    };
+    for _ in 0..steps {
+        let candidates = {
+            let inside = |p| {
+                Move::ALL_DIRECTIONS
+                    .iter()
+                    .filter(|&&m| puzzle.side(p + m) == Some(Side::In))
+                    .count()
+            };
+            let mut candidates = puzzle.points()
+                                       .filter(|&p| {
+                                           puzzle.side(p) == Some(Side::Out) && inside(p) > 0
+                                       })
+                                       .collect::<Vec<_>>();
+            if sprawl && candidates.iter().any(|&p| inside(p) == 1) {
+                candidates.retain(|&p| inside(p) == 1);
+            }
+            candidates
+        };
+        let p = match rng.choose(&candidates) {
+            Some(&p) => p,
+            None => break,
+        };
+        puzzle.set_side(p, Some(Side::In));
+        if !is_simple_region(&puzzle) {
+            puzzle.set_side(p, Some(Side::Out));
+        }
+    }
+
+    for r in 0..(size.0 + 1) {
+        for c in 0..size.1 {
+            let p = Point(r, c);
+            let line = puzzle.side(p) != puzzle.side(p + Move::UP);
+            puzzle.set_edge_h(p, Some(if line { Edge::Line } else { Edge::Cross }));
+        }
+    }
+    for r in 0..size.0 {
+        for c in 0..(size.1 + 1) {
+            let p = Point(r, c);
+            let line = puzzle.side(p) != puzzle.side(p + Move::LEFT);
+            puzzle.set_edge_v(p, Some(if line { Edge::Line } else { Edge::Cross }));
+        }
+    }
+    let bits = LoopBits::from_puzzle(&puzzle);
+    for p in puzzle.points() {
+        puzzle.set_hint(p, Some(bits.cell_lines(p)));
+    }
+    puzzle
+}
+
+// Returns true if all outside cells are connected to the outside of the
+// puzzle, and no corner is touched by two inside cells diagonally.
+fn is_simple_region(puzzle: &Puzzle) -> bool {
+    for r in 0..(puzzle.row() + 1) {
+        for c in 0..(puzzle.column() + 1) {
+            let p = Point(r, c);
+            let ul = puzzle.side(p + Move::UP + Move::LEFT);
+            let ur = puzzle.side(p + Move::UP);
+            let dl = puzzle.side(p + Move::LEFT);
+            let dr = puzzle.side(p);
+            if ul == dr && ur == dl && ul != ur {
+                return false;
+            }
+        }
+    }
+
+    let mut visited = vec![false; (puzzle.row() * puzzle.column()) as usize];
+    let mut stack = puzzle.points()
+                          .filter(|&p| {
+                              puzzle.side(p) == Some(Side::Out) &&
+                              (p.0 == 0 || p.1 == 0 || p.0 == puzzle.row() - 1 ||
+                               p.1 == puzzle.column() - 1)
+                          })
+                          .collect::<Vec<_>>();
+    while let Some(p) = stack.pop() {
+        let idx = (p.0 * puzzle.column() + p.1) as usize;
+        if visited[idx] {
+            continue;
+        }
+        visited[idx] = true;
+        for &m in &Move::ALL_DIRECTIONS {
+            let q = p + m;
+            if puzzle.contains(q) && puzzle.side(q) == Some(Side::Out) {
+                stack.push(q);
+            }
+        }
+    }
+    puzzle.points().all(|p| {
+        puzzle.side(p) == Some(Side::In) || visited[(p.0 * puzzle.column() + p.1) as usize]
+    })
+}
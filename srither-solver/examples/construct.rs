@@ -0,0 +1,36 @@
+// Copyright (c) 2016 srither-solver developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Builds a puzzle by setting its hints, and solves it.
+
+extern crate srither_core;
+extern crate srither_solver;
+
+use srither_core::geom::{Point, Size};
+use srither_core::puzzle::Puzzle;
+use srither_solver::{SolveOptions, Solutions};
+
+fn main() {
+    let hints = [(0, 0, 3), (1, 1, 1), (1, 3, 2), (2, 0, 2), (2, 2, 1), (3, 3, 3)];
+    let mut puzzle = Puzzle::new(Size(4, 4));
+    for &(r, c, n) in &hints {
+        puzzle.set_hint(Point(r, c), Some(n));
+    }
+    print!("{}", puzzle);
+
+    let solution = srither_solver::solve(&puzzle).unwrap();
+    assert!(srither_solver::verify(&puzzle, &solution));
+    print!("{}", solution);
+
+    // Removing a hint may leave the puzzle with several solutions, which are
+    // enumerated up to the maximum.
+    puzzle.set_hint(Point(0, 0), None);
+    let options = SolveOptions { max_solutions: Some(100), ..SolveOptions::default() };
+    let count = Solutions::with_options(&puzzle, &options).unwrap().count();
+    println!("{} solutions without the hint at (0, 0)", count);
+}
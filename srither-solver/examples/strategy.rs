@@ -0,0 +1,35 @@
+// Copyright (c) 2016 srither-solver developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Solves a puzzle by a custom strategy, a pipeline of the deduction steps.
+
+extern crate srither_core;
+extern crate srither_solver;
+
+use srither_core::puzzle::Puzzle;
+use srither_solver::{SolveOptions, Step, Strategy};
+
+const PUZZLE: &'static str = "3___\n_1_2\n2_1_\n___3\n";
+
+fn main() {
+    let puzzle = PUZZLE.parse::<Puzzle>().unwrap();
+
+    // The steps are applied in order, returning to the first one whenever a
+    // step fixes some edges. The solver branches when none of them does.
+    let strategies = vec![("theorems only", Strategy::new(vec![Step::Theorems])),
+                          ("parsed", "theorems\nconnect\nprobe depth=2\n".parse().unwrap())];
+    for (name, strategy) in strategies {
+        let options = SolveOptions { strategy: Some(strategy), ..SolveOptions::default() };
+        match srither_solver::solve_with_stats(&puzzle, &options) {
+            Ok((_, stats)) => {
+                println!("{}: {} branches, score {:.1}", name, stats.branches, stats.score())
+            }
+            Err(e) => println!("{}: {}", name, e),
+        }
+    }
+}
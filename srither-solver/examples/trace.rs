@@ -0,0 +1,39 @@
+// Copyright (c) 2016 srither-solver developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Follows the course of the deduction of a puzzle.
+
+extern crate srither_core;
+extern crate srither_solver;
+
+use srither_core::puzzle::Puzzle;
+use srither_solver::SolveOptions;
+
+const PUZZLE: &'static str = "3___\n_1_2\n2_1_\n___3\n";
+
+fn main() {
+    let puzzle = PUZZLE.parse::<Puzzle>().unwrap();
+    let options = SolveOptions::default();
+
+    let (board, deductions) = srither_solver::trace_deductions(&puzzle, &options).unwrap();
+    for d in &deductions {
+        println!("{:?} x{}: {} lines, {} crosses, {} cells completed",
+                 d.step,
+                 d.passes,
+                 d.lines,
+                 d.crosses,
+                 d.completed.len());
+    }
+    print!("{}", board);
+
+    // The board after each pass, e.g. to step through the deduction.
+    for (i, c) in srither_solver::trace_passes(&puzzle, &options).unwrap().iter().enumerate() {
+        println!("pass {}: {:?}", i + 1, c.step);
+        print!("{}", c.board);
+    }
+}
@@ -0,0 +1,167 @@
+// Copyright (c) 2016 srither-solver developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! The course of the deduction, step by step.
+//!
+//! The solver does not record which theorem fixed which edge, so the course
+//! is told by the steps: how many edges each step fixed and which hints it
+//! completed.
+
+use srither_core::geom::{Geom, Move, Point};
+use srither_core::puzzle::{Edge, Puzzle};
+
+use {ADAPTIVE_LEVELS, SolveOptions, SolverResult, SolverStats, Solutions, Step};
+
+/// The consecutive passes of a step which fixed some edges.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Deduction {
+    /// The step.
+    pub step: Step,
+    /// The number of the passes of the step grouped together.
+    pub passes: u32,
+    /// The number of the edges fixed to lines.
+    pub lines: usize,
+    /// The number of the edges fixed to crosses.
+    pub crosses: usize,
+    /// The cells with hints whose edges are all fixed by the deduction.
+    pub completed: Vec<Point>,
+}
+
+/// The board after a pass of the deduction.
+#[derive(Clone, Debug)]
+pub struct Checkpoint {
+    /// The step of the pass.
+    pub step: Step,
+    /// The sides and the edges fixed after the pass.
+    pub board: Puzzle,
+}
+
+/// Deduces the puzzle without branching, and returns the puzzle deduced with
+/// the course of the deduction.
+///
+/// The steps of the strategy of the options are used, or the levels of the
+/// adaptive backend without the strategy. The consecutive passes of the
+/// same step are grouped into a deduction. The edges fixed on creating the
+/// theorem pool are counted as by the theorems.
+pub fn trace_deductions(puzzle: &Puzzle,
+                        options: &SolveOptions)
+                        -> SolverResult<(Puzzle, Vec<Deduction>)> {
+    let checkpoints = try!(trace_passes(puzzle, options));
+    let mut deductions = vec![];
+    let mut prev = puzzle.clone();
+    for c in checkpoints {
+        record(&mut deductions, c.step, &prev, &c.board);
+        prev = c.board;
+    }
+    Ok((prev, deductions))
+}
+
+/// Deduces the puzzle without branching, and returns the board after each
+/// pass which changed it.
+///
+/// The first checkpoint is the board on creating the theorem pool, which is
+/// given as by the theorems even if it fixed nothing. The passes are the
+/// same as of `trace_deductions`, so the checkpoints of two versions of the
+/// solver can be compared to find where they diverge.
+pub fn trace_passes(puzzle: &Puzzle, options: &SolveOptions) -> SolverResult<Vec<Checkpoint>> {
+    let mut it = try!(Solutions::create(puzzle, options));
+    let mut solver = it.pop_node();
+    let steps = options.strategy.as_ref().map(|s| s.steps()).unwrap_or(ADAPTIVE_LEVELS);
+    let mut stats = SolverStats::default();
+
+    // The theorem pool fixes some edges on creating the solver.
+    let mut board = puzzle.clone();
+    try!(solver.complete(&mut board));
+    let mut checkpoints = vec![Checkpoint {
+                                   step: Step::Theorems,
+                                   board: board,
+                               }];
+
+    'retry: while !solver.all_filled() {
+        let rev = solver.revision();
+
+        for i in 0..steps.len() {
+            try!(::apply_step(&mut solver, steps, i, &mut stats));
+            if solver.revision() == rev {
+                continue;
+            }
+
+            let mut board = puzzle.clone();
+            try!(solver.complete(&mut board));
+            checkpoints.push(Checkpoint {
+                step: steps[i],
+                board: board,
+            });
+            continue 'retry;
+        }
+
+        break;
+    }
+
+    Ok(checkpoints)
+}
+
+// Records the edges fixed by a pass of the step, grouping it with the last
+// deduction if it is by the same step.
+fn record(deductions: &mut Vec<Deduction>, step: Step, prev: &Puzzle, next: &Puzzle) {
+    let d = diff(step, prev, next);
+    if d.lines == 0 && d.crosses == 0 {
+        return;
+    }
+    match deductions.last_mut() {
+        Some(last) if last.step == d.step => {
+            last.passes += 1;
+            last.lines += d.lines;
+            last.crosses += d.crosses;
+            last.completed.extend(d.completed);
+            return;
+        }
+        _ => {}
+    }
+    deductions.push(d);
+}
+
+fn diff(step: Step, prev: &Puzzle, next: &Puzzle) -> Deduction {
+    let mut d = Deduction {
+        step: step,
+        passes: 1,
+        lines: 0,
+        crosses: 0,
+        completed: vec![],
+    };
+    {
+        let mut count = |before: Option<Edge>, after: Option<Edge>| {
+            match (before, after) {
+                (None, Some(Edge::Line)) => d.lines += 1,
+                (None, Some(Edge::Cross)) => d.crosses += 1,
+                _ => {}
+            }
+        };
+        for r in 0..(prev.row() + 1) {
+            for c in 0..(prev.column() + 1) {
+                let p = Point(r, c);
+                if c < prev.column() {
+                    count(prev.edge_h(p), next.edge_h(p));
+                }
+                if r < prev.row() {
+                    count(prev.edge_v(p), next.edge_v(p));
+                }
+            }
+        }
+    }
+
+    let is_fixed = |puzzle: &Puzzle, p: Point| {
+        puzzle.edge_h(p).is_some() && puzzle.edge_h(p + Move::DOWN).is_some() &&
+        puzzle.edge_v(p).is_some() && puzzle.edge_v(p + Move::RIGHT).is_some()
+    };
+    d.completed = prev.points()
+                      .filter(|&p| prev.hint(p).is_some())
+                      .filter(|&p| !is_fixed(prev, p) && is_fixed(next, p))
+                      .collect();
+    d
+}
@@ -0,0 +1,171 @@
+// Copyright (c) 2016 srither-solver developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Enumeration of the solutions by iterative deepening over the depth of the
+//! assumptions, which can be stopped and resumed.
+//!
+//! A search node is given by the assumptions made from the root of the
+//! search, each a cell assumed inside or outside the loop. A round searches
+//! the nodes depth first down to the bound of the round, and leaves the
+//! children of the nodes at the bound to the frontier, which the next round
+//! searches with the bound one deeper. The state is the nodes themselves, so
+//! the enumeration stops between any two nodes, e.g. at the end of a time
+//! slice, and resumes from the state saved by the caller.
+
+use std::{cmp, mem};
+
+use srither_core::geom::{Geom, Point};
+use srither_core::puzzle::{Puzzle, Side};
+
+use {Error, FillResult, SolveOptions, SolverResult, SolverStats, fill, is_underconstrained,
+     new_solver, satisfies_hints};
+use solver::Solver;
+
+/// The assumptions of a search node from the root, in order.
+pub type SearchNode = Vec<(Point, Side)>;
+
+/// The state of the enumeration by iterative deepening.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Deepening {
+    /// The maximum number of the assumptions of the nodes searched in the
+    /// current round.
+    pub bound: u32,
+    /// The nodes left in the current round, searched from the last.
+    pub pending: Vec<SearchNode>,
+    /// The nodes deeper than the bound, searched in the next round.
+    pub frontier: Vec<SearchNode>,
+    /// The nodes completed as the solutions, in the order found.
+    pub solutions: Vec<SearchNode>,
+    /// The number of the nodes searched.
+    pub nodes: u64,
+}
+
+impl Deepening {
+    /// Creates the state of the enumeration searching the root first.
+    pub fn new() -> Deepening {
+        Deepening { pending: vec![vec![]], ..Deepening::default() }
+    }
+
+    /// Returns true if all the nodes are searched, or the maximum number of
+    /// the solutions of the options are found.
+    pub fn is_finished(&self, options: &SolveOptions) -> bool {
+        let num_solutions = self.solutions.len() as u64;
+        (self.pending.is_empty() && self.frontier.is_empty()) ||
+        options.max_solutions.map_or(false, |max| num_solutions >= max)
+    }
+
+    /// Searches the nodes until the enumeration is finished, or `stop`
+    /// returns true, which is checked before each node. Returns the
+    /// statistics of the nodes searched by this call.
+    ///
+    /// Returns an error if the puzzle is underconstrained and the options
+    /// give no maximum number of the solutions, or a node needs more
+    /// assumptions than the maximum depth of the options.
+    pub fn run<F>(&mut self,
+                  puzzle: &Puzzle,
+                  options: &SolveOptions,
+                  mut stop: F)
+                  -> SolverResult<SolverStats>
+        where F: FnMut() -> bool
+    {
+        if options.max_solutions.is_none() && is_underconstrained(puzzle) {
+            return Err(Error::underconstrained());
+        }
+        let mut stats = SolverStats::default();
+        let root = try!(new_solver(puzzle, options, &mut stats));
+        while !self.is_finished(options) {
+            if self.pending.is_empty() {
+                self.bound += 1;
+                self.pending = mem::replace(&mut self.frontier, vec![]);
+                continue;
+            }
+            if stop() {
+                break;
+            }
+            let node = self.pending.pop().unwrap();
+            self.nodes += 1;
+            try!(self.search(&root, options, node, &mut stats));
+        }
+        Ok(stats)
+    }
+
+    // Fills the node, and records it as a solution, or queues its children
+    // branching on an unknown cell.
+    fn search(&mut self,
+              root: &Solver,
+              options: &SolveOptions,
+              node: SearchNode,
+              stats: &mut SolverStats)
+              -> SolverResult<()> {
+        let (solver, pts) = match fill(assume(root, &node), options, stats) {
+            Ok(FillResult::Completed(mut solver)) => {
+                let mut solution = root.puzzle().clone();
+                if solver.validate_result().is_ok() && solver.complete(&mut solution).is_ok() &&
+                   satisfies_hints(&solution) {
+                    self.solutions.push(node);
+                }
+                return Ok(());
+            }
+            Ok(FillResult::Partial(solver, pts)) => (solver, pts),
+            Err(_) => return Ok(()),
+        };
+        if !solver.can_assume() {
+            return Err(Error::depth_exceeded());
+        }
+
+        let p = solver.puzzle().cellid_to_point(*pts.last().unwrap());
+        stats.branches += 1;
+        stats.max_depth = cmp::max(stats.max_depth, node.len() as u32 + 1);
+        let mut node_in = node.clone();
+        let mut node_out = node;
+        node_in.push((p, Side::In));
+        node_out.push((p, Side::Out));
+        let queue = if (node_in.len() as u32) <= self.bound {
+            &mut self.pending
+        } else {
+            &mut self.frontier
+        };
+        // The outside is searched first, as `Solutions` does.
+        queue.push(node_in);
+        queue.push(node_out);
+        Ok(())
+    }
+
+    /// Returns the solutions found so far, filling their nodes again.
+    pub fn solutions(&self, puzzle: &Puzzle, options: &SolveOptions) -> SolverResult<Vec<Puzzle>> {
+        let mut stats = SolverStats::default();
+        let root = try!(new_solver(puzzle, options, &mut stats));
+        let mut solutions = vec![];
+        for node in &self.solutions {
+            match try!(fill(assume(&root, node), options, &mut stats)) {
+                FillResult::Completed(mut solver) => {
+                    let mut solution = puzzle.clone();
+                    try!(solver.complete(&mut solution));
+                    solution.fill_crosses();
+                    solutions.push(solution);
+                }
+                FillResult::Partial(..) => return Err(Error::invalid_board()),
+            }
+        }
+        Ok(solutions)
+    }
+}
+
+// Returns the solver of the root with the assumptions of the node.
+fn assume<'a>(root: &Solver<'a>, node: &SearchNode) -> Solver<'a> {
+    let mut solver = root.clone();
+    for &(p, side) in node {
+        let p = solver.puzzle().point_to_cellid(p);
+        let _ = match side {
+            Side::In => solver.set_inside(p),
+            Side::Out => solver.set_outside(p),
+        };
+    }
+    solver.set_depth(node.len() as u32);
+    solver
+}
@@ -0,0 +1,96 @@
+// Copyright (c) 2016 srither-solver developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Search effort spent on each edge.
+//!
+//! A search node is a state of the solver popped from the search queue and
+//! filled by deduction. An edge fixed in many nodes is fixed again and again
+//! under different assumptions, which shows where the puzzle is hard.
+
+use srither_core::geom::{Geom, Point, Size};
+use srither_core::puzzle::Puzzle;
+
+use {Error, SolveOptions, Solutions, SolverResult};
+
+/// The number of the search nodes which fixed each edge.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SearchEffort {
+    size: Size,
+    nodes: u32,
+    edge_h: Vec<u32>,
+    edge_v: Vec<u32>,
+}
+
+impl SearchEffort {
+    /// Returns the number of the search nodes.
+    pub fn nodes(&self) -> u32 {
+        self.nodes
+    }
+
+    /// Returns the number of the search nodes which fixed the horizontal edge
+    /// above the cell.
+    pub fn edge_h(&self, p: Point) -> u32 {
+        assert!(0 <= p.0 && p.0 <= self.size.0 && 0 <= p.1 && p.1 < self.size.1);
+        self.edge_h[(p.0 * self.size.1 + p.1) as usize]
+    }
+
+    /// Returns the number of the search nodes which fixed the vertical edge
+    /// on the left of the cell.
+    pub fn edge_v(&self, p: Point) -> u32 {
+        assert!(0 <= p.0 && p.0 < self.size.0 && 0 <= p.1 && p.1 <= self.size.1);
+        self.edge_v[(p.0 * (self.size.1 + 1) + p.1) as usize]
+    }
+
+    /// Returns the largest number of the search nodes which fixed an edge.
+    pub fn max(&self) -> u32 {
+        self.edge_h.iter().chain(&self.edge_v).cloned().max().unwrap_or(0)
+    }
+}
+
+/// Solves the puzzle and returns the first solution, with the number of the
+/// search nodes which fixed each edge until the solution is found.
+pub fn search_effort(puzzle: &Puzzle,
+                     options: &SolveOptions)
+                     -> SolverResult<(Puzzle, SearchEffort)> {
+    let size = puzzle.size();
+    let mut it = try!(Solutions::create(puzzle, options));
+    it.effort = Some(SearchEffort {
+        size: size,
+        nodes: 0,
+        edge_h: vec![0; ((size.0 + 1) * size.1) as usize],
+        edge_v: vec![0; (size.0 * (size.1 + 1)) as usize],
+    });
+    match it.next() {
+        Some(solution) => Ok((solution, it.effort.take().unwrap())),
+        None if it.depth_exceeded() => Err(Error::depth_exceeded()),
+        None => Err(Error::invalid_board()),
+    }
+}
+
+// Counts the edges fixed in `after` but not in `before`, as fixed by a
+// search node.
+pub fn record(effort: &mut SearchEffort, before: &Puzzle, after: &Puzzle) {
+    effort.nodes += 1;
+    let size = effort.size;
+    for r in 0..(size.0 + 1) {
+        for c in 0..size.1 {
+            let p = Point(r, c);
+            if before.edge_h(p).is_none() && after.edge_h(p).is_some() {
+                effort.edge_h[(r * size.1 + c) as usize] += 1;
+            }
+        }
+    }
+    for r in 0..size.0 {
+        for c in 0..(size.1 + 1) {
+            let p = Point(r, c);
+            if before.edge_v(p).is_none() && after.edge_v(p).is_some() {
+                effort.edge_v[(r * (size.1 + 1) + c) as usize] += 1;
+            }
+        }
+    }
+}
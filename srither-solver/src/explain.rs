@@ -0,0 +1,151 @@
+// Copyright (c) 2016 srither-solver developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use srither_core::puzzle::{Edge, Puzzle, Side};
+use srither_core::geom::{Geom, Move, Point};
+
+use {Error, SolverResult, SolverStats};
+use model::{State, Theorem};
+use solver::Solver;
+use theorem_define::THEOREM_DEFINE;
+
+/// The state of a cell after the deduction.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CellExplanation {
+    /// The hint of the cell.
+    pub hint: Option<u8>,
+    /// The side of the cell if it is fixed.
+    pub side: Option<Side>,
+    /// The number of the edges around the cell fixed to lines.
+    pub lines: usize,
+    /// The number of the edges around the cell fixed to crosses.
+    pub crosses: usize,
+    /// The number of the ways to fix the unknown edges around the cell
+    /// without violating the hint.
+    pub possibilities: usize,
+    /// The indices of the built-in theorems which will fix the edges around
+    /// the cell when their other conditions are satisfied.
+    pub theorems: Vec<usize>,
+}
+
+impl CellExplanation {
+    /// Returns the number of the unknown edges around the cell.
+    #[inline]
+    pub fn unknowns(&self) -> usize {
+        4 - self.lines - self.crosses
+    }
+
+    /// Returns true if the edges around the cell are all fixed and satisfy
+    /// the hint.
+    pub fn is_satisfied(&self) -> bool {
+        self.unknowns() == 0 && self.possibilities == 1
+    }
+}
+
+/// Explains the cell at the point of the puzzle.
+///
+/// The puzzle may have fixed edges, such as the progress of a player. The
+/// edges around the cell are examined after the deduction by theorems and
+/// connectivity without backtracking.
+pub fn explain_cell(puzzle: &Puzzle, p: Point) -> SolverResult<CellExplanation> {
+    let theorems = THEOREM_DEFINE.iter()
+                                 .map(|theo| theo.parse::<Theorem>().unwrap())
+                                 .collect::<Vec<_>>();
+    let mut solver = try!(Solver::new(puzzle, theorems.iter().cloned()));
+    try!(::fill_absolutely_fixed(&mut solver, &mut SolverStats::default()));
+
+    let cp = puzzle.point_to_cellid(p);
+    let around = Move::ALL_DIRECTIONS
+                     .iter()
+                     .map(|&d| puzzle.point_to_cellid(p + d))
+                     .collect::<Vec<_>>();
+
+    let side = match solver.get_side(cp) {
+        State::Fixed(side) => Some(side),
+        State::Unknown => None,
+        State::Conflict => return Err(Error::invalid_board()),
+    };
+
+    let mut lines = 0;
+    let mut crosses = 0;
+    for &cp2 in &around {
+        match solver.get_edge(cp, cp2) {
+            State::Fixed(Edge::Line) => lines += 1,
+            State::Fixed(Edge::Cross) => crosses += 1,
+            State::Unknown => {}
+            State::Conflict => return Err(Error::invalid_board()),
+        }
+    }
+
+    let unknowns = 4 - lines - crosses;
+    let possibilities = match puzzle.hint(p) {
+        Some(n) if (n as usize) < lines || (n as usize) > lines + unknowns => 0,
+        Some(n) => combination(unknowns, n as usize - lines),
+        None => 1 << unknowns,
+    };
+
+    let mut pending = vec![];
+    for (i, theo) in theorems.into_iter().enumerate() {
+        for rot in theo.all_rotations() {
+            let size = rot.size();
+            for r in (p.0 - size.0)..(p.0 + 2) {
+                for c in (p.1 - size.1)..(p.1 + 2) {
+                    let edges = try!(solver.pending_result_edges(&rot, Move(r, c)));
+                    let touches = edges.iter().any(|&(p0, p1)| {
+                        (p0 == cp && around.contains(&p1)) || (p1 == cp && around.contains(&p0))
+                    });
+                    if touches && !pending.contains(&i) {
+                        pending.push(i);
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(CellExplanation {
+        hint: puzzle.hint(p),
+        side: side,
+        lines: lines,
+        crosses: crosses,
+        possibilities: possibilities,
+        theorems: pending,
+    })
+}
+
+fn combination(n: usize, k: usize) -> usize {
+    (0..k).fold(1, |acc, i| acc * (n - i) / (i + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use srither_core::geom::Point;
+    use srither_core::puzzle::Puzzle;
+    use super::explain_cell;
+
+    #[test]
+    fn explain() {
+        let puzzle = "0__\n___\n__3\n".parse::<Puzzle>().unwrap();
+
+        let e = explain_cell(&puzzle, Point(0, 0)).unwrap();
+        assert_eq!(Some(0), e.hint);
+        assert_eq!((0, 4), (e.lines, e.crosses));
+        assert_eq!(1, e.possibilities);
+        assert!(e.is_satisfied());
+
+        let e = explain_cell(&puzzle, Point(2, 2)).unwrap();
+        assert_eq!(Some(3), e.hint);
+        assert!(e.lines <= 3);
+        assert!(e.possibilities >= 1);
+    }
+
+    #[test]
+    fn violated() {
+        let puzzle = "04\n__\n".parse::<Puzzle>().unwrap();
+        assert!(explain_cell(&puzzle, Point(0, 0)).is_err());
+    }
+}
@@ -0,0 +1,410 @@
+// Copyright (c) 2016 srither-solver developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use std::collections::HashSet;
+
+use srither_core::puzzle::{Edge, Puzzle};
+use srither_core::geom::{Geom, Point};
+
+use {SolveOptions, SolverResult, solve_with_options};
+use deduction::trace_passes;
+use strategy::Step;
+
+/// An edge deduced from the board by a step, shown to the player as a hint.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Hint {
+    /// The step deducing the edge.
+    pub step: Step,
+    /// True for the horizontal edge above the point, false for the vertical
+    /// edge on the left of it.
+    pub horizontal: bool,
+    /// The point of the edge.
+    pub point: Point,
+    /// The state of the edge.
+    pub edge: Edge,
+}
+
+/// Returns the edges deduced from the board without branching, in the order
+/// of the passes deducing them.
+///
+/// The board may have fixed edges, such as the progress of a player, which
+/// must agree with the solution. An edge is deduced by the first pass fixing
+/// it, though later steps may need the edges deduced before.
+pub fn hints(puzzle: &Puzzle, options: &SolveOptions) -> SolverResult<Vec<Hint>> {
+    let mut hints = vec![];
+    let mut prev = puzzle.clone();
+    for c in try!(trace_passes(puzzle, options)) {
+        for r in 0..(puzzle.row() + 1) {
+            for col in 0..(puzzle.column() + 1) {
+                let p = Point(r, col);
+                if col < puzzle.column() {
+                    if let (None, Some(edge)) = (prev.edge_h(p), c.board.edge_h(p)) {
+                        hints.push(Hint {
+                            step: c.step,
+                            horizontal: true,
+                            point: p,
+                            edge: edge,
+                        });
+                    }
+                }
+                if r < puzzle.row() {
+                    if let (None, Some(edge)) = (prev.edge_v(p), c.board.edge_v(p)) {
+                        hints.push(Hint {
+                            step: c.step,
+                            horizontal: false,
+                            point: p,
+                            edge: edge,
+                        });
+                    }
+                }
+            }
+        }
+        prev = c.board;
+    }
+    Ok(hints)
+}
+
+/// The solution and the deduction order of a puzzle, precomputed for the
+/// hints and the checks of an interactive frontend.
+///
+/// The hints of a board are the edges of the order not fixed yet, answered
+/// without deducing again while the edges of the player agreeing with the
+/// solution are the ones the order is computed from, and the ones of the
+/// order. The wrong edges are ignored. Otherwise, e.g. after the player
+/// clears a correct edge or fixes one the order needs guessing for, the order
+/// is computed again from the correct edges of the board.
+#[derive(Clone, Debug)]
+pub struct HintCache {
+    options: SolveOptions,
+    solution: Puzzle,
+    // The board the order is deduced from, and the edges of the order.
+    base: Puzzle,
+    order: Vec<Hint>,
+    ordered: HashSet<(bool, Point)>,
+    computations: usize,
+}
+
+impl HintCache {
+    /// Solves the puzzle, and deduces the order of its edges from its hints.
+    pub fn new(puzzle: &Puzzle, options: &SolveOptions) -> SolverResult<HintCache> {
+        let base = puzzle.hints_only();
+        let solution = try!(solve_with_options(&base, options));
+        let mut cache = HintCache {
+            options: options.clone(),
+            solution: solution,
+            base: base.clone(),
+            order: vec![],
+            ordered: HashSet::new(),
+            computations: 0,
+        };
+        try!(cache.compute(base));
+        Ok(cache)
+    }
+
+    /// Returns the solution of the puzzle.
+    pub fn solution(&self) -> &Puzzle {
+        &self.solution
+    }
+
+    /// Returns the times the order has been deduced, including the first
+    /// one.
+    pub fn computations(&self) -> usize {
+        self.computations
+    }
+
+    /// Returns the edges deduced from the edges of the board agreeing with
+    /// the solution and not fixed on it, in the order of `hints`.
+    pub fn hints(&mut self, board: &Puzzle) -> SolverResult<Vec<Hint>> {
+        let correct = self.correct(board);
+        if !self.is_valid(&correct) {
+            try!(self.compute(correct));
+        }
+        Ok(self.order
+               .iter()
+               .filter(|h| edge(board, h.horizontal, h.point) != Some(h.edge))
+               .cloned()
+               .collect())
+    }
+
+    /// Returns the number of the edges of the board differing from the
+    /// solution.
+    pub fn mistakes(&self, board: &Puzzle) -> usize {
+        edges(board)
+            .filter(|&(h, p)| {
+                let e = edge(board, h, p);
+                e.is_some() && e != edge(&self.solution, h, p)
+            })
+            .count()
+    }
+
+    fn compute(&mut self, base: Puzzle) -> SolverResult<()> {
+        self.order = try!(hints(&base, &self.options));
+        self.ordered = self.order.iter().map(|h| (h.horizontal, h.point)).collect();
+        self.base = base;
+        self.computations += 1;
+        Ok(())
+    }
+
+    // The hints of the board with the edges agreeing with the solution only.
+    fn correct(&self, board: &Puzzle) -> Puzzle {
+        let mut correct = board.hints_only();
+        for (h, p) in edges(board) {
+            let e = edge(board, h, p);
+            if e.is_some() && e == edge(&self.solution, h, p) {
+                set_edge(&mut correct, h, p, e);
+            }
+        }
+        correct
+    }
+
+    fn is_valid(&self, correct: &Puzzle) -> bool {
+        edges(correct).all(|(h, p)| {
+            match (edge(&self.base, h, p), edge(correct, h, p)) {
+                (Some(b), c) => Some(b) == c,
+                (None, Some(_)) => self.ordered.contains(&(h, p)),
+                (None, None) => true,
+            }
+        })
+    }
+}
+
+// The horizontal and the vertical edges of the puzzle, as the pairs of the
+// direction and the point of `Hint`.
+fn edges<'a>(puzzle: &'a Puzzle) -> Box<Iterator<Item = (bool, Point)> + 'a> {
+    let (row, column) = (puzzle.row(), puzzle.column());
+    let horizontal = (0..(row + 1))
+                         .flat_map(move |r| (0..column).map(move |c| (true, Point(r, c))));
+    let vertical = (0..row)
+                       .flat_map(move |r| (0..(column + 1)).map(move |c| (false, Point(r, c))));
+    Box::new(horizontal.chain(vertical))
+}
+
+fn edge(puzzle: &Puzzle, horizontal: bool, p: Point) -> Option<Edge> {
+    if horizontal {
+        puzzle.edge_h(p)
+    } else {
+        puzzle.edge_v(p)
+    }
+}
+
+fn set_edge(puzzle: &mut Puzzle, horizontal: bool, p: Point, e: Option<Edge>) {
+    if horizontal {
+        puzzle.set_edge_h(p, e);
+    } else {
+        puzzle.set_edge_v(p, e);
+    }
+}
+
+/// A policy choosing the hint shown to the player among the deduced edges.
+///
+/// The player's frontend keeps the policy for the session, and reports the
+/// edges the player deduces without hints by `applied`.
+pub trait HintPolicy {
+    /// Returns the index of the hint to show, or `None` to show none.
+    fn choose(&mut self, hints: &[Hint]) -> Option<usize>;
+
+    /// Records that the player has fixed the edge of the hint, which is
+    /// deducible from the board by its step, without being shown it.
+    fn applied(&mut self, _hint: &Hint) {}
+}
+
+/// Shows the hint of the easiest step, the first of them if several.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct EasiestFirst;
+
+impl HintPolicy for EasiestFirst {
+    fn choose(&mut self, hints: &[Hint]) -> Option<usize> {
+        easiest(hints, |_| true)
+    }
+}
+
+/// Reinforces the steps the player has newly learned.
+///
+/// The steps the player has applied at least once but fewer than the times
+/// to learn them in the session are shown before the others, so that they are
+/// practiced before another step is introduced. Otherwise the hint of the
+/// easiest step is shown.
+#[derive(Clone, Debug)]
+pub struct Reinforce {
+    times: u32,
+    applied: Vec<(Step, u32)>,
+}
+
+impl Reinforce {
+    /// Creates the policy learning a step by applying it the times.
+    pub fn new(times: u32) -> Reinforce {
+        Reinforce {
+            times: times,
+            applied: vec![],
+        }
+    }
+
+    /// Returns the times the player has applied the step in the session.
+    pub fn applied_times(&self, step: Step) -> u32 {
+        self.applied.iter().find(|a| a.0 == step).map_or(0, |a| a.1)
+    }
+}
+
+impl HintPolicy for Reinforce {
+    fn choose(&mut self, hints: &[Hint]) -> Option<usize> {
+        let learning = |h: &Hint| {
+            let n = self.applied_times(h.step);
+            n > 0 && n < self.times
+        };
+        easiest(hints, learning).or_else(|| easiest(hints, |_| true))
+    }
+
+    fn applied(&mut self, hint: &Hint) {
+        if let Some(a) = self.applied.iter_mut().find(|a| a.0 == hint.step) {
+            a.1 += 1;
+            return;
+        }
+        self.applied.push((hint.step, 1));
+    }
+}
+
+// Returns the hint of the easiest step among the ones satisfying the
+// predicate.
+fn easiest<F>(hints: &[Hint], pred: F) -> Option<usize>
+    where F: Fn(&Hint) -> bool
+{
+    hints.iter()
+         .enumerate()
+         .filter(|&(_, h)| pred(h))
+         .min_by_key(|&(i, h)| (level(h.step), i))
+         .map(|(i, _)| i)
+}
+
+fn level(step: Step) -> u32 {
+    match step {
+        Step::Theorems => 0,
+        Step::Connect => 1,
+        Step::Probe { depth } => 1 + depth,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use srither_core::geom::{Geom, Point};
+    use srither_core::puzzle::{Edge, Puzzle};
+    use {SolveOptions, Step, Strategy};
+    use super::{EasiestFirst, Hint, HintCache, HintPolicy, Reinforce, hints, set_edge};
+
+    #[test]
+    fn deduced_edges() {
+        let puzzle = "3___\n_1_2\n2_1_\n___3\n".parse::<Puzzle>().unwrap();
+        let solution = ::solve(&puzzle).unwrap();
+        let hs = hints(&puzzle, &SolveOptions::default()).unwrap();
+        assert!(!hs.is_empty());
+        for h in &hs {
+            let edge = if h.horizontal {
+                solution.edge_h(h.point)
+            } else {
+                solution.edge_v(h.point)
+            };
+            assert_eq!(Some(h.edge), edge);
+        }
+
+        let mut board = puzzle.clone();
+        for h in &hs {
+            if h.horizontal {
+                board.set_edge_h(h.point, Some(h.edge));
+            } else {
+                board.set_edge_v(h.point, Some(h.edge));
+            }
+        }
+        assert!(hints(&board, &SolveOptions::default()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn policies() {
+        let puzzle = "3___\n_1_2\n2_1_\n___3\n".parse::<Puzzle>().unwrap();
+        let hs = hints(&puzzle, &SolveOptions::default()).unwrap();
+        let connect = Hint { step: Step::Connect, ..hs[0] };
+        let probe = Hint { step: Step::Probe { depth: 1 }, ..hs[0] };
+        let candidates = [probe, connect, hs[0]];
+
+        assert_eq!(Some(2), EasiestFirst.choose(&candidates));
+        let mut policy = Reinforce::new(2);
+        assert_eq!(Some(2), policy.choose(&candidates));
+        policy.applied(&probe);
+        assert_eq!(1, policy.applied_times(probe.step));
+        assert_eq!(Some(0), policy.choose(&candidates));
+        policy.applied(&probe);
+        assert_eq!(Some(2), policy.choose(&candidates));
+        assert_eq!(None, policy.choose(&[]));
+    }
+
+    #[test]
+    fn cache() {
+        let puzzle = "3___\n_1_2\n2_1_\n___3\n".parse::<Puzzle>().unwrap();
+        let options = SolveOptions::default();
+        let mut cache = HintCache::new(&puzzle, &options).unwrap();
+        let hs = hints(&puzzle, &options).unwrap();
+        assert_eq!(hs, cache.hints(&puzzle).unwrap());
+
+        // The hints fixed by the player are answered from the order.
+        let mut board = puzzle.clone();
+        set_edge(&mut board, hs[0].horizontal, hs[0].point, Some(hs[0].edge));
+        assert_eq!(&hs[1..], &cache.hints(&board).unwrap()[..]);
+        assert_eq!(0, cache.mistakes(&board));
+
+        // The wrong edges are ignored.
+        let wrong = match hs[1].edge {
+            Edge::Line => Edge::Cross,
+            Edge::Cross => Edge::Line,
+        };
+        set_edge(&mut board, hs[1].horizontal, hs[1].point, Some(wrong));
+        assert_eq!(&hs[1..], &cache.hints(&board).unwrap()[..]);
+        assert_eq!(1, cache.mistakes(&board));
+        assert_eq!(1, cache.computations());
+
+        // The board of the player is solved by the hints, without deducing
+        // again.
+        for h in &hs {
+            set_edge(&mut board, h.horizontal, h.point, Some(h.edge));
+        }
+        assert!(cache.hints(&board).unwrap().is_empty());
+        assert_eq!(1, cache.computations());
+        assert!(::verify(&puzzle, cache.solution()));
+    }
+
+    #[test]
+    fn cache_invalidation() {
+        // Only the theorems deduce a part of the edges.
+        let options = SolveOptions {
+            strategy: Some("theorems".parse::<Strategy>().unwrap()),
+            ..SolveOptions::default()
+        };
+        let puzzle = "3___\n_1_2\n2_1_\n___3\n".parse::<Puzzle>().unwrap();
+        let mut cache = HintCache::new(&puzzle, &options).unwrap();
+        let hs = cache.hints(&puzzle).unwrap();
+        let solution = cache.solution().clone();
+        let mut other = None;
+        for r in 0..(puzzle.row() + 1) {
+            for c in 0..puzzle.column() {
+                let p = Point(r, c);
+                if !hs.iter().any(|h| h.horizontal && h.point == p) {
+                    other = Some(p);
+                }
+            }
+        }
+        let p = other.unwrap();
+
+        // An edge the theorems do not deduce is fixed by the player.
+        let mut board = puzzle.clone();
+        board.set_edge_h(p, solution.edge_h(p));
+        let fresh = hints(&board, &options).unwrap();
+        assert_eq!(fresh, cache.hints(&board).unwrap());
+        assert_eq!(2, cache.computations());
+
+        // And cleared again.
+        assert_eq!(hs, cache.hints(&puzzle).unwrap());
+        assert_eq!(3, cache.computations());
+    }
+}
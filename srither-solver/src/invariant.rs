@@ -0,0 +1,49 @@
+// Copyright (c) 2016 srither-solver developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! The checks of the internal invariants of the solver.
+//!
+//! They are asserted in the debug builds, and in the release builds with the
+//! `paranoid` feature, which also checks the indices the solver does not
+//! check otherwise.
+
+// Asserts the invariant like `debug_assert!`, but also with the `paranoid`
+// feature.
+macro_rules! invariant {
+    ($($arg:tt)*) => {
+        if cfg!(any(debug_assertions, feature = "paranoid")) {
+            assert!($($arg)*)
+        }
+    }
+}
+
+// Returns the element without checking the index, unless the `paranoid`
+// feature is enabled.
+#[cfg(not(feature = "paranoid"))]
+#[inline]
+pub unsafe fn at<T>(slice: &[T], i: usize) -> &T {
+    slice.get_unchecked(i)
+}
+
+#[cfg(feature = "paranoid")]
+#[inline]
+pub unsafe fn at<T>(slice: &[T], i: usize) -> &T {
+    &slice[i]
+}
+
+#[cfg(not(feature = "paranoid"))]
+#[inline]
+pub unsafe fn at_mut<T>(slice: &mut [T], i: usize) -> &mut T {
+    slice.get_unchecked_mut(i)
+}
+
+#[cfg(feature = "paranoid")]
+#[inline]
+pub unsafe fn at_mut<T>(slice: &mut [T], i: usize) -> &mut T {
+    &mut slice[i]
+}
@@ -7,6 +7,9 @@
 // modified, or distributed except according to those terms.
 
 //! Slither Link solver logic.
+//!
+//! The items of `prelude` are stable. The others may change between the
+//! versions.
 
 #![warn(bad_style)]
 #![warn(missing_copy_implementations)]
@@ -28,26 +31,53 @@
 #![cfg_attr(feature="dev", warn(string_add))]
 #![cfg_attr(feature="dev", warn(string_add_assign))]
 
+extern crate time;
 extern crate union_find;
 extern crate srither_core;
 
-use std::{fmt, mem};
+use std::{cmp, fmt, mem};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::error::Error as ErrorTrait;
 
-use srither_core::puzzle::Puzzle;
-use srither_core::geom::CellId;
+use srither_core::puzzle::{Edge, Puzzle, Side};
+use srither_core::geom::{CellId, Geom, Move, Point, Size};
 
-use model::State;
+use model::{State, Theorem};
 use solver::Solver;
 use theorem_define::THEOREM_DEFINE;
 
+pub use deduction::{Checkpoint, Deduction, trace_deductions, trace_passes};
+pub use deepening::{Deepening, SearchNode};
+pub use effort::{SearchEffort, search_effort};
+pub use explain::{CellExplanation, explain_cell};
+pub use hint::{EasiestFirst, Hint, HintCache, HintPolicy, Reinforce, hints};
+pub use model::{HintLayout, ParseHintLayoutError};
+pub use log::{set_verbosity, verbosity};
+pub use strategy::{ParseStrategyError, Step, Strategy, parse_theorems};
+pub use theorem_define::{THEOREM_GROUPS, THEOREM_SET_VERSION};
+pub use variant::{SolverStep, StepState};
+
+#[macro_use]
+mod log;
+#[macro_use]
+mod invariant;
+
+mod deduction;
+mod deepening;
+mod effort;
+mod explain;
+mod hint;
 mod model;
+pub mod prelude;
+mod strategy;
 
 mod step {
     pub mod connect_analysis;
 }
 mod theorem_define;
 mod solver;
+mod variant;
 
 /// An error type which is returned from solving a puzzle.
 #[derive(Copy, Clone, Debug)]
@@ -55,15 +85,37 @@ pub struct Error {
     kind: ErrorKind,
 }
 
-#[derive(Copy, Clone, Debug)]
-enum ErrorKind {
+/// A kind of the errors of solving a puzzle.
+///
+/// More kinds may be added, so the matches on them need a wildcard arm.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ErrorKind {
+    /// The board contradicts itself, e.g. the hints have no solution.
     InvalidBoard,
+    /// A theorem is defined wrongly.
+    InvalidTheorem,
+    /// The version of the theorem set is unknown.
+    UnknownTheoremSet,
+    /// The strategy refers to unknown theorems.
+    InvalidStrategy,
+    /// More nested assumptions are needed than allowed.
+    DepthExceeded,
+    /// The puzzle has too few hints to enumerate its solutions.
+    Underconstrained,
+    #[doc(hidden)]
+    __Nonexhaustive,
 }
 
 impl ErrorTrait for Error {
     fn description(&self) -> &str {
         match self.kind {
             ErrorKind::InvalidBoard => "invalid board data",
+            ErrorKind::InvalidTheorem => "invalid theorem definition",
+            ErrorKind::UnknownTheoremSet => "unknown theorem set version",
+            ErrorKind::InvalidStrategy => "reference to unknown theorems",
+            ErrorKind::DepthExceeded => "more nested assumptions needed than allowed",
+            ErrorKind::Underconstrained => "too few hints to determine the solution",
+            ErrorKind::__Nonexhaustive => unreachable!(),
         }
     }
 }
@@ -75,29 +127,327 @@ impl fmt::Display for Error {
 }
 
 impl Error {
+    /// Returns the kind of the error.
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
     fn invalid_board() -> Error {
         Error { kind: ErrorKind::InvalidBoard }
     }
+    fn invalid_theorem() -> Error {
+        Error { kind: ErrorKind::InvalidTheorem }
+    }
+    fn unknown_theorem_set() -> Error {
+        Error { kind: ErrorKind::UnknownTheoremSet }
+    }
+    fn invalid_strategy() -> Error {
+        Error { kind: ErrorKind::InvalidStrategy }
+    }
+    fn depth_exceeded() -> Error {
+        Error { kind: ErrorKind::DepthExceeded }
+    }
+    fn underconstrained() -> Error {
+        Error { kind: ErrorKind::Underconstrained }
+    }
 }
 
 /// Solving puzzles result.
 pub type SolverResult<T> = Result<T, Error>;
 
+/// An algorithm used to solve puzzles.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Backend {
+    /// Deduces by theorems and connectivity, and then by shallow backtracking
+    /// before branching.
+    Native,
+    /// Deduces by theorems and connectivity, and then branches.
+    Simple,
+    /// Deduces by the cheapest technique, and escalates to more expensive
+    /// ones only when it makes no progress.
+    Adaptive,
+}
+
+/// How the search keeps the states of the branches left to search.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Backtrack {
+    /// Snapshots the boards of at least `SNAPSHOT_MIN_CELLS` cells, and clones
+    /// the smaller ones.
+    Auto,
+    /// Clones the state for each branch, and drops the states searched.
+    Clone,
+    /// Saves the state into a snapshot at each branch, and rolls back to it
+    /// for the other branch. The snapshots reuse the buffers of the states
+    /// searched instead of allocating new ones.
+    Snapshot,
+}
+
+/// The number of the cells from which `Backtrack::Auto` takes the snapshots.
+///
+/// The snapshots save the allocations of the clones, and copy a state in
+/// 0.3-1.3 us against 0.5-1.6 us of a clone on the blank boards of 4x4 to
+/// 10x10. The copies are not replaced by undoing the edges and the sides
+/// fixed in the branch, as they take only 1-4% of the search on these boards,
+/// where the deduction takes 13-270 us a branch; an undo log kept by the side
+/// map, the connectivity and the theorem pool would save no more than that.
+/// On the boards smaller than 5x5 the two strategies take the same time.
+pub const SNAPSHOT_MIN_CELLS: usize = 26;
+
+impl Backtrack {
+    // Returns the strategy used for the puzzle, which is not `Auto`.
+    fn resolve(self, puzzle: &Puzzle) -> Backtrack {
+        match self {
+            Backtrack::Auto if puzzle.cell_len() >= SNAPSHOT_MIN_CELLS => Backtrack::Snapshot,
+            Backtrack::Auto => Backtrack::Clone,
+            other => other,
+        }
+    }
+}
+
+/// The policy restarting a search which takes too long to find a solution.
+///
+/// A search searching the budget of the nodes without finding any solution
+/// starts again from the state deduced at the root, branching on the cells in
+/// an order slightly permuted by the seed. A bad choice of the first
+/// branches, which may take long on the near-symmetric boards, is then
+/// undone. The budget is doubled at each restart, so the search still ends.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct RestartPolicy {
+    /// The number of the nodes searched before the first restart.
+    pub node_budget: u64,
+    /// The seed of the permutations of the cells branched on.
+    pub seed: u64,
+}
+
+// The number of the cells from which each cell is swapped with one at
+// random, to permute the order of the cells branched on.
+const PERMUTE_SPAN: usize = 3;
+
+// The state of the restart policy in a search.
+#[derive(Clone, Debug)]
+struct Restarts {
+    budget: u64,
+    nodes: u64,
+    random: u64,
+    // Whether the order of the cells is permuted, after the first restart.
+    permute: bool,
+}
+
+impl Restarts {
+    fn new(policy: &RestartPolicy) -> Restarts {
+        Restarts {
+            budget: policy.node_budget,
+            nodes: 0,
+            random: policy.seed,
+            permute: false,
+        }
+    }
+
+    // Returns the next random number by SplitMix64, which accepts any seed.
+    fn next_random(&mut self) -> u64 {
+        self.random = self.random.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.random;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // Swaps each cell with one of the next cells at random. The cells are
+    // ordered by the number of their unknown edges, which is kept roughly.
+    fn permute(&mut self, pts: &mut [CellId]) {
+        if !self.permute {
+            return;
+        }
+        for i in 0..pts.len() {
+            let span = cmp::min(PERMUTE_SPAN, pts.len() - i);
+            let j = i + (self.next_random() % span as u64) as usize;
+            pts.swap(i, j);
+        }
+    }
+}
+
+/// Options for solving puzzles.
+#[derive(Clone, Debug)]
+pub struct SolveOptions {
+    /// The algorithm used to solve puzzles.
+    pub backend: Backend,
+    /// The version of the theorem set used to solve puzzles. Older versions
+    /// reproduce the statistics derived before theorems were added.
+    pub theorem_set: u32,
+    /// The deduction steps used instead of the backend, if any.
+    pub strategy: Option<Strategy>,
+    /// The deduction steps of the puzzle variant, applied after the
+    /// theorems.
+    pub variant_steps: Vec<Arc<SolverStep>>,
+    /// The maximum number of the nested assumptions, made by probing or
+    /// branching. Solving fails if more are needed.
+    pub max_depth: Option<u32>,
+    /// The indices of the built-in theorems not used, in addition to the ones
+    /// excluded by the strategy.
+    pub disabled_theorems: Vec<usize>,
+    /// The maximum number of the solutions enumerated. It must be given to
+    /// enumerate the solutions of an underconstrained puzzle, which has no
+    /// hint or a large blank region.
+    pub max_solutions: Option<u64>,
+    /// How the search keeps the states of the branches left to search. The
+    /// solutions and the statistics are the same for all the strategies.
+    pub backtrack: Backtrack,
+    /// The policy restarting the search taking too long to find the first
+    /// solution, if any. Once a solution is found, the search goes on
+    /// without restarting, as a restart would find it again.
+    pub restart: Option<RestartPolicy>,
+    /// The flag stopping the search once set, e.g. by another thread giving
+    /// up the result. The stopped search ends as if no solution were left,
+    /// so its result is to be discarded.
+    pub stop: Option<Arc<AtomicBool>>,
+}
+
+impl Default for SolveOptions {
+    fn default() -> SolveOptions {
+        SolveOptions {
+            backend: Backend::Native,
+            theorem_set: THEOREM_SET_VERSION,
+            strategy: None,
+            variant_steps: vec![],
+            max_depth: None,
+            disabled_theorems: vec![],
+            max_solutions: None,
+            backtrack: Backtrack::Auto,
+            restart: None,
+            stop: None,
+        }
+    }
+}
+
+/// Statistics of solving a puzzle.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SolverStats {
+    /// The number of the passes applying theorems.
+    pub theorem_passes: u32,
+    /// The number of the theorems applied, each matching the board and
+    /// fixing its result.
+    pub theorems_applied: u32,
+    /// The number of the connectivity analyses.
+    pub connect_analyses: u32,
+    /// The number of the cells probed by shallow backtracking.
+    pub probes: u32,
+    /// The number of the branches made by searching.
+    pub branches: u32,
+    /// The maximum number of the nested assumptions made by probing or
+    /// branching.
+    pub max_depth: u32,
+    /// The number of the restarts of the search by the restart policy. The
+    /// other statistics count the steps before the restarts as well.
+    pub restarts: u32,
+    /// The levels escalated to by the adaptive backend, in order. The levels
+    /// are theorems (0), connectivity (1), probing (2) and nested probing (3).
+    pub escalations: Vec<u32>,
+    /// The wall time spent on each step.
+    pub times: StepTimes,
+}
+
+impl SolverStats {
+    /// Returns the number of the guesses, which are the cells probed or
+    /// branched on.
+    pub fn guesses(&self) -> u32 {
+        self.probes + self.branches
+    }
+
+    /// Returns the difficulty score of the puzzle, which weights the
+    /// techniques by how hard they are for humans.
+    pub fn score(&self) -> f64 {
+        self.theorem_passes as f64 + 2.0 * self.connect_analyses as f64 +
+        5.0 * self.probes as f64 + 50.0 * self.branches as f64
+    }
+}
+
+/// Wall time spent on each step of solving a puzzle, in nanoseconds.
+///
+/// The time of probing excludes the steps applied under the assumptions,
+/// which are counted in their own steps.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct StepTimes {
+    /// Constructing the theorem pool.
+    pub pool: u64,
+    /// Matching the theorems while constructing the pool, by the hint the
+    /// theorems start with (0 to 4), or without hints (5).
+    pub pool_buckets: [u64; 6],
+    /// Applying the theorems.
+    pub theorems: u64,
+    /// Analyzing the connectivity.
+    pub connect: u64,
+    /// Probing cells.
+    pub probes: u64,
+}
+
+impl StepTimes {
+    fn steps(&self) -> u64 {
+        self.theorems + self.connect + self.probes
+    }
+}
+
+fn apply_theorems(solver: &mut Solver, stats: &mut SolverStats) -> SolverResult<()> {
+    let start = time::precise_time_ns();
+    let rev = solver.revision();
+    let applied = solver.num_applied_theorems();
+    stats.theorem_passes += 1;
+    let result = solver.apply_all_theorem().and_then(|()| solver.apply_variant_steps());
+    stats.theorems_applied += solver.num_applied_theorems() - applied;
+    stats.times.theorems += time::precise_time_ns() - start;
+    log!(log::PASSES,
+         "  theorems: revision {} -> {}, {} theorems pending",
+         rev,
+         solver.revision(),
+         solver.num_pending_theorems());
+    log_board(solver);
+    result
+}
+
+fn analyze_connection(solver: &mut Solver, stats: &mut SolverStats) -> SolverResult<()> {
+    let start = time::precise_time_ns();
+    let rev = solver.revision();
+    stats.connect_analyses += 1;
+    let result = solver.connect_analysis();
+    stats.times.connect += time::precise_time_ns() - start;
+    log!(log::PASSES,
+         "  connectivity: revision {} -> {}",
+         rev,
+         solver.revision());
+    log_board(solver);
+    result
+}
+
+fn log_board(solver: &Solver) {
+    log!(log::BOARDS, "{}", solver.dump().trim_right());
+}
+
+// Runs the probing, adding its time except the nested steps.
+fn timed_probe<T, F>(stats: &mut SolverStats, f: F) -> T
+    where F: FnOnce(&mut SolverStats) -> T
+{
+    let start = time::precise_time_ns();
+    let nested = stats.times.steps();
+    let result = log::muted(|| f(stats));
+    let elapsed = time::precise_time_ns() - start;
+    stats.times.probes += elapsed - (stats.times.steps() - nested);
+    result
+}
+
 enum FillResult<'a> {
     Completed(Solver<'a>),
     Partial(Solver<'a>, Vec<CellId>),
 }
 
-fn fill_absolutely_fixed(solver: &mut Solver) -> SolverResult<()> {
+fn fill_absolutely_fixed(solver: &mut Solver, stats: &mut SolverStats) -> SolverResult<()> {
     while !solver.all_filled() {
         let rev = solver.revision();
 
-        try!(solver.apply_all_theorem());
+        try!(apply_theorems(solver, stats));
         if solver.revision() != rev {
             continue;
         }
 
-        try!(solver.connect_analysis());
+        try!(analyze_connection(solver, stats));
         if solver.revision() != rev {
             continue;
         }
@@ -108,7 +458,14 @@ fn fill_absolutely_fixed(solver: &mut Solver) -> SolverResult<()> {
     Ok(())
 }
 
-fn fill_by_shallow_backtracking(solver: &mut Solver, pts: &[CellId]) -> SolverResult<bool> {
+fn fill_by_shallow_backtracking(solver: &mut Solver,
+                                pts: &[CellId],
+                                stats: &mut SolverStats)
+                                -> SolverResult<bool> {
+    if !solver.can_assume() {
+        return Ok(false);
+    }
+    let depth = solver.depth();
     let rev = solver.revision();
     let mut solver_in = solver.clone();
     let mut solver_out = solver.clone();
@@ -124,20 +481,25 @@ fn fill_by_shallow_backtracking(solver: &mut Solver, pts: &[CellId]) -> SolverRe
             }
         }
 
+        stats.probes += 1;
+        stats.max_depth = cmp::max(stats.max_depth, depth + 1);
         solver_in.clone_from(&solver);
+        solver_in.set_depth(depth + 1);
         solver_in.set_inside(p);
 
-        if fill_absolutely_fixed(&mut solver_in).is_err() {
+        if fill_absolutely_fixed(&mut solver_in, stats).is_err() {
             solver.set_outside(p);
-            try!(fill_absolutely_fixed(solver));
+            try!(fill_absolutely_fixed(solver, stats));
             continue;
         }
 
         solver_out.clone_from(&solver);
+        solver_out.set_depth(depth + 1);
         solver_out.set_outside(p);
 
-        if fill_absolutely_fixed(&mut solver_out).is_err() {
+        if fill_absolutely_fixed(&mut solver_out, stats).is_err() {
             mem::swap(solver, &mut solver_in);
+            solver.set_depth(depth);
             continue;
         }
 
@@ -147,35 +509,343 @@ fn fill_by_shallow_backtracking(solver: &mut Solver, pts: &[CellId]) -> SolverRe
     Ok(solver.revision() != rev)
 }
 
-fn fill(mut solver: Solver) -> SolverResult<FillResult> {
-    try!(fill_absolutely_fixed(&mut solver));
+// Applies the steps until none of them fixes a cell. The steps before a
+// probing step are used to find contradictions of the assumptions.
+fn fill_by_steps(solver: &mut Solver,
+                 steps: &[Step],
+                 stats: &mut SolverStats)
+                 -> SolverResult<()> {
+    'retry: while !solver.all_filled() {
+        let rev = solver.revision();
+
+        for i in 0..steps.len() {
+            try!(apply_step(solver, steps, i, stats));
+            if solver.revision() != rev {
+                continue 'retry;
+            }
+        }
+
+        break;
+    }
+
+    Ok(())
+}
+
+fn apply_step(solver: &mut Solver,
+              steps: &[Step],
+              i: usize,
+              stats: &mut SolverStats)
+              -> SolverResult<()> {
+    match steps[i] {
+        Step::Theorems => apply_theorems(solver, stats),
+        Step::Connect => analyze_connection(solver, stats),
+        Step::Probe { depth } => {
+            let mut inner = steps[..i].to_owned();
+            if depth > 1 {
+                inner.push(Step::Probe { depth: depth - 1 });
+            }
+            let rev = solver.revision();
+            let result = timed_probe(stats, |stats| fill_by_probing(solver, &inner, stats));
+            log!(log::PASSES,
+                 "  probing (depth {}): revision {} -> {}",
+                 depth,
+                 rev,
+                 solver.revision());
+            log_board(solver);
+            result
+        }
+    }
+}
+
+const ADAPTIVE_LEVELS: &'static [Step] = &[Step::Theorems,
+                                           Step::Connect,
+                                           Step::Probe { depth: 1 },
+                                           Step::Probe { depth: 2 }];
+
+// The number of the escalations resuming from the last successful level.
+const ADAPTIVE_HOLD: u32 = 2;
+
+// Applies the cheapest level until it makes no progress, and then escalates
+// level by level. After an escalated level makes progress, the cheapest level
+// is applied again. As the levels between them have just failed, the next
+// few escalations resume from the successful level instead of climbing again.
+fn fill_adaptively(solver: &mut Solver, stats: &mut SolverStats) -> SolverResult<()> {
+    let mut level = 0;
+    let mut resume = 0;
+    let mut hold = 0;
+
+    while !solver.all_filled() {
+        let rev = solver.revision();
+        try!(apply_step(solver, ADAPTIVE_LEVELS, level, stats));
+        if solver.revision() != rev {
+            if level > 0 {
+                resume = level;
+                hold = ADAPTIVE_HOLD;
+                level = 0;
+            }
+            continue;
+        }
+
+        if level == ADAPTIVE_LEVELS.len() - 1 {
+            break;
+        }
+        if level == 0 && hold > 0 {
+            hold -= 1;
+            level = resume;
+        } else {
+            level += 1;
+        }
+        stats.escalations.push(level as u32);
+    }
+
+    Ok(())
+}
+
+fn unknown_points(solver: &mut Solver) -> SolverResult<Option<Vec<CellId>>> {
+    // The steps may not cover the connectivity, which is needed to find the
+    // unknown cells.
+    try!(solver.sync_connection());
+    if solver.all_filled() {
+        return Ok(None);
+    }
+    Ok(Some(solver.get_unknown_points()))
+}
+
+fn fill_by_probing(solver: &mut Solver,
+                   steps: &[Step],
+                   stats: &mut SolverStats)
+                   -> SolverResult<()> {
+    if !solver.can_assume() {
+        return Ok(());
+    }
+    let depth = solver.depth();
+    let mut solver_in = solver.clone();
+    let mut solver_out = solver.clone();
+
+    for p in solver.get_unknown_points() {
+        match solver.get_side(p) {
+            State::Fixed(_) => continue,
+            State::Unknown => {}
+            State::Conflict => return Err(Error::invalid_board()),
+        }
+
+        stats.probes += 1;
+        stats.max_depth = cmp::max(stats.max_depth, depth + 1);
+        solver_in.clone_from(&solver);
+        solver_in.set_depth(depth + 1);
+        solver_in.set_inside(p);
+        if fill_by_steps(&mut solver_in, steps, stats).is_err() {
+            solver.set_outside(p);
+            try!(fill_by_steps(solver, steps, stats));
+            continue;
+        }
+
+        solver_out.clone_from(&solver);
+        solver_out.set_depth(depth + 1);
+        solver_out.set_outside(p);
+        if fill_by_steps(&mut solver_out, steps, stats).is_err() {
+            mem::swap(solver, &mut solver_in);
+            solver.set_depth(depth);
+            continue;
+        }
+
+        solver.mark_common(&mut solver_in, &mut solver_out);
+    }
+
+    Ok(())
+}
+
+fn fill<'a>(mut solver: Solver<'a>,
+            options: &SolveOptions,
+            stats: &mut SolverStats)
+            -> SolverResult<FillResult<'a>> {
+    match try!(fill_in_place(&mut solver, options, stats)) {
+        None => Ok(FillResult::Completed(solver)),
+        Some(pts) => Ok(FillResult::Partial(solver, pts)),
+    }
+}
+
+// Fills the solver, and returns the unknown cells to branch on, or `None` if
+// all the cells are filled. The solver is left in any state on an error, to
+// reuse its buffers.
+fn fill_in_place(solver: &mut Solver,
+                 options: &SolveOptions,
+                 stats: &mut SolverStats)
+                 -> SolverResult<Option<Vec<CellId>>> {
+    if let Some(ref strategy) = options.strategy {
+        try!(fill_by_steps(solver, strategy.steps(), stats));
+        return unknown_points(solver);
+    }
+    if options.backend == Backend::Adaptive {
+        try!(fill_adaptively(solver, stats));
+        return unknown_points(solver);
+    }
+
+    try!(fill_absolutely_fixed(solver, stats));
 
     if solver.all_filled() {
-        return Ok(FillResult::Completed(solver));
+        return Ok(None);
+    }
+    if options.backend == Backend::Simple {
+        return Ok(Some(solver.get_unknown_points()));
     }
 
     let mut pts = solver.get_unknown_points();
-    while try!(fill_by_shallow_backtracking(&mut solver, &pts)) {
+    loop {
+        let rev = solver.revision();
+        let progress = try!(timed_probe(stats, |stats| {
+            fill_by_shallow_backtracking(solver, &pts, stats)
+        }));
+        log!(log::PASSES,
+             "  probing: revision {} -> {}",
+             rev,
+             solver.revision());
+        log_board(solver);
+        if !progress {
+            break;
+        }
         if solver.all_filled() {
-            return Ok(FillResult::Completed(solver));
+            return Ok(None);
         }
         pts = solver.get_unknown_points();
     }
 
-    Ok(FillResult::Partial(solver, pts))
+    Ok(Some(pts))
+}
+
+// Writes the result of filling a node of the search, and the steps run on it
+// since `counts` of the theorem passes, connectivity analyses and probes.
+fn log_node(result: &SolverResult<Option<Vec<CellId>>>,
+            counts: (u32, u32, u32),
+            stats: &SolverStats) {
+    let state = match *result {
+        Ok(None) => "completed".to_string(),
+        Ok(Some(ref pts)) if pts.len() == 1 => "1 cell unknown".to_string(),
+        Ok(Some(ref pts)) => format!("{} cells unknown", pts.len()),
+        Err(ref e) => format!("failed ({})", e),
+    };
+    log::write(format_args!("  {}: {} theorem passes, {} connectivity analyses, {} probes",
+                            state,
+                            stats.theorem_passes - counts.0,
+                            stats.connect_analyses - counts.1,
+                            stats.probes - counts.2));
+}
+
+// Creates the solver of the root of the search with the theorems and the
+// steps of the options.
+fn new_solver<'a>(puzzle: &'a Puzzle,
+                  options: &SolveOptions,
+                  stats: &mut SolverStats)
+                  -> SolverResult<Solver<'a>> {
+    let theorem_set = try!(theorem_define::theorem_set(options.theorem_set)
+                               .ok_or(Error::unknown_theorem_set()));
+    let only = options.strategy.as_ref().and_then(|s| s.theorems());
+    let mut disabled = options.disabled_theorems.clone();
+    if let Some(ref strategy) = options.strategy {
+        disabled.extend(strategy.disabled_theorems().iter().cloned());
+    }
+    if only.unwrap_or(&[]).iter().chain(&disabled).any(|&i| i >= theorem_set.len()) {
+        return Err(Error::invalid_strategy());
+    }
+    let theorem_set = theorem_set.iter()
+                                 .enumerate()
+                                 .filter(|&(i, _)| only.map_or(true, |o| o.contains(&i)))
+                                 .filter(|&(i, _)| !disabled.contains(&i))
+                                 .map(|(_, &theo)| theo)
+                                 .collect::<Vec<_>>();
+    let theorem = theorem_set.iter().map(|theo| theo.parse().unwrap());
+    let mut solver = try!(Solver::with_times(puzzle, theorem, &mut stats.times));
+    // The theorems matching the board as the pool is constructed.
+    stats.theorems_applied += solver.num_applied_theorems();
+    solver.set_variant_steps(options.variant_steps.clone());
+    solver.set_max_depth(options.max_depth);
+    Ok(solver)
+}
+
+// A node left to search.
+#[derive(Clone, Debug)]
+enum Pending<'a> {
+    // The state of the node.
+    Node(Solver<'a>),
+    // The cell assumed inside, in the state of the last snapshot.
+    Restore(CellId),
 }
 
 /// An iterator iterates all solutions of the puzzle.
 #[derive(Clone, Debug)]
 pub struct Solutions<'a> {
-    queue: Vec<Solver<'a>>,
+    puzzle: &'a Puzzle,
+    queue: Vec<Pending<'a>>,
+    backtrack: Backtrack,
+    // The states of the parents of the nodes to restore, in the order of the
+    // nodes, and the states searched to reuse for the snapshots.
+    snapshots: Vec<Solver<'a>>,
+    spare: Vec<Solver<'a>>,
+    // The state deduced at the root, where the restarts start from, and the
+    // state of the restart policy.
+    root: Option<Solver<'a>>,
+    restarts: Option<Restarts>,
+    options: SolveOptions,
+    stats: SolverStats,
+    depth_exceeded: bool,
+    num_solutions: u64,
+    effort: Option<SearchEffort>,
+    // The sides and edges fixed in the parent of each queued solver, while
+    // the effort is recorded.
+    effort_parents: Vec<Puzzle>,
 }
 
 impl<'a> Solutions<'a> {
     /// Creates an solutions iterator of the puzzle.
     pub fn new(puzzle: &'a Puzzle) -> SolverResult<Solutions<'a>> {
-        let theorem = THEOREM_DEFINE.iter().map(|theo| theo.parse().unwrap());
-        Ok(Solutions { queue: vec![try!(Solver::new(puzzle, theorem))] })
+        Solutions::with_options(puzzle, &SolveOptions::default())
+    }
+
+    /// Creates an solutions iterator of the puzzle with the options.
+    ///
+    /// Returns an error if the puzzle is underconstrained, i.e. it has no
+    /// hint or a large blank region, unless the maximum number of the
+    /// solutions is given by the options. Such a puzzle has too many
+    /// solutions to enumerate them all.
+    pub fn with_options(puzzle: &'a Puzzle, options: &SolveOptions) -> SolverResult<Solutions<'a>> {
+        if options.max_solutions.is_none() && is_underconstrained(puzzle) {
+            return Err(Error::underconstrained());
+        }
+        Solutions::create(puzzle, options)
+    }
+
+    // Creates an solutions iterator without checking the puzzle, for the
+    // functions stopping early.
+    fn create(puzzle: &'a Puzzle, options: &SolveOptions) -> SolverResult<Solutions<'a>> {
+        let mut stats = SolverStats::default();
+        let solver = try!(new_solver(puzzle, options, &mut stats));
+        Ok(Solutions {
+            puzzle: puzzle,
+            queue: vec![Pending::Node(solver)],
+            backtrack: options.backtrack.resolve(puzzle),
+            snapshots: vec![],
+            spare: vec![],
+            root: None,
+            restarts: options.restart.as_ref().map(Restarts::new),
+            options: options.clone(),
+            stats: stats,
+            depth_exceeded: false,
+            num_solutions: 0,
+            effort: None,
+            effort_parents: vec![],
+        })
+    }
+
+    /// Returns the statistics of solving the puzzle so far.
+    pub fn stats(&self) -> &SolverStats {
+        &self.stats
+    }
+
+    /// Returns true if the search has stopped because a branch exceeds the
+    /// maximum depth of the options. The solutions found so far are valid,
+    /// but there may be more.
+    pub fn depth_exceeded(&self) -> bool {
+        self.depth_exceeded
     }
 }
 
@@ -183,39 +853,1200 @@ impl<'a> Iterator for Solutions<'a> {
     type Item = Puzzle;
 
     fn next(&mut self) -> Option<Puzzle> {
-        while let Some(solver) = self.queue.pop() {
-            let (solver, pts) = match fill(solver) {
-                Ok(FillResult::Completed(mut solver)) => {
-                    if solver.validate_result().is_err() {
+        let mut solution = self.puzzle.clone();
+        if self.next_into(&mut solution) {
+            Some(solution)
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a> Solutions<'a> {
+    // Writes the next solution into `buf`, which must be a clone of the
+    // puzzle, and returns false if there is no more solution, or if the
+    // search is stopped by the flag of the options.
+    fn next_into(&mut self, buf: &mut Puzzle) -> bool {
+        self.next_until(buf, &mut || false).unwrap_or(false)
+    }
+
+    // Like `next_into`, but calls `stop` before each node, and returns `None`
+    // if it returns true or the flag of the options is set. The node is left
+    // queued, so the search can go on.
+    fn next_until<F>(&mut self, buf: &mut Puzzle, stop: &mut F) -> Option<bool>
+        where F: FnMut() -> bool
+    {
+        if self.options.max_solutions.map_or(false, |max| self.num_solutions >= max) {
+            return Some(false);
+        }
+        while !self.queue.is_empty() {
+            if stop() || self.options.stop.as_ref().map_or(false, |s| s.load(Ordering::SeqCst)) {
+                return None;
+            }
+            if self.restart_due() {
+                self.restart();
+            }
+            let mut solver = self.pop_node();
+            let before = self.effort_before();
+            log!(log::STEPS, "node at depth {}", solver.depth());
+            let counts = (self.stats.theorem_passes,
+                          self.stats.connect_analyses,
+                          self.stats.probes);
+            let result = fill_in_place(&mut solver, &self.options, &mut self.stats);
+            if log::enabled(log::STEPS) {
+                log_node(&result, counts, &self.stats);
+            }
+            let mut after = None;
+            if let (Some(before), true) = (before, result.is_ok()) {
+                let mut puzzle = self.puzzle.clone();
+                if solver.complete(&mut puzzle).is_ok() {
+                    effort::record(self.effort.as_mut().unwrap(), &before, &puzzle);
+                    after = Some(puzzle);
+                }
+            }
+            let pts = match result {
+                Ok(None) => {
+                    let completed = solver.validate_result().is_ok() &&
+                                    solver.complete(buf).is_ok();
+                    self.recycle(solver);
+                    if !completed {
                         continue;
                     }
-                    match solver.into() {
-                        Ok(result) => return Some(result),
-                        Err(_) => continue,
+                    buf.fill_crosses();
+                    // The hints may be violated without some theorems, or if
+                    // an assumption or the connectivity completes the board
+                    // before the theorems are applied to it.
+                    if satisfies_hints(buf) {
+                        self.check_solution(buf);
+                        self.num_solutions += 1;
+                        return Some(true);
                     }
+                    continue;
+                }
+                Ok(Some(mut pts)) => {
+                    if let Some(ref mut restarts) = self.restarts {
+                        if solver.depth() == 0 && self.root.is_none() {
+                            self.root = Some(solver.clone());
+                        }
+                        restarts.permute(&mut pts);
+                    }
+                    pts
+                }
+                Err(_) => {
+                    self.recycle(solver);
+                    continue;
                 }
-                Ok(FillResult::Partial(solver, pts)) => (solver, pts),
-                Err(_) => continue,
             };
+            if !solver.can_assume() {
+                self.depth_exceeded = true;
+                self.queue.clear();
+                self.snapshots.clear();
+                self.effort_parents.clear();
+                return Some(false);
+            }
+            // The unknown cells are branched on as a whole, even if they are
+            // split into the areas sharing no unknown edge. Such areas are
+            // still coupled by the connectivity of the loop, so their
+            // solutions cannot be searched for separately and combined.
             let p = *pts.last().unwrap();
-            let mut solver_in = solver.clone();
+            let depth = solver.depth() + 1;
+            self.stats.branches += 1;
+            self.stats.max_depth = cmp::max(self.stats.max_depth, depth);
+            if self.backtrack == Backtrack::Snapshot {
+                let snapshot = self.snapshot(&solver);
+                self.snapshots.push(snapshot);
+                self.queue.push(Pending::Restore(p));
+            } else {
+                let mut solver_in = solver.clone();
+                solver_in.set_depth(depth);
+                let _ = solver_in.set_inside(p);
+                self.queue.push(Pending::Node(solver_in));
+            }
             let mut solver_out = solver;
-            solver_in.set_inside(p);
-            solver_out.set_outside(p);
-            self.queue.push(solver_in);
-            self.queue.push(solver_out);
+            solver_out.set_depth(depth);
+            let _ = solver_out.set_outside(p);
+            self.queue.push(Pending::Node(solver_out));
+            if self.effort.is_some() {
+                let after = after.unwrap_or_else(|| self.puzzle.clone());
+                self.effort_parents.push(after.clone());
+                self.effort_parents.push(after);
+            }
         }
 
-        None
+        Some(false)
+    }
+
+    // Returns true if the nodes searched since the last restart exceed the
+    // budget of the restart policy, before any solution is found. The
+    // search of the root is not restarted, as it has nothing to undo.
+    fn restart_due(&mut self) -> bool {
+        let found = self.num_solutions > 0;
+        let rooted = self.root.is_some();
+        match self.restarts {
+            Some(ref mut restarts) => {
+                restarts.nodes += 1;
+                !found && rooted && restarts.nodes > restarts.budget
+            }
+            None => false,
+        }
     }
+
+    // Drops the nodes left to search, and queues the root again with the
+    // cells to branch on permuted.
+    fn restart(&mut self) {
+        {
+            let restarts = self.restarts.as_mut().unwrap();
+            restarts.budget = restarts.budget.saturating_mul(2);
+            restarts.nodes = 1;
+            restarts.permute = true;
+        }
+        self.stats.restarts += 1;
+        log!(log::STEPS, "restart {}", self.stats.restarts);
+
+        while let Some(pending) = self.queue.pop() {
+            if let Pending::Node(solver) = pending {
+                self.recycle(solver);
+            }
+        }
+        while let Some(snapshot) = self.snapshots.pop() {
+            self.recycle(snapshot);
+        }
+        self.effort_parents.clear();
+        let root = self.root.as_ref().unwrap().clone();
+        self.queue.push(Pending::Node(root));
+    }
+
+    // Pops the next node, restoring its state from the last snapshot if it
+    // is the other branch of the snapshot.
+    fn pop_node(&mut self) -> Solver<'a> {
+        match self.queue.pop().unwrap() {
+            Pending::Node(solver) => solver,
+            Pending::Restore(p) => {
+                let mut solver = self.snapshots.pop().unwrap();
+                let depth = solver.depth() + 1;
+                solver.set_depth(depth);
+                let _ = solver.set_inside(p);
+                solver
+            }
+        }
+    }
+
+    // Copies the state into the buffers of a state searched if any.
+    fn snapshot(&mut self, solver: &Solver<'a>) -> Solver<'a> {
+        match self.spare.pop() {
+            Some(mut snapshot) => {
+                snapshot.clone_from(solver);
+                snapshot
+            }
+            None => solver.clone(),
+        }
+    }
+
+    // Keeps the state searched for the snapshots, if they are taken.
+    fn recycle(&mut self, solver: Solver<'a>) {
+        if self.backtrack == Backtrack::Snapshot {
+            self.spare.push(solver);
+        }
+    }
+
+    // Returns the sides and edges fixed in the parent of the next solver, if
+    // the effort is recorded. The edges fixed by the assumption of the branch
+    // are counted in the node, as the side map propagates them at once. The
+    // first node starts from the puzzle, as the theorem pool fixes some edges
+    // on creating the solver.
+    fn effort_before(&mut self) -> Option<Puzzle> {
+        if self.effort.is_none() {
+            return None;
+        }
+        Some(self.effort_parents.pop().unwrap_or_else(|| self.puzzle.clone()))
+    }
+
+    // With the `check-solutions` feature, panics if the solution is invalid,
+    // which means some theorem or step is unsound.
+    #[cfg(feature = "check-solutions")]
+    fn check_solution(&self, solution: &Puzzle) {
+        if !verify(self.puzzle, solution) {
+            panic!("invalid solution derived:\n{}\nfrom the puzzle:\n{}",
+                   solution,
+                   self.puzzle);
+        }
+    }
+
+    #[cfg(not(feature = "check-solutions"))]
+    #[inline]
+    fn check_solution(&self, _solution: &Puzzle) {}
+}
+
+/// Calls `f` with each solution of the puzzle until `f` returns false, and
+/// returns the number of the solutions passed to `f`.
+///
+/// Unlike `Solutions`, every solution is written into the same buffer, so
+/// counting many solutions of a large puzzle does not allocate a puzzle for
+/// each solution. As `f` decides when to stop, an underconstrained puzzle is
+/// accepted without the maximum number of the solutions. Returns an error if
+/// the search stops at the maximum depth of the options before `f` returns
+/// false.
+pub fn for_each_solution<F>(puzzle: &Puzzle,
+                            options: &SolveOptions,
+                            f: F)
+                            -> SolverResult<u64>
+    where F: FnMut(&Puzzle) -> bool
+{
+    for_each_solution_until(puzzle, options, || false, f).map(|count| count.unwrap())
+}
+
+/// Like `for_each_solution`, but calls `stop` before each node of the
+/// search, and returns `None` if it returns true, e.g. when another thread
+/// no longer needs the result.
+pub fn for_each_solution_until<S, F>(puzzle: &Puzzle,
+                                     options: &SolveOptions,
+                                     mut stop: S,
+                                     mut f: F)
+                                     -> SolverResult<Option<u64>>
+    where S: FnMut() -> bool,
+          F: FnMut(&Puzzle) -> bool
+{
+    let mut it = try!(Solutions::create(puzzle, options));
+    let mut buf = puzzle.clone();
+    let mut count = 0;
+    loop {
+        match it.next_until(&mut buf, &mut stop) {
+            Some(true) => {}
+            Some(false) => break,
+            None => return Ok(None),
+        }
+        count += 1;
+        if !f(&buf) {
+            return Ok(Some(count));
+        }
+    }
+    if it.depth_exceeded() {
+        return Err(Error::depth_exceeded());
+    }
+    Ok(Some(count))
+}
+
+/// Returns true if the solution is a solution of the puzzle: it has the same
+/// hints, all of its edges are known, and its lines are a single loop
+/// satisfying the hints.
+pub fn verify(puzzle: &Puzzle, solution: &Puzzle) -> bool {
+    if puzzle.size() != solution.size() || solution.has_unknown_edge() {
+        return false;
+    }
+    if puzzle.points().any(|p| puzzle.hint(p) != solution.hint(p)) || !satisfies_hints(solution) {
+        return false;
+    }
+
+    // Every corner has no or two lines, and the lines are connected.
+    let lines = |p: Point| {
+        [(Move::LEFT, solution.edge_h(p + Move::LEFT)),
+         (Move::RIGHT, solution.edge_h(p)),
+         (Move::UP, solution.edge_v(p + Move::UP)),
+         (Move::DOWN, solution.edge_v(p))]
+    };
+    let mut start = None;
+    let mut num_line = 0;
+    for r in 0..(solution.row() + 1) {
+        for c in 0..(solution.column() + 1) {
+            let p = Point(r, c);
+            match lines(p).iter().filter(|&&(_, e)| e == Some(Edge::Line)).count() {
+                0 => {}
+                2 => {
+                    start = Some(p);
+                    num_line += 2;
+                }
+                _ => return false,
+            }
+        }
+    }
+    let start = match start {
+        Some(p) => p,
+        None => return false,
+    };
+
+    let mut length = 0;
+    let (mut prev, mut cur) = (start, start);
+    loop {
+        let next = lines(cur)
+                       .iter()
+                       .filter(|&&(m, e)| e == Some(Edge::Line) && cur + m != prev)
+                       .map(|&(m, _)| cur + m)
+                       .next()
+                       .unwrap();
+        prev = cur;
+        cur = next;
+        length += 1;
+        if cur == start {
+            break;
+        }
+    }
+    // Each line is counted at its two ends.
+    length * 2 == num_line
+}
+
+fn satisfies_hints(puzzle: &Puzzle) -> bool {
+    puzzle.points().all(|p| {
+        match puzzle.hint(p) {
+            Some(hint) => {
+                let edges = [puzzle.edge_h(p),
+                             puzzle.edge_h(p + Move::DOWN),
+                             puzzle.edge_v(p),
+                             puzzle.edge_v(p + Move::RIGHT)];
+                edges.iter().filter(|&&e| e == Some(Edge::Line)).count() == hint as usize
+            }
+            None => true,
+        }
+    })
+}
+
+// The side of the smallest blank square making a puzzle underconstrained. A
+// blank 5x5 region alone has more than a million loops.
+const MIN_BLANK_SQUARE: usize = 5;
+
+// Returns true if the cell has no hint, side or edge.
+fn is_blank_cell(puzzle: &Puzzle, p: Point) -> bool {
+    puzzle.hint(p).is_none() && puzzle.side(p).is_none() && puzzle.edge_h(p).is_none() &&
+    puzzle.edge_h(p + Move::DOWN).is_none() && puzzle.edge_v(p).is_none() &&
+    puzzle.edge_v(p + Move::RIGHT).is_none()
+}
+
+// Returns true if the puzzle has more than one cell, all of which are blank.
+fn is_blank(puzzle: &Puzzle) -> bool {
+    puzzle.row() * puzzle.column() > 1 && puzzle.points().all(|p| is_blank_cell(puzzle, p))
+}
+
+// Returns true if the puzzle is blank, or has a blank square of the cells
+// large enough to have too many solutions to enumerate. A blank region may
+// be forced out of the loop by the hints around it, so this does not mean
+// the solution is not unique.
+fn is_underconstrained(puzzle: &Puzzle) -> bool {
+    if is_blank(puzzle) {
+        return true;
+    }
+    // The side of the largest blank square whose bottom right cell is each
+    // cell of the current row.
+    let column = puzzle.column() as usize;
+    let mut prev = vec![0; column];
+    for r in 0..puzzle.row() {
+        let mut cur = vec![0; column];
+        for c in 0..column {
+            if !is_blank_cell(puzzle, Point(r, c as i32)) {
+                continue;
+            }
+            cur[c] = if c == 0 {
+                1
+            } else {
+                cmp::min(cmp::min(prev[c], cur[c - 1]), prev[c - 1]) + 1
+            };
+            if cur[c] >= MIN_BLANK_SQUARE {
+                return true;
+            }
+        }
+        prev = cur;
+    }
+    false
 }
 
 /// Returns the first solution of the puzzle.
 pub fn solve(puzzle: &Puzzle) -> SolverResult<Puzzle> {
-    let mut it = try!(Solutions::new(puzzle));
+    solve_with_options(puzzle, &SolveOptions::default())
+}
+
+/// Returns the first solution of the puzzle solved with the options.
+pub fn solve_with_options(puzzle: &Puzzle, options: &SolveOptions) -> SolverResult<Puzzle> {
+    solve_with_stats(puzzle, options).map(|(solution, _)| solution)
+}
+
+/// Returns the first solution of the puzzle and the statistics of solving it.
+pub fn solve_with_stats(puzzle: &Puzzle,
+                        options: &SolveOptions)
+                        -> SolverResult<(Puzzle, SolverStats)> {
+    let mut it = try!(Solutions::create(puzzle, options));
     if let Some(solution) = it.next() {
-        return Ok(solution);
+        return Ok((solution, it.stats().clone()));
+    }
+    if it.depth_exceeded() {
+        return Err(Error::depth_exceeded());
     }
 
     Err(Error::invalid_board())
 }
+
+/// Returns the solution of the puzzle, or an error if the puzzle has more
+/// than one solution.
+///
+/// A puzzle without any hint, edge or side is rejected without searching, as
+/// any cell surrounded by a loop is a solution of it unless it has only one
+/// cell.
+pub fn solve_unique(puzzle: &Puzzle, options: &SolveOptions) -> SolverResult<Puzzle> {
+    if is_blank(puzzle) {
+        return Err(Error::underconstrained());
+    }
+    let mut it = try!(Solutions::create(puzzle, options));
+    let solution = match it.next() {
+        Some(solution) => solution,
+        None if it.depth_exceeded() => return Err(Error::depth_exceeded()),
+        None => return Err(Error::invalid_board()),
+    };
+    if it.next().is_some() {
+        return Err(Error::underconstrained());
+    }
+    if it.depth_exceeded() {
+        return Err(Error::depth_exceeded());
+    }
+    Ok(solution)
+}
+
+/// Deduces the edges in the window of the puzzle.
+///
+/// Only the hints in the window and the fixed sides and edges in and around
+/// the window are used, and the cells outside the window are regarded as
+/// unknown. So the deductions are valid whatever the outside is, which makes
+/// it cheap to give feedback on a part of a large board. Returns the puzzle
+/// with the edges deduced in and on the border of the window.
+///
+/// # Panics
+///
+/// Panics if the window is not contained in the puzzle.
+pub fn solve_window(puzzle: &Puzzle, origin: Point, size: Size) -> SolverResult<Puzzle> {
+    assert!(size.0 > 0 && size.1 > 0);
+    assert!(puzzle.contains(origin) && puzzle.contains(origin + Move(size.0 - 1, size.1 - 1)));
+
+    // The window is surrounded by the unknown cells. The sides of the cells
+    // outside the puzzle are known.
+    let offset = Move(origin.0 - 1, origin.1 - 1);
+    let in_window = |q: Point| q.0 > 0 && q.0 <= size.0 && q.1 > 0 && q.1 <= size.1;
+    let mut local = Puzzle::new(Size(size.0 + 2, size.1 + 2));
+    for q in local.points() {
+        let o = q + offset;
+        if in_window(q) {
+            local.set_hint(q, puzzle.hint(o));
+        }
+        local.set_side(q, puzzle.side(o));
+        if q.0 > 0 {
+            local.set_edge_h(q, puzzle.edge_h(o));
+        }
+        if q.1 > 0 {
+            local.set_edge_v(q, puzzle.edge_v(o));
+        }
+    }
+
+    let local = {
+        let theorem = THEOREM_DEFINE.iter().map(|theo| theo.parse().unwrap());
+        let mut solver = try!(Solver::new_bounded(&local, theorem));
+        loop {
+            let rev = solver.revision();
+            try!(solver.apply_all_theorem());
+            if solver.revision() == rev {
+                break;
+            }
+        }
+        let result: SolverResult<Puzzle> = solver.into();
+        try!(result)
+    };
+
+    let mut result = puzzle.clone();
+    for q in local.points() {
+        let o = q + offset;
+        let (edge_h, edge_v) = (local.edge_h(q), local.edge_v(q));
+        if in_window(q) {
+            if let Some(side) = local.side(q) {
+                result.set_side(o, Some(side));
+            }
+        }
+        if edge_h.is_some() && (in_window(q) || in_window(q + Move::UP)) {
+            result.set_edge_h(o, edge_h);
+        }
+        if edge_v.is_some() && (in_window(q) || in_window(q + Move::LEFT)) {
+            result.set_edge_v(o, edge_v);
+        }
+    }
+    Ok(result)
+}
+
+/// What lies beyond a side of the puzzle.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Border {
+    /// The cells beyond the side are outside of the loop, as in a whole
+    /// puzzle.
+    Outside,
+    /// The cells beyond the side are unknown, as in a part of a larger
+    /// puzzle. The loop may cross the side.
+    Unknown,
+}
+
+/// What lies beyond each side of the puzzle.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct BorderPolicy {
+    /// Beyond the top row.
+    pub up: Border,
+    /// Beyond the rightmost column.
+    pub right: Border,
+    /// Beyond the bottom row.
+    pub down: Border,
+    /// Beyond the leftmost column.
+    pub left: Border,
+}
+
+impl BorderPolicy {
+    /// Returns the policy with the same border on every side.
+    pub fn all(border: Border) -> BorderPolicy {
+        BorderPolicy {
+            up: border,
+            right: border,
+            down: border,
+            left: border,
+        }
+    }
+
+    fn all_outside(&self) -> bool {
+        [self.up, self.right, self.down, self.left].iter().all(|&b| b == Border::Outside)
+    }
+}
+
+impl Default for BorderPolicy {
+    fn default() -> BorderPolicy {
+        BorderPolicy::all(Border::Outside)
+    }
+}
+
+/// Deduces the edges of the puzzle without branching, regarding the cells
+/// beyond its sides as the policy says.
+///
+/// If every side is outside, the theorems and the connectivity are used as in
+/// solving. Otherwise the puzzle is solved as a window surrounded by a ring
+/// of cells, which are outside beyond the outside sides and unknown beyond
+/// the others, and only the theorems are used, because the connectivity
+/// analysis assumes that all cells beyond the sides are one outside area.
+/// Returns the puzzle with the edges deduced.
+pub fn deduce(puzzle: &Puzzle, border: BorderPolicy) -> SolverResult<Puzzle> {
+    if border.all_outside() {
+        let theorem = THEOREM_DEFINE.iter().map(|theo| theo.parse().unwrap());
+        let mut solver = try!(Solver::new(puzzle, theorem));
+        try!(fill_absolutely_fixed(&mut solver, &mut SolverStats::default()));
+        return solver.into();
+    }
+
+    let size = puzzle.size();
+    let mut padded = Puzzle::new(Size(size.0 + 2, size.1 + 2));
+    for q in padded.points() {
+        let o = q + Move(-1, -1);
+        if puzzle.contains(o) {
+            padded.set_hint(q, puzzle.hint(o));
+            padded.set_side(q, puzzle.side(o));
+        } else {
+            let outside = (o.0 < 0 && border.up == Border::Outside) ||
+                          (o.1 >= size.1 && border.right == Border::Outside) ||
+                          (o.0 >= size.0 && border.down == Border::Outside) ||
+                          (o.1 < 0 && border.left == Border::Outside);
+            if outside {
+                padded.set_side(q, Some(Side::Out));
+            }
+        }
+        if puzzle.contains(o) || puzzle.contains(o + Move::UP) {
+            padded.set_edge_h(q, puzzle.edge_h(o));
+        }
+        if puzzle.contains(o) || puzzle.contains(o + Move::LEFT) {
+            padded.set_edge_v(q, puzzle.edge_v(o));
+        }
+    }
+
+    let result = try!(solve_window(&padded, Point(1, 1), size));
+    Ok(result.crop(Point(1, 1), size))
+}
+
+/// Searches a counterexample of the theorem written in the theorem definition
+/// format.
+///
+/// The search is exhaustive on the area of the theorem extended by one cell in
+/// each direction. A counterexample is a board which matches the theorem and
+/// satisfies its hints and the loop constraints visible in the area, but
+/// violates the result of the theorem. As theorems are applied to boards
+/// larger than them, loops closed in the area are not counterexamples.
+/// Returns `None` if the theorem has no counterexample.
+pub fn find_counterexample(theorem: &str) -> SolverResult<Option<Puzzle>> {
+    let theorem = try!(theorem.parse::<Theorem>().map_err(|_| Error::invalid_theorem()));
+    Ok(theorem.find_counterexample())
+}
+
+#[cfg(test)]
+mod tests {
+    use srither_core::geom::{Geom, Point, Size};
+    use srither_core::puzzle::{Edge, Puzzle};
+    use std::cell::Cell;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use theorem_define::THEOREM_DEFINE;
+
+    use srither_core::puzzle::Side;
+    use super::{Backend, Backtrack, Border, BorderPolicy, Deepening, Error, ErrorKind,
+                RestartPolicy, SolveOptions, Solutions, SolverResult, SolverStats, Step,
+                SolverStep, StepState, Strategy, deduce, for_each_solution,
+                for_each_solution_until, search_effort, solve, solve_unique, solve_window,
+                solve_with_options, solve_with_stats, trace_deductions, trace_passes, verify};
+
+    #[test]
+    fn stats() {
+        let puzzle = "3__\n_1_\n".parse::<Puzzle>().unwrap();
+        let (_, stats) = solve_with_stats(&puzzle, &SolveOptions::default()).unwrap();
+        assert!(stats.theorem_passes > 0);
+        assert!(stats.theorems_applied > 0);
+        assert!(stats.score() > 0.0);
+        let buckets = stats.times.pool_buckets.iter().fold(0, |sum, &t| sum + t);
+        assert!(stats.times.pool >= buckets);
+
+        let stats = SolverStats { branches: 1, ..SolverStats::default() };
+        assert!(stats.score() > SolverStats { probes: 1, ..SolverStats::default() }.score());
+    }
+
+    #[test]
+    fn strategy() {
+        let puzzle = "3__\n_1_\n".parse::<Puzzle>().unwrap();
+        let expected = Solutions::new(&puzzle).unwrap().collect::<Vec<_>>();
+
+        for input in &["theorems\nconnect\nprobe depth=2\n", "connect\n", "theorems only=0-3\n"] {
+            let strategy = input.parse::<Strategy>().unwrap();
+            let options = SolveOptions { strategy: Some(strategy), ..SolveOptions::default() };
+            let solutions = Solutions::with_options(&puzzle, &options).unwrap().collect::<Vec<_>>();
+            assert_eq!(expected.len(), solutions.len());
+            assert!(solutions.iter().all(|s| expected.contains(s)));
+        }
+
+        let strategy = "theorems only=1000\n".parse::<Strategy>().unwrap();
+        let options = SolveOptions { strategy: Some(strategy), ..SolveOptions::default() };
+        assert!(solve_with_options(&puzzle, &options).is_err());
+    }
+
+    #[test]
+    fn solution_in_text() {
+        // The text of a solution carries the hints and all the edges of the
+        // loop. The sides of the cells are not written, but are derived again
+        // by solving the parsed board, which has nothing left to deduce.
+        let puzzle = "3___\n_1_2\n2_1_\n___3\n".parse::<Puzzle>().unwrap();
+        let solution = solve(&puzzle).unwrap();
+        let parsed = solution.to_string().parse::<Puzzle>().unwrap();
+        assert!(!parsed.has_unknown_edge());
+        assert_eq!(puzzle, parsed.hints_only());
+        assert!(verify(&puzzle, &parsed));
+        for p in parsed.points() {
+            assert_eq!(None, parsed.side(p));
+        }
+        assert_eq!(solution.to_string(), parsed.to_string());
+        assert_eq!(solution, solve(&parsed).unwrap());
+    }
+
+    #[test]
+    fn adaptive() {
+        let puzzle = "3__\n_1_\n".parse::<Puzzle>().unwrap();
+        let expected = Solutions::new(&puzzle).unwrap().collect::<Vec<_>>();
+
+        let options = SolveOptions { backend: Backend::Adaptive, ..SolveOptions::default() };
+        let mut it = Solutions::with_options(&puzzle, &options).unwrap();
+        let solutions = it.by_ref().collect::<Vec<_>>();
+        assert_eq!(expected.len(), solutions.len());
+        assert!(solutions.iter().all(|s| expected.contains(s)));
+
+        // The puzzle has several solutions, so the deduction gets stuck at the
+        // last level before branching.
+        let escalations = &it.stats().escalations;
+        assert_eq!(Some(&1), escalations.first());
+        assert!(escalations.contains(&3));
+        assert!(escalations.iter().all(|&l| l > 0 && l < 4));
+    }
+
+    #[test]
+    fn each_solution() {
+        let puzzle = "3__\n_1_\n".parse::<Puzzle>().unwrap();
+        let expected = Solutions::new(&puzzle).unwrap().collect::<Vec<_>>();
+        assert!(expected.len() > 1);
+
+        let mut solutions = vec![];
+        let count = {
+            let collect = |s: &Puzzle| {
+                solutions.push(s.clone());
+                true
+            };
+            for_each_solution(&puzzle, &SolveOptions::default(), collect).unwrap()
+        };
+        assert_eq!(expected.len() as u64, count);
+        assert_eq!(expected, solutions);
+
+        let count = for_each_solution(&puzzle, &SolveOptions::default(), |_| false).unwrap();
+        assert_eq!(1, count);
+    }
+
+    #[test]
+    fn each_solution_until() {
+        let puzzle = "3__\n_1_\n".parse::<Puzzle>().unwrap();
+        let options = SolveOptions::default();
+        let expected = for_each_solution(&puzzle, &options, |_| true).unwrap();
+        assert_eq!(Some(expected),
+                   for_each_solution_until(&puzzle, &options, || false, |_| true).unwrap());
+        assert_eq!(None,
+                   for_each_solution_until(&puzzle, &options, || true, |_| true).unwrap());
+
+        // Stopped after the first solution, before the search is over.
+        let count = Cell::new(0);
+        let result = for_each_solution_until(&puzzle, &options, || count.get() > 0, |_| {
+                         count.set(count.get() + 1);
+                         true
+                     })
+                         .unwrap();
+        assert_eq!(None, result);
+        assert_eq!(1, count.get());
+    }
+
+    #[test]
+    fn verify_solution() {
+        let puzzle = "3__\n_1_\n".parse::<Puzzle>().unwrap();
+        for solution in Solutions::new(&puzzle).unwrap() {
+            assert!(verify(&puzzle, &solution));
+        }
+
+        // Two loops satisfying the hints.
+        let puzzle = "2_2\n2_2\n".parse::<Puzzle>().unwrap();
+        let two_loops = "
++-+x+-+
+|2x x2|
++x+x+x+
+|2x x2|
++-+x+-+
+";
+        assert!(!verify(&puzzle, &two_loops.parse().unwrap()));
+        let one_loop = "
++-+-+-+
+|2x x2|
++x+x+x+
+|2x x2|
++-+-+-+
+";
+        assert!(verify(&puzzle, &one_loop.parse().unwrap()));
+        // Unknown edges and wrong hints.
+        let mut unknown = one_loop.parse::<Puzzle>().unwrap();
+        unknown.set_edge_h(Point(1, 1), None);
+        assert!(!verify(&puzzle, &unknown));
+        assert!(!verify(&"3_2\n2_2\n".parse().unwrap(), &one_loop.parse().unwrap()));
+    }
+
+    #[test]
+    fn window() {
+        let puzzle = "_____\n_0___\n___3_\n_____\n0____\n".parse::<Puzzle>().unwrap();
+        let result = solve_window(&puzzle, Point(1, 1), Size(2, 2)).unwrap();
+
+        assert_eq!(Some(Edge::Cross), result.edge_h(Point(1, 1)));
+        assert_eq!(Some(Edge::Cross), result.edge_h(Point(2, 1)));
+        assert_eq!(Some(Edge::Cross), result.edge_v(Point(1, 1)));
+        assert_eq!(Some(Edge::Cross), result.edge_v(Point(1, 2)));
+
+        // The hint outside the window is not used.
+        assert_eq!(None, result.edge_h(Point(4, 0)));
+        assert_eq!(None, result.edge_v(Point(4, 1)));
+        // A lone 3 cannot be deduced without the outside of the window.
+        assert_eq!(None, result.edge_v(Point(2, 4)));
+    }
+
+    #[test]
+    fn window_on_border() {
+        let puzzle = "3__\n___\n___\n".parse::<Puzzle>().unwrap();
+        let result = solve_window(&puzzle, Point(0, 0), Size(1, 1)).unwrap();
+        assert_eq!(Some(Edge::Line), result.edge_h(Point(0, 0)));
+        assert_eq!(Some(Edge::Line), result.edge_v(Point(0, 0)));
+
+        let result = solve_window(&puzzle, Point(1, 1), Size(2, 2)).unwrap();
+        assert_eq!(puzzle, result);
+    }
+
+    #[test]
+    fn border_policy() {
+        let puzzle = "3__\n___\n___\n".parse::<Puzzle>().unwrap();
+
+        let result = deduce(&puzzle, BorderPolicy::default()).unwrap();
+        assert_eq!(Some(Edge::Line), result.edge_h(Point(0, 0)));
+        assert_eq!(Some(Edge::Line), result.edge_v(Point(0, 0)));
+
+        let result = deduce(&puzzle, BorderPolicy::all(Border::Unknown)).unwrap();
+        assert_eq!(puzzle, result);
+
+        // Only the outside sides around the corner are needed.
+        let policy = BorderPolicy {
+            right: Border::Unknown,
+            down: Border::Unknown,
+            ..BorderPolicy::default()
+        };
+        let result = deduce(&puzzle, policy).unwrap();
+        assert_eq!(Some(Edge::Line), result.edge_h(Point(0, 0)));
+        assert_eq!(Some(Edge::Line), result.edge_v(Point(0, 0)));
+        assert_eq!(Size(3, 3), result.size());
+    }
+
+    // The number of the inside cells in each row, given by the "rows" layer.
+    struct RowCount;
+
+    impl SolverStep for RowCount {
+        fn apply(&self, state: &mut StepState) -> SolverResult<()> {
+            let puzzle = state.puzzle().clone();
+            let counts = puzzle.clue_layers().get("rows").unwrap_or(&[]);
+            for (r, &count) in counts.iter().enumerate() {
+                let count = match count {
+                    Some(n) => n as i32,
+                    None => continue,
+                };
+                let mut num_in = 0;
+                let mut unknown = vec![];
+                for c in 0..puzzle.column() {
+                    match try!(state.side(Point(r as i32, c))) {
+                        Some(Side::In) => num_in += 1,
+                        Some(Side::Out) => {}
+                        None => unknown.push(Point(r as i32, c)),
+                    }
+                }
+                if num_in > count || num_in + (unknown.len() as i32) < count {
+                    return Err(state.contradiction());
+                }
+                if num_in == count || num_in + (unknown.len() as i32) == count {
+                    let side = if num_in == count { Side::Out } else { Side::In };
+                    for &p in &unknown {
+                        try!(state.set_side(p, side));
+                    }
+                }
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn variant_step() {
+        let mut puzzle = "___\n___\n".parse::<Puzzle>().unwrap();
+        let count = |puzzle: &Puzzle, options: &SolveOptions| {
+            for_each_solution(puzzle, options, |_| true).unwrap()
+        };
+        assert!(count(&puzzle, &SolveOptions::default()) > 1);
+
+        puzzle.clue_layers_mut().set("rows", vec![Some(3), Some(0)]);
+        let options = SolveOptions {
+            variant_steps: vec![Arc::new(RowCount)],
+            ..SolveOptions::default()
+        };
+        assert_eq!(1, count(&puzzle, &options));
+        let solution = solve_with_options(&puzzle, &options).unwrap();
+        assert_eq!(Some(Edge::Line), solution.edge_h(Point(0, 0)));
+        assert_eq!(Some(Edge::Cross), solution.edge_h(Point(2, 0)));
+
+        puzzle.clue_layers_mut().set("rows", vec![Some(0), Some(0)]);
+        assert_eq!(0, count(&puzzle, &options));
+    }
+
+    #[test]
+    fn max_depth() {
+        let puzzle = "___\n___\n".parse::<Puzzle>().unwrap();
+        let (_, stats) = solve_with_stats(&puzzle, &SolveOptions::default()).unwrap();
+        assert!(stats.branches > 0);
+        assert!(stats.max_depth > 0);
+        assert_eq!(stats.probes + stats.branches, stats.guesses());
+
+        let options = SolveOptions { max_depth: Some(0), ..SolveOptions::default() };
+        assert!(solve_with_stats(&puzzle, &options).is_err());
+        assert!(for_each_solution(&puzzle, &options, |_| true).is_err());
+
+        let options = SolveOptions { max_depth: Some(stats.max_depth), ..options };
+        let (_, limited) = solve_with_stats(&puzzle, &options).unwrap();
+        assert_eq!((stats.guesses(), stats.max_depth),
+                   (limited.guesses(), limited.max_depth));
+
+        // A puzzle solved by the theorems needs no assumption.
+        let puzzle = "22\n22\n".parse::<Puzzle>().unwrap();
+        let options = SolveOptions { max_depth: Some(0), ..SolveOptions::default() };
+        let (_, stats) = solve_with_stats(&puzzle, &options).unwrap();
+        assert_eq!(0, stats.max_depth);
+        assert_eq!(0, stats.guesses());
+    }
+
+    #[test]
+    fn disabled_theorems() {
+        let puzzle = "22\n22\n".parse::<Puzzle>().unwrap();
+        let solution = solve_with_options(&puzzle, &SolveOptions::default()).unwrap();
+        let options = SolveOptions {
+            disabled_theorems: (0..THEOREM_DEFINE.len()).collect(),
+            ..SolveOptions::default()
+        };
+        assert_eq!(solution, solve_with_options(&puzzle, &options).unwrap());
+
+        let options = SolveOptions {
+            disabled_theorems: vec![THEOREM_DEFINE.len()],
+            ..SolveOptions::default()
+        };
+        assert!(solve_with_options(&puzzle, &options).is_err());
+    }
+
+    #[test]
+    fn effort() {
+        // Solved in the first search node, which fixes every edge once.
+        let puzzle = "22\n22\n".parse::<Puzzle>().unwrap();
+        let (solution, effort) = search_effort(&puzzle, &SolveOptions::default()).unwrap();
+        assert_eq!(solve_with_options(&puzzle, &SolveOptions::default()).unwrap(), solution);
+        assert_eq!(1, effort.nodes());
+        assert_eq!(1, effort.max());
+        assert_eq!(1, effort.edge_h(Point(1, 0)));
+        assert_eq!(1, effort.edge_v(Point(0, 2)));
+
+        let puzzle = "___\n___\n".parse::<Puzzle>().unwrap();
+        let (_, effort) = search_effort(&puzzle, &SolveOptions::default()).unwrap();
+        assert!(effort.nodes() > 1);
+        assert!(effort.max() > 1);
+
+        assert!(search_effort(&"00\n00\n".parse::<Puzzle>().unwrap(), &SolveOptions::default())
+                    .is_err());
+    }
+
+    #[test]
+    fn deductions() {
+        let puzzle = "22\n22\n".parse::<Puzzle>().unwrap();
+        let (deduced, deductions) = trace_deductions(&puzzle, &SolveOptions::default()).unwrap();
+        assert_eq!(Some(Step::Theorems), deductions.first().map(|d| d.step));
+        let lines = deductions.iter().fold(0, |n, d| n + d.lines);
+        let crosses = deductions.iter().fold(0, |n, d| n + d.crosses);
+        assert_eq!((8, 4), (lines, crosses));
+        let completed = deductions.iter().fold(0, |n, d| n + d.completed.len());
+        assert_eq!(4, completed);
+        assert!(!deduced.has_unknown_edge());
+
+        let puzzle = "___\n___\n".parse::<Puzzle>().unwrap();
+        let (deduced, _) = trace_deductions(&puzzle, &SolveOptions::default()).unwrap();
+        assert!(deduced.has_unknown_edge());
+    }
+
+    #[test]
+    fn passes() {
+        let puzzle = "3___\n_1_2\n2_1_\n___3\n".parse::<Puzzle>().unwrap();
+        let options = SolveOptions::default();
+        let checkpoints = trace_passes(&puzzle, &options).unwrap();
+        assert_eq!(Step::Theorems, checkpoints[0].step);
+        let unknown = |p: &Puzzle| {
+            let mut n = 0;
+            for r in 0..(p.row() + 1) {
+                for c in 0..(p.column() + 1) {
+                    let q = Point(r, c);
+                    if c < p.column() && p.edge_h(q).is_none() {
+                        n += 1;
+                    }
+                    if r < p.row() && p.edge_v(q).is_none() {
+                        n += 1;
+                    }
+                }
+            }
+            n
+        };
+        for w in checkpoints.windows(2) {
+            assert!(unknown(&w[1].board) <= unknown(&w[0].board));
+        }
+        let (deduced, _) = trace_deductions(&puzzle, &options).unwrap();
+        assert_eq!(deduced, checkpoints.last().unwrap().board);
+    }
+
+    #[test]
+    fn edge_theorems_far_from_hints() {
+        // The theorems without hints deduce the edges around the lines drawn
+        // far from any hint, so they cannot be skipped on sparse boards.
+        let mut puzzle = "3_____\n______\n______\n______\n______\n______\n"
+                             .parse::<Puzzle>()
+                             .unwrap();
+        puzzle.set_edge_h(Point(4, 3), Some(Edge::Line));
+        puzzle.set_edge_h(Point(4, 4), Some(Edge::Line));
+        let deduced = deduce(&puzzle, BorderPolicy::default()).unwrap();
+        assert_eq!(Some(Edge::Cross), deduced.edge_v(Point(3, 4)));
+        assert_eq!(Some(Edge::Cross), deduced.edge_v(Point(4, 4)));
+    }
+
+    #[test]
+    fn backtrack() {
+        let blank = "____\n____\n____\n".parse::<Puzzle>().unwrap();
+        let puzzle = "3__\n_1_\n".parse::<Puzzle>().unwrap();
+        for &(puzzle, max_solutions) in &[(&blank, Some(50)), (&puzzle, None)] {
+            let search = |backend, backtrack, max_depth| {
+                let options = SolveOptions {
+                    backend: backend,
+                    backtrack: backtrack,
+                    max_depth: max_depth,
+                    max_solutions: max_solutions,
+                    ..SolveOptions::default()
+                };
+                let mut it = Solutions::with_options(puzzle, &options).unwrap();
+                let solutions = it.by_ref().collect::<Vec<_>>();
+                let mut stats = it.stats().clone();
+                stats.times = Default::default();
+                let effort = search_effort(puzzle, &options).map(|e| e.1).ok();
+                (solutions, stats, it.depth_exceeded(), effort)
+            };
+            for &backend in &[Backend::Native, Backend::Simple] {
+                for &max_depth in &[None, Some(2)] {
+                    let expected = search(backend, Backtrack::Clone, max_depth);
+                    assert!(!expected.0.is_empty() || expected.2);
+                    assert_eq!(expected, search(backend, Backtrack::Snapshot, max_depth));
+                    assert_eq!(expected, search(backend, Backtrack::Auto, max_depth));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn restart() {
+        let puzzle = "3__\n_1_\n".parse::<Puzzle>().unwrap();
+        let sorted = |options: &SolveOptions| {
+            let mut it = Solutions::with_options(&puzzle, options).unwrap();
+            let mut solutions = it.by_ref().map(|s| s.to_string()).collect::<Vec<_>>();
+            solutions.sort();
+            (solutions, it.stats().restarts)
+        };
+        for &backend in &[Backend::Native, Backend::Simple] {
+            let options = SolveOptions { backend: backend, ..SolveOptions::default() };
+            let (expected, restarts) = sorted(&options);
+            assert_eq!(0, restarts);
+            for seed in 0..4 {
+                let policy = RestartPolicy {
+                    node_budget: 1,
+                    seed: seed,
+                };
+                let options = SolveOptions { restart: Some(policy), ..options.clone() };
+                let (solutions, restarts) = sorted(&options);
+                // Every solution is found once, as no restart is made after the
+                // first solution.
+                assert_eq!(expected, solutions);
+                assert!(restarts > 0);
+            }
+        }
+    }
+
+    #[test]
+    fn stop() {
+        let puzzle = "3__\n_1_\n".parse::<Puzzle>().unwrap();
+        let flag = Arc::new(AtomicBool::new(false));
+        let options = SolveOptions { stop: Some(flag.clone()), ..SolveOptions::default() };
+        let mut it = Solutions::with_options(&puzzle, &options).unwrap();
+        assert!(it.next().is_some());
+        // The search ends at the next node once the flag is set.
+        flag.store(true, Ordering::SeqCst);
+        assert_eq!(None, it.next());
+        assert_eq!(None, for_each_solution_until(&puzzle, &options, || false, |_| true).unwrap());
+        flag.store(false, Ordering::SeqCst);
+        assert_eq!(Some(3),
+                   for_each_solution_until(&puzzle, &options, || false, |_| true).unwrap());
+    }
+
+    #[test]
+    fn underconstrained() {
+        let blank = "___\n___\n".parse::<Puzzle>().unwrap();
+        assert!(Solutions::new(&blank).is_err());
+        let options = SolveOptions { max_solutions: Some(5), ..SolveOptions::default() };
+        assert_eq!(5, Solutions::with_options(&blank, &options).unwrap().count());
+        assert_eq!(5, for_each_solution(&blank, &options, |_| true).unwrap());
+        assert_eq!(40,
+                   for_each_solution(&blank, &SolveOptions::default(), |_| true).unwrap());
+        assert!(solve_with_options(&blank, &SolveOptions::default()).is_ok());
+
+        // A blank 5x5 square is too large to enumerate, even with a hint.
+        let mut puzzle = Puzzle::new(Size(6, 6));
+        puzzle.set_hint(Point(0, 0), Some(3));
+        assert!(Solutions::new(&puzzle).is_err());
+        assert!(solve_with_options(&puzzle, &SolveOptions::default()).is_ok());
+        puzzle.set_hint(Point(3, 3), Some(2));
+        assert!(Solutions::new(&puzzle).is_ok());
+        puzzle.set_hint(Point(3, 3), None);
+        puzzle.set_edge_h(Point(3, 3), Some(Edge::Line));
+        assert!(Solutions::new(&puzzle).is_ok());
+    }
+
+    #[test]
+    fn unique_solution() {
+        let options = SolveOptions::default();
+        for input in &["___\n___\n", "3__\n_1_\n"] {
+            let puzzle = input.parse::<Puzzle>().unwrap();
+            match solve_unique(&puzzle, &options) {
+                Err(Error { kind: ErrorKind::Underconstrained }) => {}
+                r => panic!("{:?}", r),
+            }
+        }
+        let puzzle = "_\n".parse::<Puzzle>().unwrap();
+        assert!(verify(&puzzle, &solve_unique(&puzzle, &options).unwrap()));
+        let puzzle = "22\n22\n".parse::<Puzzle>().unwrap();
+        assert_eq!(solve_with_options(&puzzle, &options).unwrap(),
+                   solve_unique(&puzzle, &options).unwrap());
+        let puzzle = "00\n00\n".parse::<Puzzle>().unwrap();
+        match solve_unique(&puzzle, &options) {
+            Err(Error { kind: ErrorKind::InvalidBoard }) => {}
+            r => panic!("{:?}", r),
+        }
+    }
+
+    #[test]
+    fn sparse_hint_counts() {
+        // The connectivity analysis must not drop the loops in the regions
+        // without hints, nor complete a board violating the hints. The
+        // numbers are counted by brute force.
+        let options = SolveOptions { max_solutions: Some(5000), ..SolveOptions::default() };
+        for &(input, num) in &[("____\n____\n____\n", 1049),
+                               ("____\n__2_\n____\n", 376),
+                               ("____\n__2_\n____\n____\n", 3217)] {
+            let puzzle = input.parse::<Puzzle>().unwrap();
+            let solutions = Solutions::with_options(&puzzle, &options).unwrap();
+            let solutions = solutions.collect::<Vec<_>>();
+            assert_eq!(num, solutions.len());
+            assert!(solutions.iter().all(|s| verify(&puzzle, s)));
+        }
+    }
+
+    #[test]
+    fn separated_areas_are_coupled() {
+        // The top and the bottom of the ring are left unknown by the hints
+        // around them, and are separated by the middle row. One of them must
+        // be open for the center to reach the outside, but not both, for
+        // the ring to be connected. Solving them separately would give four
+        // solutions.
+        let puzzle = "01_10\n1_2_1\n12321\n1_2_1\n01_10\n".parse::<Puzzle>().unwrap();
+        assert_eq!(2,
+                   for_each_solution(&puzzle, &SolveOptions::default(), |_| true).unwrap());
+    }
+
+    #[test]
+    fn deepening() {
+        let options = SolveOptions { max_solutions: Some(5000), ..SolveOptions::default() };
+        for input in &["3__\n_1_\n", "____\n__2_\n____\n"] {
+            let puzzle = input.parse::<Puzzle>().unwrap();
+            let mut expected = Solutions::with_options(&puzzle, &options)
+                                   .unwrap()
+                                   .map(|s| s.to_string())
+                                   .collect::<Vec<_>>();
+            expected.sort();
+
+            let mut whole = Deepening::new();
+            let _ = whole.run(&puzzle, &options, || false).unwrap();
+            assert!(whole.is_finished(&options));
+            let mut solutions = whole.solutions(&puzzle, &options)
+                                     .unwrap()
+                                     .iter()
+                                     .map(|s| s.to_string())
+                                     .collect::<Vec<_>>();
+            solutions.sort();
+            assert_eq!(expected, solutions);
+
+            // Stopped after each node, and resumed from the state.
+            let mut sliced = Deepening::new();
+            while !sliced.is_finished(&options) {
+                let mut n = 0;
+                let _ = sliced.run(&puzzle, &options, || {
+                                  n += 1;
+                                  n > 1
+                              })
+                              .unwrap();
+            }
+            assert_eq!(whole, sliced);
+        }
+
+        let blank = "___\n___\n".parse::<Puzzle>().unwrap();
+        assert!(Deepening::new().run(&blank, &SolveOptions::default(), || false).is_err());
+        let options = SolveOptions { max_solutions: Some(2), ..SolveOptions::default() };
+        let mut state = Deepening::new();
+        let _ = state.run(&blank, &options, || false).unwrap();
+        assert!(state.is_finished(&options));
+        assert_eq!(2, state.solutions.len());
+    }
+}
@@ -0,0 +1,67 @@
+// Copyright (c) 2016 srither-solver developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Diagnostic messages of the solver, written to stderr up to the verbosity
+//! set by the application.
+
+use std::cell::Cell;
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::atomic::{ATOMIC_USIZE_INIT, AtomicUsize, Ordering};
+
+/// The summaries of the steps run on each node of the search.
+pub const STEPS: u32 = 1;
+/// The revisions and the theorem counts of each pass.
+pub const PASSES: u32 = 2;
+/// The boards between the passes.
+pub const BOARDS: u32 = 3;
+
+static VERBOSITY: AtomicUsize = ATOMIC_USIZE_INIT;
+
+// The passes under the assumptions of probing would bury the others, so they
+// are not written.
+thread_local!(static MUTED: Cell<u32> = Cell::new(0));
+
+/// Sets the verbosity of the diagnostic messages. No message is written at
+/// level 0, which is the default.
+pub fn set_verbosity(level: u32) {
+    VERBOSITY.store(level as usize, Ordering::Relaxed);
+}
+
+/// Returns the verbosity of the diagnostic messages.
+pub fn verbosity() -> u32 {
+    VERBOSITY.load(Ordering::Relaxed) as u32
+}
+
+pub fn enabled(level: u32) -> bool {
+    verbosity() >= level && MUTED.with(|m| m.get() == 0)
+}
+
+// Runs `f` without writing the messages.
+pub fn muted<T, F>(f: F) -> T
+    where F: FnOnce() -> T
+{
+    MUTED.with(|m| m.set(m.get() + 1));
+    let result = f();
+    MUTED.with(|m| m.set(m.get() - 1));
+    result
+}
+
+pub fn write(args: fmt::Arguments) {
+    let _ = writeln!(io::stderr(), "{}", args);
+}
+
+// Writes the message if the level is enabled. The arguments are not
+// evaluated otherwise.
+macro_rules! log {
+    ($level:expr, $($arg:tt)*) => {
+        if ::log::enabled($level) {
+            ::log::write(format_args!($($arg)*))
+        }
+    }
+}
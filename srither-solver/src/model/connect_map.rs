@@ -21,7 +21,6 @@ pub struct Area {
     coord: CellId,
     side: State<Side>,
     unknown_edge: Vec<CellId>,
-    sum_of_hint: u32,
     size: usize,
 }
 
@@ -31,7 +30,6 @@ impl Clone for Area {
             coord: self.coord,
             side: self.side,
             unknown_edge: self.unknown_edge.clone(),
-            sum_of_hint: self.sum_of_hint,
             size: self.size,
         }
     }
@@ -40,7 +38,6 @@ impl Clone for Area {
         self.coord = other.coord;
         self.side = other.side;
         self.unknown_edge.clone_from(&other.unknown_edge);
-        self.sum_of_hint = other.sum_of_hint;
         self.size = other.size;
     }
 }
@@ -55,15 +52,11 @@ impl Area {
     pub fn unknown_edge(&self) -> &[CellId] {
         &self.unknown_edge
     }
-    pub fn sum_of_hint(&self) -> u32 {
-        self.sum_of_hint
-    }
 }
 
 impl Area {
     fn new(p: Point, puzzle: &Puzzle, side_map: &mut SideMap) -> Area {
         let cp = puzzle.point_to_cellid(p);
-        let sum = puzzle.hint(p).unwrap_or(0) as u32;
 
         let mut edge = vec![];
         if !cp.is_outside() {
@@ -92,7 +85,6 @@ impl Area {
             coord: cp,
             side: side_map.get_side(cp),
             unknown_edge: edge,
-            sum_of_hint: sum,
             size: 1,
         }
     }
@@ -128,7 +120,6 @@ impl Union for Area {
             coord: coord,
             side: side,
             unknown_edge: unknown_edge,
-            sum_of_hint: lval.sum_of_hint + rval.sum_of_hint,
             size: lval.size + rval.size,
         };
         if lval.size >= rval.size {
@@ -141,7 +132,6 @@ impl Union for Area {
 
 #[derive(Debug)]
 pub struct ConnectMap {
-    sum_of_hint: u32,
     uf: Uf<Area>,
     sync_revision: u32,
 }
@@ -149,14 +139,12 @@ pub struct ConnectMap {
 impl Clone for ConnectMap {
     fn clone(&self) -> ConnectMap {
         ConnectMap {
-            sum_of_hint: self.sum_of_hint,
             uf: self.uf.clone(),
             sync_revision: self.sync_revision,
         }
     }
 
     fn clone_from(&mut self, other: &ConnectMap) {
-        self.sum_of_hint = other.sum_of_hint;
         self.uf.clone_from(&other.uf);
         self.sync_revision = other.sync_revision;
     }
@@ -166,18 +154,12 @@ impl ConnectMap {
     pub fn new(puzzle: &Puzzle, side_map: &mut SideMap) -> ConnectMap {
         let cell_len = puzzle.cell_len();
 
-        let mut uf = Uf::from_iter((0..cell_len)
-                                       .map(CellId::new)
-                                       .map(|id| puzzle.cellid_to_point(id))
-                                       .map(|p| Area::new(p, puzzle, side_map)));
-
-        let mut sum_of_hint = 0;
-        for i in 0..cell_len {
-            sum_of_hint += uf.get(i).sum_of_hint;
-        }
+        let uf = Uf::from_iter((0..cell_len)
+                                  .map(CellId::new)
+                                  .map(|id| puzzle.cellid_to_point(id))
+                                  .map(|p| Area::new(p, puzzle, side_map)));
 
         let mut conn_map = ConnectMap {
-            sum_of_hint: sum_of_hint,
             uf: uf,
             sync_revision: 0,
         };
@@ -198,9 +180,6 @@ impl ConnectMap {
     pub fn cell_len(&self) -> usize {
         self.uf.size()
     }
-    pub fn sum_of_hint(&self) -> u32 {
-        self.sum_of_hint
-    }
 
     pub fn sync(&mut self, side_map: &mut SideMap) -> SolverResult<()> {
         let rev = side_map.revision();
@@ -0,0 +1,200 @@
+// Copyright (c) 2016 srither-solver developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use std::fmt;
+use std::error::Error as ErrorTrait;
+use std::str::FromStr;
+
+use srither_core::puzzle::Puzzle;
+use srither_core::geom::{Geom, Move, Point, Rotation, Size};
+
+use model::pattern::{HintPattern, MatchResult};
+
+/// An error type which is returned from parsing a string into hint layout.
+#[derive(Copy, Clone, Debug)]
+pub struct ParseHintLayoutError {
+    kind: HintLayoutErrorKind,
+}
+
+#[derive(Copy, Clone, Debug)]
+enum HintLayoutErrorKind {
+    NoHint,
+    UnevenRows,
+    InvalidCell,
+}
+
+impl ErrorTrait for ParseHintLayoutError {
+    fn description(&self) -> &str {
+        match self.kind {
+            HintLayoutErrorKind::NoHint => "no hint found in hint layout",
+            HintLayoutErrorKind::UnevenRows => "rows of different lengths found in hint layout",
+            HintLayoutErrorKind::InvalidCell => "invalid cell found in hint layout",
+        }
+    }
+}
+
+impl fmt::Display for ParseHintLayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.description().fmt(f)
+    }
+}
+
+impl ParseHintLayoutError {
+    fn no_hint() -> ParseHintLayoutError {
+        ParseHintLayoutError { kind: HintLayoutErrorKind::NoHint }
+    }
+    fn uneven_rows() -> ParseHintLayoutError {
+        ParseHintLayoutError { kind: HintLayoutErrorKind::UnevenRows }
+    }
+    fn invalid_cell() -> ParseHintLayoutError {
+        ParseHintLayoutError { kind: HintLayoutErrorKind::InvalidCell }
+    }
+}
+
+/// A layout of the hints, e.g. the adjacent `0`s, found in the puzzles
+/// rotated or flipped in any way.
+///
+/// A layout is parsed from the rows of the cells separated by `/` or new
+/// lines, e.g. `00` or `33/33`. A cell is `0` to `4`, the hint which must be
+/// there, or `_`, any cell with or without a hint.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HintLayout {
+    rotations: Vec<(Size, Vec<HintPattern>)>,
+}
+
+impl HintLayout {
+    fn new(size: Size, hints: Vec<HintPattern>) -> HintLayout {
+        let rots = [Rotation::CCW0,
+                    Rotation::CCW90,
+                    Rotation::CCW180,
+                    Rotation::CCW270,
+                    Rotation::H_FLIP,
+                    Rotation::H_FLIP * Rotation::CCW90,
+                    Rotation::H_FLIP * Rotation::CCW180,
+                    Rotation::H_FLIP * Rotation::CCW270];
+        let mut rotations = rots.iter().map(|&rot| rotate(size, &hints, rot)).collect::<Vec<_>>();
+        rotations.sort();
+        rotations.dedup();
+        HintLayout { rotations: rotations }
+    }
+
+    /// Returns true if the hints of the puzzle are laid out as the layout
+    /// somewhere, in any rotation or reflection.
+    pub fn matches(&self, puzzle: &Puzzle) -> bool {
+        self.rotations.iter().any(|&(size, ref hints)| {
+            let columns = puzzle.column() - size.1 + 1;
+            let mut shifts = (0..(puzzle.row() - size.0 + 1))
+                                 .flat_map(|r| (0..columns).map(move |c| Move(r, c)));
+            shifts.any(|d| {
+                hints.iter().all(|h| {
+                    match h.shift(d).matches::<()>(puzzle) {
+                        Ok(MatchResult::Complete) => true,
+                        _ => false,
+                    }
+                })
+            })
+        })
+    }
+}
+
+// Rotates the hints in the rectangle of the size, and shifts them back into
+// the rectangle from the origin, as the theorems are.
+fn rotate(size: Size, hints: &[HintPattern], rot: Rotation) -> (Size, Vec<HintPattern>) {
+    let mv = rot * Move(size.0, size.1);
+    let mut d = Move(0, 0);
+    if mv.0 < 0 {
+        d = d + Move(-mv.0 - 1, 0);
+    }
+    if mv.1 < 0 {
+        d = d + Move(0, -mv.1 - 1);
+    }
+    let mut hints = hints.iter().map(|h| h.rotate(rot).shift(d)).collect::<Vec<_>>();
+    hints.sort();
+    (Size(mv.0.abs(), mv.1.abs()), hints)
+}
+
+impl FromStr for HintLayout {
+    type Err = ParseHintLayoutError;
+
+    fn from_str(s: &str) -> Result<HintLayout, ParseHintLayoutError> {
+        let rows = s.split(|c| c == '/' || c == '\n')
+                    .map(|r| r.trim())
+                    .filter(|r| !r.is_empty())
+                    .collect::<Vec<_>>();
+        let column = rows.first().map(|r| r.chars().count()).unwrap_or(0);
+        if rows.iter().any(|r| r.chars().count() != column) {
+            return Err(ParseHintLayoutError::uneven_rows());
+        }
+        let mut hints = vec![];
+        for (r, row) in rows.iter().enumerate() {
+            for (c, ch) in row.chars().enumerate() {
+                match ch {
+                    '0'...'4' => {
+                        let p = Point(r as i32, c as i32);
+                        hints.push(HintPattern::new(ch as u8 - b'0', p));
+                    }
+                    '_' => {}
+                    _ => return Err(ParseHintLayoutError::invalid_cell()),
+                }
+            }
+        }
+        if hints.is_empty() {
+            return Err(ParseHintLayoutError::no_hint());
+        }
+        Ok(HintLayout::new(Size(rows.len() as i32, column as i32), hints))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+    use srither_core::puzzle::Puzzle;
+    use super::{HintLayout, ParseHintLayoutError};
+
+    fn check_error(input: &str, error: ParseHintLayoutError) {
+        assert_eq!(input.parse::<HintLayout>().unwrap_err().description(),
+                   error.description());
+    }
+
+    #[test]
+    fn rotations() {
+        let layout = "00".parse::<HintLayout>().unwrap();
+        assert_eq!(2, layout.rotations.len());
+        assert_eq!(layout, "0\n0".parse::<HintLayout>().unwrap());
+        let layout = "33/33".parse::<HintLayout>().unwrap();
+        assert_eq!(1, layout.rotations.len());
+        let layout = "1_/_2".parse::<HintLayout>().unwrap();
+        assert_eq!(4, layout.rotations.len());
+        assert_eq!(layout, "_1/2_".parse::<HintLayout>().unwrap());
+        assert_eq!(layout, "2_/_1".parse::<HintLayout>().unwrap());
+        assert_eq!(8, "12/_3".parse::<HintLayout>().unwrap().rotations.len());
+    }
+
+    #[test]
+    fn matches() {
+        let puzzle = "3_2__\n_2__3\n__0__\n__0_3\n____3".parse::<Puzzle>().unwrap();
+        assert!("00".parse::<HintLayout>().unwrap().matches(&puzzle));
+        assert!("3/3".parse::<HintLayout>().unwrap().matches(&puzzle));
+        assert!("3_/_2".parse::<HintLayout>().unwrap().matches(&puzzle));
+        assert!("2_/_3".parse::<HintLayout>().unwrap().matches(&puzzle));
+        assert!("3_0".parse::<HintLayout>().unwrap().matches(&puzzle));
+        assert!(!"33/33".parse::<HintLayout>().unwrap().matches(&puzzle));
+        assert!(!"22".parse::<HintLayout>().unwrap().matches(&puzzle));
+        assert!(!"0__/0__/0__".parse::<HintLayout>().unwrap().matches(&puzzle));
+        assert!(!"1______".parse::<HintLayout>().unwrap().matches(&puzzle));
+    }
+
+    #[test]
+    fn parse_errors() {
+        check_error("", ParseHintLayoutError::no_hint());
+        check_error("__/__", ParseHintLayoutError::no_hint());
+        check_error("00/0", ParseHintLayoutError::uneven_rows());
+        check_error("05", ParseHintLayoutError::invalid_cell());
+        check_error("0.0", ParseHintLayoutError::invalid_cell());
+    }
+}
@@ -9,11 +9,13 @@
 use {Error, SolverResult};
 
 pub use self::connect_map::ConnectMap;
+pub use self::layout::{HintLayout, ParseHintLayoutError};
 pub use self::side_map::{KeyPair, SideMap};
-pub use self::theorem::Theorem;
+pub use self::theorem::{MatchResult, Theorem};
 pub use self::theorem_pool::TheoremPool;
 
 mod connect_map;
+mod layout;
 mod pattern;
 mod side_map;
 mod theorem;
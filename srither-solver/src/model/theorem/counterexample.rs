@@ -0,0 +1,320 @@
+// Copyright (c) 2016 srither-solver developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+use srither_core::puzzle::{Edge, Puzzle, Side};
+use srither_core::geom::{Geom, Move, Point, Size};
+
+use model::pattern::EdgePattern;
+use model::theorem::Theorem;
+
+#[derive(Copy, Clone, Debug)]
+enum Constraint {
+    Hint(usize, u8),
+    Edge(usize, usize, Edge),
+    Block(usize, usize, usize, usize),
+}
+
+// Cells are indexed in row-major order in the area of the theorem extended by
+// one cell in each direction.
+#[derive(Clone, Debug)]
+struct Search {
+    size: Size,
+    sides: Vec<Option<Side>>,
+    relevant: Vec<usize>,
+    checks: Vec<Vec<Constraint>>,
+    free: Vec<usize>,
+    free_blocks: Vec<Vec<Constraint>>,
+    result: Vec<(usize, usize, Edge)>,
+}
+
+impl Search {
+    fn new(theorem: &Theorem) -> Search {
+        let size = Size(theorem.size.0 + 2, theorem.size.1 + 2);
+        let offset = Move(1, 1);
+        let index = |p: Point| ((p.0 + offset.0) * size.1 + (p.1 + offset.1)) as usize;
+        let edge = |pat: &EdgePattern<Point>| {
+            let (p0, p1) = pat.points();
+            (index(p0), index(p1), pat.edge())
+        };
+
+        let mut constraints = vec![];
+        for h in &theorem.hint_matcher {
+            constraints.push(Constraint::Hint(index(h.point()), h.hint()));
+        }
+        for pat in &theorem.edge_matcher {
+            let (i0, i1, e) = edge(pat);
+            constraints.push(Constraint::Edge(i0, i1, e));
+        }
+        let result = theorem.result.iter().map(edge).collect::<Vec<_>>();
+
+        let mut is_relevant = vec![false; (size.0 * size.1) as usize];
+        for c in &constraints {
+            for i in Search::cells(size, c) {
+                is_relevant[i] = true;
+            }
+        }
+        for &(i0, i1, _) in &result {
+            is_relevant[i0] = true;
+            is_relevant[i1] = true;
+        }
+
+        for r in 0..(size.0 - 1) {
+            for c in 0..(size.1 - 1) {
+                let i = (r * size.1 + c) as usize;
+                let w = size.1 as usize;
+                constraints.push(Constraint::Block(i, i + 1, i + w, i + w + 1));
+            }
+        }
+
+        let relevant = (0..is_relevant.len()).filter(|&i| is_relevant[i]).collect::<Vec<_>>();
+        let free = (0..is_relevant.len()).filter(|&i| !is_relevant[i]).collect::<Vec<_>>();
+
+        // Each constraint is checked as soon as all of its cells are assigned.
+        let mut checks = vec![vec![]; relevant.len()];
+        let mut free_blocks = vec![vec![]; free.len()];
+        for c in constraints {
+            let cells = Search::cells(size, &c);
+            if cells.iter().all(|&i| is_relevant[i]) {
+                let depth = cells.iter()
+                                 .map(|i| relevant.iter().position(|j| j == i).unwrap())
+                                 .max()
+                                 .unwrap();
+                checks[depth].push(c);
+            } else {
+                let depth = cells.iter()
+                                 .filter_map(|i| free.iter().position(|j| j == i))
+                                 .max()
+                                 .unwrap();
+                free_blocks[depth].push(c);
+            }
+        }
+
+        Search {
+            size: size,
+            sides: vec![None; is_relevant.len()],
+            relevant: relevant,
+            checks: checks,
+            free: free,
+            free_blocks: free_blocks,
+            result: result,
+        }
+    }
+
+    fn cells(size: Size, c: &Constraint) -> Vec<usize> {
+        match *c {
+            Constraint::Hint(i, _) => {
+                let w = size.1 as usize;
+                vec![i, i - w, i - 1, i + 1, i + w]
+            }
+            Constraint::Edge(i0, i1, _) => vec![i0, i1],
+            Constraint::Block(i0, i1, i2, i3) => vec![i0, i1, i2, i3],
+        }
+    }
+
+    fn same(&self, i0: usize, i1: usize) -> bool {
+        self.sides[i0] == self.sides[i1]
+    }
+
+    fn satisfies(&self, c: &Constraint) -> bool {
+        match *c {
+            Constraint::Hint(i, n) => {
+                let w = self.size.1 as usize;
+                let lines = [i - w, i - 1, i + 1, i + w]
+                                .iter()
+                                .filter(|&&j| !self.same(i, j))
+                                .count();
+                lines == n as usize
+            }
+            Constraint::Edge(i0, i1, Edge::Cross) => self.same(i0, i1),
+            Constraint::Edge(i0, i1, Edge::Line) => !self.same(i0, i1),
+            Constraint::Block(i0, i1, i2, i3) => {
+                // Four lines cannot meet at a point.
+                !(self.same(i0, i3) && self.same(i1, i2) && !self.same(i0, i1))
+            }
+        }
+    }
+
+    fn violates_result(&self) -> bool {
+        self.result.iter().any(|&(i0, i1, e)| (e == Edge::Cross) != self.same(i0, i1))
+    }
+
+    // Theorems are applied to boards larger than them, so every area divided
+    // by the loop must reach the border of the searched area or the free cells.
+    fn is_loop_consistent(&self) -> bool {
+        let w = self.size.1 as usize;
+        let h = self.sides.len() / w;
+        let mut visited = vec![false; self.sides.len()];
+
+        for start in 0..self.sides.len() {
+            if visited[start] || self.sides[start].is_none() {
+                continue;
+            }
+            let side = self.sides[start];
+            let mut open = false;
+            let mut stack = vec![start];
+            visited[start] = true;
+            while let Some(i) = stack.pop() {
+                let (r, c) = (i / w, i % w);
+                if r == 0 || c == 0 || r == h - 1 || c == w - 1 {
+                    open = true;
+                }
+                let mut adjacents = vec![];
+                if r > 0 {
+                    adjacents.push(i - w);
+                }
+                if c > 0 {
+                    adjacents.push(i - 1);
+                }
+                if c < w - 1 {
+                    adjacents.push(i + 1);
+                }
+                if r < h - 1 {
+                    adjacents.push(i + w);
+                }
+                for j in adjacents {
+                    if self.sides[j].is_none() {
+                        open = true;
+                    } else if !visited[j] && self.sides[j] == side {
+                        visited[j] = true;
+                        stack.push(j);
+                    }
+                }
+            }
+            if !open {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn search_relevant(&mut self, depth: usize) -> bool {
+        if depth == self.relevant.len() {
+            return self.violates_result() && self.is_loop_consistent() && self.complete(0);
+        }
+
+        let i = self.relevant[depth];
+        for &side in &[Side::Out, Side::In] {
+            self.sides[i] = Some(side);
+            if self.checks[depth].iter().all(|c| self.satisfies(c)) &&
+               self.search_relevant(depth + 1) {
+                return true;
+            }
+        }
+        self.sides[i] = None;
+        false
+    }
+
+    // Checks the free cells can be assigned without four lines meeting at a
+    // point. The assignment is discarded so that only the relevant cells are
+    // shown in the counterexample.
+    fn complete(&mut self, depth: usize) -> bool {
+        if depth == self.free.len() {
+            return true;
+        }
+
+        let i = self.free[depth];
+        let mut ok = false;
+        for &side in &[Side::Out, Side::In] {
+            self.sides[i] = Some(side);
+            if self.free_blocks[depth].iter().all(|c| self.satisfies(c)) &&
+               self.complete(depth + 1) {
+                ok = true;
+                break;
+            }
+        }
+        self.sides[i] = None;
+        ok
+    }
+
+    fn to_puzzle(&self, theorem: &Theorem) -> Puzzle {
+        let mut puzzle = Puzzle::new(self.size);
+        for h in &theorem.hint_matcher {
+            puzzle.set_hint(h.point() + Move(1, 1), Some(h.hint()));
+        }
+
+        let edge = |s0: Option<Side>, s1: Option<Side>| {
+            match (s0, s1) {
+                (Some(s0), Some(s1)) if s0 == s1 => Some(Edge::Cross),
+                (Some(_), Some(_)) => Some(Edge::Line),
+                _ => None,
+            }
+        };
+        let side = |p: Point| {
+            if puzzle.contains(p) {
+                self.sides[(p.0 * self.size.1 + p.1) as usize]
+            } else {
+                None
+            }
+        };
+
+        let mut edges_h = vec![];
+        let mut edges_v = vec![];
+        for p in puzzle.points() {
+            edges_h.push((p, edge(side(p + Move::UP), side(p))));
+            edges_v.push((p, edge(side(p + Move::LEFT), side(p))));
+        }
+        let sides = puzzle.points().map(|p| (p, side(p))).collect::<Vec<_>>();
+
+        for (p, e) in edges_h {
+            puzzle.set_edge_h(p, e);
+        }
+        for (p, e) in edges_v {
+            puzzle.set_edge_v(p, e);
+        }
+        for (p, s) in sides {
+            puzzle.set_side(p, s);
+        }
+        puzzle
+    }
+}
+
+impl Theorem {
+    /// Searches an assignment of the cells around the theorem which matches
+    /// the theorem but violates its result.
+    pub fn find_counterexample(&self) -> Option<Puzzle> {
+        let mut search = Search::new(self);
+        if search.search_relevant(0) {
+            Some(search.to_puzzle(self))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use srither_core::geom::Point;
+    use srither_core::puzzle::Edge;
+    use model::theorem::Theorem;
+    use theorem_define::THEOREM_DEFINE;
+
+    #[test]
+    fn sound_theorems() {
+        for s in THEOREM_DEFINE {
+            let theo = s.parse::<Theorem>().unwrap();
+            assert_eq!(None, theo.find_counterexample());
+        }
+    }
+
+    #[test]
+    fn unsound_theorem() {
+        let theo = r"
++ + ! +x+
+ 1  ! x1x
++ + ! + +
+"
+                       .parse::<Theorem>()
+                       .unwrap();
+        let puzzle = theo.find_counterexample().unwrap();
+        assert_eq!(Some(1), puzzle.hint(Point(1, 1)));
+        let edges = [puzzle.edge_h(Point(1, 1)),
+                     puzzle.edge_v(Point(1, 1)),
+                     puzzle.edge_v(Point(1, 2))];
+        assert!(edges.iter().any(|&e| e == Some(Edge::Line)));
+    }
+}
@@ -15,6 +15,7 @@ use SolverResult;
 use model::SideMap;
 use model::pattern::{EdgePattern, HintPattern, MatchResult as PatternMatchResult};
 
+mod counterexample;
 mod parse;
 
 #[derive(Clone, Debug)]
@@ -25,16 +26,20 @@ pub enum MatchResult {
 }
 
 impl MatchResult {
-    pub fn update(self, side_map: &mut SideMap, new_theorem: &mut Vec<PartialTheorem>) {
+    // Returns true if the theorem matches completely and its result is
+    // applied.
+    pub fn update(self, side_map: &mut SideMap, new_theorem: &mut Vec<PartialTheorem>) -> bool {
         match self {
             MatchResult::Complete(result) => {
                 for pat in &result {
                     pat.apply(side_map);
                 }
+                return true;
             }
             MatchResult::Partial(theo) => new_theorem.push(theo),
             MatchResult::Conflict => {}
         }
+        false
     }
 }
 
@@ -115,6 +120,18 @@ impl Theorem {
         self.hint_matcher.get(0).cloned()
     }
 
+    pub fn is_contained(&self, shift: Move, puzzle: &Puzzle) -> bool {
+        let hints = self.hint_matcher.iter().map(|h| h.point());
+        let edges = self.edge_matcher
+                        .iter()
+                        .chain(&self.result)
+                        .flat_map(|pat| {
+                            let (p0, p1) = pat.points();
+                            vec![p0, p1]
+                        });
+        hints.chain(edges).all(|p| puzzle.contains(p + shift))
+    }
+
     fn can_close(shift: Move,
                  puzzle: &Puzzle,
                  sum_of_hint: u32,
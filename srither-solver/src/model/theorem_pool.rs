@@ -6,13 +6,15 @@
 // option. All files in the project carrying such notice may not be copied,
 // modified, or distributed except according to those terms.
 
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::rc::Rc;
 use std::mem;
 use srither_core::geom::{CellId, Geom, Move};
 use srither_core::puzzle::{Edge, Puzzle};
 
-use {Error, SolverResult};
+use time;
+
+use {Error, SolverResult, StepTimes};
 use model::{KeyPair, SideMap, State};
 use model::pattern::EdgePattern;
 use model::theorem::{MatchResult, PartialTheorem, Theorem};
@@ -29,6 +31,9 @@ pub struct TheoremPool {
     counts: Vec<usize>,
     results: Rc<Vec<Vec<EdgePattern<CellId>>>>,
     index_by_edge: Vec<Rc<IndexByEdge>>,
+    // The number of the theorems matched completely, whose results are
+    // applied.
+    applied: u32,
 }
 
 impl Clone for TheoremPool {
@@ -37,6 +42,7 @@ impl Clone for TheoremPool {
             counts: self.counts.clone(),
             results: self.results.clone(),
             index_by_edge: self.index_by_edge.clone(),
+            applied: self.applied,
         }
     }
 
@@ -44,6 +50,7 @@ impl Clone for TheoremPool {
         self.counts.clone_from(&other.counts);
         self.results.clone_from(&other.results);
         self.index_by_edge.clone_from(&other.index_by_edge);
+        self.applied = other.applied;
     }
 }
 
@@ -51,16 +58,26 @@ impl TheoremPool {
     pub fn new<T>(theo_defs: T,
                   puzzle: &Puzzle,
                   sum_of_hint: u32,
-                  side_map: &mut SideMap)
+                  bounded: bool,
+                  side_map: &mut SideMap,
+                  times: &mut StepTimes)
                   -> SolverResult<TheoremPool>
         where T: IntoIterator<Item = Theorem>
     {
-        let mut matchers = try!(create_matcher_list(theo_defs, puzzle, sum_of_hint, side_map));
+        let start = time::precise_time_ns();
+        let mut applied = 0;
+        let mut matchers = try!(create_matcher_list(theo_defs,
+                                                    puzzle,
+                                                    sum_of_hint,
+                                                    bounded,
+                                                    side_map,
+                                                    &mut times.pool_buckets,
+                                                    &mut applied));
 
         loop {
             let rev = side_map.revision();
 
-            try!(apply_all_theorem(&mut matchers, side_map));
+            applied += try!(apply_all_theorem(&mut matchers, side_map));
             if side_map.revision() != rev {
                 continue;
             }
@@ -70,7 +87,9 @@ impl TheoremPool {
 
         merge_duplicate_matchers(&mut matchers);
 
-        let mut map = HashMap::new();
+        // The edges are indexed in their order, so that the theorems are applied
+        // in the same order on every run.
+        let mut map = BTreeMap::new();
         for (i, m) in matchers.iter().enumerate() {
             for pat in m.matcher_edges() {
                 let e = map.entry(pat.points()).or_insert((vec![], vec![]));
@@ -96,10 +115,12 @@ impl TheoremPool {
                        .map(Rc::new)
                        .collect();
 
+        times.pool += time::precise_time_ns() - start;
         Ok(TheoremPool {
             counts: counts,
             results: Rc::new(results),
             index_by_edge: edges,
+            applied: applied,
         })
     }
 
@@ -114,6 +135,7 @@ impl TheoremPool {
             }
             1 => {
                 self.counts[i] = 0;
+                self.applied += 1;
                 for &pat in &self.results[i] {
                     pat.apply(side_map)
                 }
@@ -124,6 +146,16 @@ impl TheoremPool {
         }
     }
 
+    // Returns the number of the theorems applied so far.
+    pub fn num_applied(&self) -> u32 {
+        self.applied
+    }
+
+    // Returns the number of the theorems which may still match.
+    pub fn num_pending(&self) -> usize {
+        self.counts.iter().filter(|&&c| c > 0).count()
+    }
+
     pub fn apply_all(&mut self, side_map: &mut SideMap) -> SolverResult<()> {
         unsafe {
             let ptr = self.index_by_edge.as_mut_ptr();
@@ -168,10 +200,17 @@ impl TheoremPool {
     }
 }
 
+// If `bounded` is set, theorems are not applied across the border of the
+// puzzle. The time spent matching the theorems starting with each hint, and
+// the theorems without hints, is added to `buckets`, and the number of the
+// theorems matching completely to `applied`.
 fn create_matcher_list<T>(theo_defs: T,
                           puzzle: &Puzzle,
                           sum_of_hint: u32,
-                          side_map: &mut SideMap)
+                          bounded: bool,
+                          side_map: &mut SideMap,
+                          buckets: &mut [u64; 6],
+                          applied: &mut u32)
                           -> SolverResult<Vec<PartialTheorem>>
     where T: IntoIterator<Item = Theorem>
 {
@@ -192,30 +231,49 @@ fn create_matcher_list<T>(theo_defs: T,
 
     for p in puzzle.points() {
         if let Some(x) = puzzle.hint(p) {
+            let start = time::precise_time_ns();
             for theo in &hint_theorem[x as usize] {
                 let o = theo.head().unwrap().point();
-                try!(theo.shift_matches(p - o, puzzle, sum_of_hint, side_map))
-                    .update(side_map, &mut data);
+                if bounded && !theo.is_contained(p - o, puzzle) {
+                    continue;
+                }
+                if try!(theo.shift_matches(p - o, puzzle, sum_of_hint, side_map))
+                       .update(side_map, &mut data) {
+                    *applied += 1;
+                }
             }
+            buckets[x as usize] += time::precise_time_ns() - start;
         }
     }
 
+    // The theorems without hints match the edges anywhere, including the
+    // regions far from any hint which the loop passes later, so they are
+    // slid over the whole board however sparse the hints are.
+    let start = time::precise_time_ns();
     for theo in nonhint_theorem {
         let sz = theo.size();
         for r in (1 - sz.0)..(puzzle.row() + sz.0 - 1) {
             for c in (1 - sz.1)..(puzzle.column() + sz.1 - 1) {
-                try!(theo.shift_matches(Move(r, c), puzzle, sum_of_hint, side_map))
-                    .update(side_map, &mut data);
+                if bounded && !theo.is_contained(Move(r, c), puzzle) {
+                    continue;
+                }
+                if try!(theo.shift_matches(Move(r, c), puzzle, sum_of_hint, side_map))
+                       .update(side_map, &mut data) {
+                    *applied += 1;
+                }
             }
         }
     }
+    buckets[5] += time::precise_time_ns() - start;
 
     Ok(data)
 }
 
+// Returns the number of the theorems matched completely.
 fn apply_all_theorem(matchers: &mut Vec<PartialTheorem>,
                      side_map: &mut SideMap)
-                     -> SolverResult<()> {
+                     -> SolverResult<u32> {
+    let mut applied = 0;
     unsafe {
         let ptr = matchers.as_mut_ptr();
 
@@ -225,6 +283,7 @@ fn apply_all_theorem(matchers: &mut Vec<PartialTheorem>,
             let m = mem::replace(&mut *read, PartialTheorem::dummy());
             match try!(m.matches(side_map)) {
                 MatchResult::Complete(result) => {
+                    applied += 1;
                     for pat in &result {
                         pat.apply(side_map);
                     }
@@ -241,7 +300,7 @@ fn apply_all_theorem(matchers: &mut Vec<PartialTheorem>,
         matchers.truncate(w);
     }
 
-    Ok(())
+    Ok(applied)
 }
 
 fn merge_duplicate_matchers(matchers: &mut Vec<PartialTheorem>) {
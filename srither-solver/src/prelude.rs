@@ -0,0 +1,32 @@
+// Copyright (c) 2016 srither-solver developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! The stable surface of the crate, with that of `srither_core::prelude`.
+//!
+//! The items here are kept across the versions of the same major version.
+//! The other items, e.g. the strategies and the traces of the deduction, are
+//! still changing with the solver, and may change in any version.
+//!
+//! ```
+//! use srither_solver::prelude::*;
+//!
+//! let puzzle = "3___\n_1_2\n2_1_\n___3\n".parse::<Puzzle>().unwrap();
+//! let solution = solve_with_options(&puzzle, &SolveOptions::default()).unwrap();
+//! assert_eq!(Some(Edge::Line), solution.edge_h(Point(0, 0)));
+//!
+//! let contradiction = "04".parse::<Puzzle>().unwrap();
+//! match solve(&contradiction) {
+//!     Err(ref e) if e.kind() == ErrorKind::InvalidBoard => {}
+//!     _ => panic!(),
+//! }
+//! ```
+
+pub use srither_core::prelude::*;
+
+pub use {Backend, Error, ErrorKind, SolveOptions, Solutions, SolverResult};
+pub use {solve, solve_with_options};
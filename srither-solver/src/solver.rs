@@ -6,11 +6,14 @@
 // option. All files in the project carrying such notice may not be copied,
 // modified, or distributed except according to those terms.
 
-use srither_core::puzzle::{Puzzle, Side};
-use srither_core::geom::{CellId, Geom};
+use std::sync::Arc;
 
-use {Error, SolverResult};
-use model::{ConnectMap, SideMap, State, Theorem, TheoremPool};
+use srither_core::puzzle::{Edge, Puzzle, Side};
+use srither_core::geom::{CellId, Geom, Move};
+
+use {Error, SolverResult, StepTimes};
+use model::{ConnectMap, MatchResult, SideMap, State, Theorem, TheoremPool};
+use variant::{self, SolverStep};
 
 #[derive(Debug)]
 pub struct Solver<'a> {
@@ -20,6 +23,9 @@ pub struct Solver<'a> {
     side_map: SideMap,
     connect_map: Option<ConnectMap>,
     conn_analysis_revision: Option<u32>,
+    variant_steps: Vec<Arc<SolverStep>>,
+    depth: u32,
+    max_depth: Option<u32>,
 }
 
 impl<'a> Clone for Solver<'a> {
@@ -31,6 +37,9 @@ impl<'a> Clone for Solver<'a> {
             side_map: self.side_map.clone(),
             connect_map: self.connect_map.clone(),
             conn_analysis_revision: self.conn_analysis_revision,
+            variant_steps: self.variant_steps.clone(),
+            depth: self.depth,
+            max_depth: self.max_depth,
         }
     }
 
@@ -41,12 +50,44 @@ impl<'a> Clone for Solver<'a> {
         self.side_map.clone_from(&other.side_map);
         self.connect_map.clone_from(&other.connect_map);
         self.conn_analysis_revision = other.conn_analysis_revision;
+        self.variant_steps.clone_from(&other.variant_steps);
+        self.depth = other.depth;
+        self.max_depth = other.max_depth;
     }
 }
 
 impl<'a> Solver<'a> {
     pub fn new<I>(puzzle: &'a Puzzle, theorem: I) -> SolverResult<Solver<'a>>
         where I: Iterator<Item = Theorem>
+    {
+        Solver::with_theorem(puzzle, theorem, false, &mut StepTimes::default())
+    }
+
+    // Creates a solver, adding the time spent constructing the theorem pool
+    // to `times`.
+    pub fn with_times<I>(puzzle: &'a Puzzle,
+                         theorem: I,
+                         times: &mut StepTimes)
+                         -> SolverResult<Solver<'a>>
+        where I: Iterator<Item = Theorem>
+    {
+        Solver::with_theorem(puzzle, theorem, false, times)
+    }
+
+    // Creates a solver whose theorems are not applied across the border of
+    // the puzzle, so that the deductions do not depend on the outside.
+    pub fn new_bounded<I>(puzzle: &'a Puzzle, theorem: I) -> SolverResult<Solver<'a>>
+        where I: Iterator<Item = Theorem>
+    {
+        Solver::with_theorem(puzzle, theorem, true, &mut StepTimes::default())
+    }
+
+    fn with_theorem<I>(puzzle: &'a Puzzle,
+                       theorem: I,
+                       bounded: bool,
+                       times: &mut StepTimes)
+                       -> SolverResult<Solver<'a>>
+        where I: Iterator<Item = Theorem>
     {
         let mut sum_of_hint = 0;
         for p in puzzle.points() {
@@ -56,7 +97,12 @@ impl<'a> Solver<'a> {
         }
 
         let mut side_map = SideMap::from(puzzle);
-        let pool = try!(TheoremPool::new(theorem, puzzle, sum_of_hint, &mut side_map));
+        let pool = try!(TheoremPool::new(theorem,
+                                         puzzle,
+                                         sum_of_hint,
+                                         bounded,
+                                         &mut side_map,
+                                         times));
 
         Ok(Solver {
             puzzle: puzzle,
@@ -65,9 +111,34 @@ impl<'a> Solver<'a> {
             side_map: side_map,
             connect_map: None,
             conn_analysis_revision: None,
+            variant_steps: vec![],
+            depth: 0,
+            max_depth: None,
         })
     }
 
+    pub fn puzzle(&self) -> &'a Puzzle {
+        self.puzzle
+    }
+    pub fn set_variant_steps(&mut self, steps: Vec<Arc<SolverStep>>) {
+        self.variant_steps = steps;
+    }
+
+    // The number of the nested assumptions made to reach this state.
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+    pub fn set_depth(&mut self, depth: u32) {
+        self.depth = depth;
+    }
+    pub fn set_max_depth(&mut self, max_depth: Option<u32>) {
+        self.max_depth = max_depth;
+    }
+    // Returns true if another assumption is within the depth limit.
+    pub fn can_assume(&self) -> bool {
+        self.max_depth.map_or(true, |m| self.depth < m)
+    }
+
     pub fn revision(&self) -> u32 {
         self.side_map.revision()
     }
@@ -78,6 +149,9 @@ impl<'a> Solver<'a> {
     pub fn get_side(&mut self, p: CellId) -> State<Side> {
         self.side_map.get_side(p)
     }
+    pub fn get_edge(&mut self, p0: CellId, p1: CellId) -> State<Edge> {
+        self.side_map.get_edge(p0, p1)
+    }
     pub fn set_inside(&mut self, p: CellId) -> bool {
         self.side_map.set_inside(p)
     }
@@ -110,9 +184,16 @@ impl<'a> Solver<'a> {
         pts.into_iter().map(|pair| pair.0).collect()
     }
 
+    pub fn num_applied_theorems(&self) -> u32 {
+        self.theorem_pool.num_applied()
+    }
     pub fn apply_all_theorem(&mut self) -> SolverResult<()> {
         self.theorem_pool.apply_all(&mut self.side_map)
     }
+    pub fn apply_variant_steps(&mut self) -> SolverResult<()> {
+        let steps = self.variant_steps.clone();
+        variant::apply_steps(self, &steps)
+    }
     pub fn connect_analysis(&mut self) -> SolverResult<()> {
         self.create_connect_map();
         ::step::connect_analysis::run(&mut self.side_map,
@@ -120,6 +201,22 @@ impl<'a> Solver<'a> {
                                       &mut self.conn_analysis_revision)
     }
 
+    pub fn pending_result_edges(&mut self,
+                                theorem: &Theorem,
+                                shift: Move)
+                                -> SolverResult<Vec<(CellId, CellId)>> {
+        let result = try!(theorem.shift_matches(shift,
+                                                self.puzzle,
+                                                self.sum_of_hint,
+                                                &mut self.side_map));
+        match result {
+            MatchResult::Partial(theo) => {
+                Ok(theo.result_edges().into_iter().map(|pat| pat.points()).collect())
+            }
+            MatchResult::Complete(_) | MatchResult::Conflict => Ok(vec![]),
+        }
+    }
+
     pub fn mark_common(&mut self, s0: &mut Solver, s1: &mut Solver) {
         for i in 0..self.puzzle.cell_len() {
             let p = CellId::new(i);
@@ -161,25 +258,37 @@ impl<'a> Solver<'a> {
         self.create_connect_map();
         self.connect_map.as_mut().unwrap()
     }
-    fn sync_connection(&mut self) -> SolverResult<()> {
+    pub fn sync_connection(&mut self) -> SolverResult<()> {
         self.create_connect_map();
         self.connect_map.as_mut().unwrap().sync(&mut self.side_map)
     }
 
-    // Utility function for debug.
-    // pub fn dump(&self) -> String {
-    //     if let Ok(result) = self.side_map.clone().into() {
-    //         format!("{}", result)
-    //     } else {
-    //         format!("Error!")
-    //     }
-    // }
+    pub fn num_pending_theorems(&self) -> usize {
+        self.theorem_pool.num_pending()
+    }
+
+    // Returns the board filled so far, for the diagnostic messages.
+    pub fn dump(&self) -> String {
+        let mut puzzle = self.puzzle.clone();
+        match self.side_map.clone().complete_puzzle(&mut puzzle) {
+            Ok(()) => puzzle.to_string(),
+            Err(e) => format!("(invalid board: {})", e),
+        }
+    }
+}
+
+impl<'a> Solver<'a> {
+    // Writes the sides and edges into `puzzle`, which must have the same
+    // hints as the solved puzzle.
+    pub fn complete(&mut self, puzzle: &mut Puzzle) -> SolverResult<()> {
+        self.side_map.complete_puzzle(puzzle)
+    }
 }
 
 impl<'a> Into<SolverResult<Puzzle>> for Solver<'a> {
     fn into(mut self) -> SolverResult<Puzzle> {
         let mut puzzle = self.puzzle.clone();
-        try!(self.side_map.complete_puzzle(&mut puzzle));
+        try!(self.complete(&mut puzzle));
         Ok(puzzle)
     }
 }
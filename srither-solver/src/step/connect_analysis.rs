@@ -10,7 +10,8 @@ use std::{cmp, usize};
 use srither_core::puzzle::Side;
 use srither_core::geom::{CellId, Geom};
 
-use SolverResult;
+use {Error, SolverResult};
+use invariant::{at, at_mut};
 use model::{ConnectMap, SideMap, State};
 
 fn create_conn_graph(conn_map: &mut ConnectMap,
@@ -78,35 +79,37 @@ fn get_articulation(graph: &[Vec<usize>],
                   ord: &mut [usize],
                   low: &mut [usize],
                   ord_cnt: &mut usize) {
-        debug_assert!(!visited[v]);
+        invariant!(!visited[v]);
 
-        *visited.get_unchecked_mut(v) = true;
-        *gvisited.get_unchecked_mut(v) = true;
+        *at_mut(visited, v) = true;
+        *at_mut(gvisited, v) = true;
 
         let ord_v = *ord_cnt;
-        *ord.get_unchecked_mut(v) = ord_v;
-        *low.get_unchecked_mut(v) = ord_v;
+        *at_mut(ord, v) = ord_v;
+        *at_mut(low, v) = ord_v;
         *ord_cnt += 1;
 
         let mut is_articulation = false;
         let mut num_child = 0;
 
-        for &u in graph.get_unchecked(v) {
+        for &u in at(graph, v) {
             if u == v {
                 continue;
             }
 
-            if !*visited.get_unchecked(u) {
+            if !*at(visited, u) {
                 dfs(graph, u, v, arts, gvisited, visited, ord, low, ord_cnt);
 
-                let low_u = *low.get_unchecked(u);
+                let low_u = *at(low, u);
                 num_child += 1;
-                *low.get_unchecked_mut(v) = cmp::min(*low.get_unchecked(v), low_u);
+                let low_v = cmp::min(*at(low, v), low_u);
+                *at_mut(low, v) = low_v;
                 if ord_v <= low_u {
                     is_articulation = true;
                 }
             } else if u != prev {
-                *low.get_unchecked_mut(v) = cmp::min(*low.get_unchecked(v), *ord.get_unchecked(u));
+                let low_v = cmp::min(*at(low, v), *at(ord, u));
+                *at_mut(low, v) = low_v;
             } else {
             }
         }
@@ -120,60 +123,19 @@ fn get_articulation(graph: &[Vec<usize>],
     }
 }
 
-fn find_disconn_area(conn_map: &mut ConnectMap,
-                     pts: &[CellId],
-                     visited: &[bool])
-                     -> SolverResult<Vec<usize>> {
-    let mut disconn = vec![];
-    let mut disconn_sum = 0;
-    for (u, &vis) in visited.iter().enumerate() {
-        if !vis {
-            disconn.push(u);
-            disconn_sum += conn_map.get(pts[u]).sum_of_hint();
-        }
-    }
-    if disconn.is_empty() {
-        // All area is connected
-        return Ok(vec![]);
-    }
-    if disconn_sum == 0 {
-        // Disconnected components does not contain any edges. It is a hole in
-        // the exclude_side area.
-        return Ok(disconn);
-    }
-
-    let mut conn = vec![];
-    let mut conn_sum = 0;
-    for (u, &vis) in visited.iter().enumerate() {
-        if vis {
-            conn.push(u);
-            conn_sum += conn_map.get(pts[u]).sum_of_hint();
-        }
-    }
-    if conn_sum == 0 {
-        // Conencted area does not contain any edges. It is a hole in the
-        // exclude_side area.
-        return Ok(conn);
-    }
-
-    // Graph is splitted into more than two parts, but both parts contain edges.
-    // This may be valid in some situation, so, return empty.
-    Ok(vec![])
-}
-
 fn splits(graph: &[Vec<usize>], v: usize, sides: &[State<Side>], set_side: Side) -> bool {
     let mut visited = vec![false; graph.len()];
 
     unsafe {
-        *visited.get_unchecked_mut(v) = true;
+        *at_mut(&mut visited, v) = true;
 
         let mut hit = false;
         for u in 0..graph.len() {
-            let side = *sides.get_unchecked(u);
+            let side = *at(sides, u);
             if side != State::Fixed(set_side) {
                 continue;
             }
-            if *visited.get_unchecked(u) {
+            if *at(&visited, u) {
                 continue;
             }
 
@@ -189,10 +151,10 @@ fn splits(graph: &[Vec<usize>], v: usize, sides: &[State<Side>], set_side: Side)
     return false;
 
     unsafe fn visit(graph: &[Vec<usize>], v: usize, visited: &mut [bool]) {
-        *visited.get_unchecked_mut(v) = true;
+        *at_mut(visited, v) = true;
 
-        for &u in graph.get_unchecked(v) {
-            if *visited.get_unchecked(u) {
+        for &u in at(graph, v) {
+            if *at(visited, u) {
                 continue;
             }
             visit(graph, u, visited);
@@ -220,6 +182,11 @@ pub fn run(side_map: &mut SideMap,
 
         let mut arts = vec![];
         let mut gvisited = vec![false; graph.len()];
+        // The cells of `set_side` are connected, so the areas disconnected
+        // from the one with such cells are of `exclude_side`. Without such
+        // cells, nothing is known, as any area can be inside the loop.
+        let mut connected = false;
+        let mut disconn = vec![];
 
         #[cfg_attr(feature="dev", allow(needless_range_loop))]
         for v in 0..graph.len() {
@@ -228,14 +195,19 @@ pub fn run(side_map: &mut SideMap,
             }
 
             let visited = get_articulation(&graph, v, &mut arts, &mut gvisited);
-
-            if set_side == Side::Out || conn_map.sum_of_hint() != 0 {
-                // If there is no edge in puzzle (sum_of_hint == 0) and set_side ==
-                // Side::In, any disconnected area can be loop.
-                let disconn = try!(find_disconn_area(conn_map, &pts, &visited));
-                for v in disconn {
-                    side_map.set_side(pts[v], exclude_side);
+            let area = (0..graph.len()).filter(|&u| visited[u]).collect::<Vec<_>>();
+            if area.iter().any(|&u| sides[u] == State::Fixed(set_side)) {
+                if connected {
+                    return Err(Error::invalid_board());
                 }
+                connected = true;
+            } else {
+                disconn.extend(area);
+            }
+        }
+        if connected {
+            for v in disconn {
+                side_map.set_side(pts[v], exclude_side);
             }
         }
 
@@ -0,0 +1,291 @@
+// Copyright (c) 2016 srither-solver developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Strategies describing the deduction steps of the solver.
+//!
+//! In text form, each line is a step followed by its `key=value` parameters.
+//! Empty lines and lines starting with `#` are ignored.
+//!
+//! ```text
+//! # apply theorems #0 to #9 and #12 only, except the ones about zeros
+//! theorems only=0-9,12 except=zero
+//! connect
+//! probe depth=2
+//! ```
+//!
+//! The theorems are given by the indices, the ranges of them, and the names
+//! of the groups in `THEOREM_GROUPS`, separated by commas.
+//!
+//! The steps are tried in order. When a step fixes some cells, the steps are
+//! tried again from the first one, so cheap steps should come first. `probe`
+//! fixes the cells whose assumptions make a contradiction by the steps before
+//! it, or by the nested probes if `depth` is larger than 1.
+
+use std::fmt;
+use std::error::Error;
+use std::str::FromStr;
+
+use theorem_define::THEOREM_GROUPS;
+
+/// A deduction step of the solver.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Step {
+    /// Applies the theorems.
+    Theorems,
+    /// Analyzes the connectivity of the cells.
+    Connect,
+    /// Assumes each unknown cell is inside and outside.
+    Probe {
+        /// The nesting depth of the assumptions.
+        depth: u32,
+    },
+}
+
+/// An ordered list of the deduction steps.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Strategy {
+    theorems: Option<Vec<usize>>,
+    disabled_theorems: Vec<usize>,
+    steps: Vec<Step>,
+}
+
+impl Strategy {
+    /// Creates a strategy from the steps, using all theorems.
+    pub fn new(steps: Vec<Step>) -> Strategy {
+        Strategy {
+            theorems: None,
+            disabled_theorems: vec![],
+            steps: steps,
+        }
+    }
+
+    /// Returns the steps.
+    #[inline]
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+
+    /// Returns the indices of the theorems used, or `None` if all theorems
+    /// are used.
+    #[inline]
+    pub fn theorems(&self) -> Option<&[usize]> {
+        self.theorems.as_ref().map(|t| &t[..])
+    }
+
+    /// Restricts the theorems used to the indices.
+    pub fn set_theorems(&mut self, theorems: Option<Vec<usize>>) {
+        self.theorems = theorems;
+    }
+
+    /// Returns the indices of the theorems not used.
+    #[inline]
+    pub fn disabled_theorems(&self) -> &[usize] {
+        &self.disabled_theorems
+    }
+
+    /// Excludes the theorems of the indices.
+    pub fn set_disabled_theorems(&mut self, theorems: Vec<usize>) {
+        self.disabled_theorems = theorems;
+    }
+}
+
+/// An error type which is returned from parsing a string into strategy.
+#[derive(Copy, Clone, Debug)]
+pub struct ParseStrategyError {
+    kind: StrategyErrorKind,
+    line: usize,
+}
+
+#[derive(Copy, Clone, Debug)]
+enum StrategyErrorKind {
+    Empty,
+    UnknownStep,
+    UnknownParameter,
+    InvalidValue,
+    DuplicatedTheorems,
+}
+
+impl ParseStrategyError {
+    fn new(kind: StrategyErrorKind, line: usize) -> ParseStrategyError {
+        ParseStrategyError {
+            kind: kind,
+            line: line,
+        }
+    }
+
+    /// Returns the line number where the error occurred, if any.
+    pub fn line(&self) -> Option<usize> {
+        match self.kind {
+            StrategyErrorKind::Empty => None,
+            _ => Some(self.line),
+        }
+    }
+}
+
+impl Error for ParseStrategyError {
+    fn description(&self) -> &str {
+        match self.kind {
+            StrategyErrorKind::Empty => "no step found in string",
+            StrategyErrorKind::UnknownStep => "unknown step",
+            StrategyErrorKind::UnknownParameter => "unknown parameter",
+            StrategyErrorKind::InvalidValue => "invalid parameter value",
+            StrategyErrorKind::DuplicatedTheorems => "theorems step appears more than once",
+        }
+    }
+}
+
+impl fmt::Display for ParseStrategyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line() {
+            Some(line) => write!(f, "line {}: {}", line, self.description()),
+            None => self.description().fmt(f),
+        }
+    }
+}
+
+impl FromStr for Strategy {
+    type Err = ParseStrategyError;
+
+    fn from_str(s: &str) -> Result<Strategy, ParseStrategyError> {
+        let mut strategy = Strategy::new(vec![]);
+        let mut has_theorems = false;
+
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let err = |kind| ParseStrategyError::new(kind, i + 1);
+
+            let mut words = line.split_whitespace();
+            let name = words.next().unwrap();
+            let mut params = vec![];
+            for word in words {
+                match word.find('=') {
+                    Some(idx) => params.push((&word[..idx], &word[idx + 1..])),
+                    None => return Err(err(StrategyErrorKind::InvalidValue)),
+                }
+            }
+
+            let step = match name {
+                "theorems" => {
+                    if has_theorems {
+                        return Err(err(StrategyErrorKind::DuplicatedTheorems));
+                    }
+                    has_theorems = true;
+                    for (key, value) in params {
+                        let theorems = try!(parse_theorems(value)
+                                                .ok_or(err(StrategyErrorKind::InvalidValue)));
+                        match key {
+                            "only" => strategy.theorems = Some(theorems),
+                            "except" => strategy.disabled_theorems = theorems,
+                            _ => return Err(err(StrategyErrorKind::UnknownParameter)),
+                        }
+                    }
+                    Step::Theorems
+                }
+                "connect" => {
+                    if !params.is_empty() {
+                        return Err(err(StrategyErrorKind::UnknownParameter));
+                    }
+                    Step::Connect
+                }
+                "probe" => {
+                    let mut depth = 1;
+                    for (key, value) in params {
+                        if key != "depth" {
+                            return Err(err(StrategyErrorKind::UnknownParameter));
+                        }
+                        depth = match value.parse() {
+                            Ok(d) if d > 0 => d,
+                            _ => return Err(err(StrategyErrorKind::InvalidValue)),
+                        };
+                    }
+                    Step::Probe { depth: depth }
+                }
+                _ => return Err(err(StrategyErrorKind::UnknownStep)),
+            };
+            strategy.steps.push(step);
+        }
+
+        if strategy.steps.is_empty() {
+            return Err(ParseStrategyError::new(StrategyErrorKind::Empty, 0));
+        }
+        Ok(strategy)
+    }
+}
+
+/// Parses the theorems separated by commas, such as `0-9,12,adjacent-3s`,
+/// into the sorted indices. Each is an index, a range of indices, or the name
+/// of a group in `THEOREM_GROUPS`.
+pub fn parse_theorems(s: &str) -> Option<Vec<usize>> {
+    let mut indices = vec![];
+    for part in s.split(',') {
+        if let Some(&(_, group)) = THEOREM_GROUPS.iter().find(|&&(name, _)| name == part) {
+            indices.extend(group.iter().cloned());
+            continue;
+        }
+        let (start, end) = match part.find('-') {
+            Some(idx) => (&part[..idx], &part[idx + 1..]),
+            None => (part, part),
+        };
+        let (start, end) = match (start.parse::<usize>(), end.parse::<usize>()) {
+            (Ok(start), Ok(end)) if start <= end => (start, end),
+            _ => return None,
+        };
+        indices.extend(start..(end + 1));
+    }
+    indices.sort();
+    indices.dedup();
+    Some(indices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Step, Strategy};
+
+    #[test]
+    fn parse() {
+        let input = "
+# comment
+theorems only=0-2,5,1
+connect
+
+probe depth=2
+probe
+";
+        let strategy = input.parse::<Strategy>().unwrap();
+        assert_eq!(Some(&[0, 1, 2, 5][..]), strategy.theorems());
+        assert_eq!(&[Step::Theorems,
+                     Step::Connect,
+                     Step::Probe { depth: 2 },
+                     Step::Probe { depth: 1 }],
+                   strategy.steps());
+
+        let strategy = "theorems except=adjacent-3s,8\n".parse::<Strategy>().unwrap();
+        assert_eq!(None, strategy.theorems());
+        assert_eq!(&[3, 4, 8], strategy.disabled_theorems());
+
+        let strategy = "connect\n".parse::<Strategy>().unwrap();
+        assert_eq!(None, strategy.theorems());
+        assert_eq!(&[Step::Connect], strategy.steps());
+    }
+
+    #[test]
+    fn parse_error() {
+        assert_eq!(None, "# only comment\n".parse::<Strategy>().unwrap_err().line());
+        let err = |s: &str| s.parse::<Strategy>().unwrap_err().line();
+        assert_eq!(Some(2), err("connect\nsearch\n"));
+        assert_eq!(Some(1), err("probe depth=0\n"));
+        assert_eq!(Some(1), err("probe size=1\n"));
+        assert_eq!(Some(1), err("connect depth=1\n"));
+        assert_eq!(Some(1), err("theorems only=3-1\n"));
+        assert_eq!(Some(1), err("theorems except=threes\n"));
+        assert_eq!(Some(2), err("theorems\ntheorems only=1\n"));
+    }
+}
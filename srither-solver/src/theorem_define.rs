@@ -6,6 +6,32 @@
 // option. All files in the project carrying such notice may not be copied,
 // modified, or distributed except according to those terms.
 
+/// The version of the built-in theorem set.
+pub const THEOREM_SET_VERSION: u32 = 1;
+
+// The number of the theorems in each version of the theorem set. Theorems are
+// only appended, so every version is a prefix of `THEOREM_DEFINE`.
+const THEOREM_SET_LEN: &'static [usize] = &[36];
+
+/// Returns the theorems of the version of the theorem set.
+pub fn theorem_set(version: u32) -> Option<&'static [&'static str]> {
+    if version == 0 {
+        return None;
+    }
+    THEOREM_SET_LEN.get(version as usize - 1).map(|&len| &THEOREM_DEFINE[..len])
+}
+
+/// The named groups of the built-in theorems, with their indices.
+pub const THEOREM_GROUPS: &'static [(&'static str, &'static [usize])] =
+    &[("zero", &[0, 1, 2]),
+      ("adjacent-3s", &[3, 4]),
+      ("diagonal-3s", &[5, 6]),
+      ("lines", &[7, 17, 18]),
+      ("hint-edges", &[8, 9, 10, 11, 12, 13, 14, 15, 16]),
+      ("corner-sides", &[19, 20, 21, 22, 23, 24]),
+      ("three-patterns", &[25, 26, 27, 28, 29]),
+      ("opposite-sides", &[30, 31, 32, 33, 34, 35])];
+
 // a & a: same side, a & A: Different side
 pub const THEOREM_DEFINE: &'static [&'static str] = &[r"
 + + ! +x+
@@ -314,4 +340,17 @@ mod tests {
             assert!(s.parse::<Theorem>().is_ok());
         }
     }
+
+    #[test]
+    fn versions() {
+        use super::{THEOREM_DEFINE, THEOREM_SET_LEN, THEOREM_SET_VERSION, theorem_set};
+
+        assert_eq!(THEOREM_SET_LEN.len(), THEOREM_SET_VERSION as usize);
+        assert_eq!(Some(THEOREM_DEFINE), theorem_set(THEOREM_SET_VERSION));
+        assert_eq!(None, theorem_set(0));
+        assert_eq!(None, theorem_set(THEOREM_SET_VERSION + 1));
+        for w in THEOREM_SET_LEN.windows(2) {
+            assert!(w[0] < w[1]);
+        }
+    }
 }
@@ -0,0 +1,87 @@
+// Copyright (c) 2016 srither-solver developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Deduction steps of puzzle variants.
+//!
+//! A variant adds its constraints to the solver by implementing `SolverStep`
+//! and registering it in `SolveOptions::variant_steps`. The steps are applied
+//! after the theorems in every pass of the theorems, including the passes
+//! under the assumptions of probing. The clues of the variant are usually
+//! kept in the clue layers of the puzzle.
+
+use std::fmt;
+use std::sync::Arc;
+
+use srither_core::geom::{Geom, Point};
+use srither_core::puzzle::{Puzzle, Side};
+
+use {Error, SolverResult};
+use model::State;
+use solver::Solver;
+
+/// A deduction step of a puzzle variant.
+pub trait SolverStep: Send + Sync {
+    /// Fixes the sides of the cells deduced from the constraints of the
+    /// variant. Returns an error if the constraints are violated.
+    fn apply(&self, state: &mut StepState) -> SolverResult<()>;
+}
+
+impl fmt::Debug for SolverStep {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("SolverStep")
+    }
+}
+
+/// The state of the solver seen by the steps of variants.
+#[derive(Debug)]
+pub struct StepState<'s, 'a: 's> {
+    solver: &'s mut Solver<'a>,
+}
+
+impl<'s, 'a: 's> StepState<'s, 'a> {
+    /// Returns the puzzle being solved, with its hints and clue layers.
+    pub fn puzzle(&self) -> &Puzzle {
+        self.solver.puzzle()
+    }
+
+    /// Returns the side of the cell, or `None` if it is unknown. Returns an
+    /// error if the cell has conflicting sides.
+    pub fn side(&mut self, p: Point) -> SolverResult<Option<Side>> {
+        let cp = self.solver.puzzle().point_to_cellid(p);
+        match self.solver.get_side(cp) {
+            State::Fixed(side) => Ok(Some(side)),
+            State::Unknown => Ok(None),
+            State::Conflict => Err(Error::invalid_board()),
+        }
+    }
+
+    /// Fixes the side of the cell. Returns an error if the cell has been
+    /// fixed to the other side.
+    pub fn set_side(&mut self, p: Point, side: Side) -> SolverResult<()> {
+        let cp = self.solver.puzzle().point_to_cellid(p);
+        let _ = match side {
+            Side::In => self.solver.set_inside(cp),
+            Side::Out => self.solver.set_outside(cp),
+        };
+        try!(self.side(p));
+        Ok(())
+    }
+
+    /// Returns the error for a violation of the constraints of the variant.
+    pub fn contradiction(&self) -> Error {
+        Error::invalid_board()
+    }
+}
+
+// Applies the steps in order.
+pub fn apply_steps(solver: &mut Solver, steps: &[Arc<SolverStep>]) -> SolverResult<()> {
+    for step in steps {
+        try!(step.apply(&mut StepState { solver: solver }));
+    }
+    Ok(())
+}
@@ -0,0 +1,172 @@
+// Copyright (c) 2016 srither-testdata developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! The corpus of Slither Link puzzles with the known numbers of solutions.
+//!
+//! The puzzles are categorized by how hard they are for the solver, and are
+//! shared by the tests, the benchmarks and the soak test.
+
+#![warn(bad_style)]
+#![warn(missing_copy_implementations)]
+#![warn(missing_debug_implementations)]
+#![warn(missing_docs)]
+#![warn(trivial_casts)]
+#![warn(trivial_numeric_casts)]
+#![warn(unused)]
+#![warn(unused_extern_crates)]
+#![warn(unused_import_braces)]
+#![warn(unused_qualifications)]
+#![warn(unused_results)]
+
+#![cfg_attr(feature="dev", feature(plugin))]
+#![cfg_attr(feature="dev", plugin(clippy))]
+#![cfg_attr(feature="dev", warn(mut_mut))]
+#![cfg_attr(feature="dev", warn(string_add))]
+#![cfg_attr(feature="dev", warn(string_add_assign))]
+
+extern crate srither_core;
+
+use srither_core::puzzle::Puzzle;
+
+/// The category of the puzzles.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Category {
+    /// Puzzles with a unique solution, solved by the theorems and the
+    /// connectivity mostly.
+    Easy,
+    /// Puzzles with a unique solution and the minimal hints, which need
+    /// probing or branching.
+    Hard,
+    /// Puzzles without hints, with many solutions or with no solution.
+    Pathological,
+}
+
+static CATEGORIES: &'static [Category] = &[Category::Easy,
+                                           Category::Hard,
+                                           Category::Pathological];
+
+impl Category {
+    /// Returns all the categories.
+    pub fn all() -> &'static [Category] {
+        CATEGORIES
+    }
+
+    /// Returns the name of the category, e.g. `easy`.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Category::Easy => "easy",
+            Category::Hard => "hard",
+            Category::Pathological => "pathological",
+        }
+    }
+
+    /// Returns the category of the name.
+    pub fn from_name(name: &str) -> Option<Category> {
+        CATEGORIES.iter().find(|c| c.name() == name).cloned()
+    }
+}
+
+/// A puzzle of the corpus.
+#[derive(Copy, Clone, Debug)]
+pub struct Entry {
+    name: &'static str,
+    category: Category,
+    source: &'static str,
+    num_solutions: u64,
+}
+
+impl Entry {
+    /// Returns the name of the puzzle, which is unique in the corpus.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns the category of the puzzle.
+    pub fn category(&self) -> Category {
+        self.category
+    }
+
+    /// Returns the puzzle written in the text format.
+    pub fn source(&self) -> &'static str {
+        self.source
+    }
+
+    /// Returns the puzzle.
+    pub fn puzzle(&self) -> Puzzle {
+        // The sources are checked by the tests.
+        self.source.parse().unwrap()
+    }
+
+    /// Returns the number of the solutions of the puzzle.
+    pub fn num_solutions(&self) -> u64 {
+        self.num_solutions
+    }
+}
+
+// The puzzles are read from `puzzles/DIR/NAME.txt`.
+macro_rules! entry {
+    ($category:ident, $dir:expr, $name:expr, $num_solutions:expr) => {
+        Entry {
+            name: $name,
+            category: Category::$category,
+            source: include_str!(concat!("../puzzles/", $dir, "/", $name, ".txt")),
+            num_solutions: $num_solutions,
+        }
+    }
+}
+
+static ENTRIES: &'static [Entry] = &[entry!(Easy, "easy", "example-8x8", 1),
+                                     entry!(Easy, "easy", "generated-5x5", 1),
+                                     entry!(Easy, "easy", "generated-5x7", 1),
+                                     entry!(Hard, "hard", "generated-10x10", 1),
+                                     entry!(Hard, "hard", "generated-12x12", 1),
+                                     entry!(Hard, "hard", "generated-10x15", 1),
+                                     entry!(Pathological, "pathological", "single-4", 1),
+                                     entry!(Pathological, "pathological", "adjacent-3s-2x1", 1),
+                                     entry!(Pathological, "pathological", "zeros-2x2", 0),
+                                     entry!(Pathological, "pathological", "empty-2x1", 3),
+                                     entry!(Pathological, "pathological", "empty-2x2", 13),
+                                     entry!(Pathological, "pathological", "empty-3x3", 213),
+                                     entry!(Pathological, "pathological", "empty-4x4", 9349)];
+
+/// Returns all the puzzles of the corpus.
+pub fn entries() -> &'static [Entry] {
+    ENTRIES
+}
+
+/// Returns the puzzles of the category.
+pub fn entries_of(category: Category) -> Vec<&'static Entry> {
+    ENTRIES.iter().filter(|e| e.category == category).collect()
+}
+
+/// Returns the puzzle of the name.
+pub fn find(name: &str) -> Option<&'static Entry> {
+    ENTRIES.iter().find(|e| e.name == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Category, entries, entries_of, find};
+
+    #[test]
+    fn categories() {
+        for &category in Category::all() {
+            assert_eq!(Some(category), Category::from_name(category.name()));
+            assert!(!entries_of(category).is_empty());
+        }
+        assert_eq!(None, Category::from_name("medium"));
+    }
+
+    #[test]
+    fn unique_names() {
+        for entry in entries() {
+            assert_eq!(entry.name(), find(entry.name()).unwrap().name());
+        }
+        assert!(find("unknown").is_none());
+    }
+}